@@ -0,0 +1,16 @@
+//! The pose table against the golden images in `tests/golden`; renders that
+//! differ are saved in `target/tmp/golden`.
+
+use std::path::Path;
+
+#[test]
+fn poses_match_the_goldens() {
+    let goldens = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let out = Path::new(env!("CARGO_TARGET_TMPDIR")).join("golden");
+    let failed = tovaras_core::check_goldens(&goldens, &out, false).unwrap();
+    assert!(
+        failed.is_empty(),
+        "poses differ from their goldens (see {}): {failed:?}",
+        out.display()
+    );
+}
@@ -0,0 +1,87 @@
+//! Accessories: small images (a hat, a bow) worn on the attachment points the
+//! manifest declares per frame, so they follow the art through walk and climb
+//! cycles instead of sitting at a fixed offset.
+//!
+//! Accessories are children of the pet sprite and inherit its scale, rotation
+//! and flips; clips without the point hide them (e.g. while hiding).
+
+use bevy::prelude::*;
+
+use crate::animation::{load_sheet_file, Anim, SheetInfo};
+use crate::config::Config;
+use crate::{paths, Pet};
+
+#[derive(Component)]
+pub struct Accessory {
+    point: String,
+    offset: Vec2,
+}
+
+pub fn spawn_accessories(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    cfg: Res<Config>,
+    pets: Query<Entity, With<Pet>>,
+) {
+    let Ok(pet) = pets.get_single() else {
+        return;
+    };
+    for acc in &cfg.accessories {
+        let Ok(image) = load_sheet_file(&paths::config_dir().join(&acc.image)) else {
+            continue; // already logged
+        };
+        let size = image.size().as_vec2() / acc.scale.max(f32::EPSILON);
+        let child = commands
+            .spawn((
+                SpriteBundle {
+                    texture: images.add(image),
+                    sprite: Sprite {
+                        custom_size: Some(size),
+                        ..default()
+                    },
+                    // just above the pet, below particles
+                    transform: Transform::from_xyz(0.0, 0.0, 0.5),
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                Accessory {
+                    point: acc.point.clone(),
+                    offset: Vec2::new(acc.offset.0, -acc.offset.1),
+                },
+            ))
+            .id();
+        commands.entity(pet).add_child(child);
+    }
+}
+
+/// Move every accessory onto its point on the current frame.
+pub fn follow_points(
+    sheet: Res<SheetInfo>,
+    pets: Query<(&Anim, &TextureAtlas), With<Pet>>,
+    mut accessories: Query<(&Accessory, &mut Transform, &mut Visibility)>,
+) {
+    let Ok((anim, atlas)) = pets.get_single() else {
+        return;
+    };
+    let frame = Vec2::new(sheet.frame_w, sheet.frame_h);
+    for (acc, mut tf, mut vis) in &mut accessories {
+        let at = sheet
+            .ready
+            .then(|| anim.point(&acc.point, atlas.index, frame))
+            .flatten();
+        let want = if at.is_some() {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        if *vis != want {
+            *vis = want;
+        }
+        if let Some(at) = at {
+            let pos = at + acc.offset;
+            if tf.translation.truncate() != pos {
+                tf.translation = pos.extend(tf.translation.z);
+            }
+        }
+    }
+}
@@ -0,0 +1,421 @@
+//! What each action is, looked up in the [`Actions`] registry instead of
+//! being matched on all over the core: the clip it plays, the surfaces it
+//! may happen on (and how often random mode picks it there), how the pet
+//! moves meanwhile and how long it lasts.
+//!
+//! The built-in actions are registered first, weighted by `behavior` in
+//! config.ron. A pack adds its own under `actions` in its manifest, e.g. a
+//! dance on the floor:
+//!
+//! ```ron
+//! actions: {
+//!     "dance": (clip: "dance", surfaces: {Floor: 8.0}, secs: Some((2.0, 4.0))),
+//! },
+//! ```
+//!
+//! Registered names are kept for the whole run ([`CustomAction`]), so they
+//! can be asked for by name (`tovaras ctl action dance`) and stay readable in
+//! logs and snapshots after their pack is switched away.
+
+use bevy::prelude::*;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+use crate::config::BehaviorConfig;
+use crate::manifest::Manifest;
+use crate::{Action, Surface};
+
+/// Padding after one pass of a clip that decides its action's length, so the
+/// last frame stays up a moment.
+pub const CLIP_PADDING: f32 = 0.5;
+
+/// Names of the actions registered at run time, indexed by [`CustomAction`].
+static NAMES: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+/// An action registered at run time, by its index in `NAMES`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "devtools", derive(Reflect))]
+pub struct CustomAction(u16);
+
+impl CustomAction {
+    /// The action called `name`, taking a new id the first time.
+    pub fn named(name: &str) -> Self {
+        if let Some(known) = Self::lookup(name) {
+            return known;
+        }
+        let mut names = NAMES.write().unwrap();
+        names.push(name.to_string());
+        Self((names.len() - 1) as u16)
+    }
+
+    /// The action called `name`, if one was registered.
+    pub fn lookup(name: &str) -> Option<Self> {
+        let names = NAMES.read().unwrap();
+        let i = names.iter().position(|n| n == name)?;
+        Some(Self(i as u16))
+    }
+
+    pub fn name(self) -> String {
+        NAMES.read().unwrap()[self.0 as usize].clone()
+    }
+}
+
+impl std::fmt::Debug for CustomAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.name())
+    }
+}
+
+impl Serialize for CustomAction {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.name())
+    }
+}
+
+/// Only names already registered (by a pack), so clients can't grow `NAMES`.
+impl<'de> Deserialize<'de> for CustomAction {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Self::lookup(&name).ok_or_else(|| D::Error::custom(format!("unknown action {name:?}")))
+    }
+}
+
+impl Action {
+    /// A built-in action by its name (`GivingFlowers`) or a registered one
+    /// (`dance`).
+    pub fn parse(name: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::from(name))
+            .ok()
+            .or_else(|| CustomAction::lookup(name).map(Action::Custom))
+    }
+}
+
+/// How the pet moves while doing an action.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Deserialize)]
+pub enum Motion {
+    /// Stays where it is.
+    #[default]
+    Still,
+    /// Stays where it is, resting (updated less often, see powersave.rs).
+    Rest,
+    /// Walks or climbs along its surface.
+    Walk,
+    /// Takes off (see physics.rs).
+    Jump,
+    /// Holds still after touching down, sliding off what is left of the jump.
+    Land,
+}
+
+/// How long an action lasts once a driver picked it.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Span {
+    /// A random number of seconds in the range.
+    Between(f32, f32),
+    /// One pass of its clip.
+    Clip,
+    /// Until the pet is back on a surface; drivers wait for that.
+    Flight,
+    /// Only when asked for, until told otherwise.
+    Asked,
+}
+
+/// Everything the core needs to know about one action.
+#[derive(Clone, Debug)]
+pub struct ActionDef {
+    /// Clip to play; `None` plays the manifest's pose clip for the surface.
+    pub clip: Option<String>,
+    /// Built-in action whose pose (rotation and flips) it is shown with.
+    pub pose: Action,
+    /// Surfaces it may happen on, with how often random mode picks it there
+    /// (0 = only when asked for).
+    pub surfaces: Vec<(Surface, f32)>,
+    pub motion: Motion,
+    pub span: Span,
+}
+
+impl ActionDef {
+    pub fn allowed_on(&self, surface: Surface) -> bool {
+        self.surfaces.iter().any(|&(s, _)| s == surface)
+    }
+
+    fn weight_on(&self, surface: Surface) -> f32 {
+        self.surfaces
+            .iter()
+            .find(|&&(s, _)| s == surface)
+            .map_or(0.0, |&(_, w)| w.max(0.0))
+    }
+
+    /// Seconds it lasts when started on `surface`; `roll` is uniform in 0..1.
+    pub fn secs(&self, manifest: &Manifest, surface: Surface, roll: f32) -> f32 {
+        match self.span {
+            Span::Between(min, max) => min + (max - min) * roll,
+            Span::Clip => {
+                let clip = match &self.clip {
+                    Some(name) => manifest.clip(name),
+                    None => manifest.clip_for(surface, self.pose),
+                };
+                clip.duration() + CLIP_PADDING
+            }
+            // drivers hold off in the air and while landing
+            Span::Flight => 0.2,
+            Span::Asked => 0.0,
+        }
+    }
+}
+
+/// An action a pack adds in its manifest.
+#[derive(Clone, Debug, Deserialize)]
+pub struct PackAction {
+    pub clip: String,
+    /// Surfaces it may happen on, with its random mode weight there.
+    pub surfaces: BTreeMap<Surface, f32>,
+    /// Shown with the pose of this built-in action.
+    #[serde(default = "idle")]
+    pub pose: Action,
+    /// `Still`, `Rest` or `Walk`.
+    #[serde(default)]
+    pub motion: Motion,
+    /// Seconds, picked at random between the two; `None` = one pass of the clip.
+    #[serde(default)]
+    pub secs: Option<(f32, f32)>,
+}
+
+fn idle() -> Action {
+    Action::Idle
+}
+
+impl PackAction {
+    pub fn validate(&self) -> Result<(), String> {
+        if matches!(self.motion, Motion::Jump | Motion::Land) {
+            return Err(format!("motion {:?} is for built-in actions", self.motion));
+        }
+        if matches!(self.pose, Action::Custom(_)) {
+            return Err("pose must be a built-in action".into());
+        }
+        if self.secs.is_some_and(|(min, max)| min <= 0.0 || max < min) {
+            return Err("secs must be a positive range".into());
+        }
+        Ok(())
+    }
+
+    fn def(&self) -> ActionDef {
+        ActionDef {
+            clip: Some(self.clip.clone()),
+            pose: self.pose,
+            surfaces: self.surfaces.iter().map(|(&s, &w)| (s, w)).collect(),
+            motion: self.motion,
+            span: match self.secs {
+                Some((min, max)) => Span::Between(min, max),
+                None => Span::Clip,
+            },
+        }
+    }
+}
+
+/// The registry: built-in actions, then the pack's.
+#[derive(Resource, Clone, Debug)]
+pub struct Actions {
+    /// In registration order, which random mode's picks follow (walking and
+    /// climbing first: what a pet with no weights keeps doing).
+    defs: Vec<(Action, ActionDef)>,
+    /// How many of `defs` are built in.
+    builtin: usize,
+}
+
+impl Default for Actions {
+    fn default() -> Self {
+        Self::builtin(&BehaviorConfig::default())
+    }
+}
+
+impl Actions {
+    /// The built-in actions, weighted by `behavior`, and `manifest`'s own.
+    pub fn new(behavior: &BehaviorConfig, manifest: &Manifest) -> Self {
+        let mut actions = Self::builtin(behavior);
+        actions.load_pack(manifest);
+        actions
+    }
+
+    fn builtin(b: &BehaviorConfig) -> Self {
+        use Surface::*;
+        let def = |surfaces: Vec<(Surface, f32)>, pose, motion, span| ActionDef {
+            clip: None,
+            pose,
+            surfaces,
+            motion,
+            span,
+        };
+        let anywhere = |floor| {
+            vec![
+                (Floor, floor),
+                (RightWall, 0.0),
+                (Ceiling, 0.0),
+                (LeftWall, 0.0),
+            ]
+        };
+        let defs = vec![
+            (
+                Action::Move,
+                def(
+                    vec![(Floor, b.floor.walk)],
+                    Action::Move,
+                    Motion::Walk,
+                    Span::Between(3.0, 6.0),
+                ),
+            ),
+            (
+                Action::Climb,
+                def(
+                    vec![
+                        (RightWall, b.wall.climb),
+                        (Ceiling, b.ceiling.climb),
+                        (LeftWall, b.wall.climb),
+                    ],
+                    Action::Climb,
+                    Motion::Walk,
+                    Span::Between(3.0, 6.0),
+                ),
+            ),
+            (
+                Action::Idle,
+                def(
+                    anywhere(b.floor.idle),
+                    Action::Idle,
+                    Motion::Still,
+                    Span::Between(3.0, 6.0),
+                ),
+            ),
+            (
+                Action::GivingFlowers,
+                def(
+                    vec![(Floor, b.floor.flowers)],
+                    Action::GivingFlowers,
+                    Motion::Still,
+                    Span::Clip,
+                ),
+            ),
+            (
+                Action::Hiding,
+                def(
+                    vec![
+                        (Floor, b.floor.hide),
+                        (RightWall, b.wall.hide),
+                        (Ceiling, b.ceiling.hide),
+                        (LeftWall, b.wall.hide),
+                    ],
+                    Action::Hiding,
+                    Motion::Rest,
+                    Span::Between(1.5, 2.5),
+                ),
+            ),
+            // no jumps from the ceiling
+            (
+                Action::Jumping,
+                def(
+                    vec![
+                        (Floor, b.floor.jump),
+                        (RightWall, b.wall.jump),
+                        (LeftWall, b.wall.jump),
+                    ],
+                    Action::Jumping,
+                    Motion::Jump,
+                    Span::Flight,
+                ),
+            ),
+            (
+                Action::Landing,
+                def(
+                    vec![(Floor, 0.0)],
+                    Action::Landing,
+                    Motion::Land,
+                    Span::Flight,
+                ),
+            ),
+            (
+                Action::Sleeping,
+                def(anywhere(0.0), Action::Sleeping, Motion::Rest, Span::Asked),
+            ),
+        ];
+        Self {
+            builtin: defs.len(),
+            defs,
+        }
+    }
+
+    /// Add (or replace) the action `name`.
+    pub fn register(&mut self, name: &str, def: ActionDef) -> Action {
+        let action = Action::Custom(CustomAction::named(name));
+        match self.defs.iter_mut().find(|(a, _)| *a == action) {
+            Some((_, old)) => *old = def,
+            None => self.defs.push((action, def)),
+        }
+        action
+    }
+
+    /// Replace the previous pack's actions with `manifest`'s.
+    pub fn load_pack(&mut self, manifest: &Manifest) {
+        self.defs.truncate(self.builtin);
+        for (name, action) in &manifest.actions {
+            self.register(name, action.def());
+        }
+    }
+
+    /// `action`'s definition; one no longer registered (its pack was
+    /// switched away) behaves like idling.
+    pub fn get(&self, action: Action) -> &ActionDef {
+        self.defs
+            .iter()
+            .find(|(a, _)| *a == action)
+            .or_else(|| self.defs.iter().find(|(a, _)| *a == Action::Idle))
+            .map(|(_, def)| def)
+            .expect("idle is built in")
+    }
+
+    /// Seconds `action` lasts when started on `surface` (see [`ActionDef::secs`]).
+    pub fn secs(&self, action: Action, manifest: &Manifest, surface: Surface, roll: f32) -> f32 {
+        self.get(action).secs(manifest, surface, roll)
+    }
+
+    /// Draw the next action allowed on `surface`, as often as the weights
+    /// say, for a uniform `roll` in 0..1. With every weight at zero the pet
+    /// keeps walking or climbing.
+    pub fn pick(&self, surface: Surface, roll: f32) -> Action {
+        let options: Vec<(Action, f32)> = self
+            .defs
+            .iter()
+            .filter(|(_, def)| def.allowed_on(surface) && def.span != Span::Asked)
+            .map(|(action, def)| (*action, def.weight_on(surface)))
+            .collect();
+        let total: f32 = options.iter().map(|(_, w)| w).sum();
+        let mut left = roll * total;
+        let mut picked = options.first().map_or(Action::Idle, |&(a, _)| a);
+        for &(action, weight) in options.iter().filter(|(_, w)| *w > 0.0) {
+            picked = action;
+            if left < weight {
+                break;
+            }
+            left -= weight;
+        }
+        picked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_registered_actions_deserialize() {
+        let unknown = serde_json::json!({"Custom": "never-registered"});
+        assert!(serde_json::from_value::<Action>(unknown).is_err());
+        assert_eq!(CustomAction::lookup("never-registered"), None);
+        let dance = CustomAction::named("test-dance");
+        let known = serde_json::json!({"Custom": "test-dance"});
+        assert_eq!(
+            serde_json::from_value::<Action>(known).unwrap(),
+            Action::Custom(dance)
+        );
+    }
+}
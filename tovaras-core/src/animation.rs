@@ -0,0 +1,581 @@
+//! Sprite animation: the sheet the pet is cut from ([`SheetInfo`]), the clip
+//! playing on it ([`Anim`]) and the manifest's frame events ([`FrameEvent`]).
+//!
+//! [`AnimationPlugin`] builds the sheet at startup, cuts it into frames once the
+//! image is loaded and steps every pet's clip each frame. Which clip plays is
+//! decided by the motion system through [`set_visual_for`].
+//!
+//! Sheets are read and decoded off the main thread ([`SheetInfo::start_loading`]).
+//! Until the first one is done the pet is a placeholder; a sheet that fails to
+//! load is tried again a few times (files still being written or synced), then
+//! the error is logged and kept in [`SheetInfo::error`]. A sheet in use is
+//! kept on the GPU only, and unloaded as soon as another one replaces it.
+
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::texture::{CompressedImageFormats, ImageSampler, ImageType, TextureError};
+use bevy::sprite::TextureAtlasLayout;
+use bevy::tasks::futures_lite::future;
+use bevy::tasks::{block_on, AsyncComputeTaskPool, Task};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use crate::config::{Config, Filter};
+use crate::crop::Crops;
+use crate::manifest::{Clip, LoopMode, Manifest};
+use crate::pet_window::PetWindow;
+use crate::skins::SkinCatalog;
+use crate::{atlas, fallback, outline, recolor, Action, Pet, Surface, SCALE};
+
+/// Loads the sheet and plays the pets' clips. Needs [`Manifest`], [`Config`]
+/// and [`SkinCatalog`]; sends [`FrameEvent`].
+pub struct AnimationPlugin;
+
+impl Plugin for AnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SheetInfo>()
+            .add_event::<FrameEvent>()
+            .add_systems(Startup, load_assets)
+            .add_systems(
+                Update,
+                (poll_sheet_load, finalize_after_load, animate_sprite).chain(),
+            );
+    }
+}
+
+/// Tries a sheet gets before its error is final.
+const LOAD_ATTEMPTS: u32 = 3;
+/// Wait before the second try; doubled for each one after.
+const RETRY_SECS: f32 = 1.0;
+
+#[derive(Resource, Default)]
+pub struct SheetInfo {
+    pub frame_w: f32,
+    pub frame_h: f32,
+    pub atlas_layout: Handle<TextureAtlasLayout>,
+    pub texture: Handle<Image>,
+    pub ready: bool,
+    /// Why the placeholder is shown instead of (part of) the real sheet.
+    pub error: Option<String>,
+    load: SheetLoad,
+}
+
+/// Where the next sheet is in loading; `texture` keeps the last one meanwhile.
+#[derive(Default)]
+enum SheetLoad {
+    #[default]
+    Done,
+    Building {
+        task: Task<Built>,
+        attempt: u32,
+        started: Instant,
+    },
+    /// The last try failed; the next one starts when `wait` runs out.
+    Retrying {
+        wait: Timer,
+        attempt: u32,
+        from: Box<(SkinCatalog, Manifest)>,
+    },
+}
+
+/// A finished build, with what it was built from for another try.
+struct Built {
+    image: Image,
+    error: Option<String>,
+    catalog: SkinCatalog,
+    manifest: Manifest,
+}
+
+impl SheetInfo {
+    /// Build the sheet for `manifest` (see [`build_sheet`]) on a background
+    /// thread; [`poll_sheet_load`] shows it once it is done.
+    pub fn start_loading(&mut self, catalog: &SkinCatalog, manifest: &Manifest) {
+        self.start_attempt(catalog.clone(), manifest.clone(), 1);
+    }
+
+    /// Show `image` from now on and unload the sheet shown so far.
+    /// [`finalize_after_load`] cuts the new one into frames and hands it to the
+    /// pets, whose handles were the last ones keeping the old one on the GPU.
+    pub fn replace_texture(&mut self, images: &mut Assets<Image>, image: Image) {
+        let old = std::mem::replace(&mut self.texture, images.add(image));
+        images.remove(&old);
+        self.ready = false;
+    }
+
+    /// Whether a sheet is still being built or retried.
+    pub fn loading(&self) -> bool {
+        !matches!(self.load, SheetLoad::Done)
+    }
+
+    fn start_attempt(&mut self, catalog: SkinCatalog, manifest: Manifest, attempt: u32) {
+        let task = AsyncComputeTaskPool::get().spawn(async move {
+            let (image, error) = build_sheet(&catalog, &manifest);
+            Built {
+                image,
+                error,
+                catalog,
+                manifest,
+            }
+        });
+        self.load = SheetLoad::Building {
+            task,
+            attempt,
+            started: Instant::now(),
+        };
+    }
+}
+
+#[derive(Component)]
+#[cfg_attr(feature = "devtools", derive(Reflect), reflect(Component))]
+pub struct Anim {
+    pub start_index: usize,
+    pub len: usize,
+    pub mode: LoopMode,
+    pub durations: Vec<f32>,          // per-frame seconds; empty = fixed FPS
+    pub backwards: bool,              // ping-pong direction
+    pub events: Vec<(usize, String)>, // (frame, event name) from the manifest
+    pub anchor: Vec2,                 // clip offset in sheet px (+y down)
+    #[cfg_attr(feature = "devtools", reflect(ignore))]
+    pub points: BTreeMap<String, Vec<(f32, f32)>>, // attachment points per frame
+    pub extent: Option<Vec2>,         // window-sized part of the frame in sheet px
+    pub passes: u32,                  // times played through since it started
+    pub timer: Timer,
+}
+
+/// A manifest frame event: playback reached a frame tagged with `name`.
+#[derive(Event)]
+pub struct FrameEvent {
+    pub name: String,
+}
+
+impl Anim {
+    pub fn new(start_index: usize, clip: &Clip) -> Self {
+        let first = clip.mode.first_frame(clip.frames);
+        let spf = clip
+            .durations
+            .get(first)
+            .copied()
+            .unwrap_or(1.0 / clip.fps.max(1.0));
+        Self {
+            start_index,
+            len: clip.frames,
+            mode: clip.mode,
+            durations: clip.durations.clone(),
+            backwards: false,
+            events: frame_events(clip),
+            anchor: Vec2::from(clip.anchor),
+            points: clip.points.clone(),
+            extent: clip.extent.map(Vec2::from),
+            passes: 0,
+            timer: Timer::from_seconds(spf, TimerMode::Repeating),
+        }
+    }
+
+    /// Attachment point `name` on the frame shown at atlas `index`, sprite-local
+    /// (origin at the frame center, +y up, unscaled sheet pixels).
+    pub fn point(&self, name: &str, index: usize, frame: Vec2) -> Option<Vec2> {
+        let at = self.points.get(name)?;
+        let local = index.saturating_sub(self.start_index);
+        let &(x, y) = at.get(local).or(at.last())?;
+        Some(Vec2::new(x - frame.x / 2.0, frame.y / 2.0 - y))
+    }
+
+    /// Atlas index the clip starts on (its last frame when played reversed).
+    pub fn first_index(&self) -> usize {
+        self.start_index + self.mode.first_frame(self.len)
+    }
+
+    /// Atlas index of the frame a pass ends on.
+    fn last_index(&self) -> usize {
+        self.start_index + self.mode.last_frame(self.len)
+    }
+
+    /// Whether the clip has played through once since it started, however
+    /// slowly its frames came: one-shot actions end on this.
+    pub fn played_through(&self) -> bool {
+        self.passes > 0
+    }
+
+    /// Play the clip over from its first frame.
+    pub fn restart(&mut self, atlas: &mut TextureAtlas) {
+        let first = self.mode.first_frame(self.len);
+        if let Some(&secs) = self.durations.get(first) {
+            self.timer.set_duration(Duration::from_secs_f32(secs));
+        }
+        self.timer.reset();
+        self.backwards = false;
+        self.passes = 0;
+        atlas.index = self.first_index();
+    }
+
+    /// One pass through the clip, first frame to last.
+    fn pass(&self) -> Duration {
+        if self.durations.is_empty() {
+            self.timer.duration() * self.len as u32
+        } else {
+            Duration::from_secs_f32(self.durations.iter().sum())
+        }
+    }
+}
+
+/// Start building the sheet, show a placeholder meanwhile, and make an atlas
+/// layout (grid).
+pub fn load_assets(
+    mut images: ResMut<Assets<Image>>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut sheet: ResMut<SheetInfo>,
+    catalog: Res<SkinCatalog>,
+    manifest: Res<Manifest>,
+) {
+    info!("Loading the pet sheet");
+    sheet.replace_texture(&mut images, fallback::placeholder_sheet(&manifest));
+    sheet.start_loading(&catalog, &manifest);
+    // placeholder layout; overwritten after image loads
+    let layout = manifest.layout(UVec2::ONE);
+    sheet.atlas_layout = layouts.add(layout);
+}
+
+/// Show a sheet once it is built, or start another try after a while when it
+/// failed.
+pub fn poll_sheet_load(
+    time: Res<Time<Real>>,
+    mut sheet: ResMut<SheetInfo>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let sheet = &mut *sheet;
+    match &mut sheet.load {
+        SheetLoad::Done => {}
+        SheetLoad::Retrying { wait, .. } => {
+            if !wait.tick(time.delta()).finished() {
+                return;
+            }
+            let SheetLoad::Retrying { attempt, from, .. } = std::mem::take(&mut sheet.load) else {
+                return;
+            };
+            let (catalog, manifest) = *from;
+            sheet.start_attempt(catalog, manifest, attempt + 1);
+        }
+        SheetLoad::Building {
+            task,
+            attempt,
+            started,
+        } => {
+            let Some(built) = block_on(future::poll_once(task)) else {
+                return;
+            };
+            let attempt = *attempt;
+            match &built.error {
+                Some(e) if attempt < LOAD_ATTEMPTS => {
+                    let secs = RETRY_SECS * 2f32.powi(attempt as i32 - 1);
+                    warn!("Sheet try {attempt} of {LOAD_ATTEMPTS} failed, next in {secs} s: {e}");
+                    sheet.load = SheetLoad::Retrying {
+                        wait: Timer::from_seconds(secs, TimerMode::Once),
+                        attempt,
+                        from: Box::new((built.catalog, built.manifest)),
+                    };
+                    return;
+                }
+                Some(e) => error!("Could not load the pet sheet: {e}"),
+                None => info!("Sheet loaded in {} ms", started.elapsed().as_millis()),
+            }
+            sheet.load = SheetLoad::Done;
+            sheet.replace_texture(&mut images, built.image);
+            sheet.error = built.error;
+        }
+    }
+}
+
+/// Once the image is loaded, compute frame size, update atlas, and resize the window.
+/// The atlas layout is recut in place, so every pet's handle stays valid.
+#[allow(clippy::too_many_arguments)]
+pub fn finalize_after_load(
+    mut sheet: ResMut<SheetInfo>,
+    mut images: ResMut<Assets<Image>>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut windows: Query<&mut Window>,
+    mut pets: Query<(&mut Handle<Image>, &PetWindow), With<Pet>>,
+    manifest: Res<Manifest>,
+    mut crops: Option<ResMut<Crops>>,
+    cfg: Res<Config>,
+) {
+    if sheet.ready {
+        return;
+    }
+    let Some(img) = images.get_mut(&sheet.texture) else {
+        return;
+    };
+    img.sampler = match cfg.render.filter {
+        Filter::Nearest => ImageSampler::nearest(),
+        Filter::Linear => ImageSampler::linear(),
+    };
+    // nothing reads the pixels after this frame: once uploaded, the sheet is
+    // dropped from RAM and only kept on the GPU
+    img.asset_usage = RenderAssetUsages::RENDER_WORLD;
+    if cfg.outline.enabled {
+        let frames = manifest.layout(img.size()).textures;
+        outline::bake(img, &frames, &cfg.outline);
+    }
+    let img = &*img;
+
+    let frame = manifest.frame_size(img.size());
+    let (frame_w, frame_h) = (frame.x, frame.y);
+    sheet.frame_w = frame_w;
+    sheet.frame_h = frame_h;
+
+    if let Some(layout) = layouts.get_mut(&sheet.atlas_layout) {
+        *layout = manifest.layout(img.size());
+        if let Some(crops) = crops.as_mut() {
+            crops.measure(img, layout, &manifest);
+        }
+    }
+
+    for (mut texture, &PetWindow(entity)) in &mut pets {
+        *texture = sheet.texture.clone();
+        let Ok(mut win) = windows.get_mut(entity) else {
+            continue;
+        };
+        // Window is 5x smaller than the sprite frame
+        win.resolution.set(frame_w * SCALE, frame_h * SCALE);
+    }
+
+    sheet.ready = true;
+}
+
+/// Only change the animation clip/FPS when it actually changes.
+/// When changed, snap atlas to the first frame of the new clip so it's visible immediately.
+pub fn set_anim_if_changed(anim: &mut Anim, atlas: &mut TextureAtlas, start: usize, clip: &Clip) {
+    let len = clip.frames;
+    let spf = 1.0 / clip.fps.max(1.0);
+
+    let needs_change = anim.start_index != start
+        || anim.anchor != Vec2::from(clip.anchor)
+        || anim.len != len
+        || anim.mode != clip.mode
+        || anim.durations != clip.durations
+        || (clip.durations.is_empty()
+            && (anim.timer.duration().as_secs_f32() - spf).abs() > f32::EPSILON);
+
+    if needs_change {
+        let _span = info_span!("switch_clip", start, len).entered();
+        anim.start_index = start;
+        anim.len = len;
+        anim.mode = clip.mode;
+        anim.durations = clip.durations.clone();
+        anim.events = frame_events(clip);
+        anim.anchor = Vec2::from(clip.anchor);
+        anim.points = clip.points.clone();
+        anim.extent = clip.extent.map(Vec2::from);
+        anim.timer.set_duration(Duration::from_secs_f32(spf));
+        anim.restart(atlas); // snap to the clip's first frame
+    }
+}
+
+/// Advance the frame within the current row safely.
+pub fn frame_events(clip: &Clip) -> Vec<(usize, String)> {
+    clip.events
+        .iter()
+        .flat_map(|(name, frames)| frames.iter().map(move |&f| (f, name.clone())))
+        .collect()
+}
+
+pub fn animate_sprite(
+    time: Res<Time>,
+    mut q: Query<(&mut TextureAtlas, &mut Anim), With<Pet>>,
+    mut frame_events: EventWriter<FrameEvent>,
+) {
+    for (mut atlas, mut anim) in &mut q {
+        play(&mut anim, &mut atlas, time.delta(), |anim, frame| {
+            for (_, name) in anim.events.iter().filter(|(f, _)| *f == frame) {
+                frame_events.send(FrameEvent { name: name.clone() });
+            }
+        });
+    }
+}
+
+/// Play `delta` more of the clip: every frame whose time has come, each for
+/// its own duration, so a late update (a stall, or a slow one, see
+/// powersave.rs) catches up with the clock instead of drifting behind it.
+/// `reached` gets each frame (clip-local) playback steps onto; leaving the
+/// last frame counts a pass.
+pub fn play(
+    anim: &mut Anim,
+    atlas: &mut TextureAtlas,
+    delta: Duration,
+    mut reached: impl FnMut(&Anim, usize),
+) {
+    let mut owed = anim.timer.elapsed() + delta;
+    // a long stall skips whole passes, and their frame events, but the last
+    let pass = anim.pass();
+    if !pass.is_zero() && owed > pass * 2 {
+        let passes = (owed.as_nanos() / pass.as_nanos()) as u32;
+        owed -= pass * (passes - 1);
+        anim.passes = anim.passes.saturating_add(passes - 1);
+    }
+    loop {
+        let frame = anim.timer.duration();
+        if frame.is_zero() || owed < frame {
+            break;
+        }
+        owed -= frame;
+        if atlas.index == anim.last_index() {
+            anim.passes = anim.passes.saturating_add(1);
+        }
+        let _span = info_span!("advance_frame").entered();
+        if let Some(next) = advance_frame(anim, atlas) {
+            reached(anim, next);
+        }
+    }
+    anim.timer.set_elapsed(owed);
+}
+
+/// Step to the clip's next frame, setting the timer to that frame's duration;
+/// returns the new frame (clip-local) when it changed.
+pub fn advance_frame(anim: &mut Anim, atlas: &mut TextureAtlas) -> Option<usize> {
+    if anim.len == 0 {
+        return None;
+    }
+    if atlas.index < anim.start_index || atlas.index >= anim.start_index + anim.len {
+        atlas.index = anim.start_index;
+    }
+    let local = atlas.index.saturating_sub(anim.start_index);
+    let (mode, len) = (anim.mode, anim.len);
+    let next_local = mode.next_frame(local, len, &mut anim.backwards);
+    atlas.index = anim.start_index + next_local;
+    if let Some(&secs) = anim.durations.get(next_local) {
+        anim.timer.set_duration(Duration::from_secs_f32(secs));
+    }
+    (next_local != local).then_some(next_local)
+}
+
+/// Decide visuals (clip, rotation, flips) for (surface, action, facing) from the manifest.
+/// flip_x = mirror across Y axis (left/right); flip_y = mirror across X axis (up/down)
+/// `clip` replaces the pose's clip (e.g. the idle picked for this stretch).
+#[allow(clippy::too_many_arguments)]
+pub fn set_visual_for(
+    manifest: &Manifest,
+    surface: Surface,
+    action: Action,
+    facing: f32,
+    clip: Option<&str>,
+    anim: &mut Anim,
+    atlas: &mut TextureAtlas,
+    tf: &mut Transform,
+) {
+    let pose = manifest.pose(surface, action);
+    let mut clip = match clip {
+        Some(name) => manifest.by_time(manifest.clip(name)),
+        None => manifest.clip_for(surface, action),
+    };
+    let rot = pose.map_or(0.0, |p| p.rotation.to_radians());
+    let mut flip_x = pose.is_some_and(|p| p.flip_x.applies(facing));
+    // Asymmetric characters: dedicated art for the other side beats mirroring
+    if let Some(mirrored) = clip.mirrored.as_deref().filter(|_| flip_x) {
+        clip = manifest.clip(mirrored);
+        flip_x = false;
+    }
+    let flip_y = pose.is_some_and(|p| p.flip_y.applies(facing));
+
+    set_anim_if_changed(anim, atlas, manifest.clip_start(clip), clip);
+    // Preserve base SCALE when flipping
+    let sx = if flip_x { -SCALE } else { SCALE };
+    let sy = if flip_y { -SCALE } else { SCALE };
+    tf.rotation = Quat::from_rotation_z(rot);
+    tf.scale = Vec3::new(sx, sy, 1.0);
+}
+
+/// The full sheet texture: seasonal sheet if one is installed for today, else
+/// the manifest's own (or generated) sheet, else the embedded default — with any
+/// extra sheets declared by the manifest stacked below it. A sheet that can't
+/// be loaded is replaced by a placeholder and reported in the second value.
+/// The selected color variant is applied last.
+pub fn build_sheet(catalog: &SkinCatalog, manifest: &Manifest) -> (Image, Option<String>) {
+    let mut errors = Vec::new();
+    let main = catalog
+        .load_active()
+        .or_else(|| manifest.baked.clone())
+        .or_else(|| {
+            let path = manifest.sheet_path()?;
+            Some(load_sheet_file(&path).unwrap_or_else(|e| {
+                errors.push(e);
+                fallback::placeholder_sheet(manifest)
+            }))
+        })
+        .unwrap_or_else(embedded_sheet);
+
+    let mut image = if manifest.extra_sheets.is_empty() {
+        main
+    } else {
+        stack_extra_sheets(main, manifest, &mut errors)
+    };
+    if let Some(variant) = manifest.active_variant() {
+        recolor::apply(&mut image, variant);
+    }
+    (image, (!errors.is_empty()).then(|| errors.join("\n")))
+}
+
+pub fn stack_extra_sheets(main: Image, manifest: &Manifest, errors: &mut Vec<String>) -> Image {
+    // every extra sheet shares the main grid's frame size
+    let frame_h = main.height() / manifest.rows as u32;
+    let mut parts = vec![main];
+    for (extra, path) in manifest
+        .extra_sheets
+        .iter()
+        .zip(manifest.extra_sheet_paths())
+    {
+        let want_h = frame_h * extra.rows as u32;
+        let img = load_sheet_file(&path)
+            .and_then(|img| {
+                if img.height() == want_h {
+                    Ok(img)
+                } else {
+                    Err(format!(
+                        "{} is {}px tall, expected {want_h}px",
+                        path.display(),
+                        img.height()
+                    ))
+                }
+            })
+            // keep row offsets stable even when a sheet is missing
+            .unwrap_or_else(|e| {
+                errors.push(e);
+                atlas::blank(parts[0].width(), want_h.max(1))
+            });
+        parts.push(img);
+    }
+    match atlas::stack_vertically(&parts) {
+        Ok(img) => img,
+        Err(e) => {
+            errors.push(format!("could not merge extra sheets: {e}"));
+            parts.swap_remove(0)
+        }
+    }
+}
+
+/// Read and decode an installed sheet, logging why it can't be used.
+pub fn load_sheet_file(path: &std::path::Path) -> Result<Image, String> {
+    std::fs::read(path)
+        .map_err(|e| e.to_string())
+        .and_then(|bytes| decode_sheet(&bytes).map_err(|e| e.to_string()))
+        .map_err(|e| {
+            let e = format!("{}: {e}", path.display());
+            warn!("Sheet unavailable: {e}");
+            e
+        })
+}
+
+pub fn embedded_sheet() -> Image {
+    let bytes: &[u8] = include_bytes!("../assets/pet.png");
+    decode_sheet(bytes).expect("failed to decode embedded pet.png")
+}
+
+/// Decode a PNG sprite sheet the same way for embedded and installed sheets.
+pub fn decode_sheet(bytes: &[u8]) -> Result<Image, TextureError> {
+    Image::from_buffer(
+        bytes,
+        ImageType::Extension("png"),
+        CompressedImageFormats::all(),
+        true, // sRGB for regular color sprites
+        ImageSampler::nearest(),
+        RenderAssetUsages::RENDER_WORLD | RenderAssetUsages::MAIN_WORLD,
+    )
+}
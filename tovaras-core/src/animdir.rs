@@ -0,0 +1,104 @@
+//! Import of a directory of animated GIF/APNG files, one per clip.
+//!
+//! `walk.gif`, `idle.png`, ... become clips named after the file, keeping the
+//! per-frame delays. Every file becomes one row of a generated grid sheet
+//! (see `packer.rs`).
+
+use bevy::prelude::*;
+use image::codecs::gif::GifDecoder;
+use image::codecs::png::PngDecoder;
+use image::{AnimationDecoder, Frames, RgbaImage};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::BufReader;
+use std::path::Path;
+
+use crate::manifest::{Clip, LoopMode};
+use crate::packer::{self, Strip};
+
+/// Generated sheet plus the grid and clips describing it.
+pub struct AnimationDir {
+    pub image: Image,
+    pub columns: usize,
+    pub rows: usize,
+    pub clips: BTreeMap<String, Clip>,
+}
+
+pub fn load(dir: &Path) -> Result<AnimationDir, String> {
+    let mut entries: Vec<_> = fs::read_dir(dir)
+        .map_err(|e| e.to_string())?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| {
+            matches!(
+                p.extension().and_then(|e| e.to_str()),
+                Some("gif" | "png" | "apng")
+            )
+        })
+        .collect();
+    // stable row order regardless of the file system
+    entries.sort();
+
+    let mut strips = Vec::new();
+    for path in &entries {
+        let name = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .ok_or_else(|| format!("{}: bad file name", path.display()))?
+            .to_string();
+        let (frames, durations) = decode(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        if frames.is_empty() {
+            return Err(format!("{}: no frames", path.display()));
+        }
+        strips.push(Strip {
+            name,
+            frames,
+            durations,
+            mode: LoopMode::Loop,
+        });
+    }
+    if strips.is_empty() {
+        return Err("no .gif or .png animations found".into());
+    }
+
+    let packed = packer::pack(strips);
+    Ok(AnimationDir {
+        image: packer::to_image(&packed.sheet),
+        columns: packed.columns,
+        rows: packed.rows,
+        clips: packed.clips,
+    })
+}
+
+/// Frames and their delays; a still PNG counts as a single frame.
+fn decode(path: &Path) -> Result<(Vec<RgbaImage>, Vec<f32>), String> {
+    let file = BufReader::new(fs::File::open(path).map_err(|e| e.to_string())?);
+    let is_gif = path.extension().is_some_and(|e| e == "gif");
+    let frames: Frames = if is_gif {
+        GifDecoder::new(file)
+            .map_err(|e| e.to_string())?
+            .into_frames()
+    } else {
+        let png = PngDecoder::new(file).map_err(|e| e.to_string())?;
+        if !png.is_apng().map_err(|e| e.to_string())? {
+            let still = image::open(path).map_err(|e| e.to_string())?.into_rgba8();
+            return Ok((vec![still], vec![0.1]));
+        }
+        png.apng().map_err(|e| e.to_string())?.into_frames()
+    };
+
+    let mut images = Vec::new();
+    let mut durations = Vec::new();
+    for frame in frames {
+        let frame = frame.map_err(|e| e.to_string())?;
+        let (num, den) = frame.delay().numer_denom_ms();
+        // browsers treat 0 delays as "as fast as allowed", roughly 100 ms
+        let ms = if num == 0 {
+            100.0
+        } else {
+            num as f32 / den.max(1) as f32
+        };
+        durations.push(ms / 1000.0);
+        images.push(frame.into_buffer());
+    }
+    Ok((images, durations))
+}
@@ -0,0 +1,150 @@
+//! Import of Aseprite's "Export Sprite Sheet" output (JSON data + PNG).
+//!
+//! Every frame tag becomes a clip of the same name (so tags called `walk`,
+//! `idle`, `climb`, ... line up with the default poses) and Aseprite's
+//! per-frame durations are kept. Both the "Array" and "Hash" JSON flavours
+//! work; frames are expected to be exported untrimmed.
+
+use bevy::math::URect;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::manifest::{Clip, LoopMode};
+
+#[derive(Deserialize)]
+struct Rect {
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+}
+
+#[derive(Deserialize)]
+struct Frame {
+    frame: Rect,
+    /// Milliseconds.
+    #[serde(default = "default_duration")]
+    duration: u32,
+}
+
+fn default_duration() -> u32 {
+    100
+}
+
+#[derive(Deserialize)]
+struct Tag {
+    name: String,
+    from: usize,
+    to: usize,
+    #[serde(default)]
+    direction: String,
+}
+
+#[derive(Deserialize)]
+struct Meta {
+    image: String,
+    #[serde(default, rename = "frameTags")]
+    frame_tags: Vec<Tag>,
+}
+
+#[derive(Deserialize)]
+struct Export {
+    frames: Value,
+    meta: Meta,
+}
+
+/// What the manifest needs from an Aseprite export.
+pub struct AsepriteSheet {
+    /// Sheet image file name, relative to the JSON file.
+    pub image: String,
+    /// Atlas rects in frame order.
+    pub rects: Vec<URect>,
+    pub clips: BTreeMap<String, Clip>,
+}
+
+pub fn load(path: &Path) -> Result<AsepriteSheet, String> {
+    let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let export: Export = serde_json::from_str(&text).map_err(|e| e.to_string())?;
+
+    // "Array" export is a list, "Hash" export an object keyed by file name
+    // (kept in file order thanks to serde_json's preserve_order)
+    let raw: Vec<Value> = match export.frames {
+        Value::Array(list) => list,
+        Value::Object(map) => map.into_iter().map(|(_, v)| v).collect(),
+        _ => return Err("\"frames\" must be an array or an object".into()),
+    };
+    let frames = raw
+        .into_iter()
+        .map(serde_json::from_value::<Frame>)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    if frames.is_empty() {
+        return Err("export has no frames".into());
+    }
+
+    let rects = frames
+        .iter()
+        .map(|f| {
+            URect::new(
+                f.frame.x,
+                f.frame.y,
+                f.frame.x + f.frame.w,
+                f.frame.y + f.frame.h,
+            )
+        })
+        .collect();
+
+    let mut clips = BTreeMap::new();
+    for tag in &export.meta.frame_tags {
+        if tag.from > tag.to || tag.to >= frames.len() {
+            return Err(format!("tag {:?} points outside the frames", tag.name));
+        }
+        let mode = match tag.direction.as_str() {
+            "" | "forward" => LoopMode::Loop,
+            "reverse" => LoopMode::Reverse,
+            "pingpong" => LoopMode::PingPong,
+            other => {
+                bevy::log::warn!(
+                    "tag {:?}: direction {other:?} not supported, playing forward",
+                    tag.name
+                );
+                LoopMode::Loop
+            }
+        };
+        let durations: Vec<f32> = frames[tag.from..=tag.to]
+            .iter()
+            .map(|f| f.duration.max(1) as f32 / 1000.0)
+            .collect();
+        let total: f32 = durations.iter().sum();
+        clips.insert(
+            tag.name.clone(),
+            Clip {
+                sheet: 0,
+                row: 0,
+                file: None,
+                frames: durations.len(),
+                // average speed, only used where a single FPS is needed
+                fps: durations.len() as f32 / total,
+                mode,
+                frame_durations: BTreeMap::new(),
+                events: BTreeMap::new(),
+                points: BTreeMap::new(),
+                mirrored: None,
+                night: None,
+                anchor: (0.0, 0.0),
+                extent: None,
+                first: Some(tag.from),
+                durations,
+            },
+        );
+    }
+
+    Ok(AsepriteSheet {
+        image: export.meta.image,
+        rects,
+        clips,
+    })
+}
@@ -0,0 +1,68 @@
+//! Building one atlas image out of several source images, so packs can split
+//! their art over multiple files while the renderer still sees a single sheet.
+
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+
+const BPP: usize = 4; // RGBA8
+
+fn rgba8(img: &Image) -> Result<(), String> {
+    match img.texture_descriptor.format {
+        TextureFormat::Rgba8UnormSrgb | TextureFormat::Rgba8Unorm => Ok(()),
+        other => Err(format!(
+            "unsupported pixel format {other:?} (expected RGBA8)"
+        )),
+    }
+}
+
+/// Blank (fully transparent) RGBA8 sRGB image.
+pub fn blank(width: u32, height: u32) -> Image {
+    Image::new(
+        Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        vec![0; width as usize * height as usize * BPP],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD | RenderAssetUsages::MAIN_WORLD,
+    )
+}
+
+/// Copy `src` into `dst` with its top-left corner at `at` (clipped to `dst`).
+pub fn blit(dst: &mut Image, src: &Image, at: UVec2) {
+    let (dw, dh) = (dst.width() as usize, dst.height() as usize);
+    let (sw, sh) = (src.width() as usize, src.height() as usize);
+    let (ox, oy) = (at.x as usize, at.y as usize);
+    if ox >= dw || oy >= dh {
+        return;
+    }
+    let row_px = sw.min(dw - ox);
+    for y in 0..sh.min(dh - oy) {
+        let s = y * sw * BPP;
+        let d = ((oy + y) * dw + ox) * BPP;
+        dst.data[d..d + row_px * BPP].copy_from_slice(&src.data[s..s + row_px * BPP]);
+    }
+}
+
+/// Stack images top to bottom; narrower ones are padded with transparency.
+pub fn stack_vertically(parts: &[Image]) -> Result<Image, String> {
+    for img in parts {
+        rgba8(img)?;
+    }
+    let width = parts.iter().map(|i| i.width()).max().unwrap_or(1);
+    let height = parts.iter().map(|i| i.height()).sum::<u32>().max(1);
+
+    let mut out = blank(width, height);
+    if let Some(first) = parts.first() {
+        out.sampler = first.sampler.clone();
+    }
+    let mut y = 0;
+    for img in parts {
+        blit(&mut out, img, UVec2::new(0, y));
+        y += img.height();
+    }
+    Ok(out)
+}
@@ -0,0 +1,187 @@
+//! `tovaras --bench`: time random mode's behavior and physics headlessly
+//! (see `headless.rs`) and report where the time and memory traffic go:
+//!
+//! ```bash
+//! tovaras --bench --minutes 30 --seed 42 --screen 2560x1440
+//! ```
+//!
+//! Frames are stepped back to back on the 60 Hz clock, so the numbers are the
+//! CPU cost per frame, not frame pacing. Reported are frame-time percentiles,
+//! heap allocations per frame and the time each system takes. Allocations are
+//! counted by [`CountingAlloc`], which the `tovaras` binary installs as its
+//! global allocator; apps embedding the pet keep their own.
+
+use bevy::prelude::*;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::animation::animate_sprite;
+use crate::commands::arbitrate;
+use crate::config::Config;
+use crate::drivers::{random_driver, DriverTick};
+use crate::headless::{flag_value, sim_app, Sim};
+use crate::manifest::Manifest;
+use crate::physics::apply_motion_and_orientation;
+
+/// Heap allocations since the process started.
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+
+/// The system allocator, counting allocations for the benchmark; a relaxed
+/// increment per allocation costs next to nothing when nobody reads it.
+pub struct CountingAlloc;
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+/// Systems timed, in the order they run.
+const SYSTEMS: [&str; 4] = [
+    "random_driver",
+    "arbitrate",
+    "animate_sprite",
+    "apply_motion_and_orientation",
+];
+
+/// Per-system durations, one for each time it ran.
+#[derive(Resource)]
+struct Timings {
+    /// When the current system started.
+    started: Instant,
+    samples: [Vec<Duration>; SYSTEMS.len()],
+}
+
+/// A system that starts timing the next system.
+fn start(mut timings: ResMut<Timings>) {
+    timings.started = Instant::now();
+}
+
+/// A system that closes the timing of `SYSTEMS[i - 1]` and starts `SYSTEMS[i]`.
+fn mark(i: usize) -> impl FnMut(ResMut<Timings>) {
+    move |mut timings: ResMut<Timings>| {
+        let took = timings.started.elapsed();
+        timings.samples[i - 1].push(took);
+        timings.started = Instant::now();
+    }
+}
+
+/// Sorts `samples` and returns the `p`th percentile.
+fn percentile(samples: &mut [Duration], p: f64) -> Duration {
+    if samples.is_empty() {
+        return Duration::ZERO;
+    }
+    samples.sort_unstable();
+    let i = ((samples.len() - 1) as f64 * p / 100.0).round() as usize;
+    samples[i]
+}
+
+fn micros(d: Duration) -> String {
+    format!("{:.1} µs", d.as_secs_f64() * 1e6)
+}
+
+/// `tovaras --bench [--minutes N] [--seed N] [--screen WxH]`; returns the exit code.
+pub fn cli(args: &[String], manifest: Manifest, config: Config) -> i32 {
+    let minutes = match flag_value(args, "--minutes").map(str::parse::<f32>) {
+        None => 10.0,
+        Some(Ok(minutes)) if minutes > 0.0 => minutes,
+        Some(_) => {
+            eprintln!("tovaras: --minutes needs a positive number");
+            return 2;
+        }
+    };
+    let sim = match Sim::from_args(args, minutes * 60.0) {
+        Ok(sim) => sim,
+        Err(e) => {
+            eprintln!("tovaras: {e}");
+            return 2;
+        }
+    };
+
+    let mut app = sim_app(manifest, config, &sim);
+    app.insert_resource(Timings {
+        started: Instant::now(),
+        samples: Default::default(),
+    })
+    // the driver runs on its own clock, not every frame
+    .add_systems(DriverTick, (start, random_driver, mark(1)).chain())
+    .add_systems(
+        Update,
+        (
+            start,
+            arbitrate,
+            mark(2),
+            animate_sprite,
+            mark(3),
+            apply_motion_and_orientation,
+            mark(4),
+        )
+            .chain(),
+    );
+    let steps = sim.steps();
+    let mut frames = Vec::with_capacity(steps as usize);
+    let mut allocations = Vec::with_capacity(steps as usize);
+    let started = Instant::now();
+    for _ in 0..steps {
+        let (t0, a0) = (Instant::now(), ALLOCATIONS.load(Ordering::Relaxed));
+        app.update();
+        frames.push(t0.elapsed());
+        allocations.push(ALLOCATIONS.load(Ordering::Relaxed) - a0);
+    }
+    let wall = started.elapsed();
+
+    println!(
+        "Benchmarked {minutes} min on a {}x{} screen ({steps} frames) in {:.1} s, seed {}",
+        sim.screen.x,
+        sim.screen.y,
+        wall.as_secs_f32(),
+        sim.seed,
+    );
+    let mean = wall / steps.max(1) as u32;
+    println!(
+        "frame        mean {}  p50 {}  p90 {}  p99 {}  max {}",
+        micros(mean),
+        micros(percentile(&mut frames, 50.0)),
+        micros(percentile(&mut frames, 90.0)),
+        micros(percentile(&mut frames, 99.0)),
+        micros(percentile(&mut frames, 100.0)),
+    );
+    let total: u64 = allocations.iter().sum();
+    if total == 0 {
+        println!("allocations  not counted (the global allocator isn't `CountingAlloc`)");
+    } else {
+        println!(
+            "allocations  {:.2} per frame, max {}",
+            total as f64 / steps.max(1) as f64,
+            allocations.iter().max().copied().unwrap_or(0),
+        );
+    }
+    let Some(mut timings) = app.world_mut().remove_resource::<Timings>() else {
+        return 0;
+    };
+    for (name, samples) in SYSTEMS.iter().zip(timings.samples.iter_mut()) {
+        let sum: Duration = samples.iter().sum();
+        println!(
+            "  {name:<30} mean {}  p99 {}",
+            micros(sum / samples.len().max(1) as u32),
+            micros(percentile(samples, 99.0)),
+        );
+    }
+    0
+}
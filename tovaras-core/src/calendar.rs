@@ -0,0 +1,548 @@
+//! Calendar reminders: the pet reads the `.ics` calendars listed under
+//! `calendar` in config.ron and, `lead_minutes` before an event, walks toward
+//! the clock and says what is coming up.
+//!
+//! Calendars are read on a background thread at start and then every
+//! `refresh_minutes`. Single events and daily or weekly repeats (`INTERVAL`,
+//! `BYDAY`, `COUNT`, `UNTIL`, `EXDATE`) are understood, and so are single
+//! occurrences moved or cancelled with a `RECURRENCE-ID`; other repeats are
+//! only reminded of on their first date, and all-day events not at all. Times
+//! with a `TZID` are taken as local time.
+
+use bevy::prelude::*;
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveDateTime, TimeZone, Utc, Weekday};
+use std::collections::HashSet;
+use std::fs;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use crate::commands::{Issued, PetCommand, Source};
+use crate::config::{CalendarConfig, CalendarSource, Side};
+use crate::ScreenGeometry;
+
+#[cfg(feature = "http")]
+const TIMEOUT: Duration = Duration::from_secs(20);
+/// Seconds a reminder stays up.
+const SAY_SECS: f32 = 20.0;
+/// Seconds between two looks at the clock.
+const CHECK_SECS: f32 = 1.0;
+/// Days of events kept from each read: plenty until the next one.
+const HORIZON_DAYS: i64 = 2;
+/// Days a repeat is followed from its first date.
+const MAX_REPEAT_DAYS: i64 = 366 * 30;
+
+/// One occurrence of an event.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Appointment {
+    pub title: String,
+    pub start: DateTime<Local>,
+}
+
+#[derive(Resource)]
+pub struct Calendar {
+    lead: chrono::Duration,
+    clock: Side,
+    upcoming: Vec<Appointment>,
+    /// Occurrences already warned of.
+    warned: HashSet<(String, DateTime<Local>)>,
+    updates: Mutex<Receiver<Vec<Appointment>>>,
+    check: Timer,
+}
+
+impl Calendar {
+    /// Start reading the enabled calendars of `cfg` in the background.
+    pub fn start(cfg: &CalendarConfig) -> Self {
+        let sources: Vec<CalendarSource> = cfg
+            .calendars
+            .iter()
+            .filter(|calendar| calendar.enabled)
+            .cloned()
+            .collect();
+        let refresh = Duration::from_secs_f32(cfg.refresh_minutes.max(1.0) * 60.0);
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || loop {
+            let now = Local::now();
+            let mut all = Vec::new();
+            for source in &sources {
+                match read(source) {
+                    Ok(text) => all.extend(upcoming(&text, now)),
+                    Err(e) => warn!("Calendar {}: {e}", source.source),
+                }
+            }
+            info!("Read {} upcoming events from the calendars", all.len());
+            if tx.send(all).is_err() {
+                return;
+            }
+            thread::sleep(refresh);
+        });
+        Self {
+            lead: chrono::Duration::seconds((cfg.lead_minutes.max(0.0) * 60.0) as i64),
+            clock: cfg.clock,
+            upcoming: Vec::new(),
+            warned: HashSet::new(),
+            updates: Mutex::new(rx),
+            check: Timer::from_seconds(CHECK_SECS, TimerMode::Repeating),
+        }
+    }
+}
+
+/// The text of a calendar, from the web or a file.
+fn read(source: &CalendarSource) -> Result<String, String> {
+    match source.url() {
+        Some(url) => fetch(&url),
+        None => {
+            let path = source.path();
+            fs::read_to_string(&path).map_err(|e| format!("{}: {e}", path.display()))
+        }
+    }
+}
+
+#[cfg(feature = "http")]
+fn fetch(url: &str) -> Result<String, String> {
+    ureq::AgentBuilder::new()
+        .timeout(TIMEOUT)
+        .build()
+        .get(url)
+        .call()
+        .map_err(|e| e.to_string())?
+        .into_string()
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "http"))]
+fn fetch(_url: &str) -> Result<String, String> {
+    Err("web calendars need the \"http\" feature".into())
+}
+
+/// Warn of the events starting within the lead time: walk toward the clock
+/// and say what and when.
+pub fn remind_of_events(
+    time: Res<Time<Real>>,
+    mut calendar: ResMut<Calendar>,
+    screen: Res<ScreenGeometry>,
+    mut issue: EventWriter<Issued>,
+) {
+    if !calendar.check.tick(time.delta()).just_finished() {
+        return;
+    }
+    let calendar = &mut *calendar;
+    if let Some(read) = calendar.updates.get_mut().unwrap().try_iter().last() {
+        calendar.upcoming = read;
+    }
+    let now = Local::now();
+    calendar.warned.retain(|(_, start)| *start > now);
+    let mut lines = Vec::new();
+    for appointment in &calendar.upcoming {
+        if appointment.start <= now || appointment.start - calendar.lead > now {
+            continue;
+        }
+        if !calendar
+            .warned
+            .insert((appointment.title.clone(), appointment.start))
+        {
+            continue;
+        }
+        let minutes = ((appointment.start - now).num_seconds() as f32 / 60.0).ceil();
+        info!(
+            "Reminding of {:?} at {}",
+            appointment.title, appointment.start
+        );
+        lines.push(format!("{} in {minutes} min", appointment.title));
+    }
+    if lines.is_empty() {
+        return;
+    }
+    let x = match calendar.clock {
+        Side::Left => 0,
+        Side::Right => screen.size.x,
+    };
+    issue.send(PetCommand::GoTo { x }.by(Source::Ambient));
+    issue.send(
+        PetCommand::Say {
+            text: lines.join("\n"),
+            secs: Some(SAY_SECS),
+        }
+        .by(Source::Ambient),
+    );
+}
+
+/// What one `VEVENT` says about when it happens.
+#[derive(Default)]
+struct Entry {
+    uid: String,
+    title: String,
+    start: Option<NaiveDateTime>,
+    /// For one occurrence of a repeat moved (or cancelled): the start it
+    /// replaces.
+    replaces: Option<NaiveDateTime>,
+    rule: Option<String>,
+    except: Vec<NaiveDateTime>,
+    cancelled: bool,
+}
+
+/// A daily or weekly `RRULE`.
+struct Repeat {
+    weekly: bool,
+    interval: i64,
+    days: Vec<Weekday>,
+    count: Option<u32>,
+    until: Option<NaiveDateTime>,
+}
+
+/// The occurrences of the events in iCalendar `text` from `now` through the
+/// next `HORIZON_DAYS`, soonest first.
+pub fn upcoming(text: &str, now: DateTime<Local>) -> Vec<Appointment> {
+    let from = now.naive_local();
+    let to = from + chrono::Duration::days(HORIZON_DAYS);
+    let mut entries = Vec::new();
+    let mut entry: Option<Entry> = None;
+    // components inside the event (alarms) have fields of their own
+    let mut nested = 0;
+    for line in unfold(text) {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let (name, params) = name.split_once(';').unwrap_or((name, ""));
+        let value = value.trim();
+        match (name.to_ascii_uppercase().as_str(), entry.as_mut()) {
+            ("BEGIN", None) if value.eq_ignore_ascii_case("VEVENT") => {
+                entry = Some(Entry::default());
+            }
+            ("BEGIN", Some(_)) => nested += 1,
+            ("END", Some(_)) if nested > 0 => nested -= 1,
+            ("END", Some(_)) => entries.extend(entry.take()),
+            (_, Some(_)) if nested > 0 => {}
+            ("UID", Some(entry)) => entry.uid = value.to_string(),
+            ("SUMMARY", Some(entry)) => entry.title = unescape(value),
+            ("DTSTART", Some(entry)) => entry.start = parse_time(params, value),
+            ("RECURRENCE-ID", Some(entry)) => entry.replaces = parse_time(params, value),
+            ("RRULE", Some(entry)) => entry.rule = Some(value.to_string()),
+            ("EXDATE", Some(entry)) => entry
+                .except
+                .extend(value.split(',').filter_map(|at| parse_time(params, at))),
+            ("STATUS", Some(entry)) => entry.cancelled = value.eq_ignore_ascii_case("CANCELLED"),
+            _ => {}
+        }
+    }
+    // occurrences moved elsewhere (or cancelled) by an entry of their own
+    let replaced: HashSet<(&str, NaiveDateTime)> = entries
+        .iter()
+        .filter_map(|entry| Some((entry.uid.as_str(), entry.replaces?)))
+        .collect();
+    let mut starts = Vec::new();
+    for entry in entries.iter().filter(|entry| !entry.cancelled) {
+        for start in entry.occurrences(from, to) {
+            if entry.replaces.is_none() && replaced.contains(&(entry.uid.as_str(), start)) {
+                continue;
+            }
+            starts.push((entry.title.clone(), start));
+        }
+    }
+    let mut out: Vec<Appointment> = starts
+        .into_iter()
+        .filter_map(|(title, start)| {
+            Some(Appointment {
+                title,
+                start: Local.from_local_datetime(&start).earliest()?,
+            })
+        })
+        .collect();
+    out.sort_by_key(|appointment| appointment.start);
+    out
+}
+
+impl Entry {
+    /// When it starts between `from` and `to`.
+    fn occurrences(&self, from: NaiveDateTime, to: NaiveDateTime) -> Vec<NaiveDateTime> {
+        let Some(start) = self.start else {
+            return Vec::new();
+        };
+        // a moved occurrence happens once, whatever rule it was copied with
+        let rule = self.rule.as_deref().filter(|_| self.replaces.is_none());
+        let Some(repeat) = rule.and_then(parse_rule) else {
+            return (from..to)
+                .contains(&start)
+                .then_some(start)
+                .into_iter()
+                .collect();
+        };
+        let first = start.date();
+        let mut out = Vec::new();
+        let mut seen = 0;
+        for offset in 0..MAX_REPEAT_DAYS {
+            let day = first + chrono::Duration::days(offset);
+            let at = day.and_time(start.time());
+            if at >= to
+                || repeat.until.is_some_and(|until| at > until)
+                || repeat.count.is_some_and(|count| seen >= count)
+            {
+                break;
+            }
+            let on = if repeat.weekly {
+                let weeks = (week_of(day) - week_of(first)).num_days() / 7;
+                weeks % repeat.interval == 0
+                    && if repeat.days.is_empty() {
+                        day.weekday() == first.weekday()
+                    } else {
+                        repeat.days.contains(&day.weekday())
+                    }
+            } else {
+                offset % repeat.interval == 0
+            };
+            if !on {
+                continue;
+            }
+            // left-out dates still count toward COUNT
+            seen += 1;
+            if at >= from && !self.except.contains(&at) {
+                out.push(at);
+            }
+        }
+        out
+    }
+}
+
+/// The Monday starting the week of `day`.
+fn week_of(day: NaiveDate) -> NaiveDate {
+    day - chrono::Duration::days(day.weekday().num_days_from_monday().into())
+}
+
+/// `FREQ=WEEKLY;BYDAY=MO,WE;...`; `None` for repeats other than daily and
+/// weekly.
+fn parse_rule(rule: &str) -> Option<Repeat> {
+    let mut repeat = Repeat {
+        weekly: false,
+        interval: 1,
+        days: Vec::new(),
+        count: None,
+        until: None,
+    };
+    let mut freq = None;
+    for part in rule.split(';') {
+        let (key, value) = part.split_once('=')?;
+        match key.to_ascii_uppercase().as_str() {
+            "FREQ" => freq = Some(value.to_ascii_uppercase()),
+            "INTERVAL" => repeat.interval = value.parse().ok().filter(|&n: &i64| n > 0)?,
+            "COUNT" => repeat.count = value.parse().ok(),
+            "UNTIL" => {
+                repeat.until = parse_time("", value).or_else(|| {
+                    // a date: through the end of that day
+                    NaiveDate::parse_from_str(value, "%Y%m%d")
+                        .ok()?
+                        .and_hms_opt(23, 59, 59)
+                })
+            }
+            "BYDAY" => {
+                repeat.days = value
+                    .split(',')
+                    .filter_map(|day| weekday(day.get(day.len().saturating_sub(2)..)?))
+                    .collect()
+            }
+            _ => {}
+        }
+    }
+    match freq.as_deref() {
+        Some("DAILY") => Some(repeat),
+        Some("WEEKLY") => Some(Repeat {
+            weekly: true,
+            ..repeat
+        }),
+        _ => None,
+    }
+}
+
+fn weekday(code: &str) -> Option<Weekday> {
+    Some(match code.to_ascii_uppercase().as_str() {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    })
+}
+
+/// A date-time value as local time: `...Z` is UTC, anything else (floating
+/// or with a `TZID`) is taken as local. `None` for plain dates.
+fn parse_time(params: &str, value: &str) -> Option<NaiveDateTime> {
+    if params
+        .split(';')
+        .any(|param| param.eq_ignore_ascii_case("VALUE=DATE"))
+    {
+        return None;
+    }
+    let value = value.trim();
+    if let Some(utc) = value.strip_suffix('Z') {
+        let at = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some(
+            Utc.from_utc_datetime(&at)
+                .with_timezone(&Local)
+                .naive_local(),
+        );
+    }
+    NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()
+}
+
+/// Lines with their continuations (lines starting with a space or tab)
+/// joined back on.
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// A TEXT value with its escapes undone; line breaks become spaces.
+fn unescape(value: &str) -> String {
+    let mut out = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => out.push(' '),
+            Some(other) => out.push(other),
+            None => {}
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Monday, 5 January 2026, 08:00 local time.
+    fn now() -> DateTime<Local> {
+        Local.with_ymd_and_hms(2026, 1, 5, 8, 0, 0).unwrap()
+    }
+
+    fn local(day: u32, hour: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2026, 1, day, hour, 0, 0).unwrap()
+    }
+
+    /// The starts in the two days from [`now`] of a calendar of `events`,
+    /// each given as its `VEVENT` lines.
+    fn starts(events: &[&str]) -> Vec<(String, DateTime<Local>)> {
+        let mut text = String::from("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n");
+        for lines in events {
+            text += "BEGIN:VEVENT\r\n";
+            for line in lines.lines() {
+                text += line.trim();
+                text += "\r\n";
+            }
+            text += "END:VEVENT\r\n";
+        }
+        text += "END:VCALENDAR\r\n";
+        upcoming(&text, now())
+            .into_iter()
+            .map(|appointment| (appointment.title, appointment.start))
+            .collect()
+    }
+
+    fn times(events: &[&str]) -> Vec<DateTime<Local>> {
+        starts(events).into_iter().map(|(_, start)| start).collect()
+    }
+
+    #[test]
+    fn single_events_within_the_horizon() {
+        let soon = "SUMMARY:Dentist\nDTSTART:20260105T140000";
+        let past = "SUMMARY:Breakfast\nDTSTART:20260105T070000";
+        let later = "SUMMARY:Trip\nDTSTART:20260110T090000";
+        let cancelled = "SUMMARY:Lunch\nDTSTART:20260105T120000\nSTATUS:CANCELLED";
+        let all_day = "SUMMARY:Holiday\nDTSTART;VALUE=DATE:20260106";
+        assert_eq!(
+            starts(&[soon, past, later, cancelled, all_day]),
+            [("Dentist".to_string(), local(5, 14))]
+        );
+    }
+
+    #[test]
+    fn daily_rules_repeat_every_interval() {
+        let daily = "DTSTART:20251201T090000\nRRULE:FREQ=DAILY";
+        assert_eq!(times(&[daily]), [local(5, 9), local(6, 9)]);
+        // 1 December plus an even number of days
+        let every_other = "DTSTART:20251201T090000\nRRULE:FREQ=DAILY;INTERVAL=2";
+        assert_eq!(times(&[every_other]), [local(6, 9)]);
+    }
+
+    #[test]
+    fn weekly_rules_follow_byday() {
+        // from Tuesday 2 December
+        let weekly = "DTSTART:20251202T100000\nRRULE:FREQ=WEEKLY";
+        assert_eq!(times(&[weekly]), [local(6, 10)]);
+        let byday = "DTSTART:20251202T100000\nRRULE:FREQ=WEEKLY;BYDAY=MO,TU";
+        assert_eq!(times(&[byday]), [local(5, 10), local(6, 10)]);
+        // five weeks on: an odd one
+        let fortnightly = "DTSTART:20251202T100000\nRRULE:FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TU";
+        assert!(times(&[fortnightly]).is_empty());
+    }
+
+    #[test]
+    fn count_and_until_end_repeats() {
+        // the 36th day from 1 December is 5 January
+        let count = "DTSTART:20251201T090000\nRRULE:FREQ=DAILY;COUNT=36";
+        assert_eq!(times(&[count]), [local(5, 9)]);
+        let until = "DTSTART:20251201T090000\nRRULE:FREQ=DAILY;UNTIL=20260105T235959";
+        assert_eq!(times(&[until]), [local(5, 9)]);
+        let until_date = "DTSTART:20251201T090000\nRRULE:FREQ=DAILY;UNTIL=20260105";
+        assert_eq!(times(&[until_date]), [local(5, 9)]);
+    }
+
+    #[test]
+    fn exdate_leaves_out_occurrences_that_still_count() {
+        let except = "DTSTART:20251201T090000\nRRULE:FREQ=DAILY\nEXDATE:20260105T090000";
+        assert_eq!(times(&[except]), [local(6, 9)]);
+        let counted =
+            "DTSTART:20251201T090000\nRRULE:FREQ=DAILY;COUNT=37\nEXDATE:20260105T090000";
+        assert_eq!(times(&[counted]), [local(6, 9)]);
+    }
+
+    #[test]
+    fn utc_times_are_converted_and_floating_ones_kept() {
+        let utc = "SUMMARY:Call\nDTSTART:20260106T060000Z";
+        let floating = "SUMMARY:Gym\nDTSTART:20260106T060000";
+        let zoned = "SUMMARY:Review\nDTSTART;TZID=Europe/Paris:20260106T070000";
+        let call = Utc
+            .with_ymd_and_hms(2026, 1, 6, 6, 0, 0)
+            .unwrap()
+            .with_timezone(&Local);
+        let mut want = vec![
+            ("Call".to_string(), call),
+            ("Gym".to_string(), local(6, 6)),
+            ("Review".to_string(), local(6, 7)),
+        ];
+        want.sort_by_key(|(_, start)| *start);
+        assert_eq!(starts(&[utc, floating, zoned]), want);
+    }
+
+    #[test]
+    fn recurrence_ids_move_and_cancel_occurrences() {
+        let standup = "UID:standup\nSUMMARY:Standup\nDTSTART:20251201T090000\nRRULE:FREQ=DAILY";
+        let moved = "UID:standup\nSUMMARY:Late standup\nRECURRENCE-ID:20260105T090000\n\
+                     DTSTART:20260105T150000";
+        assert_eq!(
+            starts(&[standup, moved]),
+            [
+                ("Late standup".to_string(), local(5, 15)),
+                ("Standup".to_string(), local(6, 9)),
+            ]
+        );
+        let cancelled = "UID:standup\nRECURRENCE-ID:20260106T090000\n\
+                         DTSTART:20260106T090000\nSTATUS:CANCELLED";
+        assert_eq!(times(&[standup, cancelled]), [local(5, 9)]);
+        // another event's override leaves this one alone
+        let other = "UID:retro\nRECURRENCE-ID:20260105T090000\nDTSTART:20260105T160000";
+        assert_eq!(times(&[standup, other]), [local(5, 9), local(5, 16), local(6, 9)]);
+    }
+}
@@ -0,0 +1,35 @@
+//! `--chroma <color>`: the pet on a solid background in an ordinary window.
+//!
+//! Some compositors and capture setups can't record transparent windows. With
+//! a chroma color the pet window is opaque, decorated and not kept on top, so
+//! OBS & co. capture it like any other window and a chroma key filter cuts
+//! the background out again. Use the `Nearest` filter for clean edges.
+
+use bevy::prelude::*;
+use bevy::window::WindowLevel;
+
+/// Background color of the pet window in chroma mode.
+#[derive(Resource, Clone, Copy)]
+pub struct Chroma(pub Color);
+
+impl Chroma {
+    /// `green`, `blue`, `magenta` or a hex color such as `#00ff00`.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let color = match s.to_lowercase().as_str() {
+            "green" => Color::srgb_u8(0, 255, 0),
+            "blue" => Color::srgb_u8(0, 0, 255),
+            "magenta" => Color::srgb_u8(255, 0, 255),
+            hex => Srgba::hex(hex).map(Color::from).map_err(|_| {
+                format!("not a chroma color: {s:?} (green, blue, magenta or #rrggbb)")
+            })?,
+        };
+        Ok(Self(color.with_alpha(1.0)))
+    }
+
+    /// Turn the pet window into an ordinary, capturable one.
+    pub fn apply(&self, window: &mut Window) {
+        window.transparent = false;
+        window.decorations = true;
+        window.window_level = WindowLevel::Normal;
+    }
+}
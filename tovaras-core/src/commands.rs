@@ -0,0 +1,357 @@
+//! What drivers and inputs want the pet to do, as [`PetCommand`]s. The
+//! random and test drivers, social play, Twitch chat and the remote APIs (IPC,
+//! WebSocket, scripts, plugins) send them as [`Issued`] events instead of
+//! writing the pet's [`PetState`]; [`arbitrate`] alone carries them out, once
+//! per frame, before the physics step.
+//!
+//! When several senders want something in the same frame, the highest
+//! [`Source`] wins and the others' commands are dropped. An action asked for
+//! with `secs`, or a walk to a goal, also holds off lower sources until it is
+//! over (the random driver waits meanwhile); for a one-shot action that is at
+//! least until its clip has played through ([`await_clip`]). Speech never
+//! conflicts and always goes through.
+//!
+//! The physics step and collision responses (bumping into another pet) still
+//! write the state themselves: they carry out motion rather than decide it. A
+//! mirror copies the primary's state as it is.
+
+use bevy::prelude::*;
+
+use crate::actions::{Actions, Span, CLIP_PADDING};
+use crate::animation::Anim;
+use crate::drivers::RandomCtrl;
+use crate::pet_window::PetWindow;
+use crate::speech::Say;
+use crate::{Action, FlightKind, Pet, PetState, ScreenGeometry, Surface};
+
+/// Seconds a walk to a goal holds off lower sources after arriving.
+const ARRIVE_HOLD_SECS: f32 = 10.0;
+/// A walk ends this close to its goal, px.
+const ARRIVE_PX: i32 = 4;
+/// The random driver's countdown is kept at least this long while it is held
+/// off, so it decides again as soon as the hold ends.
+const DRIVER_NUDGE_SECS: f32 = 0.1;
+
+/// Who sent a command, from the least to the most important.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Source {
+    /// The random or test driver.
+    Driver,
+    /// Reactions to the surroundings (see weather.rs).
+    Ambient,
+    /// Playing with other pets (see social.rs).
+    Social,
+    /// Twitch chat.
+    #[cfg(feature = "twitch")]
+    Chat,
+    /// The user, through IPC, WebSocket, scripts or plugins.
+    Remote,
+}
+
+/// Where a jump goes.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum JumpTarget {
+    /// Window x on the floor.
+    Floor(i32),
+    /// Window x on top of another pet, standing `perch` px above the floor.
+    OnPet { x: i32, perch: i32 },
+    /// Window y on a wall.
+    Wall(Surface, i32),
+}
+
+/// A change to what the pet does.
+#[derive(Clone, PartialEq, Debug)]
+pub enum PetCommand {
+    /// Start `action` where the pet is.
+    SetAction {
+        action: Action,
+        /// The way it moves, and looks; `None` keeps both.
+        dir: Option<f32>,
+        /// Clip to vary the action with (idles, see `Manifest::pick_idle`).
+        clip: Option<String>,
+        /// Seconds lower sources are held off; `None` holds nobody.
+        secs: Option<f32>,
+    },
+    /// Walk (or jump down) to desktop x, the pet's center.
+    GoTo {
+        x: i32,
+    },
+    Say {
+        text: String,
+        secs: Option<f32>,
+    },
+    Jump {
+        target: JumpTarget,
+    },
+    /// Put the pet at rest on `surface` with its window at `pos` (test mode).
+    Place {
+        surface: Surface,
+        pos: IVec2,
+        dir: f32,
+    },
+}
+
+impl PetCommand {
+    /// Play `action` with nothing else to say about it.
+    pub fn set_action(action: Action) -> Self {
+        Self::SetAction {
+            action,
+            dir: None,
+            clip: None,
+            secs: None,
+        }
+    }
+
+    pub fn by(self, from: Source) -> Issued {
+        Issued {
+            from,
+            command: self,
+        }
+    }
+}
+
+/// A [`PetCommand`] and who sent it.
+#[derive(Event, Clone, Debug)]
+pub struct Issued {
+    pub from: Source,
+    pub command: PetCommand,
+}
+
+/// The arbiter's memory between frames.
+#[derive(Resource, Default)]
+pub struct Arbiter {
+    /// Source holding off lower ones, and for how many more seconds.
+    hold: Option<(Source, f32)>,
+    /// The one-shot action the hold is for.
+    one_shot: Option<Action>,
+    /// Desktop x the pet walks to, and who asked.
+    goal: Option<(Source, i32)>,
+    /// Already walking toward the goal (so turning around means it passed it).
+    walking: bool,
+}
+
+impl Arbiter {
+    /// Commands from below this source are dropped.
+    fn floor(&self) -> Option<Source> {
+        self.hold
+            .map(|(from, _)| from)
+            .max(self.goal.map(|(from, _)| from))
+    }
+}
+
+/// Carry out this frame's commands from the most important sender, and walks
+/// to a goal.
+#[allow(clippy::too_many_arguments)]
+pub fn arbitrate(
+    time: Res<Time>,
+    mut arbiter: ResMut<Arbiter>,
+    mut issued: EventReader<Issued>,
+    actions: Res<Actions>,
+    screen: Res<ScreenGeometry>,
+    windows: Query<&Window>,
+    mut ctrl: Option<ResMut<RandomCtrl>>,
+    mut say: Option<ResMut<Events<Say>>>,
+    mut pets: Query<(&mut PetState, &Anim, &PetWindow), With<Pet>>,
+) {
+    let mut commands: Vec<Issued> = issued.read().cloned().collect();
+    commands.retain(|issued| match &issued.command {
+        PetCommand::Say { text, secs } => {
+            if let Some(say) = say.as_mut() {
+                say.send(Say {
+                    text: text.clone(),
+                    secs: *secs,
+                });
+            }
+            false
+        }
+        _ => true,
+    });
+    let Ok((mut st, anim, pet_window)) = pets.get_single_mut() else {
+        return;
+    };
+    let Ok(win) = windows.get(pet_window.0) else {
+        return;
+    };
+    let Arbiter { hold, one_shot, .. } = &mut *arbiter;
+    if let Some((_, left)) = hold.as_mut() {
+        if !await_clip(one_shot, left, &st, anim) {
+            *left -= time.delta_seconds();
+            if *left <= 0.0 {
+                *hold = None;
+            }
+        }
+    }
+    let (fw, fh) = (
+        win.resolution.physical_width() as i32,
+        win.resolution.physical_height() as i32,
+    );
+    let max_x = screen.desktop(fw, fh).0 - fw;
+
+    let top = commands.iter().map(|issued| issued.from).max();
+    let floor = top.max(arbiter.floor());
+    for Issued { from, command } in commands {
+        if Some(from) < floor {
+            debug!("Dropped {command:?} from {from:?}: {floor:?} has the pet");
+            continue;
+        }
+        match command {
+            PetCommand::SetAction {
+                action,
+                dir,
+                clip,
+                secs,
+            } => {
+                if st.flight != FlightKind::None {
+                    continue; // can't change course mid-air
+                }
+                if !actions.get(action).allowed_on(st.surface) {
+                    debug!(
+                        "Dropped {action:?} from {from:?}: not on the {:?}",
+                        st.surface
+                    );
+                    continue;
+                }
+                let one_shot = actions.get(action).span == Span::Clip;
+                st.action = action;
+                st.idle_clip = clip;
+                st.restart_clip = one_shot;
+                if action == Action::Jumping {
+                    // hop in place (walls: drop straight down)
+                    st.target_x = st.window_pos.x;
+                    st.wall_target = None;
+                }
+                if let Some(dir) = dir {
+                    st.move_dir = dir;
+                    st.facing = dir;
+                }
+                if from > Source::Driver {
+                    arbiter.goal = None;
+                }
+                if let Some(secs) = secs {
+                    arbiter.hold = Some((from, secs));
+                    arbiter.one_shot = one_shot.then_some(action);
+                }
+            }
+            PetCommand::GoTo { x } => {
+                arbiter.goal = Some((from, x));
+                arbiter.walking = false;
+                arbiter.hold = None;
+                arbiter.one_shot = None;
+            }
+            PetCommand::Jump { target } => {
+                if st.flight == FlightKind::None && st.surface != Surface::Ceiling {
+                    jump(&mut st, target, max_x);
+                }
+            }
+            PetCommand::Place { surface, pos, dir } => place(&mut st, surface, pos, dir),
+            PetCommand::Say { .. } => {}
+        }
+    }
+
+    if let Some((from, goal)) = arbiter.goal {
+        // stop short of the corners, which would start a climb
+        let target = (goal - fw / 2).clamp(1, (max_x - 1).max(1));
+        let grounded = st.flight == FlightKind::None && !matches!(st.action, Action::Landing);
+        match st.surface {
+            Surface::Floor if grounded => {
+                let dx = target - st.window_pos.x;
+                let passed = arbiter.walking && st.move_dir * dx as f32 <= 0.0;
+                if dx.abs() <= ARRIVE_PX || passed {
+                    st.action = Action::Idle;
+                    st.idle_clip = None;
+                    arbiter.goal = None;
+                    arbiter.hold = Some((from, ARRIVE_HOLD_SECS));
+                    arbiter.one_shot = None;
+                } else {
+                    st.action = Action::Move;
+                    st.move_dir = dx.signum() as f32;
+                    arbiter.walking = true;
+                }
+            }
+            Surface::LeftWall | Surface::RightWall if grounded => {
+                jump(&mut st, JumpTarget::Floor(target), max_x);
+            }
+            // on the ceiling or in the air: carry on until back on a wall/floor
+            _ => {}
+        }
+    }
+    if arbiter.floor().is_some_and(|from| from > Source::Driver) {
+        if let Some(ctrl) = ctrl.as_mut() {
+            ctrl.left = ctrl.left.max(DRIVER_NUDGE_SECS);
+        }
+    }
+}
+
+/// Whether the one-shot action `pending` is still playing its clip, however
+/// slowly its frames come; a countdown `left` waits meanwhile and, once the
+/// clip has played through, only has its padding left. `pending` is cleared
+/// when it is over or another action took its place.
+pub fn await_clip(
+    pending: &mut Option<Action>,
+    left: &mut f32,
+    st: &PetState,
+    anim: &Anim,
+) -> bool {
+    let Some(action) = *pending else {
+        return false;
+    };
+    if st.action == action && !anim.played_through() {
+        return true;
+    }
+    if st.action == action {
+        *left = left.min(CLIP_PADDING);
+    }
+    *pending = None;
+    false
+}
+
+/// Take off toward `target`, heading where it will land; `max_x` is the right
+/// wall's window x.
+fn jump(st: &mut PetState, target: JumpTarget, max_x: i32) {
+    st.action = Action::Jumping;
+    st.idle_clip = None;
+    st.wall_target = None;
+    let to_x = match target {
+        JumpTarget::Floor(x) | JumpTarget::OnPet { x, .. } => {
+            st.target_x = x;
+            st.perch = match target {
+                JumpTarget::OnPet { perch, .. } => perch,
+                _ => 0,
+            };
+            x
+        }
+        JumpTarget::Wall(wall, y) => {
+            st.wall_target = Some((wall, y));
+            if wall == Surface::LeftWall {
+                0
+            } else {
+                max_x
+            }
+        }
+    };
+    st.move_dir = match st.surface {
+        // off a wall it lands heading away from it
+        Surface::RightWall => -1.0,
+        Surface::LeftWall => 1.0,
+        _ if to_x >= st.window_pos.x => 1.0,
+        _ => -1.0,
+    };
+}
+
+/// At rest on `surface` at `pos`, heading `dir`.
+fn place(st: &mut PetState, surface: Surface, pos: IVec2, dir: f32) {
+    st.surface = surface;
+    st.window_pos = pos;
+    st.move_dir = dir;
+    st.facing = dir;
+    st.speed = 0.0;
+    st.speed_v = 0.0;
+    st.stumble_left = 0.0;
+    st.flight = FlightKind::None;
+    st.flight_from = surface;
+    st.vx = 0.0;
+    st.vy = 0.0;
+    st.landing_left = 0.0;
+    st.target_x = 0;
+    st.wall_target = None;
+}
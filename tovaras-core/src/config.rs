@@ -0,0 +1,753 @@
+//! User configuration loaded from `<config_dir>/config.ron`.
+//!
+//! Every field has a default, so the file only needs the settings you change:
+//!
+//! ```ron
+//! (
+//!     night: (tint: (1.0, 0.8, 0.6), start_hour: 22),
+//! )
+//! ```
+
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::paths;
+
+const CONFIG_FILE: &str = "config.ron";
+
+#[derive(Resource, Clone, Debug, Default, Deserialize)]
+#[cfg_attr(feature = "devtools", derive(Reflect), reflect(Resource))]
+#[serde(default)]
+pub struct Config {
+    /// Tone down purely decorative motion (e.g. no floating "z"s while sleeping).
+    pub reduced_motion: bool,
+    /// Installed pet pack to use (see `tovaras packs list`).
+    pub pack: Option<String>,
+    /// Sheet to use instead of the embedded one (relative to the config directory).
+    pub sheet: Option<PathBuf>,
+    /// Color variant offered by the pet's manifest (e.g. "black").
+    pub variant: Option<String>,
+    /// Shrink the window to the visible part of each animation.
+    pub auto_crop: bool,
+    /// Starting tovaras again only greets the running pet instead of adding one.
+    pub single_instance: bool,
+    pub motion: MotionConfig,
+    pub behavior: BehaviorConfig,
+    pub render: RenderConfig,
+    pub outline: OutlineConfig,
+    pub night: NightConfig,
+    pub footprints: FootprintConfig,
+    /// Images worn on the manifest's attachment points (hats, scarves, ...).
+    pub accessories: Vec<AccessoryConfig>,
+    pub social: SocialConfig,
+    pub visit: VisitConfig,
+    pub sync: SyncConfig,
+    pub twitch: TwitchConfig,
+    pub websocket: WebSocketConfig,
+    pub mirror: MirrorConfig,
+    /// URLs notified of notable moments (see `webhooks.rs`).
+    pub webhooks: Vec<WebhookConfig>,
+    pub calendar: CalendarConfig,
+    pub github: GithubConfig,
+    pub mail: MailConfig,
+    pub weather: WeatherConfig,
+    pub system_monitor: SystemMonitorConfig,
+    pub nag: NagConfig,
+    pub log: LogConfig,
+}
+
+/// How quickly walking/climbing speeds up and slows down. On the floor the
+/// pet accelerates at a steady rate, rocks a little past its stops and
+/// stumbles before turning around at speed.
+#[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "devtools", derive(Reflect))]
+#[serde(default)]
+pub struct MotionConfig {
+    /// Seconds from standing still to full speed.
+    pub ease_in_secs: f32,
+    /// Seconds from full speed to a stop (also used when turning around).
+    pub ease_out_secs: f32,
+    /// Part of its speed a pet stopping on the floor rocks back with (0 for a
+    /// dead stop).
+    pub overshoot: f32,
+}
+
+impl Default for MotionConfig {
+    fn default() -> Self {
+        Self {
+            ease_in_secs: 0.45,
+            ease_out_secs: 0.3,
+            overshoot: 0.1,
+        }
+    }
+}
+
+/// How often the random driver picks each action, per surface.
+#[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "devtools", derive(Reflect))]
+#[serde(default)]
+pub struct BehaviorConfig {
+    pub floor: Weights,
+    /// Both walls.
+    pub wall: Weights,
+    pub ceiling: Weights,
+}
+
+impl Default for BehaviorConfig {
+    fn default() -> Self {
+        Self {
+            floor: Weights {
+                walk: 17.0,
+                idle: 17.0,
+                flowers: 17.0,
+                hide: 17.0,
+                climb: 0.0,
+                jump: 12.0,
+            },
+            wall: Weights {
+                climb: 16.0,
+                hide: 5.0,
+                jump: 4.0,
+                ..default()
+            },
+            ceiling: Weights {
+                climb: 7.0,
+                hide: 3.0,
+                ..default()
+            },
+        }
+    }
+}
+
+/// Relative chances of the next action; only their ratios matter and actions
+/// left out are never picked. Actions a surface doesn't allow are ignored
+/// there (walking and giving flowers happen on the floor, climbing on walls
+/// and the ceiling, and there are no jumps from the ceiling).
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[cfg_attr(feature = "devtools", derive(Reflect))]
+#[serde(default)]
+pub struct Weights {
+    pub walk: f32,
+    pub idle: f32,
+    pub flowers: f32,
+    pub hide: f32,
+    pub climb: f32,
+    pub jump: f32,
+}
+
+/// Texture filtering used when the sheet is drawn scaled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[cfg_attr(feature = "devtools", derive(Reflect))]
+pub enum Filter {
+    /// Crisp pixels, best for pixel art.
+    #[default]
+    Nearest,
+    /// Smooth, best for painted/high-resolution art.
+    Linear,
+}
+
+/// How the sprite is put on screen.
+#[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "devtools", derive(Reflect))]
+#[serde(default)]
+pub struct RenderConfig {
+    pub filter: Filter,
+    /// Keep every sheet pixel on whole screen pixels: no squash & stretch
+    /// deformation and sprite offsets rounded to full pixels.
+    pub integer_scale: bool,
+    /// Update on every display refresh only while the pet is on the move, and
+    /// on new animation frames while it idles, sleeps or hides (see powersave.rs).
+    #[serde(alias = "pause_when_resting")]
+    pub adaptive_tick_rate: bool,
+    /// Let the window glide after the pet instead of jumping several px per
+    /// frame, for window managers that show the steps as stutter.
+    pub smooth_motion: bool,
+}
+
+impl Default for RenderConfig {
+    fn default() -> Self {
+        Self {
+            filter: Filter::default(),
+            integer_scale: false,
+            adaptive_tick_rate: true,
+            smooth_motion: false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[cfg_attr(feature = "devtools", derive(Reflect))]
+pub enum OutlineStyle {
+    /// Hard edge of uniform color.
+    #[default]
+    Outline,
+    /// Soft halo fading out.
+    Glow,
+}
+
+/// Halo drawn around the pet so it stands out from the wallpaper.
+#[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "devtools", derive(Reflect))]
+#[serde(default)]
+pub struct OutlineConfig {
+    pub enabled: bool,
+    pub style: OutlineStyle,
+    /// Screen pixels.
+    pub thickness: f32,
+    /// RGB, 0..1.
+    pub color: (f32, f32, f32),
+    pub opacity: f32,
+}
+
+impl Default for OutlineConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            style: OutlineStyle::Outline,
+            thickness: 1.0,
+            color: (1.0, 1.0, 1.0),
+            opacity: 0.9,
+        }
+    }
+}
+
+/// Warm/dim tint applied to the sprite at night.
+#[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "devtools", derive(Reflect))]
+#[serde(default)]
+pub struct NightConfig {
+    pub enabled: bool,
+    /// RGB multiplier applied to the sprite while it is night.
+    pub tint: (f32, f32, f32),
+    /// Local hour (0-23) at which night starts.
+    pub start_hour: u32,
+    /// Local hour (0-23) at which night ends.
+    pub end_hour: u32,
+    /// Also treat the desktop's dark mode / night light as "night".
+    pub follow_system: bool,
+    /// Seconds to blend between day and night colors.
+    pub fade_secs: f32,
+}
+
+impl Default for NightConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            tint: (0.85, 0.72, 0.6),
+            start_hour: 21,
+            end_hour: 7,
+            follow_system: true,
+            fade_secs: 5.0,
+        }
+    }
+}
+
+/// Fading footprints left on the floor (drawn in a full-screen overlay window).
+#[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "devtools", derive(Reflect))]
+#[serde(default)]
+pub struct FootprintConfig {
+    pub enabled: bool,
+    /// Pixels walked between two footprints (smaller = denser trail).
+    pub spacing: f32,
+    /// Seconds until a footprint has faded out completely.
+    pub fade_secs: f32,
+}
+
+impl Default for FootprintConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            spacing: 12.0,
+            fade_secs: 6.0,
+        }
+    }
+}
+
+/// Interactions with other tovaras pets running on the same desktop.
+#[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "devtools", derive(Reflect))]
+#[serde(default)]
+pub struct SocialConfig {
+    pub enabled: bool,
+    /// Screen pixels between two pets' centers that count as "near".
+    pub distance: f32,
+    /// Seconds after an interaction before the next one can start.
+    pub cooldown_secs: f32,
+    /// Pets on the floor bump into, hop over or briefly stand on each other
+    /// instead of walking through.
+    pub collisions: bool,
+    /// With five or more pets, they follow a leader around the screen edge.
+    pub flock: bool,
+}
+
+impl Default for SocialConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            distance: 250.0,
+            cooldown_secs: 90.0,
+            collisions: true,
+            flock: false,
+        }
+    }
+}
+
+/// Visits between pets on different machines of the LAN (see `visit.rs`).
+#[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "devtools", derive(Reflect))]
+#[serde(default)]
+pub struct VisitConfig {
+    /// Accept visitors on `port`.
+    pub enabled: bool,
+    pub port: u16,
+    /// Shared pairing code; visits with another code are turned away.
+    pub code: String,
+    /// `host:port` of friends our pet visits (V key goes to the first one).
+    pub friends: Vec<String>,
+    /// Seconds our pet stays when visiting.
+    pub stay_secs: f32,
+}
+
+impl Default for VisitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 48720,
+            code: String::new(),
+            friends: Vec::new(),
+            stay_secs: 60.0,
+        }
+    }
+}
+
+/// Where the pet's profile (needs, level, inventory) is kept.
+#[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "devtools", derive(Reflect))]
+#[serde(default)]
+pub struct SyncConfig {
+    /// Folder shared between machines (Syncthing, Dropbox, ...); a leading `~/`
+    /// is the home directory. None keeps the profile on this machine only.
+    pub folder: Option<PathBuf>,
+    /// Name of this machine's file in the folder; defaults to the hostname.
+    pub machine: Option<String>,
+    /// Seconds between merging the other machines' changes and saving ours.
+    pub interval_secs: f32,
+}
+
+impl Default for SyncConfig {
+    fn default() -> Self {
+        Self {
+            folder: None,
+            machine: None,
+            interval_secs: 30.0,
+        }
+    }
+}
+
+impl SyncConfig {
+    /// The shared folder, resolved to a full path.
+    pub fn folder_path(&self) -> Option<PathBuf> {
+        let folder = self.folder.as_ref()?;
+        Some(match folder.strip_prefix("~") {
+            Ok(rest) => paths::home().join(rest),
+            Err(_) => paths::config_dir().join(folder),
+        })
+    }
+}
+
+/// Twitch chat reactions (needs a build with `--features twitch`).
+#[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "devtools", derive(Reflect))]
+#[serde(default)]
+pub struct TwitchConfig {
+    /// Channel whose chat the pet follows; empty turns the integration off.
+    pub channel: String,
+    /// OAuth user token (`oauth:...` or bare).
+    pub token: String,
+    /// Account the token belongs to; defaults to the channel.
+    pub nick: Option<String>,
+    /// Application client id; needed for follower events only.
+    pub client_id: String,
+    /// Seconds after a chat command before the next one is obeyed.
+    pub cooldown_secs: f32,
+}
+
+impl Default for TwitchConfig {
+    fn default() -> Self {
+        Self {
+            channel: String::new(),
+            token: String::new(),
+            nick: None,
+            client_id: String::new(),
+            cooldown_secs: 15.0,
+        }
+    }
+}
+
+/// Local WebSocket control API (see `websocket.rs`).
+#[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "devtools", derive(Reflect))]
+#[serde(default)]
+pub struct WebSocketConfig {
+    pub enabled: bool,
+    /// Port on 127.0.0.1.
+    pub port: u16,
+}
+
+impl Default for WebSocketConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 48721,
+        }
+    }
+}
+
+/// Showing the same pet on several machines (see `mirror.rs`).
+#[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "devtools", derive(Reflect))]
+#[serde(default)]
+pub struct MirrorConfig {
+    /// Stream this pet's state to mirrors on `port`.
+    pub serve: bool,
+    pub port: u16,
+    /// `host:port` of the primary to follow instead of running our own pet.
+    pub source: Option<String>,
+}
+
+impl Default for MirrorConfig {
+    fn default() -> Self {
+        Self {
+            serve: false,
+            port: 48722,
+            source: None,
+        }
+    }
+}
+
+/// Log level and the optional log file (see `logging.rs`).
+#[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "devtools", derive(Reflect))]
+#[serde(default)]
+pub struct LogConfig {
+    /// "error", "warn", "info", "debug" or "trace"; `--log-level` wins.
+    pub level: String,
+    /// Also write the log to `<data_dir>/logs`, one file per day.
+    pub file: bool,
+    /// Daily files kept before the oldest is deleted.
+    pub keep_days: usize,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".into(),
+            file: false,
+            keep_days: 7,
+        }
+    }
+}
+
+/// One webhook endpoint.
+#[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "devtools", derive(Reflect))]
+pub struct WebhookConfig {
+    pub url: String,
+    /// Event names to send ("level_up", "daily_summary"); empty = all.
+    #[serde(default)]
+    #[cfg_attr(not(feature = "http"), allow(dead_code))]
+    pub events: Vec<String>,
+}
+
+/// Reminders of upcoming events from iCalendar files (see `calendar.rs`).
+#[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "devtools", derive(Reflect))]
+#[serde(default)]
+pub struct CalendarConfig {
+    /// Calendars to read; none turns the reminders off.
+    pub calendars: Vec<CalendarSource>,
+    /// Minutes before an event the pet warns of it.
+    pub lead_minutes: f32,
+    /// Minutes between two reads of the calendars.
+    pub refresh_minutes: f32,
+    /// The side of the screen the clock is on; the pet walks there to warn.
+    pub clock: Side,
+}
+
+impl Default for CalendarConfig {
+    fn default() -> Self {
+        Self {
+            calendars: Vec::new(),
+            lead_minutes: 10.0,
+            refresh_minutes: 15.0,
+            clock: Side::Right,
+        }
+    }
+}
+
+/// A side of the screen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[cfg_attr(feature = "devtools", derive(Reflect))]
+pub enum Side {
+    Left,
+    Right,
+}
+
+/// One `.ics` calendar.
+#[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "devtools", derive(Reflect))]
+#[serde(default)]
+pub struct CalendarSource {
+    /// An `http(s)://` or `webcal://` URL, or a file; a leading `~/` is the
+    /// home directory, other relative paths start in the config directory.
+    pub source: String,
+    pub enabled: bool,
+}
+
+impl Default for CalendarSource {
+    fn default() -> Self {
+        Self {
+            source: String::new(),
+            enabled: true,
+        }
+    }
+}
+
+impl CalendarSource {
+    /// The URL to fetch, or `None` for a file.
+    pub fn url(&self) -> Option<String> {
+        let source = self.source.trim();
+        if let Some(rest) = source.strip_prefix("webcal://") {
+            return Some(format!("https://{rest}"));
+        }
+        (source.starts_with("http://") || source.starts_with("https://"))
+            .then(|| source.to_string())
+    }
+
+    /// The file to read, resolved to a full path.
+    pub fn path(&self) -> PathBuf {
+        let source = Path::new(self.source.trim());
+        match source.strip_prefix("~") {
+            Ok(rest) => paths::home().join(rest),
+            Err(_) => paths::config_dir().join(source),
+        }
+    }
+}
+
+/// GitHub notifications (see `github.rs`).
+#[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "devtools", derive(Reflect))]
+#[serde(default)]
+pub struct GithubConfig {
+    /// Personal access token allowed to read notifications; empty turns the
+    /// integration off.
+    pub token: String,
+    /// Minutes between two polls (GitHub may ask for longer).
+    pub poll_minutes: f32,
+    /// Notification reasons the pet reacts to and counts.
+    pub reasons: Vec<String>,
+}
+
+impl Default for GithubConfig {
+    fn default() -> Self {
+        Self {
+            token: String::new(),
+            poll_minutes: 1.0,
+            reasons: vec![
+                "review_requested".into(),
+                "mention".into(),
+                "team_mention".into(),
+            ],
+        }
+    }
+}
+
+/// Unread mail over IMAP (see `mail.rs`; needs the "mail" feature). The
+/// password comes from the system keyring (`tovaras mail-password` stores it).
+#[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "devtools", derive(Reflect))]
+#[serde(default)]
+pub struct MailConfig {
+    /// IMAP server (TLS); empty turns the integration off.
+    pub host: String,
+    pub port: u16,
+    pub user: String,
+    /// Mailbox whose unread messages are counted.
+    pub mailbox: String,
+}
+
+impl Default for MailConfig {
+    fn default() -> Self {
+        Self {
+            host: String::new(),
+            port: 993,
+            user: String::new(),
+            mailbox: "INBOX".into(),
+        }
+    }
+}
+
+/// Weather ambience (see `weather.rs`).
+#[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "devtools", derive(Reflect))]
+#[serde(default)]
+pub struct WeatherConfig {
+    /// Latitude and longitude whose weather the pet follows; `None` turns it
+    /// off.
+    pub location: Option<(f32, f32)>,
+    /// Minutes between two looks at the weather.
+    pub refresh_minutes: f32,
+    /// Temperature from which on it is a heat wave, °C.
+    pub heat_celsius: f32,
+}
+
+impl Default for WeatherConfig {
+    fn default() -> Self {
+        Self {
+            location: None,
+            refresh_minutes: 30.0,
+            heat_celsius: 30.0,
+        }
+    }
+}
+
+/// The pet's posture shows how the machine is doing (see `sysmon.rs`).
+#[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "devtools", derive(Reflect))]
+#[serde(default)]
+pub struct SystemMonitorConfig {
+    pub enabled: bool,
+    /// CPU use (0..1) from which on the CPU counts as pegged.
+    pub cpu_busy: f32,
+    /// A path on the filesystem whose fullness is shown.
+    pub disk: String,
+    /// Fullness (0..1) from which on the disk counts as full.
+    pub disk_full: f32,
+}
+
+impl Default for SystemMonitorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            cpu_busy: 0.9,
+            disk: "/".into(),
+            disk_full: 0.9,
+        }
+    }
+}
+
+/// Nagging about low disk space and stale backups (see `nag.rs`).
+#[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "devtools", derive(Reflect))]
+#[serde(default)]
+pub struct NagConfig {
+    /// Paths whose filesystems must keep `min_free_gib` free.
+    pub disks: Vec<String>,
+    pub min_free_gib: f32,
+    /// Backup files or folders that must have changed in the last
+    /// `max_age_days`.
+    pub backups: Vec<String>,
+    pub max_age_days: f32,
+    /// Minutes between the first two nags; they come quicker after that.
+    pub every_minutes: f32,
+}
+
+impl Default for NagConfig {
+    fn default() -> Self {
+        Self {
+            disks: Vec::new(),
+            min_free_gib: 10.0,
+            backups: Vec::new(),
+            max_age_days: 7.0,
+            every_minutes: 60.0,
+        }
+    }
+}
+
+/// One accessory image pinned to a named attachment point of the pet.
+#[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "devtools", derive(Reflect))]
+#[serde(default)]
+pub struct AccessoryConfig {
+    /// PNG, relative to the config directory.
+    pub image: PathBuf,
+    /// Attachment point declared by the manifest's clips, e.g. "head".
+    pub point: String,
+    /// Extra shift in sheet pixels (+y down) from the point to the image center.
+    pub offset: (f32, f32),
+    /// Image pixels per sheet pixel.
+    pub scale: f32,
+}
+
+impl Default for AccessoryConfig {
+    fn default() -> Self {
+        Self {
+            image: PathBuf::new(),
+            point: "head".into(),
+            offset: (0.0, 0.0),
+            scale: 1.0,
+        }
+    }
+}
+
+/// Set `pack` in the config file, keeping the rest of it (comments
+/// included) as it is.
+pub fn set_pack(pack: Option<&str>) -> Result<(), String> {
+    let path = paths::config_dir().join(CONFIG_FILE);
+    let value = match pack {
+        Some(name) => format!("pack: Some({name:?}),"),
+        None => "pack: None,".to_string(),
+    };
+    let text = fs::read_to_string(&path).unwrap_or_else(|_| "(\n)\n".into());
+    let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+    if let Some(line) = lines
+        .iter_mut()
+        .find(|l| l.trim_start().starts_with("pack:"))
+    {
+        let indent = line.len() - line.trim_start().len();
+        *line = format!("{}{value}", &line[..indent]);
+    } else if let Some(open) = lines.iter().position(|l| l.trim_start().starts_with('(')) {
+        lines.insert(open + 1, format!("    {value}"));
+    } else {
+        return Err(format!("no settings found in {}", path.display()));
+    }
+    let text = lines.join("\n") + "\n";
+    if let Err(e) = ron::from_str::<Config>(&text) {
+        return Err(format!(
+            "couldn't update {} ({e}); set `{value}` there by hand",
+            path.display()
+        ));
+    }
+    fs::create_dir_all(paths::config_dir())
+        .and_then(|_| fs::write(&path, text))
+        .map_err(|e| format!("{}: {e}", path.display()))
+}
+
+impl Config {
+    /// The configured replacement sheet, resolved to a full path.
+    pub fn sheet_path(&self) -> Option<PathBuf> {
+        let sheet = self.sheet.as_ref()?;
+        Some(paths::config_dir().join(sheet))
+    }
+
+    /// Read the config file; missing or malformed files fall back to defaults.
+    pub fn load() -> Self {
+        let path = paths::config_dir().join(CONFIG_FILE);
+        let Ok(text) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        match ron::from_str(&text) {
+            Ok(cfg) => {
+                info!("Loaded config from {}", path.display());
+                cfg
+            }
+            Err(e) => {
+                warn!("Ignoring malformed {}: {e}", path.display());
+                Self::default()
+            }
+        }
+    }
+}
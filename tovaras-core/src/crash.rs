@@ -0,0 +1,206 @@
+//! Crash reports: when tovaras panics, the pet's last state, the RNG seed,
+//! the recent log, its last transitions and the configuration go to
+//! `<data_dir>/crashes/crash-<time>.txt`, and a notification says where,
+//! instead of the pet silently vanishing. With the seed and state, the crash
+//! can often be replayed with `--headless --seed`.
+
+use bevy::log::tracing_subscriber::layer::Context;
+use bevy::log::tracing_subscriber::Layer;
+use bevy::prelude::*;
+use bevy::utils::tracing::field::{Field, Visit};
+use bevy::utils::tracing::{Event, Subscriber};
+use std::backtrace::Backtrace;
+use std::collections::VecDeque;
+use std::fmt::{self, Write as _};
+use std::fs;
+use std::panic::{self, PanicHookInfo};
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::Mutex;
+
+use crate::config::Config;
+use crate::rng::Rng;
+use crate::{history, paths, Pet, PetState};
+
+const CRASHES_DIR: &str = "crashes";
+/// Log lines kept for the report.
+const RECENT_LINES: usize = 100;
+
+/// What the last frame knew, for the report.
+struct Snapshot {
+    state: Option<PetState>,
+    seed: Option<u32>,
+    config: String,
+}
+
+static SNAPSHOT: Mutex<Snapshot> = Mutex::new(Snapshot {
+    state: None,
+    seed: None,
+    config: String::new(),
+});
+static RECENT: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// Write a report on panic, then let the default hook print as usual.
+pub fn install(config: &Config) {
+    if let Ok(mut snapshot) = SNAPSHOT.lock() {
+        snapshot.config = format!("{:#?}", redacted(config));
+    }
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        match write_report(info) {
+            Ok(file) => {
+                eprintln!("tovaras crashed; the report is in {}", file.display());
+                notify(&format!("The report is in {}", file.display()));
+            }
+            Err(e) => eprintln!("tovaras crashed and couldn't write a report: {e}"),
+        }
+        default_hook(info);
+    }));
+}
+
+/// `config` without secrets, since reports get pasted into bug reports.
+fn redacted(config: &Config) -> Config {
+    let mut config = config.clone();
+    let hide = |secret: &mut String| {
+        if !secret.is_empty() {
+            *secret = "<redacted>".into();
+        }
+    };
+    hide(&mut config.twitch.token);
+    hide(&mut config.github.token);
+    hide(&mut config.visit.code);
+    for hook in &mut config.webhooks {
+        hide(&mut hook.url);
+    }
+    // private calendar feeds carry a token in the URL
+    for calendar in &mut config.calendar.calendars {
+        if calendar.url().is_some() {
+            hide(&mut calendar.source);
+        }
+    }
+    config
+}
+
+fn write_report(info: &PanicHookInfo) -> std::io::Result<PathBuf> {
+    let now = chrono::Local::now();
+    let mut text = format!(
+        "tovaras {} crashed at {}\n{info}\n",
+        env!("CARGO_PKG_VERSION"),
+        now.format("%Y-%m-%d %H:%M:%S")
+    );
+    // the panic may have hit while one of these was held
+    match SNAPSHOT.try_lock() {
+        Ok(snapshot) => {
+            let seed = snapshot.seed.map_or("none".into(), |s| s.to_string());
+            let _ = write!(
+                text,
+                "\nseed: {seed}\n\nstate:\n{:#?}\n\nconfig:\n{}\n",
+                snapshot.state, snapshot.config
+            );
+        }
+        Err(_) => text += "\nstate: unavailable\n",
+    }
+    match RECENT.try_lock() {
+        Ok(recent) => {
+            text += "\nrecent log:\n";
+            for line in recent.iter() {
+                let _ = writeln!(text, "  {line}");
+            }
+        }
+        Err(_) => text += "\nrecent log: unavailable\n",
+    }
+    match history::lines() {
+        Some(lines) => text += &format!("\ntransitions (oldest first):\n{lines}"),
+        None => text += "\ntransitions: unavailable\n",
+    }
+    let _ = write!(text, "\nbacktrace:\n{}", Backtrace::force_capture());
+
+    let dir = paths::data_dir().join(CRASHES_DIR);
+    fs::create_dir_all(&dir)?;
+    let file = dir.join(format!("crash-{}.txt", now.format("%Y%m%d-%H%M%S")));
+    fs::write(&file, text)?;
+    Ok(file)
+}
+
+/// A desktop notification, where `notify-send` is around.
+fn notify(body: &str) {
+    let _ = Command::new("notify-send")
+        .args(["--app-name=tovaras", "tovaras crashed", body])
+        .spawn();
+}
+
+/// Keep the pet's state and the seed current for the report.
+pub fn record_state(pets: Query<&PetState, With<Pet>>, rng: Option<Res<Rng>>) {
+    let Ok(mut snapshot) = SNAPSHOT.lock() else {
+        return;
+    };
+    snapshot.state = pets.get_single().ok().cloned();
+    snapshot.seed = rng.and_then(|rng| rng.seed());
+}
+
+/// Tracing layer keeping the last log lines for the report.
+pub struct RecentLog;
+
+impl<S: Subscriber> Layer<S> for RecentLog {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let meta = event.metadata();
+        let mut line = Line(format!(
+            "{} {:>5} {}:",
+            chrono::Local::now().format("%H:%M:%S%.3f"),
+            meta.level(),
+            meta.target()
+        ));
+        event.record(&mut line);
+        let Ok(mut recent) = RECENT.lock() else {
+            return;
+        };
+        if recent.len() == RECENT_LINES {
+            recent.pop_front();
+        }
+        recent.push_back(line.0);
+    }
+}
+
+/// One log line, as the console shows it.
+struct Line(String);
+
+impl Visit for Line {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        let _ = if field.name() == "message" {
+            write!(self.0, " {value:?}")
+        } else {
+            write!(self.0, " {}={value:?}", field.name())
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{CalendarSource, WebhookConfig};
+
+    #[test]
+    fn reports_leave_out_the_secrets() {
+        let mut config = Config::default();
+        config.twitch.token = "oauth:twitch-secret".into();
+        config.github.token = "ghp_github-secret".into();
+        config.visit.code = "visit-secret".into();
+        config.webhooks.push(WebhookConfig {
+            url: "https://hooks.example/hook-secret".into(),
+            events: Vec::new(),
+        });
+        config.calendar.calendars = vec![
+            CalendarSource {
+                source: "webcal://cal.example/private/calendar-secret/basic.ics".into(),
+                ..default()
+            },
+            CalendarSource {
+                source: "~/work.ics".into(),
+                ..default()
+            },
+        ];
+        let report = format!("{:#?}", redacted(&config));
+        assert!(!report.contains("secret"), "{report}");
+        assert!(report.contains("~/work.ics"));
+    }
+}
@@ -0,0 +1,104 @@
+//! Auto-crop: size the pet window to the visible part of the current clip.
+//!
+//! Frames usually carry a lot of transparent margin, and the window blocks
+//! clicks over all of it. When enabled, every clip's opaque bounding box
+//! (union over its frames) is measured once the sheet is loaded; the window
+//! then takes that box's size and the sprite is shifted so the box fills it.
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::animation::Anim;
+use crate::config::Config;
+use crate::manifest::Manifest;
+use crate::pet_window::PetWindow;
+use crate::{Pet, SCALE};
+
+/// Pixels with at most this alpha count as empty.
+const ALPHA_EMPTY: u8 = 8;
+
+/// Frame-local opaque box per clip, keyed by the clip's first atlas index.
+#[derive(Resource, Default)]
+pub struct Crops {
+    boxes: HashMap<usize, Rect>,
+    frame: Vec2,
+}
+
+impl Crops {
+    /// Measure every clip of `manifest` on a freshly loaded sheet.
+    pub fn measure(&mut self, img: &Image, layout: &TextureAtlasLayout, manifest: &Manifest) {
+        self.boxes.clear();
+        self.frame = manifest.frame_size(img.size());
+        for clip in manifest.clips.values() {
+            let start = manifest.clip_start(clip);
+            let rects = layout.textures.iter().skip(start).take(clip.frames);
+            let Some(bounds) = rects
+                .filter_map(|r| opaque_box(img, *r))
+                .reduce(|a, b| a.union(b))
+            else {
+                continue; // fully transparent clip, keep the whole frame
+            };
+            self.boxes.insert(start, bounds);
+        }
+    }
+}
+
+/// Opaque bounding box of `rect` within the sheet, relative to the rect's corner.
+fn opaque_box(img: &Image, rect: URect) -> Option<Rect> {
+    let w = img.width() as usize;
+    let mut min = UVec2::MAX;
+    let mut max = UVec2::ZERO;
+    for y in rect.min.y..rect.max.y.min(img.height()) {
+        for x in rect.min.x..rect.max.x.min(img.width()) {
+            let alpha = img.data.get((y as usize * w + x as usize) * 4 + 3);
+            if alpha.is_some_and(|&a| a > ALPHA_EMPTY) {
+                min = min.min(UVec2::new(x, y));
+                max = max.max(UVec2::new(x + 1, y + 1));
+            }
+        }
+    }
+    (min.x < max.x)
+        .then(|| Rect::from_corners((min - rect.min).as_vec2(), (max - rect.min).as_vec2()))
+}
+
+/// Resize the window to the current clip's box and recenter the sprite on it.
+pub fn fit_window_to_clip(
+    crops: Res<Crops>,
+    cfg: Res<Config>,
+    mut windows: Query<&mut Window>,
+    mut q: Query<(&Anim, &mut Transform, &PetWindow), With<Pet>>,
+) {
+    let Ok((anim, mut tf, pet_window)) = q.get_single_mut() else {
+        return;
+    };
+    let Ok(mut win) = windows.get_mut(pet_window.0) else {
+        return;
+    };
+    if anim.extent.is_some() {
+        return; // the manifest sizes this clip (see extent.rs)
+    }
+    let Some(bounds) = crops.boxes.get(&anim.start_index) else {
+        return;
+    };
+
+    // box center relative to the frame center, sprite-local (y up, pose flips)
+    let off = bounds.center() - crops.frame / 2.0;
+    let local = Vec2::new(off.x * tf.scale.x.signum(), -off.y * tf.scale.y.signum());
+    let mut world = (tf.rotation * local.extend(0.0)).truncate() * SCALE;
+    if cfg.render.integer_scale {
+        world = world.round();
+    }
+    tf.translation = (-world).extend(tf.translation.z);
+
+    // a quarter turn swaps the box's width and height on screen
+    let turned = (tf.rotation * Vec3::X).y.abs() > 0.5;
+    let size = if turned {
+        Vec2::new(bounds.height(), bounds.width())
+    } else {
+        bounds.size()
+    };
+    let size = (size * SCALE).ceil().max(Vec2::ONE);
+    if win.resolution.width() != size.x || win.resolution.height() != size.y {
+        win.resolution.set(size.x, size.y);
+    }
+}
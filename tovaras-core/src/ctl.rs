@@ -0,0 +1,44 @@
+//! `tovaras ctl`: control the running pet from scripts, makefiles and shell
+//! hooks over the control socket (see `ipc.rs`):
+//!
+//! ```sh
+//! make && tovaras ctl say "build done" || tovaras ctl say "build failed"
+//! tovaras ctl sleep
+//! tovaras ctl goto 500
+//! tovaras ctl timer 25m "tea"
+//! ```
+
+use crate::ipc;
+
+const USAGE: &str = "usage: tovaras ctl <say TEXT | sleep [SECS] | wake | goto X | action ACTION [SECS] | skin [PACK] | timer TIME [LABEL] | state | watch>";
+
+/// `tovaras ctl REQUEST...`; returns the exit code.
+pub fn cli(args: &[String]) -> i32 {
+    let Some(verb) = args.first() else {
+        eprintln!("{USAGE}");
+        return 1;
+    };
+    if verb == "watch" {
+        return match ipc::watch(|update| println!("{update}")) {
+            Ok(()) => 0,
+            Err(e) => {
+                eprintln!("tovaras: {e}");
+                1
+            }
+        };
+    }
+    // one request per line: multi-line text is said on one line
+    let line = args.join(" ").replace(['\n', '\r'], " ");
+    match ipc::forward(&line) {
+        Ok(reply) => {
+            if reply != "ok" {
+                println!("{reply}");
+            }
+            0
+        }
+        Err(e) => {
+            eprintln!("tovaras: {e}");
+            1
+        }
+    }
+}
@@ -0,0 +1,252 @@
+//! `tovaras --debug`: a HUD window beside the pet with what it is doing and
+//! the numbers behind it (surface, action, velocities, jump targets, the RNG
+//! seed, time left on the current case, the bounds it moves in, the update
+//! pace), for "why is my pet floating" questions.
+//!
+//! The jump targets are also marked on the desktop, in the overlay window: a
+//! cross at `target_x` on the floor, a bar at the wall target, and during a
+//! jump the arc the pet will fly, green when it ends at its target and red
+//! when it misses (the planner shortens jumps it can't make, so a red arc is
+//! a planning bug). Markers are where the center of the pet's window will be.
+
+use bevy::prelude::*;
+use bevy::render::view::RenderLayers;
+use bevy::window::WindowResolution;
+use bevy_egui::{egui, EguiContext};
+
+use crate::drivers::{RandomCtrl, TestSeq};
+use crate::invariants::Violations;
+use crate::overlay::{self, Overlay, OVERLAY_LAYER};
+use crate::pet_window::{spawn_owned_window, PetWindow};
+use crate::physics::GRAVITY;
+use crate::powersave::Pace;
+use crate::rng::Rng;
+use crate::{FlightKind, Pet, PetState, ScreenGeometry, Surface};
+
+/// Nothing is drawn on it: the HUD window's camera only clears.
+const HUD_LAYER: usize = 4;
+
+/// The window the HUD is drawn in.
+#[derive(Component)]
+pub struct DebugHud;
+
+/// Steps of the predicted arc, s; matches the usual frame.
+const ARC_STEP: f32 = 1.0 / 60.0;
+/// The arc is cut off after this long.
+const ARC_MAX_SECS: f32 = 5.0;
+/// An arc landing this close to `target_x` reaches it, px.
+const REACH_PX: f32 = 4.0;
+const MARKER: f32 = 8.0;
+
+/// Jump markers, drawn only in the overlay.
+#[derive(Default, Reflect, GizmoConfigGroup)]
+pub struct MarkerGizmos;
+
+pub fn spawn_hud(mut commands: Commands, pets: Query<Entity, With<Pet>>) {
+    let Ok(pet) = pets.get_single() else {
+        return;
+    };
+    let window = Window {
+        title: "tovaras debug".into(),
+        resolution: WindowResolution::new(320., 380.),
+        ..default()
+    };
+    let window = spawn_owned_window(&mut commands, pet, window, RenderLayers::layer(HUD_LAYER));
+    commands.entity(window).insert(DebugHud);
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn show_hud(
+    mut huds: Query<&mut EguiContext, With<DebugHud>>,
+    pets: Query<(&PetState, &PetWindow), With<Pet>>,
+    windows: Query<&Window>,
+    screen: Res<ScreenGeometry>,
+    rng: Option<Res<Rng>>,
+    ctrl: Option<Res<RandomCtrl>>,
+    seq: Option<Res<TestSeq>>,
+    violations: Option<Res<Violations>>,
+    pace: Option<Res<Pace>>,
+) {
+    let Ok(mut hud) = huds.get_single_mut() else {
+        return;
+    };
+    let Ok((st, pet_window)) = pets.get_single() else {
+        return;
+    };
+    let Ok(win) = windows.get(pet_window.0) else {
+        return;
+    };
+    let (fw, fh) = (
+        win.resolution.physical_width() as i32,
+        win.resolution.physical_height() as i32,
+    );
+    let (screen_w, screen_h) = screen.desktop(fw, fh);
+    let next_case = match (&seq, &ctrl) {
+        (Some(seq), _) => format!("{:.2} s (case {})", seq.left, seq.i + 1),
+        (None, Some(ctrl)) => format!("{:.2} s", ctrl.left),
+        (None, None) => "-".into(),
+    };
+    let mut rows = vec![
+        ("surface", format!("{:?}", st.surface)),
+        ("action", format!("{:?}", st.action)),
+        (
+            "idle clip",
+            st.idle_clip.clone().unwrap_or_else(|| "-".into()),
+        ),
+        ("dir", format!("{:+}", st.move_dir)),
+        ("facing", format!("{:+}", st.facing)),
+        (
+            "position",
+            format!("{}, {}", st.window_pos.x, st.window_pos.y),
+        ),
+        ("perch", format!("{} px", st.perch)),
+        (
+            "on surface",
+            format!(
+                "{:.1} s{}",
+                st.surface_secs,
+                if st.corner_clear {
+                    ""
+                } else {
+                    ", by the corner"
+                }
+            ),
+        ),
+        ("speed", format!("{:+.2}", st.speed)),
+        (
+            "flight",
+            match st.flight {
+                FlightKind::None => "-".into(),
+                FlightKind::Parabola => format!("from {:?}", st.flight_from),
+            },
+        ),
+        ("velocity", format!("{:.0}, {:.0} px/s", st.vx, st.vy)),
+        (
+            "landing",
+            format!(
+                "{:.2} s, {:+.0} px off",
+                st.landing_left.max(0.0),
+                st.landing_fix
+            ),
+        ),
+        ("target x", st.target_x.to_string()),
+        (
+            "wall target",
+            st.wall_target
+                .map_or("-".into(), |(wall, y)| format!("{wall:?} at y {y}")),
+        ),
+        ("next case", next_case),
+        (
+            "rng seed",
+            rng.and_then(|rng| rng.seed())
+                .map_or("-".into(), |seed| seed.to_string()),
+        ),
+        ("window", format!("{fw} x {fh}")),
+        (
+            "max x / y",
+            format!("{} / {}", screen_w - fw, screen_h - fh),
+        ),
+    ];
+    if let Some(pace) = pace {
+        rows.push(("pace", format!("{:?}", *pace)));
+    }
+    if let Some(violations) = violations {
+        rows.push(("violations", violations.count.to_string()));
+    }
+    egui::CentralPanel::default().show(hud.get_mut(), |ui| {
+        egui::Grid::new("pet state").striped(true).show(ui, |ui| {
+            for (name, value) in rows {
+                ui.label(name);
+                ui.monospace(value);
+                ui.end_row();
+            }
+        });
+    });
+}
+
+/// Open the overlay for the jump markers and draw them only there.
+pub fn spawn_markers(
+    mut commands: Commands,
+    mut overlay: ResMut<Overlay>,
+    mut gizmos: ResMut<GizmoConfigStore>,
+) {
+    overlay::spawn_overlay(&mut commands, &mut overlay);
+    let (config, _) = gizmos.config_mut::<MarkerGizmos>();
+    config.render_layers = RenderLayers::layer(OVERLAY_LAYER);
+    config.line_width = 2.0;
+}
+
+/// Mark `target_x`, the wall target and the arc of the current jump.
+pub fn draw_markers(
+    mut gizmos: Gizmos<MarkerGizmos>,
+    overlay: Res<Overlay>,
+    screen: Res<ScreenGeometry>,
+    pets: Query<(&PetState, &PetWindow), With<Pet>>,
+    windows: Query<&Window>,
+) {
+    let Ok((st, pet_window)) = pets.get_single() else {
+        return;
+    };
+    let Ok(win) = windows.get(pet_window.0) else {
+        return;
+    };
+    let (fw, fh) = (
+        win.resolution.physical_width() as i32,
+        win.resolution.physical_height() as i32,
+    );
+    let (screen_w, screen_h) = screen.desktop(fw, fh);
+    let (max_x, max_y) = ((screen_w - fw) as f32, (screen_h - fh) as f32);
+    let half = Vec2::new(fw as f32, fh as f32) / 2.0;
+    // window top-left on the desktop -> its center in the overlay
+    let at = |x: f32, y: f32| overlay.to_world(Vec2::new(x, y) + half);
+
+    let floor_target = at(st.target_x as f32, max_y - st.perch as f32);
+    gizmos.line_2d(
+        floor_target - Vec2::splat(MARKER),
+        floor_target + Vec2::splat(MARKER),
+        Color::srgb(1.0, 0.8, 0.2),
+    );
+    gizmos.line_2d(
+        floor_target + Vec2::new(-MARKER, MARKER),
+        floor_target + Vec2::new(MARKER, -MARKER),
+        Color::srgb(1.0, 0.8, 0.2),
+    );
+    if let Some((wall, y)) = st.wall_target {
+        let x = if wall == Surface::LeftWall {
+            0.0
+        } else {
+            max_x
+        };
+        let target = at(x, y as f32);
+        gizmos.line_2d(
+            target - Vec2::X * MARKER * 2.0,
+            target + Vec2::X * MARKER * 2.0,
+            Color::srgb(0.3, 0.7, 1.0),
+        );
+    }
+    if st.flight == FlightKind::None {
+        return;
+    }
+
+    // the motion system's integration, run ahead until the floor
+    let (mut pos, mut vy) = (st.window_pos.as_vec2(), st.vy);
+    let mut arc = vec![at(pos.x, pos.y)];
+    let mut t = 0.0;
+    while t < ARC_MAX_SECS {
+        vy += GRAVITY * ARC_STEP;
+        pos.x = (pos.x + st.vx * ARC_STEP).clamp(0.0, max_x);
+        pos.y = (pos.y + vy * ARC_STEP).clamp(0.0, max_y);
+        arc.push(at(pos.x, pos.y));
+        if pos.y >= max_y - st.perch as f32 && vy >= 0.0 {
+            break;
+        }
+        t += ARC_STEP;
+    }
+    let reaches = (pos.x - st.target_x as f32).abs() <= REACH_PX;
+    let color = if reaches {
+        Color::srgb(0.3, 1.0, 0.4)
+    } else {
+        Color::srgb(1.0, 0.3, 0.3)
+    };
+    gizmos.linestrip_2d(arc, color);
+}
@@ -0,0 +1,64 @@
+//! Live tuning (built with `--features devtools`): `tovaras --debug` also
+//! opens an inspector window where the pet's `PetState` and `Anim` and the
+//! whole `Config` can be edited while it runs. Edits are not saved.
+
+use bevy::prelude::*;
+use bevy::render::view::RenderLayers;
+use bevy::window::WindowResolution;
+use bevy_egui::{egui, EguiContext};
+use bevy_inspector_egui::{bevy_inspector, DefaultInspectorConfigPlugin};
+
+use crate::animation::Anim;
+use crate::config::Config;
+use crate::pet_window::{spawn_owned_window, spawn_pet};
+use crate::{Pet, PetState};
+
+/// Nothing is drawn on it: the inspector window's camera only clears.
+const INSPECTOR_LAYER: usize = 5;
+
+/// The window the inspector is drawn in.
+#[derive(Component)]
+struct Inspector;
+
+/// Register the inspected types and open the inspector with the pet (needs
+/// the `EguiPlugin`).
+pub fn add(app: &mut App) {
+    app.add_plugins(DefaultInspectorConfigPlugin)
+        .register_type::<PetState>()
+        .register_type::<Anim>()
+        .register_type::<Config>()
+        .add_systems(Startup, spawn_inspector.after(spawn_pet))
+        .add_systems(Update, inspector_ui);
+}
+
+fn spawn_inspector(mut commands: Commands, pets: Query<Entity, With<Pet>>) {
+    let Ok(pet) = pets.get_single() else {
+        return;
+    };
+    let window = Window {
+        title: "tovaras inspector".into(),
+        resolution: WindowResolution::new(420., 640.),
+        ..default()
+    };
+    let layers = RenderLayers::layer(INSPECTOR_LAYER);
+    let window = spawn_owned_window(&mut commands, pet, window, layers);
+    commands.entity(window).insert(Inspector);
+}
+
+fn inspector_ui(world: &mut World) {
+    let mut inspectors = world.query_filtered::<&EguiContext, With<Inspector>>();
+    let Ok(ctx) = inspectors.get_single(world) else {
+        return;
+    };
+    // the UI below needs the whole world
+    let mut ctx = ctx.clone();
+    egui::CentralPanel::default().show(ctx.get_mut(), |ui| {
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            ui.heading("Pet");
+            bevy_inspector::ui_for_world_entities_filtered::<With<Pet>>(world, ui, false);
+            ui.separator();
+            ui.heading("Config");
+            bevy_inspector::ui_for_resource::<Config>(world, ui);
+        });
+    });
+}
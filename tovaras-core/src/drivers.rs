@@ -0,0 +1,612 @@
+//! Behavior drivers: what the pet does next. The test driver cycles through
+//! scripted cases (`--test`), the random driver picks actions on its own, and
+//! a mirror follows another machine's pet (`--mirror`).
+//!
+//! Drivers send [`PetCommand`]s (see `commands.rs`) rather than write the
+//! pet's [`PetState`]; the physics plugin carries out what the arbiter lets
+//! through. They hold off while the pet is in the air, and while a one-shot
+//! action they picked still plays its clip (see `commands::await_clip`).
+//!
+//! The random and test drivers run in their own [`DriverTick`] schedule,
+//! [`DRIVER_HZ`] times a second of virtual time whatever the frame rate, so
+//! their countdowns and draws from the RNG are the same on a 60 Hz laptop and
+//! a 240 Hz desktop. It leaves `FixedUpdate` and its timestep to the host app.
+
+use bevy::core::FrameCount;
+use bevy::ecs::schedule::ScheduleLabel;
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::actions::{Actions, Motion, Span};
+use crate::animation::{Anim, SheetInfo};
+use crate::commands::{self, Arbiter, Issued, JumpTarget, PetCommand, Source};
+use crate::config::SocialConfig;
+use crate::input::Petted;
+use crate::manifest::Manifest;
+#[cfg(feature = "websocket")]
+use crate::mirror;
+use crate::pet_window::PetWindow;
+use crate::physics::apply_motion_and_orientation;
+use crate::rng::{PetRng, Rng};
+use crate::social::{self, Social};
+use crate::{scenario, Action, FlightKind, PetState, ScreenGeometry, Surface, START_MARGIN};
+
+/// Decisions per second of the random and test drivers.
+pub const DRIVER_HZ: f64 = 10.0;
+/// Seconds between two decisions.
+const DRIVER_STEP: f32 = (1.0 / DRIVER_HZ) as f32;
+
+/// The schedule the random and test drivers run in, [`DRIVER_HZ`] times a
+/// second (see [`add_driver_tick`]).
+#[derive(ScheduleLabel, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DriverTick;
+
+/// Counts virtual time out in driver steps.
+#[derive(Resource)]
+struct DriverClock(Timer);
+
+/// Run [`DriverTick`] once per driver step of virtual time, before `Update`
+/// so the arbiter sees what the drivers sent in the same frame.
+pub fn add_driver_tick(app: &mut App) -> &mut App {
+    app.init_schedule(DriverTick)
+        .insert_resource(DriverClock(Timer::from_seconds(
+            DRIVER_STEP,
+            TimerMode::Repeating,
+        )))
+        .add_systems(PreUpdate, run_driver_ticks)
+}
+
+/// Catch up with the steps since the last frame (none, one, or more on a
+/// slow frame).
+fn run_driver_ticks(world: &mut World) {
+    let delta = world.resource::<Time<Virtual>>().delta();
+    let mut clock = world.resource_mut::<DriverClock>();
+    let ticks = clock.0.tick(delta).times_finished_this_tick();
+    for _ in 0..ticks {
+        world.run_schedule(DriverTick);
+    }
+}
+
+/// Drives the pet in one of the run modes. Needs [`Manifest`] and [`ScreenGeometry`].
+pub enum DriversPlugin {
+    /// Scripted cases, one after the other; on a keypress or `ctl step` with
+    /// `stepping`.
+    Test {
+        cases: Vec<TestCase>,
+        stepping: bool,
+    },
+    /// Random behavior, with other pets on the desktop when `social` is on.
+    Random { social: SocialConfig },
+    /// Follow the pet served at `host:port`.
+    #[cfg(feature = "websocket")]
+    Mirror(String),
+}
+
+impl Plugin for DriversPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Issued>()
+            .init_resource::<Arbiter>()
+            .add_systems(
+                Update,
+                commands::arbitrate.before(apply_motion_and_orientation),
+            );
+        match self {
+            #[cfg(feature = "websocket")]
+            DriversPlugin::Mirror(source) => {
+                app.insert_resource(mirror::connect(source)).add_systems(
+                    Update,
+                    mirror::follow_primary
+                        .after(commands::arbitrate)
+                        .before(apply_motion_and_orientation),
+                );
+                info!("Running in MIRROR mode, following {source}.");
+            }
+            DriversPlugin::Test { cases, stepping } => {
+                add_driver_tick(app)
+                    .insert_resource(TestSeq::new(cases.clone(), *stepping))
+                    .add_systems(DriverTick, test_driver)
+                    .add_systems(Update, step_on_key);
+                info!("Running in TEST mode (pass --random to switch to random mode).");
+                if *stepping {
+                    info!(
+                        "Press Space or → on the pet (or run `tovaras ctl step`) for the next case."
+                    );
+                }
+            }
+            DriversPlugin::Random { social: cfg } => {
+                // a source the host app brought (e.g. a `Scripted` one) wins
+                if !app.world().contains_resource::<Rng>() {
+                    app.insert_resource(Rng::seeded());
+                }
+                add_driver_tick(app)
+                    .insert_resource(RandomCtrl::default())
+                    .add_systems(DriverTick, random_driver);
+                if cfg.enabled {
+                    app.insert_resource(Social::new())
+                        .add_systems(Update, social::socialize.before(commands::arbitrate));
+                    if cfg.collisions {
+                        app.add_systems(
+                            Update,
+                            social::avoid_collisions
+                                .after(social::socialize)
+                                .before(commands::arbitrate),
+                        );
+                    }
+                    if cfg.flock {
+                        // a click on the pet scatters the flock
+                        app.add_event::<Petted>().add_systems(
+                            Update,
+                            social::flock
+                                .after(social::socialize)
+                                .before(commands::arbitrate),
+                        );
+                    }
+                }
+                info!("Running in RANDOM mode (pass --test to run deterministic test cases).");
+            }
+        }
+    }
+}
+
+// ===== Test sequencer config =====
+pub const CASE_DUR: f32 = 1.5; // seconds per case (paused during Jump/Land)
+
+// === Test driver types ===
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+pub enum JumpPreset {
+    // Floor jump: start %, target % of [0..max_x]
+    FloorPct {
+        start_pct: f32,
+        target_pct: f32,
+    },
+    // Floor -> Wall jump: choose wall, start % on floor, and target Y % on wall height
+    FloorToWall {
+        wall: Surface,
+        start_pct: f32,
+        target_y_pct: f32,
+    },
+    // Wall -> floor jump: target % of [0..max_x], from start Y % of the wall height
+    WallToFloorPct {
+        target_pct: f32,
+        #[serde(default = "scenario::halfway")]
+        start_y_pct: f32,
+    },
+    #[default]
+    None,
+}
+
+#[derive(Clone, Copy)]
+pub struct TestCase {
+    pub surface: Surface,
+    pub action: Action,
+    pub dir: f32, // usually movement sense; for jumps we keep it for facing
+    pub dur: f32,
+    pub preset: JumpPreset,
+}
+
+#[derive(Resource)]
+pub struct TestSeq {
+    pub cases: Vec<TestCase>,
+    pub i: usize,
+    pub left: f32,
+    /// `--step`: cases change on a keypress or `step` command, not a timer.
+    pub stepping: bool,
+    /// A step was asked for and not taken yet.
+    pub advance: bool,
+    /// The case's one-shot action, still playing its clip.
+    pub one_shot: Option<Action>,
+}
+
+impl TestSeq {
+    pub fn new(cases: Vec<TestCase>, stepping: bool) -> Self {
+        Self {
+            cases,
+            i: 0,
+            left: CASE_DUR,
+            stepping,
+            advance: false,
+            one_shot: None,
+        }
+    }
+}
+
+// Random controller
+#[derive(Resource)]
+pub struct RandomCtrl {
+    pub left: f32,
+    /// The picked one-shot action, still playing its clip.
+    pub one_shot: Option<Action>,
+}
+
+impl Default for RandomCtrl {
+    fn default() -> Self {
+        // Longer action durations overall (slower changes)
+        Self {
+            left: 1.2,
+            one_shot: None,
+        }
+    }
+}
+
+// ----------------- TEST MODE DRIVER -----------------
+/// `--step`: Space or → on the pet asks for the next case; one asked for
+/// mid-air is taken after landing.
+pub fn step_on_key(keys: Res<ButtonInput<KeyCode>>, mut seq: ResMut<TestSeq>) {
+    if seq.stepping && keys.any_just_pressed([KeyCode::Space, KeyCode::ArrowRight]) {
+        seq.advance = true;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn test_driver(
+    frame: Res<FrameCount>,
+    mut seq: ResMut<TestSeq>,
+    windows: Query<&Window>,
+    q: Query<(&PetState, &Anim, &PetWindow)>,
+    screen: Res<ScreenGeometry>,
+    sheet: Res<SheetInfo>,
+    actions: Res<Actions>,
+    mut issue: EventWriter<Issued>,
+    mut sent_in: Local<Option<u32>>,
+) {
+    let Ok((st, anim, &PetWindow(win_entity))) = q.get_single() else {
+        return;
+    };
+    let Ok(win) = windows.get(win_entity) else {
+        return;
+    };
+
+    // Pause the sequencer while in air or landing
+    if st.flight != FlightKind::None || matches!(st.action, Action::Jumping | Action::Landing) {
+        return;
+    }
+    // and only count down until the last case is set up (a slow frame runs
+    // this more than once)
+    if *sent_in == Some(frame.0) {
+        if !seq.stepping {
+            seq.left -= DRIVER_STEP;
+        }
+        return;
+    }
+
+    let fw = win.resolution.physical_width() as i32;
+    let fh = win.resolution.physical_height() as i32;
+
+    // If the cell size isn't known yet, wait
+    if sheet.frame_w == 0.0 || sheet.frame_h == 0.0 {
+        return;
+    }
+
+    let next = if seq.stepping {
+        std::mem::take(&mut seq.advance)
+    } else {
+        let TestSeq { one_shot, left, .. } = &mut *seq;
+        if commands::await_clip(one_shot, left, st, anim) {
+            return;
+        }
+        seq.left -= DRIVER_STEP;
+        seq.left <= 0.0
+    };
+    if next {
+        let _span = info_span!("next_case", case = seq.i).entered();
+        seq.i = (seq.i + 1) % seq.cases.len();
+        let case = seq.cases[seq.i];
+        seq.left = case.dur;
+        seq.one_shot = (actions.get(case.action).span == Span::Clip).then_some(case.action);
+        if seq.stepping {
+            info!(
+                "Case {}/{}: {:?} {:?}, dir {}, {:?}",
+                seq.i + 1,
+                seq.cases.len(),
+                case.surface,
+                case.action,
+                case.dir,
+                case.preset
+            );
+        }
+
+        for command in case_commands(st, &screen, fw, fh, case) {
+            issue.send(command.by(Source::Driver));
+        }
+        *sent_in = Some(frame.0);
+    }
+}
+
+// ----------------- RANDOM MODE DRIVER (continuous) -----------------
+#[allow(clippy::too_many_arguments)]
+pub fn random_driver(
+    frame: Res<FrameCount>,
+    mut rnd: ResMut<Rng>,
+    mut ctrl: ResMut<RandomCtrl>,
+    windows: Query<&Window>,
+    q: Query<(&PetState, &Anim, &PetWindow)>,
+    manifest: Res<Manifest>,
+    actions: Res<Actions>,
+    screen: Res<ScreenGeometry>,
+    mut issue: EventWriter<Issued>,
+    mut sent_in: Local<Option<u32>>,
+) {
+    let Ok((st, anim, pet_window)) = q.get_single() else {
+        return;
+    };
+    let Ok(win) = windows.get(pet_window.0) else {
+        return;
+    };
+
+    // Pause while in flight / landing
+    if st.flight != FlightKind::None || matches!(st.action, Action::Jumping | Action::Landing) {
+        return;
+    }
+    // and only count down until the last pick is carried out (a slow frame
+    // runs this more than once)
+    if *sent_in == Some(frame.0) {
+        ctrl.left -= DRIVER_STEP;
+        return;
+    }
+
+    let fw = win.resolution.physical_width() as i32;
+    let fh = win.resolution.physical_height() as i32;
+
+    let RandomCtrl { left, one_shot } = &mut *ctrl;
+    if commands::await_clip(one_shot, left, st, anim) {
+        return;
+    }
+    ctrl.left -= DRIVER_STEP;
+    if ctrl.left > 0.0 {
+        return;
+    }
+
+    let _span = info_span!("pick_case", surface = ?st.surface).entered();
+    // ----- pick next random case respecting rules (slower/less distracting) -----
+    let case = pick_random_case(&mut *rnd.0, st.surface, &actions);
+    ctrl.left = actions.secs(case.action, &manifest, st.surface, rnd.f32());
+    ctrl.one_shot = (actions.get(case.action).span == Span::Clip).then_some(case.action);
+    let clip = (case.action == Action::Idle)
+        .then(|| manifest.pick_idle(rnd.f32()).map(str::to_string))
+        .flatten();
+
+    // Continuous: never reposition. Jumps aim from where the pet is.
+    let command = match case.action {
+        Action::Jumping => PetCommand::Jump {
+            target: random_jump_target(st, &screen, fw, fh, &mut *rnd.0, case.dir),
+        },
+        action => PetCommand::SetAction {
+            action,
+            dir: (actions.get(action).motion == Motion::Walk).then_some(case.dir),
+            clip,
+            secs: None,
+        },
+    };
+    issue.send(command.by(Source::Driver));
+    *sent_in = Some(frame.0);
+}
+
+// Build a random case for the given surface
+fn pick_random_case(rng: &mut dyn PetRng, current_surface: Surface, actions: &Actions) -> TestCase {
+    let action = actions.pick(current_surface, rng.f32());
+
+    let dir = match actions.get(action).motion {
+        // left or right on the floor and ceiling, up or down on walls
+        Motion::Walk => rng.sign(),
+        // wall jumps head away from their wall (see random_jump_target)
+        Motion::Jump if current_surface == Surface::Floor => rng.sign(),
+        _ => 1.0,
+    };
+
+    TestCase {
+        surface: current_surface,
+        action,
+        dir,
+        dur: 1.0,
+        preset: JumpPreset::None,
+    }
+}
+
+// Deterministic test: positions are explicitly set for clarity (teleport OK in TEST mode)
+fn case_commands(
+    st: &PetState,
+    screen: &ScreenGeometry,
+    fw: i32,
+    fh: i32,
+    case: TestCase,
+) -> [PetCommand; 2] {
+    // Bounds helpers
+    let (screen_w, screen_h) = screen.desktop(fw, fh);
+    let max_x = (screen_w - fw).max(0);
+    let max_y = (screen_h - fh).max(0);
+    // wall jumps start from the preset's height
+    let jump_y = match case.preset {
+        JumpPreset::WallToFloorPct { start_y_pct, .. } => {
+            ((max_y as f32) * start_y_pct).round() as i32
+        }
+        _ => max_y / 2,
+    };
+    let jumping = case.action == Action::Jumping;
+    let dir = case.dir;
+
+    // Position window to a reasonable start for each surface/direction
+    let mut pos = st.window_pos;
+    // without a preset a jump hops in place (off a wall: straight down)
+    let mut then = PetCommand::set_action(case.action);
+
+    match case.surface {
+        Surface::Floor => {
+            let y = max_y;
+            match case.preset {
+                JumpPreset::FloorPct {
+                    start_pct,
+                    target_pct,
+                } if jumping => {
+                    let start_x = ((max_x as f32) * start_pct).round() as i32;
+                    let target_x = ((max_x as f32) * target_pct).round() as i32;
+                    pos = IVec2::new(start_x.clamp(0, max_x), y);
+                    then = PetCommand::Jump {
+                        target: JumpTarget::Floor(target_x.clamp(0, max_x)),
+                    };
+                }
+                JumpPreset::FloorToWall {
+                    wall,
+                    start_pct,
+                    target_y_pct,
+                } if jumping => {
+                    let start_x = ((max_x as f32) * start_pct).round() as i32;
+                    pos = IVec2::new(start_x.clamp(0, max_x), y);
+                    let ty = ((max_y as f32) * target_y_pct).round() as i32;
+                    then = PetCommand::Jump {
+                        target: JumpTarget::Wall(wall, ty.clamp(0, max_y)),
+                    };
+                }
+                _ if jumping => pos.y = y,
+                _ => {
+                    let x = if dir >= 0.0 {
+                        START_MARGIN
+                    } else {
+                        max_x - START_MARGIN
+                    };
+                    pos = IVec2::new(x, y);
+                }
+            }
+        }
+        Surface::RightWall | Surface::LeftWall => {
+            let x = if case.surface == Surface::LeftWall {
+                0
+            } else {
+                max_x
+            };
+            let y = if jumping {
+                jump_y
+            } else if dir >= 0.0 {
+                max_y - START_MARGIN
+            } else {
+                START_MARGIN
+            };
+            pos = IVec2::new(x, y.clamp(0, max_y));
+            if let JumpPreset::WallToFloorPct { target_pct, .. } = case.preset {
+                if jumping {
+                    // lands heading away from the wall
+                    then = PetCommand::Jump {
+                        target: JumpTarget::Floor(((max_x as f32) * target_pct).round() as i32),
+                    };
+                }
+            }
+        }
+        Surface::Ceiling => {
+            let y = 0;
+            let x = if dir < 0.0 {
+                max_x - START_MARGIN
+            } else {
+                START_MARGIN
+            };
+            pos = IVec2::new(x.clamp(0, max_x), y);
+        }
+    }
+
+    [
+        PetCommand::Place {
+            surface: case.surface,
+            pos,
+            dir,
+        },
+        then,
+    ]
+}
+
+// Continuous random: where a jump from the pet's current edge goes
+fn random_jump_target(
+    st: &PetState,
+    screen: &ScreenGeometry,
+    fw: i32,
+    fh: i32,
+    rng: &mut dyn PetRng,
+    dir: f32,
+) -> JumpTarget {
+    let (screen_w, screen_h) = screen.desktop(fw, fh);
+    let max_x = (screen_w - fw).max(0);
+    let max_y = (screen_h - fh).max(0);
+    let x = st.window_pos.x.clamp(0, max_x);
+
+    match st.surface {
+        // 50% chance: jump to wall; 50%: jump to floor
+        Surface::Floor if rng.chance(0.5) => {
+            let wall = if rng.chance(0.5) {
+                Surface::LeftWall
+            } else {
+                Surface::RightWall
+            };
+            let target_y = rng.range_i32(
+                (0.10 * (max_y as f32)) as i32,
+                (0.90 * (max_y as f32)) as i32,
+            );
+            JumpTarget::Wall(wall, target_y)
+        }
+        Surface::Floor => {
+            // Floor -> Floor (choose a target relative to current x)
+            let min_dx = (screen_w as f32 * 0.10) as i32;
+            let max_dx = (screen_w as f32 * 0.35) as i32;
+            let dx = rng.range_i32(min_dx, max_dx) * if dir >= 0.0 { 1 } else { -1 };
+            JumpTarget::Floor((x + dx).clamp(0, max_x))
+        }
+        // any floor x; it lands heading away from the wall
+        Surface::RightWall | Surface::LeftWall => JumpTarget::Floor(rng.range_i32(0, max_x)),
+        // no jumps on ceiling
+        Surface::Ceiling => JumpTarget::Floor(x),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{BehaviorConfig, Weights};
+    use crate::rng::Scripted;
+
+    #[test]
+    fn the_lowest_and_highest_rolls_pick_allowed_cases() {
+        let actions = Actions::new(&BehaviorConfig::default(), &Manifest::embedded());
+        for surface in [Surface::Floor, Surface::RightWall, Surface::Ceiling] {
+            for roll in [0.0, 1.0] {
+                let case = pick_random_case(&mut Scripted::rolls(&[roll]), surface, &actions);
+                assert!(
+                    actions.get(case.action).allowed_on(surface),
+                    "{:?} on {surface:?} with roll {roll}",
+                    case.action
+                );
+                assert_eq!(case.dir.abs(), 1.0);
+            }
+        }
+    }
+
+    #[test]
+    fn scripted_rolls_pick_the_action_and_the_way() {
+        let behavior = BehaviorConfig {
+            floor: Weights {
+                walk: 1.0,
+                ..default()
+            },
+            ..default()
+        };
+        let actions = Actions::new(&behavior, &Manifest::embedded());
+        let left = pick_random_case(&mut Scripted::rolls(&[0.5, 0.0]), Surface::Floor, &actions);
+        assert_eq!((left.action, left.dir), (Action::Move, -1.0));
+        let right = pick_random_case(&mut Scripted::rolls(&[0.5, 1.0]), Surface::Floor, &actions);
+        assert_eq!((right.action, right.dir), (Action::Move, 1.0));
+    }
+
+    #[test]
+    fn the_highest_rolls_jump_within_the_desktop() {
+        let screen = ScreenGeometry::new(IVec2::new(1000, 600));
+        let (fw, fh) = (100, 100);
+        let (max_x, max_y) = (1000 - fw, 600 - fh);
+        let mut st = PetState::new(IVec2::new(0, max_y));
+        // to the right wall, as high as it goes
+        let mut rng = Scripted::rolls(&[0.0, 1.0, 1.0]);
+        let target = random_jump_target(&st, &screen, fw, fh, &mut rng, 1.0);
+        let top = (0.9 * max_y as f32) as i32;
+        assert_eq!(target, JumpTarget::Wall(Surface::RightWall, top));
+        // down from the wall, as far right as it goes
+        st.surface = Surface::LeftWall;
+        let mut rng = Scripted::rolls(&[1.0]);
+        let target = random_jump_target(&st, &screen, fw, fh, &mut rng, 1.0);
+        assert_eq!(target, JumpTarget::Floor(max_x));
+    }
+}
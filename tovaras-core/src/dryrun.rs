@@ -0,0 +1,135 @@
+//! `tovaras --dry-run <minutes>`: run random mode headlessly (see
+//! `headless.rs`) and print what the driver decides, when, and where jumps
+//! are aimed, without opening a window. Handy to check `behavior` weights in
+//! config.ron before living with them:
+//!
+//! ```bash
+//! tovaras --dry-run 30 --seed 42
+//! ```
+
+use bevy::prelude::*;
+use std::collections::BTreeMap;
+
+use crate::animation::animate_sprite;
+use crate::commands::arbitrate;
+use crate::config::Config;
+use crate::drivers::{random_driver, DriverTick, RandomCtrl};
+use crate::headless::{flag_value, sim_app, Sim};
+use crate::manifest::Manifest;
+use crate::physics::apply_motion_and_orientation;
+use crate::{Action, Pet, PetState, Surface};
+
+/// Decisions counted per action, for the summary.
+#[derive(Resource, Default)]
+struct Tally(BTreeMap<String, u32>);
+
+/// `m:ss.s`
+fn clock(secs: f32) -> String {
+    format!("{}:{:04.1}", (secs / 60.0) as u32, secs % 60.0)
+}
+
+/// Which way `dir` points on `surface`.
+fn heading(surface: Surface, dir: f32) -> &'static str {
+    match (surface, dir > 0.0) {
+        (Surface::Floor | Surface::Ceiling, true) => "right",
+        (Surface::Floor | Surface::Ceiling, false) => "left",
+        (Surface::LeftWall | Surface::RightWall, true) => "up",
+        (Surface::LeftWall | Surface::RightWall, false) => "down",
+    }
+}
+
+/// Print a line whenever the driver picks its next action (it restarts its
+/// countdown then).
+fn print_decisions(
+    time: Res<Time>,
+    ctrl: Res<RandomCtrl>,
+    pets: Query<&PetState, With<Pet>>,
+    mut tally: ResMut<Tally>,
+    mut left: Local<Option<f32>>,
+) {
+    let decided = left.is_some_and(|before| ctrl.left > before);
+    *left = Some(ctrl.left);
+    let Ok(st) = pets.get_single() else {
+        return;
+    };
+    if !decided {
+        return;
+    }
+    let plan = match (st.action, st.wall_target) {
+        (Action::Jumping, Some((wall, y))) => format!("-> {wall:?} at y {y}"),
+        (Action::Jumping, None) => format!("-> floor at x {}", st.target_x),
+        (Action::Idle, _) => match &st.idle_clip {
+            Some(clip) => format!("{:.1} s, {clip}", ctrl.left),
+            None => format!("{:.1} s", ctrl.left),
+        },
+        _ => format!("{:.1} s", ctrl.left),
+    };
+    let action = format!("{:?}", st.action);
+    println!(
+        "{:>8}  {:<10} {:<14} {:<6} {plan}",
+        clock(time.elapsed_seconds()),
+        format!("{:?}", st.surface),
+        action,
+        // jumps face the way they will land
+        match st.action {
+            Action::Jumping => heading(Surface::Floor, st.move_dir),
+            _ => heading(st.surface, st.move_dir),
+        },
+    );
+    *tally.0.entry(action).or_default() += 1;
+}
+
+/// `tovaras --dry-run <minutes> [--seed N] [--screen WxH]`; returns the exit code.
+pub fn cli(args: &[String], manifest: Manifest, config: Config) -> i32 {
+    let minutes = match flag_value(args, "--dry-run").map(str::parse::<f32>) {
+        Some(Ok(minutes)) if minutes > 0.0 => minutes,
+        _ => {
+            eprintln!("tovaras: --dry-run needs a positive number of minutes");
+            return 2;
+        }
+    };
+    let sim = match Sim::from_args(args, minutes * 60.0) {
+        Ok(sim) => sim,
+        Err(e) => {
+            eprintln!("tovaras: {e}");
+            return 2;
+        }
+    };
+
+    let mut app = sim_app(manifest, config, &sim);
+    app.init_resource::<Tally>()
+        .add_systems(DriverTick, random_driver)
+        .add_systems(
+            Update,
+            (
+                arbitrate,
+                print_decisions,
+                animate_sprite,
+                apply_motion_and_orientation,
+            )
+                .chain(),
+        );
+    println!(
+        "Random mode for {minutes} min on a {}x{} screen, seed {}:",
+        sim.screen.x, sim.screen.y, sim.seed
+    );
+    println!(
+        "{:>8}  {:<10} {:<14} {:<6} plan",
+        "time", "surface", "action", "dir"
+    );
+    for _ in 0..sim.steps() {
+        app.update();
+    }
+    let tally = app
+        .world_mut()
+        .remove_resource::<Tally>()
+        .unwrap_or_default();
+    let total: u32 = tally.0.values().sum();
+    let counts: Vec<String> = tally
+        .0
+        .iter()
+        .map(|(action, n)| format!("{action} {n} ({:.0}%)", 100.0 * *n as f32 / total as f32))
+        .collect();
+    println!("{total} decisions: {}", counts.join(", "));
+    0
+}
@@ -0,0 +1,147 @@
+//! [`TovarasPetPlugin`]: the pet as a plugin any Bevy app can add. The pet
+//! opens its own transparent, always-on-top window, so it roams the desktop
+//! over the host app's windows.
+//!
+//! The host brings `DefaultPlugins` (windows, rendering, input and assets).
+//! The pet's sprite is drawn on the default render layer, so cameras of the
+//! host that look at the world origin with that layer see it too; give them
+//! another layer to keep it out of their view.
+//!
+//! Random mode draws from the [`Rng`](crate::Rng) resource; a host that
+//! inserts its own before adding the plugin (say a
+//! [`Scripted`](crate::Scripted) sequence in a test) gets the pet to make
+//! exactly the choices it scripted.
+//!
+//! The `tovaras` binary is this plugin plus the command line and the extras
+//! (speech, particles, remote control, ...).
+
+use bevy::prelude::*;
+use std::path::{Path, PathBuf};
+
+use crate::actions::Actions;
+use crate::animation::AnimationPlugin;
+use crate::config::{BehaviorConfig, Config};
+use crate::drivers::DriversPlugin;
+use crate::packs::ActivePack;
+use crate::pet_window::WindowingPlugin;
+use crate::physics::PhysicsPlugin;
+use crate::scenario::{self, Case};
+use crate::skins::SkinCatalog;
+
+/// Who decides what the pet does.
+#[derive(Clone, Debug, Default)]
+pub enum PetMode {
+    /// Random actions, weighted by [`TovarasPetPlugin::behavior`].
+    #[default]
+    Random,
+    /// Cycle through the test scenario (see [`TovarasPetPlugin::scenario`]).
+    Test,
+    /// Follow the pet another machine serves at `host:port` (needs the
+    /// "websocket" feature).
+    #[cfg(feature = "websocket")]
+    Mirror(String),
+}
+
+/// A desktop pet for a Bevy app. Built like
+/// `TovarasPetPlugin::new().pack("cat").mode(PetMode::Random)`; without
+/// settings it is the built-in pet with default behavior.
+#[derive(Default)]
+pub struct TovarasPetPlugin {
+    config: Config,
+    sheet: Option<PathBuf>,
+    mode: PetMode,
+    scenario: Option<Vec<Case>>,
+    stepping: bool,
+}
+
+impl TovarasPetPlugin {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start from the user's `config.ron`, like the `tovaras` binary does.
+    pub fn user_config(mut self) -> Self {
+        self.config = Config::load();
+        self
+    }
+
+    /// Settings of the `tovaras` binary, loaded and amended by it.
+    pub(crate) fn config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Installed pet pack to show (see `tovaras packs list`).
+    pub fn pack(mut self, name: impl Into<String>) -> Self {
+        self.config.pack = Some(name.into());
+        self
+    }
+
+    /// Sprite sheet to use instead of the pack's, laid out like it.
+    pub fn sheet(mut self, path: impl Into<PathBuf>) -> Self {
+        self.sheet = Some(path.into());
+        self
+    }
+
+    /// Color variant offered by the pet's manifest (e.g. "black").
+    pub fn variant(mut self, name: impl Into<String>) -> Self {
+        self.config.variant = Some(name.into());
+        self
+    }
+
+    /// How often each action is picked in [`PetMode::Random`].
+    pub fn behavior(mut self, behavior: BehaviorConfig) -> Self {
+        self.config.behavior = behavior;
+        self
+    }
+
+    pub fn mode(mut self, mode: PetMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Cases for [`PetMode::Test`] from a scenario file (the format of
+    /// `assets/scenario.ron`, the built-in tour used otherwise).
+    pub fn scenario(mut self, path: &Path) -> Result<Self, String> {
+        self.scenario = Some(scenario::load(Some(path))?);
+        Ok(self)
+    }
+
+    /// In [`PetMode::Test`], wait for Space or → (or `tovaras ctl step`)
+    /// before each case instead of a timer.
+    pub fn stepping(mut self, stepping: bool) -> Self {
+        self.stepping = stepping;
+        self
+    }
+}
+
+impl Plugin for TovarasPetPlugin {
+    fn build(&self, app: &mut App) {
+        let pack = ActivePack::from_config(&self.config, self.sheet.clone());
+        let manifest = pack.load_manifest();
+        let actions = Actions::new(&self.config.behavior, &manifest);
+        let drivers = match &self.mode {
+            PetMode::Random => DriversPlugin::Random {
+                social: self.config.social.clone(),
+            },
+            PetMode::Test => {
+                let cases = match &self.scenario {
+                    Some(cases) => cases.clone(),
+                    None => scenario::load(None).expect("the built-in scenario is valid"),
+                };
+                DriversPlugin::Test {
+                    cases: scenario::test_cases(&cases, &actions, &manifest),
+                    stepping: self.stepping,
+                }
+            }
+            #[cfg(feature = "websocket")]
+            PetMode::Mirror(source) => DriversPlugin::Mirror(source.clone()),
+        };
+        app.insert_resource(self.config.clone())
+            .insert_resource(manifest)
+            .insert_resource(actions)
+            .insert_resource(pack)
+            .insert_resource(SkinCatalog::load())
+            .add_plugins((AnimationPlugin, WindowingPlugin, PhysicsPlugin, drivers));
+    }
+}
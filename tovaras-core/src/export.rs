@@ -0,0 +1,134 @@
+//! `tovaras export-gif CLIP [OUT.gif]`: render one clip of the configured pet
+//! to an animated GIF with transparency, for pack previews and sharing.
+//!
+//! The clip can be named directly ("walk") or by the floor action that plays
+//! it ("GivingFlowers"). Per-frame durations and loop modes are kept; the
+//! selected color variant and seasonal skin are applied like on the desktop.
+
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{imageops, Delay, Frame, RgbaImage};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use crate::animation::build_sheet;
+use crate::config::Config;
+use crate::manifest::{Clip, LoopMode, Manifest};
+use crate::packs::ActivePack;
+use crate::skins::SkinCatalog;
+use crate::{Action, Surface};
+
+/// GIF delays are in hundredths of a second; most viewers treat less as 0.1 s.
+const MIN_DELAY_MS: u32 = 20;
+
+fn find_clip<'a>(manifest: &'a Manifest, name: &str) -> Option<&'a Clip> {
+    manifest.clips.get(name).or_else(|| {
+        let action = Action::parse(name)?;
+        manifest.pose(Surface::Floor, action)?;
+        Some(manifest.clip_for(Surface::Floor, action))
+    })
+}
+
+/// Frame order of one pass through the clip.
+fn playback_order(clip: &Clip) -> Vec<usize> {
+    let forward: Vec<usize> = (0..clip.frames).collect();
+    match clip.mode {
+        LoopMode::Loop | LoopMode::Once => forward,
+        LoopMode::Reverse | LoopMode::OnceReverse => forward.into_iter().rev().collect(),
+        LoopMode::PingPong => {
+            let back = (1..clip.frames.saturating_sub(1)).rev();
+            forward.iter().copied().chain(back).collect()
+        }
+    }
+}
+
+pub fn export(manifest: &Manifest, clip_name: &str, out: &Path, scale: u32) -> Result<(), String> {
+    let clip = find_clip(manifest, clip_name).ok_or(format!("no clip {clip_name:?}"))?;
+    let (image, error) = build_sheet(&SkinCatalog::load(), manifest);
+    if let Some(error) = error {
+        return Err(error);
+    }
+    let size = image.size();
+    let sheet = RgbaImage::from_raw(size.x, size.y, image.data).ok_or("sheet is not 8-bit RGBA")?;
+    let rects = manifest.layout(size).textures;
+    let start = manifest.clip_start(clip);
+
+    let file = File::create(out).map_err(|e| format!("{}: {e}", out.display()))?;
+    let mut encoder = GifEncoder::new_with_speed(file, 10);
+    encoder
+        .set_repeat(Repeat::Infinite)
+        .map_err(|e| e.to_string())?;
+    for i in playback_order(clip) {
+        let rect = rects.get(start + i).ok_or("clip frames out of range")?;
+        let mut frame =
+            imageops::crop_imm(&sheet, rect.min.x, rect.min.y, rect.width(), rect.height())
+                .to_image();
+        if scale > 1 {
+            frame = imageops::resize(
+                &frame,
+                frame.width() * scale,
+                frame.height() * scale,
+                imageops::FilterType::Nearest,
+            );
+        }
+        let secs = clip
+            .durations
+            .get(i)
+            .copied()
+            .unwrap_or(1.0 / clip.fps.max(0.001));
+        let ms = ((secs * 1000.0).round() as u32).max(MIN_DELAY_MS);
+        encoder
+            .encode_frame(Frame::from_parts(
+                frame,
+                0,
+                0,
+                Delay::from_numer_denom_ms(ms, 1),
+            ))
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// `tovaras export-gif CLIP [OUT.gif] [--scale N] [--pack NAME]`; returns the exit code.
+pub fn cli(args: &[String]) -> i32 {
+    let flag = |name: &str| {
+        args.iter()
+            .position(|a| a == name)
+            .and_then(|i| args.get(i + 1))
+    };
+    let scale = flag("--scale")
+        .and_then(|v| v.parse::<u32>().ok())
+        .unwrap_or(1)
+        .clamp(1, 16);
+    let positional: Vec<&String> = args
+        .iter()
+        .enumerate()
+        .filter(|(i, a)| !a.starts_with("--") && (*i == 0 || !args[i - 1].starts_with("--")))
+        .map(|(_, a)| a)
+        .collect();
+    let Some(clip) = positional.first() else {
+        eprintln!("usage: tovaras export-gif CLIP [OUT.gif] [--scale N] [--pack NAME]");
+        return 1;
+    };
+    let out = positional
+        .get(1)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(format!("{clip}.gif")));
+
+    let config = Config::load();
+    let mut pack = ActivePack::from_config(&config, None);
+    if let Some(name) = flag("--pack") {
+        pack.name = Some(name.clone());
+        pack.sheet_override = None;
+    }
+    let manifest = pack.load_manifest();
+    match export(&manifest, clip, &out, scale) {
+        Ok(()) => {
+            println!("Wrote {}", out.display());
+            0
+        }
+        Err(e) => {
+            eprintln!("tovaras: {e}");
+            1
+        }
+    }
+}
@@ -0,0 +1,73 @@
+//! Per-clip window size: clips can declare the part of the frame they need
+//! (`extent: Some((w, h))`, bottom-centered in the frame), so narrow clips
+//! like walk get a narrow window and wide ones like giving flowers a wide one.
+//!
+//! When the size changes the window grows or shrinks around the pet's feet:
+//! the edge touching the current surface stays put and the window stays
+//! centered along it.
+
+use bevy::prelude::*;
+
+use crate::animation::{Anim, SheetInfo};
+use crate::config::Config;
+use crate::crop::Crops;
+use crate::pet_window::PetWindow;
+use crate::{FlightKind, Pet, PetState, Surface, SCALE};
+
+pub fn fit_window_to_extent(
+    sheet: Res<SheetInfo>,
+    cfg: Res<Config>,
+    crops: Option<Res<Crops>>,
+    mut windows: Query<&mut Window>,
+    mut q: Query<(&Anim, &mut Transform, &mut PetState, &PetWindow), With<Pet>>,
+) {
+    let Ok((anim, mut tf, mut st, pet_window)) = q.get_single_mut() else {
+        return;
+    };
+    let Ok(mut win) = windows.get_mut(pet_window.0) else {
+        return;
+    };
+    if !sheet.ready || (anim.extent.is_none() && crops.is_some()) {
+        return; // auto-crop sizes clips without an extent
+    }
+    let frame = Vec2::new(sheet.frame_w, sheet.frame_h);
+    let extent = anim.extent.unwrap_or(frame).min(frame);
+
+    // extent center relative to the frame center, sprite-local (y up, pose flips)
+    let local = Vec2::new(0.0, (extent.y - frame.y) / 2.0 * tf.scale.y.signum());
+    let mut world = (tf.rotation * local.extend(0.0)).truncate() * SCALE;
+    if cfg.render.integer_scale {
+        world = world.round();
+    }
+    tf.translation = (-world).extend(tf.translation.z);
+
+    // a quarter turn swaps width and height on screen
+    let turned = (tf.rotation * Vec3::X).y.abs() > 0.5;
+    let size = if turned {
+        Vec2::new(extent.y, extent.x)
+    } else {
+        extent
+    };
+    let size = (size * SCALE).round().max(Vec2::ONE);
+    let old = Vec2::new(win.resolution.width(), win.resolution.height());
+    if old == size {
+        return;
+    }
+    win.resolution.set(size.x, size.y);
+
+    // keep the edge on the surface in place (the floor edge while airborne)
+    let surface = if st.flight == FlightKind::None {
+        st.surface
+    } else {
+        Surface::Floor
+    };
+    let grow = (size - old) * win.scale_factor();
+    let shift = match surface {
+        Surface::Floor => Vec2::new(-grow.x / 2.0, -grow.y),
+        Surface::Ceiling => Vec2::new(-grow.x / 2.0, 0.0),
+        Surface::LeftWall => Vec2::new(0.0, -grow.y / 2.0),
+        Surface::RightWall => Vec2::new(-grow.x, -grow.y / 2.0),
+    };
+    let shift = shift.round().as_ivec2();
+    st.window_pos += shift;
+}
@@ -0,0 +1,188 @@
+//! What to show when an installed sheet can't be used: a generated placeholder
+//! pet plus an error message floating above it (drawn in the overlay window),
+//! and a speech bubble saying so when it happens. The placeholder also stands
+//! in while the sheet is loading. The sheet files are re-checked every few
+//! seconds and reloaded as soon as they change, e.g. once a missing `pet.png`
+//! appears.
+
+use bevy::prelude::*;
+use bevy::render::view::RenderLayers;
+use bevy::sprite::Anchor;
+use bevy::text::Text2dBounds;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use crate::animation::SheetInfo;
+use crate::manifest::Manifest;
+use crate::overlay::{self, Overlay, OVERLAY_LAYER};
+use crate::pet_window::PetWindow;
+use crate::skins::SkinCatalog;
+use crate::speech::Say;
+use crate::{atlas, shapes, PetState};
+
+/// Frame size of the generated placeholder grid (same as the built-in sheet).
+const PLACEHOLDER_FRAME: u32 = 128;
+const PLACEHOLDER_COLOR: [u8; 3] = [170, 170, 185];
+const RETRY_SECS: f32 = 2.0;
+const TEXT_WIDTH: f32 = 360.0;
+const ERROR_SAY_SECS: f32 = 8.0;
+
+/// Placeholder sheet laid out like the manifest expects, one blob per frame.
+pub fn placeholder_sheet(manifest: &Manifest) -> Image {
+    if manifest.rects.is_empty() {
+        let f = PLACEHOLDER_FRAME;
+        let (cols, rows) = (manifest.columns as u32, manifest.rows as u32);
+        let blob = shapes::blob(f, f, PLACEHOLDER_COLOR);
+        let mut img = atlas::blank(cols * f, rows * f);
+        for row in 0..rows {
+            for col in 0..cols {
+                atlas::blit(&mut img, &blob, UVec2::new(col * f, row * f));
+            }
+        }
+        img
+    } else {
+        let size = manifest.rects.iter().fold(UVec2::ONE, |m, r| m.max(r.max));
+        let mut img = atlas::blank(size.x, size.y);
+        for rect in &manifest.rects {
+            let blob = shapes::blob(rect.width().max(1), rect.height().max(1), PLACEHOLDER_COLOR);
+            atlas::blit(&mut img, &blob, rect.min);
+        }
+        img
+    }
+}
+
+#[derive(Component)]
+pub struct SheetErrorText;
+
+/// Modification times of the sheet files, to notice when they get fixed.
+#[derive(Resource)]
+pub struct SheetRetry {
+    timer: Timer,
+    stamps: Vec<Option<SystemTime>>,
+}
+
+impl SheetRetry {
+    pub fn new(manifest: &Manifest) -> Self {
+        Self {
+            timer: Timer::from_seconds(RETRY_SECS, TimerMode::Repeating),
+            stamps: stamps(manifest),
+        }
+    }
+}
+
+fn watched(manifest: &Manifest) -> Vec<PathBuf> {
+    manifest
+        .sheet_path()
+        .into_iter()
+        .chain(manifest.extra_sheet_paths())
+        .collect()
+}
+
+fn stamps(manifest: &Manifest) -> Vec<Option<SystemTime>> {
+    watched(manifest)
+        .iter()
+        .map(|p| p.metadata().and_then(|m| m.modified()).ok())
+        .collect()
+}
+
+/// Rebuild the sheet once a broken or missing file has changed on disk.
+pub fn retry_failed_sheet(
+    time: Res<Time>,
+    mut retry: ResMut<SheetRetry>,
+    mut sheet: ResMut<SheetInfo>,
+    catalog: Res<SkinCatalog>,
+    manifest: Res<Manifest>,
+) {
+    if sheet.error.is_none() || sheet.loading() {
+        return;
+    }
+    retry.timer.tick(time.delta());
+    if !retry.timer.just_finished() {
+        return;
+    }
+    let now = stamps(&manifest);
+    if now == retry.stamps {
+        return;
+    }
+    retry.stamps = now;
+    info!("Sheet files changed, loading them again");
+    sheet.start_loading(&catalog, &manifest);
+}
+
+/// Have the pet say so once when its sheet fails to load (again).
+pub fn announce_sheet_error(
+    sheet: Res<SheetInfo>,
+    mut said: Local<Option<String>>,
+    mut say: EventWriter<Say>,
+) {
+    if !sheet.is_changed() || *said == sheet.error {
+        return;
+    }
+    said.clone_from(&sheet.error);
+    if let Some(error) = &sheet.error {
+        let first = error.lines().next().unwrap_or_default();
+        say.send(Say {
+            text: format!("I couldn't load my sheet: {first}"),
+            secs: Some(ERROR_SAY_SECS),
+        });
+    }
+}
+
+/// Keep the error message above the pet while there is one.
+pub fn show_sheet_error(
+    mut commands: Commands,
+    sheet: Res<SheetInfo>,
+    mut overlay: ResMut<Overlay>,
+    windows: Query<&Window>,
+    pets: Query<(&PetState, &PetWindow)>,
+    mut texts: Query<(Entity, &mut Text, &mut Transform), With<SheetErrorText>>,
+) {
+    let Some(error) = &sheet.error else {
+        for (entity, ..) in &texts {
+            commands.entity(entity).despawn();
+        }
+        return;
+    };
+    let message = format!("Could not load the pet sheet:\n{error}");
+
+    let Ok((_, mut text, mut tf)) = texts.get_single_mut() else {
+        overlay::spawn_overlay(&mut commands, &mut overlay);
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(
+                    message,
+                    TextStyle {
+                        font_size: 14.0,
+                        color: Color::srgb(1.0, 0.85, 0.8),
+                        ..default()
+                    },
+                )
+                .with_justify(JustifyText::Center),
+                text_anchor: Anchor::BottomCenter,
+                text_2d_bounds: Text2dBounds {
+                    size: Vec2::new(TEXT_WIDTH, f32::INFINITY),
+                },
+                ..default()
+            },
+            RenderLayers::layer(OVERLAY_LAYER),
+            SheetErrorText,
+        ));
+        return;
+    };
+    if text.sections[0].value != message {
+        text.sections[0].value = message;
+    }
+
+    let Ok((st, pet_window)) = pets.get_single() else {
+        return;
+    };
+    let Ok(win) = windows.get(pet_window.0) else {
+        return;
+    };
+    // just above the top-center of the pet window
+    let screen = Vec2::new(
+        st.window_pos.x as f32 + win.resolution.physical_width() as f32 / 2.0,
+        st.window_pos.y as f32 - 6.0,
+    );
+    tf.translation = overlay.to_world(screen).extend(10.0);
+}
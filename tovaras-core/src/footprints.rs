@@ -0,0 +1,113 @@
+//! Fading footprints left behind while the pet walks along the floor,
+//! drawn in the full-screen overlay window.
+
+use bevy::prelude::*;
+use bevy::render::view::RenderLayers;
+
+use crate::config::Config;
+use crate::overlay::{Overlay, OVERLAY_LAYER};
+use crate::pet_window::PetWindow;
+use crate::{shapes, Action, FlightKind, PetState, Surface};
+
+const PRINT_W: u32 = 6;
+const PRINT_H: u32 = 3;
+const PRINT_ALPHA: f32 = 0.55;
+
+#[derive(Component)]
+pub struct Footprint {
+    age: f32,
+    life: f32,
+}
+
+#[derive(Resource)]
+pub struct FootprintTrail {
+    texture: Handle<Image>,
+    last_x: Option<i32>,
+    left_foot: bool,
+}
+
+impl FootprintTrail {
+    pub fn new(images: &mut Assets<Image>) -> Self {
+        Self {
+            texture: images.add(shapes::soft_oval(PRINT_W, PRINT_H, [70, 50, 40])),
+            last_x: None,
+            left_foot: false,
+        }
+    }
+}
+
+/// Drop a footprint every `spacing` pixels walked on the floor.
+pub fn spawn_footprints(
+    mut commands: Commands,
+    cfg: Res<Config>,
+    overlay: Res<Overlay>,
+    mut trail: ResMut<FootprintTrail>,
+    windows: Query<&Window>,
+    q: Query<(&PetState, &PetWindow)>,
+) {
+    let Ok((st, pet_window)) = q.get_single() else {
+        return;
+    };
+    let Ok(win) = windows.get(pet_window.0) else {
+        return;
+    };
+
+    let walking = st.flight == FlightKind::None
+        && st.surface == Surface::Floor
+        && matches!(st.action, Action::Move | Action::Landing);
+    if !walking {
+        trail.last_x = None;
+        return;
+    }
+
+    let x = st.window_pos.x;
+    let last = *trail.last_x.get_or_insert(x);
+    if ((x - last).abs() as f32) < cfg.footprints.spacing.max(1.0) {
+        return;
+    }
+    trail.last_x = Some(x);
+    trail.left_foot = !trail.left_foot;
+
+    // bottom-center of the pet window, feet alternating a pixel up/down
+    let fw = win.resolution.physical_width() as f32;
+    let fh = win.resolution.physical_height() as f32;
+    let foot = if trail.left_foot { -1.0 } else { 1.0 };
+    let screen = Vec2::new(
+        x as f32 + fw / 2.0,
+        st.window_pos.y as f32 + fh - 2.0 + foot,
+    );
+
+    commands.spawn((
+        SpriteBundle {
+            texture: trail.texture.clone(),
+            sprite: Sprite {
+                color: Color::srgba(1.0, 1.0, 1.0, PRINT_ALPHA),
+                ..default()
+            },
+            transform: Transform::from_translation(overlay.to_world(screen).extend(0.0)),
+            ..default()
+        },
+        RenderLayers::layer(OVERLAY_LAYER),
+        Footprint {
+            age: 0.0,
+            life: cfg.footprints.fade_secs.max(0.1),
+        },
+    ));
+}
+
+pub fn fade_footprints(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut q: Query<(Entity, &mut Footprint, &mut Sprite)>,
+) {
+    for (e, mut fp, mut sprite) in &mut q {
+        fp.age += time.delta_seconds();
+        if fp.age >= fp.life {
+            commands.entity(e).despawn();
+        } else {
+            sprite
+                .color
+                .set_alpha(PRINT_ALPHA * (1.0 - fp.age / fp.life));
+        }
+    }
+}
@@ -0,0 +1,204 @@
+//! `tovaras --fuzz`: headless runs (see `headless.rs`) over random screen and
+//! window geometry: 1px windows, tiny screens, windows as big as the screen or
+//! bigger. Every run checks the headless invariants, among them that the pet
+//! stays within the desktop and never bounces between two surfaces at a corner:
+//!
+//! ```bash
+//! tovaras --fuzz --cases 200 --seed 7
+//! ```
+//!
+//! A failing case is shrunk toward the smallest screen and window that still
+//! fail and printed as the `tovaras --headless` command that replays it.
+//! `cargo test` runs the same check as a proptest, zero-size screens
+//! included.
+
+use bevy::prelude::*;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::Config;
+use crate::headless::{flag_value, simulate, Sim};
+use crate::manifest::Manifest;
+use crate::rng::{PetRng, TinyRng};
+
+/// Simulated seconds per case.
+const CASE_SECS: f32 = 120.0;
+const DEFAULT_CASES: u32 = 50;
+/// Shrinking gives up after this many extra runs.
+const MAX_SHRINK_RUNS: usize = 40;
+
+/// One generated geometry.
+#[derive(Clone, Copy)]
+struct Case {
+    seed: u32,
+    screen: IVec2,
+    window: IVec2,
+}
+
+impl Case {
+    fn generate(rng: &mut TinyRng) -> Self {
+        Self {
+            seed: rng.next_u32(),
+            screen: IVec2::new(side(rng), side(rng)),
+            window: IVec2::new(side(rng), side(rng)),
+        }
+    }
+
+    fn sim(&self) -> Sim {
+        Sim {
+            secs: CASE_SECS,
+            seed: self.seed,
+            screen: self.screen,
+            window: Some(self.window),
+        }
+    }
+
+    /// The first invariant the case breaks, if any.
+    fn run(&self, manifest: &Manifest, config: &Config) -> Option<String> {
+        let report = simulate(manifest.clone(), config.clone(), &self.sim());
+        report.violations.into_iter().next()
+    }
+
+    /// Smaller versions of the case: each side halved, then one px less.
+    fn shrunk(&self) -> Vec<Case> {
+        let mut smaller = Vec::new();
+        for i in 0..4 {
+            let side = self.side(i);
+            for to in [side / 2, side - 1] {
+                if to >= 1 {
+                    let mut case = *self;
+                    *case.side_mut(i) = to;
+                    smaller.push(case);
+                }
+            }
+        }
+        smaller
+    }
+
+    fn side(&self, i: usize) -> i32 {
+        [self.screen.x, self.screen.y, self.window.x, self.window.y][i]
+    }
+
+    fn side_mut(&mut self, i: usize) -> &mut i32 {
+        match i {
+            0 => &mut self.screen.x,
+            1 => &mut self.screen.y,
+            2 => &mut self.window.x,
+            _ => &mut self.window.y,
+        }
+    }
+}
+
+/// A screen or window side in px, from a handful up to far beyond any monitor.
+fn side(rng: &mut TinyRng) -> i32 {
+    match rng.next_u32() % 4 {
+        0 => rng.range_i32(1, 16),
+        1 => rng.range_i32(16, 400),
+        2 => rng.range_i32(400, 4000),
+        _ => rng.range_i32(4000, 16000),
+    }
+}
+
+/// Shrink a failing case as far as it keeps failing; returns it with its failure.
+fn shrink(
+    mut case: Case,
+    mut failure: String,
+    manifest: &Manifest,
+    config: &Config,
+) -> (Case, String) {
+    let mut runs = 0;
+    'smaller: loop {
+        for candidate in case.shrunk() {
+            if runs == MAX_SHRINK_RUNS {
+                break 'smaller;
+            }
+            runs += 1;
+            if let Some(f) = candidate.run(manifest, config) {
+                (case, failure) = (candidate, f);
+                continue 'smaller;
+            }
+        }
+        break;
+    }
+    (case, failure)
+}
+
+/// `tovaras --fuzz [--cases N] [--seed N]`; returns the exit code.
+pub fn cli(args: &[String], manifest: Manifest, config: Config) -> i32 {
+    let cases = match flag_value(args, "--cases").map(str::parse::<u32>) {
+        None => DEFAULT_CASES,
+        Some(Ok(cases)) if cases > 0 => cases,
+        Some(_) => {
+            eprintln!("tovaras: --cases needs a positive whole number");
+            return 2;
+        }
+    };
+    let seed = match flag_value(args, "--seed").map(str::parse::<u32>) {
+        None => SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .subsec_nanos(),
+        Some(Ok(seed)) => seed,
+        Some(Err(_)) => {
+            eprintln!("tovaras: --seed needs a whole number");
+            return 2;
+        }
+    };
+    let mut rng = TinyRng::from_seed(seed);
+    for i in 1..=cases {
+        let case = Case::generate(&mut rng);
+        let Some(failure) = case.run(&manifest, &config) else {
+            continue;
+        };
+        println!(
+            "Case {i}/{cases} failed on a {}x{} screen with a {}x{} window: {failure}",
+            case.screen.x, case.screen.y, case.window.x, case.window.y
+        );
+        let (case, failure) = shrink(case, failure, &manifest, &config);
+        println!(
+            "Smallest failing case: {failure}\n  tovaras --headless --hours {} --seed {} --screen {}x{} --window {}x{}",
+            CASE_SECS / 3600.0,
+            case.seed,
+            case.screen.x,
+            case.screen.y,
+            case.window.x,
+            case.window.y
+        );
+        return 1;
+    }
+    println!("All invariants held in {cases} cases of {CASE_SECS} s, seed {seed}");
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// A side in px, from `min` up to far beyond any monitor (see [`side`]).
+    fn sides(min: i32) -> impl Strategy<Value = i32> {
+        prop_oneof![min..16, 16..400, 400..4000, 4000..16000]
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(32))]
+
+        /// Zero-size screens, 1px windows and windows far bigger than the
+        /// screen: the pet stays within `[0, max_x] × [0, max_y]` and never
+        /// bounces between two surfaces at a corner (both headless
+        /// invariants).
+        #[test]
+        fn any_geometry_keeps_the_invariants(
+            seed in any::<u32>(),
+            (sw, sh) in (sides(0), sides(0)),
+            (ww, wh) in (sides(1), sides(1)),
+        ) {
+            let case = Case {
+                seed,
+                screen: IVec2::new(sw, sh),
+                window: IVec2::new(ww, wh),
+            };
+            let report = simulate(Manifest::embedded(), Config::default(), &case.sim());
+            prop_assert_eq!(report.violation_count, 0, "{:#?}", report.violations);
+        }
+    }
+}
@@ -0,0 +1,337 @@
+//! `tovaras --gallery`: every clip of the active manifest playing side by side
+//! in a normal window, so pack authors can check rows, frame counts, FPS and
+//! the pose mapping before letting the pet loose on the desktop.
+//!
+//! Attachment points are marked on every frame (one color per point name) and
+//! declared window extents are outlined.
+//!
+//! Keys: ←/→ select a clip, ↑/↓ change its FPS, R resets it, Space pauses,
+//! A toggles the point and extent markers.
+
+use bevy::prelude::*;
+use bevy::render::texture::ImageSampler;
+use bevy::sprite::Anchor;
+use bevy::text::Text2dBounds;
+
+use crate::animation::{build_sheet, Anim};
+use crate::config::{Config, Filter};
+use crate::manifest::Manifest;
+use crate::skins::SkinCatalog;
+
+/// Largest side of a frame on screen.
+const CELL: f32 = 160.0;
+const LABEL_H: f32 = 54.0;
+const PAD: f32 = 12.0;
+const FPS_STEP: f32 = 1.0;
+
+const LABEL_COLOR: Color = Color::srgb(0.85, 0.85, 0.85);
+const SELECTED_COLOR: Color = Color::srgb(1.0, 0.8, 0.3);
+const EXTENT_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.35);
+const POINT_COLORS: [Color; 4] = [
+    Color::srgb(1.0, 0.3, 0.3),
+    Color::srgb(0.3, 0.9, 0.4),
+    Color::srgb(0.3, 0.6, 1.0),
+    Color::srgb(1.0, 0.4, 1.0),
+];
+
+/// One previewed clip.
+#[derive(Component)]
+struct Preview {
+    name: String,
+    base_fps: f32,
+    fps: f32,
+    /// Poses that play this clip, "Floor/Move" style.
+    poses: Vec<String>,
+}
+
+impl Preview {
+    fn label(&self, anim: &Anim) -> String {
+        let poses = if self.poses.is_empty() {
+            "no pose".to_string()
+        } else {
+            self.poses.join(", ")
+        };
+        let points = if anim.points.is_empty() {
+            String::new()
+        } else {
+            let names: Vec<&str> = anim.points.keys().map(String::as_str).collect();
+            format!("\npoints: {}", names.join(", "))
+        };
+        format!(
+            "{}  ·  {} frames  ·  {:?}\n{:.1} fps{}\n{poses}{points}",
+            self.name,
+            anim.len,
+            anim.mode,
+            self.fps,
+            if self.fps != self.base_fps {
+                format!(" (manifest {:.1})", self.base_fps)
+            } else {
+                String::new()
+            },
+        )
+    }
+}
+
+#[derive(Component)]
+struct PreviewLabel(Entity);
+
+#[derive(Resource, Default)]
+struct Gallery {
+    selected: usize,
+    paused: bool,
+    hide_points: bool,
+    order: Vec<Entity>,
+    /// Frame size in sheet pixels.
+    frame: Vec2,
+    /// Every point name in the manifest, for stable marker colors.
+    point_names: Vec<String>,
+}
+
+/// Open the gallery window for `manifest`; returns when it is closed.
+pub fn run(manifest: Manifest, config: Config) {
+    App::new()
+        .add_plugins(DefaultPlugins.set(WindowPlugin {
+            primary_window: Some(Window {
+                title: "tovaras gallery".into(),
+                ..default()
+            }),
+            ..default()
+        }))
+        .insert_resource(ClearColor(Color::srgb(0.16, 0.16, 0.18)))
+        .insert_resource(manifest)
+        .insert_resource(config)
+        .init_resource::<Gallery>()
+        .add_systems(Startup, setup)
+        .add_systems(
+            Update,
+            (controls, animate, update_labels, draw_points).chain(),
+        )
+        .run();
+}
+
+fn setup(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut windows: Query<&mut Window>,
+    mut gallery: ResMut<Gallery>,
+    manifest: Res<Manifest>,
+    cfg: Res<Config>,
+) {
+    commands.spawn(Camera2dBundle::default());
+
+    let (mut image, error) = build_sheet(&SkinCatalog::load(), &manifest);
+    if let Some(error) = error {
+        warn!("Sheet unavailable, previewing the placeholder: {error}");
+    }
+    image.sampler = match cfg.render.filter {
+        Filter::Nearest => ImageSampler::nearest(),
+        Filter::Linear => ImageSampler::linear(),
+    };
+    let frame = manifest.frame_size(image.size());
+    gallery.frame = frame;
+    let mut names: Vec<String> = (manifest.clips.values())
+        .flat_map(|c| c.points.keys().cloned())
+        .collect();
+    names.sort();
+    names.dedup();
+    gallery.point_names = names;
+    let layout = layouts.add(manifest.layout(image.size()));
+    let texture = images.add(image);
+
+    let scale = CELL / frame.max_element().max(1.0);
+    let cell = frame * scale + Vec2::splat(PAD);
+    let count = manifest.clips.len().max(1);
+    let columns = (count as f32).sqrt().ceil() as usize;
+    let rows = count.div_ceil(columns);
+    let size = Vec2::new(
+        columns as f32 * cell.x.max(CELL + PAD),
+        rows as f32 * (cell.y + LABEL_H),
+    );
+    if let Ok(mut win) = windows.get_single_mut() {
+        win.resolution.set(size.x, size.y);
+    }
+
+    for (i, (name, clip)) in manifest.clips.iter().enumerate() {
+        let (col, row) = (i % columns, i / columns);
+        let center = Vec2::new(
+            (col as f32 + 0.5) * cell.x.max(CELL + PAD) - size.x / 2.0,
+            size.y / 2.0 - row as f32 * (cell.y + LABEL_H) - PAD / 2.0 - frame.y * scale / 2.0,
+        );
+        let poses = manifest
+            .poses
+            .iter()
+            .filter(|p| p.clip == *name)
+            .map(|p| format!("{:?}/{:?}", p.surface, p.action))
+            .collect();
+        let anim = Anim::new(manifest.clip_start(clip), clip);
+        let sprite = commands
+            .spawn((
+                SpriteBundle {
+                    texture: texture.clone(),
+                    transform: Transform::from_translation(center.extend(0.0))
+                        .with_scale(Vec3::splat(scale)),
+                    ..default()
+                },
+                TextureAtlas {
+                    layout: layout.clone(),
+                    index: anim.first_index(),
+                },
+                Preview {
+                    name: name.clone(),
+                    base_fps: clip.fps,
+                    fps: clip.fps,
+                    poses,
+                },
+                anim,
+            ))
+            .id();
+        gallery.order.push(sprite);
+
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 13.0,
+                        color: LABEL_COLOR,
+                        ..default()
+                    },
+                )
+                .with_justify(JustifyText::Center),
+                text_anchor: Anchor::TopCenter,
+                text_2d_bounds: Text2dBounds {
+                    size: Vec2::new(cell.x.max(CELL + PAD) - PAD, LABEL_H),
+                },
+                transform: Transform::from_translation(
+                    (center - Vec2::new(0.0, frame.y * scale / 2.0 + 4.0)).extend(1.0),
+                ),
+                ..default()
+            },
+            PreviewLabel(sprite),
+        ));
+    }
+}
+
+fn controls(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut gallery: ResMut<Gallery>,
+    mut previews: Query<&mut Preview>,
+) {
+    let count = gallery.order.len();
+    if count == 0 {
+        return;
+    }
+    if keys.just_pressed(KeyCode::ArrowRight) {
+        gallery.selected = (gallery.selected + 1) % count;
+    }
+    if keys.just_pressed(KeyCode::ArrowLeft) {
+        gallery.selected = (gallery.selected + count - 1) % count;
+    }
+    if keys.just_pressed(KeyCode::Space) {
+        gallery.paused = !gallery.paused;
+    }
+    if keys.just_pressed(KeyCode::KeyA) {
+        gallery.hide_points = !gallery.hide_points;
+    }
+    let Ok(mut preview) = previews.get_mut(gallery.order[gallery.selected]) else {
+        return;
+    };
+    if keys.just_pressed(KeyCode::ArrowUp) {
+        preview.fps += FPS_STEP;
+    }
+    if keys.just_pressed(KeyCode::ArrowDown) {
+        preview.fps = (preview.fps - FPS_STEP).max(FPS_STEP);
+    }
+    if keys.just_pressed(KeyCode::KeyR) {
+        preview.fps = preview.base_fps;
+    }
+}
+
+/// Like the pet's animation, sped up or slowed down by the chosen FPS.
+fn animate(
+    time: Res<Time>,
+    gallery: Res<Gallery>,
+    mut q: Query<(&mut TextureAtlas, &mut Anim, &Preview)>,
+) {
+    if gallery.paused {
+        return;
+    }
+    for (mut atlas, mut anim, preview) in &mut q {
+        let speed = preview.fps / preview.base_fps.max(f32::EPSILON);
+        anim.timer.tick(time.delta().mul_f32(speed));
+        if anim.timer.just_finished() && anim.len > 0 {
+            let local = atlas
+                .index
+                .saturating_sub(anim.start_index)
+                .min(anim.len - 1);
+            let (mode, len) = (anim.mode, anim.len);
+            let mut next = mode.next_frame(local, len, &mut anim.backwards);
+            if next == local && len > 1 {
+                // one-shot clips start over instead of holding forever
+                next = mode.first_frame(len);
+            }
+            atlas.index = anim.start_index + next;
+            if let Some(&secs) = anim.durations.get(next) {
+                anim.timer
+                    .set_duration(std::time::Duration::from_secs_f32(secs));
+            }
+        }
+    }
+}
+
+fn update_labels(
+    gallery: Res<Gallery>,
+    previews: Query<(&Preview, &Anim)>,
+    mut labels: Query<(&PreviewLabel, &mut Text)>,
+) {
+    let selected = gallery.order.get(gallery.selected).copied();
+    for (label, mut text) in &mut labels {
+        let Ok((preview, anim)) = previews.get(label.0) else {
+            continue;
+        };
+        let value = preview.label(anim);
+        let color = if Some(label.0) == selected {
+            SELECTED_COLOR
+        } else {
+            LABEL_COLOR
+        };
+        let section = &mut text.sections[0];
+        if section.value != value {
+            section.value = value;
+        }
+        if section.style.color != color {
+            section.style.color = color;
+        }
+    }
+}
+
+/// Mark each clip's attachment points on the frame it currently shows.
+fn draw_points(
+    mut gizmos: Gizmos,
+    gallery: Res<Gallery>,
+    q: Query<(&Anim, &TextureAtlas, &GlobalTransform)>,
+) {
+    if gallery.hide_points {
+        return;
+    }
+    for (anim, atlas, tf) in &q {
+        if let Some(extent) = anim.extent {
+            let center = Vec2::new(0.0, (extent.y - gallery.frame.y) / 2.0);
+            let at = tf.transform_point(center.extend(0.0)).truncate();
+            let scale = tf.compute_transform().scale.truncate();
+            gizmos.rect_2d(at, 0.0, extent * scale, EXTENT_COLOR);
+        }
+        for name in anim.points.keys() {
+            let Some(local) = anim.point(name, atlas.index, gallery.frame) else {
+                continue;
+            };
+            let slot = gallery.point_names.iter().position(|n| n == name);
+            let color = POINT_COLORS[slot.unwrap_or(0) % POINT_COLORS.len()];
+            let at = tf.transform_point(local.extend(0.0)).truncate();
+            gizmos.circle_2d(at, 3.0, color);
+            gizmos.line_2d(at - Vec2::X * 5.0, at + Vec2::X * 5.0, color);
+            gizmos.line_2d(at - Vec2::Y * 5.0, at + Vec2::Y * 5.0, color);
+        }
+    }
+}
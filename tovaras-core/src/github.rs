@@ -0,0 +1,358 @@
+//! GitHub notifications: with a `github` token in config.ron the pet polls
+//! the notifications API and, when a new review request or mention (the
+//! configured `reasons`) comes in, waves (a pack's `wave` action) or hops and
+//! says what it is about.
+//!
+//! A badge on the pet's top-right corner counts the unread ones; clicking it
+//! opens the notifications page and clears it until more come in. Polling
+//! happens on a background thread, no more often than GitHub's
+//! `X-Poll-Interval` allows, and asks only for changes since the last poll.
+
+use bevy::input::mouse::MouseButtonInput;
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+use bevy::render::view::RenderLayers;
+use bevy::sprite::Anchor;
+use bevy::window::{WindowLevel, WindowResolution};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::process;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use crate::commands::{Issued, PetCommand, Source};
+use crate::config::GithubConfig;
+use crate::pet_window::{spawn_owned_window, PetWindow};
+use crate::{shapes, Action, Pet, PetState, ScreenGeometry};
+
+const API_URL: &str = "https://api.github.com/notifications";
+/// Opened by a click on the badge.
+const PAGE_URL: &str = "https://github.com/notifications";
+const TIMEOUT: Duration = Duration::from_secs(20);
+/// Seconds a new notification is shown.
+const SAY_SECS: f32 = 8.0;
+/// Seconds the wave (or hop) holds off the driver.
+const REACT_SECS: f32 = 2.0;
+/// The badge is drawn only by its own window's camera.
+const BADGE_LAYER: usize = 6;
+/// Badge diameter, px.
+const BADGE_SIZE: f32 = 20.0;
+const FONT_SIZE: f32 = 12.0;
+const BADGE_COLOR: [u8; 3] = [214, 48, 49];
+
+/// An unread notification the pet cares about.
+#[derive(Clone, Debug)]
+struct Notice {
+    /// Thread id.
+    id: String,
+    reason: String,
+    title: String,
+}
+
+#[derive(Resource)]
+pub struct Github {
+    updates: Mutex<Receiver<Vec<Notice>>>,
+    /// Unread notifications, as of the last poll.
+    unread: Vec<Notice>,
+    /// Threads already reacted to.
+    seen: HashSet<String>,
+    /// Threads cleared from the badge with a click.
+    dismissed: HashSet<String>,
+}
+
+impl Github {
+    /// Start polling with the token of `cfg` in the background.
+    pub fn start(cfg: &GithubConfig) -> Self {
+        let cfg = cfg.clone();
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let agent = ureq::AgentBuilder::new().timeout(TIMEOUT).build();
+            let poll = Duration::from_secs_f32(cfg.poll_minutes.max(1.0) * 60.0);
+            let mut since = None;
+            loop {
+                let mut wait = poll;
+                match fetch(&agent, &cfg, since.as_deref()) {
+                    Ok(polled) => {
+                        wait = wait.max(polled.interval);
+                        if let Some(notices) = polled.notices {
+                            since = polled.modified;
+                            if tx.send(notices).is_err() {
+                                return;
+                            }
+                        }
+                    }
+                    Err(e) => warn!("GitHub notifications: {e}"),
+                }
+                thread::sleep(wait);
+            }
+        });
+        Self {
+            updates: Mutex::new(rx),
+            unread: Vec::new(),
+            seen: HashSet::new(),
+            dismissed: HashSet::new(),
+        }
+    }
+
+    /// Unread notifications not cleared with a click.
+    fn count(&self) -> usize {
+        self.unread
+            .iter()
+            .filter(|notice| !self.dismissed.contains(&notice.id))
+            .count()
+    }
+}
+
+/// The answer to one poll.
+struct Polled {
+    /// `None` when nothing changed since the last poll.
+    notices: Option<Vec<Notice>>,
+    /// To ask for changes since this poll.
+    modified: Option<String>,
+    /// The least GitHub wants between two polls.
+    interval: Duration,
+}
+
+fn fetch(agent: &ureq::Agent, cfg: &GithubConfig, since: Option<&str>) -> Result<Polled, String> {
+    let mut req = agent
+        .get(API_URL)
+        .set("Accept", "application/vnd.github+json")
+        .set("Authorization", &format!("Bearer {}", cfg.token.trim()))
+        .set("User-Agent", "tovaras")
+        .set("X-GitHub-Api-Version", "2022-11-28");
+    if let Some(since) = since {
+        req = req.set("If-Modified-Since", since);
+    }
+    let resp = req.call().map_err(|e| e.to_string())?;
+    let interval = resp
+        .header("X-Poll-Interval")
+        .and_then(|secs| secs.parse().ok())
+        .map(Duration::from_secs)
+        .unwrap_or_default();
+    let modified = resp.header("Last-Modified").map(str::to_string);
+    if resp.status() == 304 {
+        return Ok(Polled {
+            notices: None,
+            modified,
+            interval,
+        });
+    }
+    let threads: Vec<Value> = resp.into_json().map_err(|e| e.to_string())?;
+    let notices = threads
+        .iter()
+        .filter(|thread| thread["unread"].as_bool().unwrap_or(true))
+        .filter_map(|thread| {
+            let reason = thread["reason"].as_str()?;
+            if !cfg.reasons.iter().any(|r| r == reason) {
+                return None;
+            }
+            Some(Notice {
+                id: thread["id"].as_str()?.to_string(),
+                reason: reason.to_string(),
+                title: thread["subject"]["title"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+            })
+        })
+        .collect();
+    Ok(Polled {
+        notices: Some(notices),
+        modified,
+        interval,
+    })
+}
+
+/// Wave (or hop) and say what is new when notifications come in.
+pub fn react_to_notifications(mut github: ResMut<Github>, mut issue: EventWriter<Issued>) {
+    let github = &mut *github;
+    let Some(unread) = github.updates.get_mut().unwrap().try_iter().last() else {
+        return;
+    };
+    let fresh: Vec<&Notice> = unread
+        .iter()
+        .filter(|notice| !github.seen.contains(&notice.id))
+        .collect();
+    if let [first, ..] = fresh[..] {
+        info!("{} new GitHub notifications", fresh.len());
+        let text = match fresh.len() {
+            1 if first.reason == "review_requested" => format!("Review requested: {}", first.title),
+            1 => format!("Mentioned in: {}", first.title),
+            n => format!("{n} new GitHub notifications"),
+        };
+        let action = Action::parse("wave").unwrap_or(Action::Jumping);
+        issue.send(
+            PetCommand::SetAction {
+                action,
+                dir: None,
+                clip: None,
+                secs: Some(REACT_SECS),
+            }
+            .by(Source::Ambient),
+        );
+        issue.send(
+            PetCommand::Say {
+                text,
+                secs: Some(SAY_SECS),
+            }
+            .by(Source::Ambient),
+        );
+    }
+    let ids: HashSet<String> = unread.iter().map(|notice| notice.id.clone()).collect();
+    github.dismissed.retain(|id| ids.contains(id));
+    github.seen = ids;
+    github.unread = unread;
+}
+
+#[derive(Component)]
+pub struct Badge {
+    window: Entity,
+    shown: usize,
+}
+
+/// Keep the badge on the pet's top-right corner showing the unread count,
+/// and close it when there is none.
+pub fn show_badge(
+    mut commands: Commands,
+    github: Res<Github>,
+    mut images: ResMut<Assets<Image>>,
+    mut badges: Query<(Entity, &mut Badge, &mut Text)>,
+    pets: Query<(&PetState, &PetWindow), With<Pet>>,
+    mut windows: Query<&mut Window>,
+    screen: Res<ScreenGeometry>,
+) {
+    let count = github.count();
+    let badge = badges.get_single_mut().ok();
+    let Some((entity, mut badge, mut text)) = badge else {
+        if count > 0 {
+            spawn_badge(&mut commands, &mut images, count);
+        }
+        return;
+    };
+    if count == 0 {
+        commands.entity(entity).despawn_recursive();
+        return;
+    }
+    if badge.shown != count {
+        badge.shown = count;
+        text.sections[0].value = label(count);
+    }
+    let Ok((st, pet_window)) = pets.get_single() else {
+        return;
+    };
+    let Ok(pet_width) = windows
+        .get(pet_window.0)
+        .map(|win| win.resolution.physical_width() as i32)
+    else {
+        return;
+    };
+    let Ok(mut win) = windows.get_mut(badge.window) else {
+        return;
+    };
+    let half = BADGE_SIZE as i32 / 2;
+    let at =
+        screen.window_at((st.window_pos + IVec2::new(pet_width - half, -half)).max(IVec2::ZERO));
+    if win.position != at {
+        win.position = at;
+    }
+    if !win.visible {
+        win.visible = true;
+    }
+}
+
+fn spawn_badge(commands: &mut Commands, images: &mut Assets<Image>, count: usize) {
+    let mut resolution = WindowResolution::new(BADGE_SIZE, BADGE_SIZE);
+    resolution.set_scale_factor_override(Some(1.0));
+    let window = Window {
+        title: "tovaras-badge".into(),
+        name: Some("tovaras-badge".into()),
+        resolution,
+        resizable: false,
+        decorations: false,
+        transparent: true,
+        focused: false,
+        visible: false, // shown once it is placed
+        window_level: WindowLevel::AlwaysOnTop,
+        ..default()
+    };
+    let layer = RenderLayers::layer(BADGE_LAYER);
+    let badge = commands.spawn_empty().id();
+    let window = spawn_owned_window(commands, badge, window, layer.clone());
+    let size = BADGE_SIZE as u32 * 2;
+    let dot = shapes::mask_image(size, size, BADGE_COLOR, |x, y| {
+        (1.0 - (x * x + y * y).sqrt()) * BADGE_SIZE
+    });
+    commands
+        .entity(badge)
+        .insert((
+            Text2dBundle {
+                text: Text::from_section(
+                    label(count),
+                    TextStyle {
+                        font_size: FONT_SIZE,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ),
+                text_anchor: Anchor::Center,
+                transform: Transform::from_xyz(0.0, 0.0, 1.0),
+                ..default()
+            },
+            layer.clone(),
+            Badge {
+                window,
+                shown: count,
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                SpriteBundle {
+                    texture: images.add(dot),
+                    sprite: Sprite {
+                        custom_size: Some(Vec2::splat(BADGE_SIZE)),
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(0.0, 0.0, -1.0),
+                    ..default()
+                },
+                layer,
+            ));
+        });
+}
+
+fn label(count: usize) -> String {
+    if count > 9 {
+        "9+".into()
+    } else {
+        count.to_string()
+    }
+}
+
+/// A click on the badge opens the notifications page and clears the badge.
+pub fn click_badge(
+    mut clicks: EventReader<MouseButtonInput>,
+    badges: Query<&Badge>,
+    mut github: ResMut<Github>,
+) {
+    let Ok(badge) = badges.get_single() else {
+        clicks.clear();
+        return;
+    };
+    let clicked = clicks.read().any(|click| {
+        click.window == badge.window
+            && click.button == MouseButton::Left
+            && click.state == ButtonState::Pressed
+    });
+    if !clicked {
+        return;
+    }
+    if let Err(e) = process::Command::new("xdg-open").arg(PAGE_URL).spawn() {
+        warn!("Couldn't open {PAGE_URL}: {e}");
+    }
+    let github = &mut *github;
+    github
+        .dismissed
+        .extend(github.unread.iter().map(|notice| notice.id.clone()));
+}
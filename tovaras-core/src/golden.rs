@@ -0,0 +1,166 @@
+//! `tovaras golden [DIR] [--out DIR] [--update]`: visual regression check of
+//! the pose table, whose rotations and flips are easy to break without
+//! noticing. `cargo test` runs it too (`tests/golden.rs`).
+//!
+//! Every (surface, action, dir) the built-in manifest has a pose for is set
+//! up with `set_visual_for` and drawn on the CPU the way the pet window shows
+//! it (the clip's first frame, turned and mirrored by the sprite transform),
+//! at sheet resolution so the renders stay readable, then compared pixel for
+//! pixel with `DIR/<surface>-<action>-<dir>.png` (default `tests/golden`).
+//! Mismatches are saved as `*.actual.png` in the `--out` directory (default
+//! `target/tmp/golden`), out of the tracked goldens; `--update` accepts the
+//! current renders as the new goldens.
+
+use bevy::math::URect;
+use bevy::prelude::*;
+use image::{Rgba, RgbaImage};
+use std::fs;
+use std::path::Path;
+
+use crate::animation::{build_sheet, set_visual_for, Anim};
+use crate::headless::flag_value;
+use crate::manifest::Manifest;
+use crate::skins::SkinCatalog;
+use crate::{Action, Surface, SCALE};
+
+const DEFAULT_DIR: &str = "tests/golden";
+const DEFAULT_OUT: &str = "target/tmp/golden";
+const SURFACES: [Surface; 4] = [
+    Surface::Floor,
+    Surface::RightWall,
+    Surface::Ceiling,
+    Surface::LeftWall,
+];
+const ACTIONS: [Action; 8] = [
+    Action::Idle,
+    Action::Move,
+    Action::Climb,
+    Action::Jumping,
+    Action::Landing,
+    Action::Sleeping,
+    Action::Hiding,
+    Action::GivingFlowers,
+];
+
+/// Draw frame `rect` of `sheet` through the sprite transform `tf` into a
+/// frame-sized image (sprite centered, like in the pet window).
+fn render(sheet: &RgbaImage, rect: URect, tf: &Transform) -> RgbaImage {
+    let frame = rect.size().as_vec2();
+    let size = rect.size();
+    // the pet window's SCALE left out: same pose, at sheet resolution
+    let unscaled = Transform {
+        scale: tf.scale / SCALE,
+        ..*tf
+    };
+    let to_sprite = unscaled.compute_matrix().inverse();
+    RgbaImage::from_fn(size.x, size.y, |x, y| {
+        // pixel center, origin in the middle, y up
+        let window = Vec3::new(
+            x as f32 + 0.5 - size.x as f32 / 2.0,
+            size.y as f32 / 2.0 - (y as f32 + 0.5),
+            0.0,
+        );
+        // rounded so the sines of right angles can't tip a pixel either way
+        let local = (to_sprite.transform_point3(window) * 1000.0).round() / 1000.0;
+        let (fx, fy) = (local.x + frame.x / 2.0, frame.y / 2.0 - local.y);
+        if fx < 0.0 || fy < 0.0 || fx >= frame.x || fy >= frame.y {
+            return Rgba([0, 0, 0, 0]);
+        }
+        *sheet.get_pixel(rect.min.x + fx as u32, rect.min.y + fy as u32)
+    })
+}
+
+/// Renders of every posed (surface, action, dir), named like their goldens.
+fn render_poses() -> Result<Vec<(String, RgbaImage)>, String> {
+    let manifest = Manifest::embedded();
+    let (image, error) = build_sheet(&SkinCatalog::empty(), &manifest);
+    if let Some(error) = error {
+        return Err(error);
+    }
+    let size = image.size();
+    let sheet = RgbaImage::from_raw(size.x, size.y, image.data).ok_or("sheet is not 8-bit RGBA")?;
+    let rects = manifest.layout(size).textures;
+    let fallback = manifest.clip(&manifest.fallback);
+
+    let mut renders = Vec::new();
+    for surface in SURFACES {
+        for action in ACTIONS {
+            if manifest.pose(surface, action).is_none() {
+                continue;
+            }
+            for dir in [1.0, -1.0] {
+                let mut anim = Anim::new(manifest.clip_start(fallback), fallback);
+                let mut atlas = TextureAtlas {
+                    layout: default(),
+                    index: anim.first_index(),
+                };
+                let mut tf = Transform::default();
+                set_visual_for(
+                    &manifest, surface, action, dir, None, &mut anim, &mut atlas, &mut tf,
+                );
+                let rect = *rects.get(atlas.index).ok_or("clip frames out of range")?;
+                let side = if dir > 0.0 { "forward" } else { "backward" };
+                let name = format!("{surface:?}-{action:?}-{side}").to_lowercase();
+                renders.push((name, render(&sheet, rect, &tf)));
+            }
+        }
+    }
+    Ok(renders)
+}
+
+/// Compare (or with `update`, replace) the goldens in `dir`, saving the
+/// renders that differ into `out`; returns the names that didn't match.
+pub fn check_goldens(dir: &Path, out: &Path, update: bool) -> Result<Vec<String>, String> {
+    for dir in [dir, out] {
+        fs::create_dir_all(dir).map_err(|e| format!("{}: {e}", dir.display()))?;
+    }
+    let mut failed = Vec::new();
+    for (name, render) in render_poses()? {
+        let golden = dir.join(format!("{name}.png"));
+        let actual = out.join(format!("{name}.actual.png"));
+        let _ = fs::remove_file(&actual);
+        let matches = image::open(&golden).is_ok_and(|g| g.to_rgba8() == render);
+        if update || !matches {
+            let path = if update { &golden } else { &actual };
+            render
+                .save(path)
+                .map_err(|e| format!("{}: {e}", path.display()))?;
+        }
+        if !update && !matches {
+            failed.push(name);
+        }
+    }
+    Ok(failed)
+}
+
+/// `tovaras golden [DIR] [--out DIR] [--update]`; returns the exit code.
+pub fn cli(args: &[String]) -> i32 {
+    let update = args.iter().any(|a| a == "--update");
+    let out = flag_value(args, "--out").unwrap_or(DEFAULT_OUT);
+    let dir = args
+        .iter()
+        .enumerate()
+        .find(|(i, a)| !a.starts_with("--") && (*i == 0 || args[i - 1] != "--out"))
+        .map_or(DEFAULT_DIR, |(_, a)| a.as_str());
+    match check_goldens(Path::new(dir), Path::new(out), update) {
+        Ok(_) if update => {
+            println!("Updated the goldens in {dir}");
+            0
+        }
+        Ok(failed) if failed.is_empty() => {
+            println!("All poses match the goldens in {dir}");
+            0
+        }
+        Ok(failed) => {
+            println!("{} poses differ from their goldens:", failed.len());
+            for name in &failed {
+                println!("  {name} (see {out}/{name}.actual.png)");
+            }
+            1
+        }
+        Err(e) => {
+            eprintln!("tovaras: {e}");
+            1
+        }
+    }
+}
@@ -0,0 +1,313 @@
+//! `tovaras --headless`: random mode's behavior and physics without a window
+//! or GPU, stepped on a fixed 60 Hz clock as fast as the CPU allows, with
+//! invariants checked after every step:
+//!
+//! ```bash
+//! tovaras --headless --hours 8 --seed 42 --screen 2560x1440
+//! ```
+//!
+//! The pet moves a `Window` component nobody opens, on a [`ScreenGeometry`] of
+//! the given size (default 1920×1080); `--window WxH` replaces the window size
+//! the sheet would give. The run prints a summary and exits with 1 when an
+//! invariant broke, with the time and state it broke at; the same seed replays
+//! the same run. Tests call [`simulate`] directly.
+
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::actions::Actions;
+use crate::animation::{animate_sprite, build_sheet, Anim, FrameEvent};
+use crate::commands::{arbitrate, Arbiter, Issued};
+use crate::config::Config;
+use crate::drivers::{add_driver_tick, random_driver, DriverTick, RandomCtrl};
+use crate::invariants::{broken_frame, broken_invariant};
+use crate::manifest::Manifest;
+use crate::pet_window::PetWindow;
+use crate::physics::apply_motion_and_orientation;
+use crate::rng::Rng;
+use crate::skins::SkinCatalog;
+use crate::{Action, FlightKind, Pet, PetState, ScreenGeometry, Surface, SCALE, START_MARGIN};
+
+/// Simulated seconds per step.
+const STEP: f32 = 1.0 / 60.0;
+/// Violations reported in full; the rest are only counted.
+const MAX_REPORTED: usize = 20;
+/// More turns at the screen corners than this within `CORNER_WINDOW_SECS` is
+/// the pet bouncing between two surfaces.
+const MAX_CORNER_TURNS: usize = 2;
+const CORNER_WINDOW_SECS: f32 = 1.0;
+
+/// What a headless run simulates.
+pub struct Sim {
+    pub secs: f32,
+    pub seed: u32,
+    pub screen: IVec2,
+    /// Window size in px instead of the sheet's frame size.
+    pub window: Option<IVec2>,
+}
+
+impl Sim {
+    /// `secs` of simulation with `--seed N` (default: random), `--screen WxH`
+    /// (default: [`ScreenGeometry::default`]) and `--window WxH` from `args`.
+    pub fn from_args(args: &[String], secs: f32) -> Result<Self, String> {
+        let seed = match flag_value(args, "--seed").map(str::parse::<u32>) {
+            None => SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .subsec_nanos(),
+            Some(Ok(seed)) => seed,
+            Some(Err(_)) => return Err("--seed needs a whole number".into()),
+        };
+        let screen = match flag_value(args, "--screen").map(parse_screen) {
+            None => ScreenGeometry::default().size,
+            Some(Some(size)) => size,
+            Some(None) => return Err("--screen needs a size like 1920x1080".into()),
+        };
+        let window = match flag_value(args, "--window").map(parse_screen) {
+            None => None,
+            Some(Some(size)) => Some(size),
+            Some(None) => return Err("--window needs a size like 64x64".into()),
+        };
+        Ok(Self {
+            secs,
+            seed,
+            screen,
+            window,
+        })
+    }
+
+    /// Steps the run takes.
+    pub fn steps(&self) -> u64 {
+        (self.secs / STEP).ceil() as u64
+    }
+}
+
+/// The word after `flag` in `args` ("" when it is last), if `flag` is there.
+pub fn flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|a| a == flag)
+        .map(|i| args.get(i + 1).map(String::as_str).unwrap_or_default())
+}
+
+/// Outcome of a headless run.
+#[derive(Resource, Default)]
+pub struct Report {
+    pub steps: u64,
+    pub jumps: u32,
+    /// Farthest a floor landing came down from its target, px.
+    pub worst_landing: i32,
+    /// The first violations, with when and where they happened.
+    pub violations: Vec<String>,
+    pub violation_count: u64,
+    /// Seconds in the current flight.
+    airborne: f32,
+    /// Surface at the last step.
+    surface: Option<Surface>,
+    /// When the pet last turned a corner without jumping.
+    corner_turns: VecDeque<f32>,
+}
+
+fn check_invariants(
+    time: Res<Time>,
+    screen: Res<ScreenGeometry>,
+    actions: Res<Actions>,
+    mut report: ResMut<Report>,
+    windows: Query<&Window>,
+    pets: Query<(&PetState, &Anim, &TextureAtlas, &PetWindow), With<Pet>>,
+) {
+    let Ok((st, anim, atlas, pet_window)) = pets.get_single() else {
+        return;
+    };
+    let Ok(win) = windows.get(pet_window.0) else {
+        return;
+    };
+    let (fw, fh) = (
+        win.resolution.physical_width() as i32,
+        win.resolution.physical_height() as i32,
+    );
+    let (screen_w, screen_h) = screen.desktop(fw, fh);
+    report.steps += 1;
+    // landings and wall catches change the surface too, but end a flight
+    let turned = report.surface.is_some_and(|s| s != st.surface) && report.airborne == 0.0;
+    report.surface = Some(st.surface);
+    if st.flight == FlightKind::None {
+        if report.airborne > 0.0 && st.action == Action::Landing {
+            report.worst_landing = report.worst_landing.max(st.landing_fix.abs() as i32);
+        }
+        report.airborne = 0.0;
+    } else {
+        if report.airborne == 0.0 {
+            report.jumps += 1;
+        }
+        report.airborne += time.delta_seconds();
+    }
+    let now = time.elapsed_seconds();
+    if turned {
+        report.corner_turns.push_back(now);
+    }
+    while report
+        .corner_turns
+        .front()
+        .is_some_and(|&t| now - t > CORNER_WINDOW_SECS)
+    {
+        report.corner_turns.pop_front();
+    }
+    let oscillating = report.corner_turns.len() > MAX_CORNER_TURNS;
+    let broken = broken_invariant(st, &actions, screen_w - fw, screen_h - fh, report.airborne)
+        .or_else(|| broken_frame(anim, atlas.index, None));
+    let Some(what) = broken.or(oscillating.then_some("oscillating at a corner")) else {
+        return;
+    };
+    report.violation_count += 1;
+    if report.violations.len() < MAX_REPORTED {
+        report.violations.push(format!(
+            "t={now:.2}s: {what} ({:?} {:?} at {}, dir {}, v ({:.0}, {:.0}))",
+            st.surface, st.action, st.window_pos, st.move_dir, st.vx, st.vy,
+        ));
+    }
+}
+
+/// An app with the pet and random mode's resources on a manual 60 Hz clock,
+/// [`DriverTick`] at the drivers' rate; the caller adds the systems to step.
+pub fn sim_app(manifest: Manifest, config: Config, sim: &Sim) -> App {
+    let (sheet, _) = build_sheet(&SkinCatalog::load(), &manifest);
+    let frame = sim.window.map_or_else(
+        || manifest.frame_size(sheet.size()) * SCALE,
+        |size| size.as_vec2(),
+    );
+    let idle = manifest.clip(&manifest.fallback);
+    let anim = Anim::new(manifest.clip_start(idle), idle);
+    let actions = Actions::new(&config.behavior, &manifest);
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+            STEP,
+        )))
+        .insert_resource(ScreenGeometry::new(sim.screen))
+        .insert_resource(Rng::from_seed(sim.seed))
+        .insert_resource(RandomCtrl::default())
+        .insert_resource(config)
+        .init_resource::<Arbiter>()
+        .add_event::<Issued>()
+        .add_event::<FrameEvent>();
+    add_driver_tick(&mut app);
+    let mut window = Window::default();
+    window.resolution.set(frame.x, frame.y);
+    let window = app.world_mut().spawn(window).id();
+    app.world_mut().spawn((
+        Pet,
+        TextureAtlas {
+            layout: default(),
+            index: anim.first_index(),
+        },
+        anim,
+        Transform::from_scale(Vec3::splat(SCALE)),
+        PetState::new(IVec2::new(START_MARGIN, 0)),
+        PetWindow(window),
+    ));
+    app.insert_resource(actions);
+    app.insert_resource(manifest);
+    app.finish();
+    app.cleanup();
+    app
+}
+
+/// Run `sim` in random mode, checking the invariants after every step.
+pub fn simulate(manifest: Manifest, config: Config, sim: &Sim) -> Report {
+    let mut app = sim_app(manifest, config, sim);
+    app.insert_resource(Report::default())
+        .add_systems(DriverTick, random_driver)
+        .add_systems(
+            Update,
+            (
+                arbitrate,
+                animate_sprite,
+                apply_motion_and_orientation,
+                check_invariants,
+            )
+                .chain(),
+        );
+    for _ in 0..sim.steps() {
+        app.update();
+    }
+    app.world_mut()
+        .remove_resource::<Report>()
+        .unwrap_or_default()
+}
+
+/// A `WxH` size with both sides positive.
+pub fn parse_screen(text: &str) -> Option<IVec2> {
+    let (w, h) = text.split_once('x')?;
+    let size = IVec2::new(w.parse().ok()?, h.parse().ok()?);
+    (size.min_element() > 0).then_some(size)
+}
+
+/// `tovaras --headless [--hours H] [--seed N] [--screen WxH] [--window WxH]`;
+/// returns the exit code.
+pub fn cli(args: &[String], manifest: Manifest, config: Config) -> i32 {
+    let hours = match flag_value(args, "--hours").map(str::parse::<f32>) {
+        None => 1.0,
+        Some(Ok(hours)) if hours > 0.0 => hours,
+        Some(_) => {
+            eprintln!("tovaras: --hours needs a positive number");
+            return 2;
+        }
+    };
+    let sim = match Sim::from_args(args, hours * 3600.0) {
+        Ok(sim) => sim,
+        Err(e) => {
+            eprintln!("tovaras: {e}");
+            return 2;
+        }
+    };
+    let (seed, screen) = (sim.seed, sim.screen);
+    let started = Instant::now();
+    let report = simulate(manifest, config, &sim);
+    println!(
+        "Simulated {hours} h on a {}x{} screen ({} steps, {} jumps) in {:.1} s, seed {seed}",
+        screen.x,
+        screen.y,
+        report.steps,
+        report.jumps,
+        started.elapsed().as_secs_f32(),
+    );
+    println!(
+        "Floor landings came down within {} px of their targets",
+        report.worst_landing
+    );
+    if report.violation_count == 0 {
+        println!("All invariants held");
+        return 0;
+    }
+    println!("{} invariant violations:", report.violation_count);
+    for line in &report.violations {
+        println!("  {line}");
+    }
+    let more = report.violation_count - report.violations.len() as u64;
+    if more > 0 {
+        println!("  ... and {more} more");
+    }
+    1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hours_of_random_mode_keep_the_invariants() {
+        let sim = Sim {
+            secs: 3.0 * 3600.0,
+            seed: 42,
+            screen: IVec2::new(1920, 1080),
+            window: None,
+        };
+        let report = simulate(Manifest::embedded(), Config::default(), &sim);
+        assert_eq!(report.steps, sim.steps());
+        assert!(report.jumps > 0, "the pet never jumped");
+        assert_eq!(report.violation_count, 0, "{:#?}", report.violations);
+    }
+}
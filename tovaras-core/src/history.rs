@@ -0,0 +1,108 @@
+//! The last state transitions, with when and where they happened, for
+//! "the pet froze mid-climb" reports: `tovaras ctl history` prints them as
+//! a JSON array (oldest first) and crash reports include them.
+//!
+//! ```json
+//! [{"at":"2026-10-16 14:02:11.250","from":{"surface":"Floor","action":"Move"},
+//!   "to":{"surface":"Floor","action":"Jumping"},"flight":"Parabola",
+//!   "x":812,"y":1004,"dir":1.0,"vx":310.5,"vy":-420.0}]
+//! ```
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::sync::Mutex;
+
+use crate::{Action, FlightKind, PetState, Surface};
+
+/// Transitions kept.
+const LEN: usize = 200;
+
+#[derive(Clone, Copy, Serialize)]
+struct Pose {
+    surface: Surface,
+    action: Action,
+}
+
+#[derive(Serialize)]
+struct Transition {
+    /// Local wall-clock time.
+    at: String,
+    from: Pose,
+    to: Pose,
+    /// Flight after the transition ("None" on a surface).
+    flight: String,
+    /// Window top-left, screen px.
+    x: i32,
+    y: i32,
+    dir: f32,
+    vx: f32,
+    vy: f32,
+}
+
+/// Read by the control socket's threads and the panic hook, hence a static.
+static HISTORY: Mutex<VecDeque<Transition>> = Mutex::new(VecDeque::new());
+
+/// Note that the pet went from `surface`/`action` to what `st` says.
+pub fn record(surface: Surface, action: Action, st: &PetState) {
+    let transition = Transition {
+        at: chrono::Local::now()
+            .format("%Y-%m-%d %H:%M:%S%.3f")
+            .to_string(),
+        from: Pose { surface, action },
+        to: Pose {
+            surface: st.surface,
+            action: st.action,
+        },
+        flight: match st.flight {
+            FlightKind::None => "None".into(),
+            FlightKind::Parabola => "Parabola".into(),
+        },
+        x: st.window_pos.x,
+        y: st.window_pos.y,
+        dir: st.move_dir,
+        vx: st.vx,
+        vy: st.vy,
+    };
+    let Ok(mut history) = HISTORY.lock() else {
+        return;
+    };
+    if history.len() == LEN {
+        history.pop_front();
+    }
+    history.push_back(transition);
+}
+
+/// The history as a JSON array on one line.
+pub fn json() -> String {
+    let Ok(history) = HISTORY.lock() else {
+        return "[]".into();
+    };
+    let entries: Vec<&Transition> = history.iter().collect();
+    serde_json::to_string(&entries).unwrap_or_else(|_| "[]".into())
+}
+
+/// One line per transition, for crash reports; `None` while the history is
+/// locked (the panic may have hit while recording).
+pub fn lines() -> Option<String> {
+    let history = HISTORY.try_lock().ok()?;
+    let mut out = String::new();
+    for t in history.iter() {
+        let _ = writeln!(
+            out,
+            "  {} {:?}/{:?} -> {:?}/{:?} at ({}, {}) dir {} flight {} v ({:.0}, {:.0})",
+            t.at,
+            t.from.surface,
+            t.from.action,
+            t.to.surface,
+            t.to.action,
+            t.x,
+            t.y,
+            t.dir,
+            t.flight,
+            t.vx,
+            t.vy
+        );
+    }
+    Some(out)
+}
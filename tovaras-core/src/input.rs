@@ -0,0 +1,92 @@
+//! Mouse interaction with the pet window.
+//!
+//! An idle pet looks toward the cursor. winit only reports the cursor over
+//! our own windows, so that is where it was last seen on one of them, for
+//! `GAZE_SECS` after.
+
+use bevy::input::mouse::MouseButtonInput;
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+
+use crate::pet_window::PetWindow;
+use crate::{Action, FlightKind, Pet, PetState, ScreenGeometry, Surface};
+
+/// Seconds an idle pet keeps looking at where the cursor last was.
+const GAZE_SECS: f32 = 4.0;
+
+/// The user clicked (petted) the pet.
+#[derive(Event)]
+pub struct Petted;
+
+/// A left click inside a pet's window counts as petting.
+pub fn detect_petting(
+    mut clicks: EventReader<MouseButtonInput>,
+    pets: Query<&PetWindow, With<Pet>>,
+    mut petted: EventWriter<Petted>,
+) {
+    for click in clicks.read() {
+        if pets.iter().any(|w| w.0 == click.window)
+            && click.button == MouseButton::Left
+            && click.state == ButtonState::Pressed
+        {
+            petted.send(Petted);
+        }
+    }
+}
+
+/// Where the cursor was last seen on the desktop (px), and when (real time).
+#[derive(Resource, Default)]
+pub struct Cursor {
+    at: Option<(IVec2, f32)>,
+}
+
+/// Note where the cursor moves over any of our windows.
+pub fn track_cursor(
+    real: Res<Time<Real>>,
+    mut moves: EventReader<CursorMoved>,
+    windows: Query<&Window>,
+    mut cursor: ResMut<Cursor>,
+) {
+    for moved in moves.read() {
+        let Ok(win) = windows.get(moved.window) else {
+            continue;
+        };
+        let WindowPosition::At(origin) = win.position else {
+            continue;
+        };
+        let px = (moved.position * win.scale_factor()).as_ivec2();
+        cursor.at = Some((origin + px, real.elapsed_seconds()));
+    }
+}
+
+/// Turn a pet idling on the floor toward the cursor while it was seen lately.
+pub fn face_cursor(
+    real: Res<Time<Real>>,
+    cursor: Res<Cursor>,
+    screen: Res<ScreenGeometry>,
+    windows: Query<&Window>,
+    mut pets: Query<(&mut PetState, &PetWindow), With<Pet>>,
+) {
+    let Some((at, seen)) = cursor.at else {
+        return;
+    };
+    if real.elapsed_seconds() - seen > GAZE_SECS {
+        return;
+    }
+    for (mut st, pet_window) in &mut pets {
+        if st.action != Action::Idle
+            || st.surface != Surface::Floor
+            || st.flight != FlightKind::None
+        {
+            continue;
+        }
+        let Ok(win) = windows.get(pet_window.0) else {
+            continue;
+        };
+        let center = screen.origin.x + st.window_pos.x + win.resolution.physical_width() as i32 / 2;
+        let dx = at.x - center;
+        if dx != 0 {
+            st.facing = dx.signum() as f32;
+        }
+    }
+}
@@ -0,0 +1,152 @@
+//! What must hold for the pet after every frame, checked by `--headless` runs
+//! and, with `tovaras --assert`, by the live pet:
+//!
+//! ```bash
+//! tovaras --assert
+//! ```
+//!
+//! A broken invariant is logged as an error with the pet's state when it
+//! starts (not on every frame it lasts) and counted; `--debug` shows the count
+//! in its HUD. Glitches that just look odd on the desktop (a pet hanging off a
+//! wall, walking on the ceiling, showing a frame of another clip) get a line
+//! in the log saying which state machine step went wrong.
+
+use bevy::prelude::*;
+
+use crate::actions::Actions;
+use crate::animation::Anim;
+use crate::pet_window::PetWindow;
+use crate::physics::segments::Segments;
+use crate::{FlightKind, Pet, PetState, ScreenGeometry};
+
+/// Airborne longer than this is a flight that never lands.
+pub const MAX_FLIGHT_SECS: f32 = 10.0;
+
+/// The first invariant `st` breaks on a desktop where the window's top-left
+/// ranges over `0..=max_x` × `0..=max_y`.
+pub fn broken_invariant(
+    st: &PetState,
+    actions: &Actions,
+    max_x: i32,
+    max_y: i32,
+    airborne: f32,
+) -> Option<&'static str> {
+    let pos = st.window_pos;
+    if ![st.vx, st.vy, st.speed, st.speed_v, st.squash]
+        .iter()
+        .all(|v| v.is_finite())
+    {
+        return Some("motion is not finite");
+    }
+    if st.move_dir.abs() != 1.0 {
+        return Some("direction is not ±1");
+    }
+    if st.facing.abs() != 1.0 {
+        return Some("facing is not ±1");
+    }
+    if !(0..=max_x).contains(&pos.x) || !(0..=max_y).contains(&pos.y) {
+        return Some("off the desktop");
+    }
+    if airborne > MAX_FLIGHT_SECS {
+        return Some("stuck in the air");
+    }
+    if st.flight != FlightKind::None {
+        // an arc planned to stay on screen peaks below the top
+        return (pos.y == 0 && st.vy < 0.0).then_some("flew into the top of the desktop");
+    }
+    let attached = Segments::desktop(max_x, max_y)
+        .under(st.surface, pos)
+        .is_some_and(|seg| seg.holds(pos, st.perch));
+    if !attached {
+        return Some("detached from its surface");
+    }
+    if !actions.get(st.action).allowed_on(st.surface) {
+        return Some("action not allowed on its surface");
+    }
+    None
+}
+
+/// The shown frame is not one of the playing clip's (`frames` in the atlas).
+pub fn broken_frame(anim: &Anim, index: usize, frames: Option<usize>) -> Option<&'static str> {
+    if frames.is_some_and(|frames| index >= frames) {
+        return Some("frame outside the sheet");
+    }
+    if anim.len == 0 || !(anim.start_index..anim.start_index + anim.len).contains(&index) {
+        return Some("frame outside its clip");
+    }
+    None
+}
+
+/// Broken invariants of the live pet.
+#[derive(Resource, Default)]
+pub struct Violations {
+    pub count: u64,
+    /// The one broken last frame.
+    current: Option<&'static str>,
+    /// Seconds in the current flight.
+    airborne: f32,
+}
+
+/// Check the invariants once the frame's motion is done; log each violation
+/// as it starts.
+pub fn assert_invariants(
+    time: Res<Time>,
+    screen: Res<ScreenGeometry>,
+    layouts: Res<Assets<TextureAtlasLayout>>,
+    actions: Res<Actions>,
+    mut violations: ResMut<Violations>,
+    windows: Query<&Window>,
+    pets: Query<(&PetState, &Anim, &TextureAtlas, &PetWindow), With<Pet>>,
+) {
+    let Ok((st, anim, atlas, pet_window)) = pets.get_single() else {
+        return;
+    };
+    let Ok(win) = windows.get(pet_window.0) else {
+        return;
+    };
+    let (fw, fh) = (
+        win.resolution.physical_width() as i32,
+        win.resolution.physical_height() as i32,
+    );
+    let (screen_w, screen_h) = screen.desktop(fw, fh);
+    if st.flight == FlightKind::None {
+        violations.airborne = 0.0;
+    } else {
+        violations.airborne += time.delta_seconds();
+    }
+    // the anchor may shift the window a little, but never off the desktop
+    let off_desktop = match win.position {
+        WindowPosition::At(p) => {
+            let p = p - screen.origin;
+            p.x + fw <= 0 || p.y + fh <= 0 || p.x >= screen_w || p.y >= screen_h
+        }
+        _ => false,
+    };
+    let frames = layouts.get(&atlas.layout).map(|l| l.textures.len());
+    let broken = broken_invariant(
+        st,
+        &actions,
+        screen_w - fw,
+        screen_h - fh,
+        violations.airborne,
+    )
+    .or(off_desktop.then_some("window off the desktop"))
+    .or_else(|| broken_frame(anim, atlas.index, frames));
+    if let Some(what) = broken.filter(|&what| violations.current != Some(what)) {
+        violations.count += 1;
+        error!(
+            "Invariant broken: {what} ({:?} {:?} at {}, dir {}, facing {}, v ({:.0}, {:.0}), frame {} of {}..{})",
+            st.surface,
+            st.action,
+            st.window_pos,
+            st.move_dir,
+            st.facing,
+            st.vx,
+            st.vy,
+            atlas.index,
+            anim.start_index,
+            anim.start_index + anim.len,
+        );
+    }
+    violations.current = broken;
+}
@@ -0,0 +1,269 @@
+//! Line-based control socket: `$XDG_RUNTIME_DIR/tovaras/ctl.sock`.
+//!
+//! The first running pet listens; `tovaras ctl` and single-instance
+//! forwarding connect to it. Each request is one line, answered with `ok`,
+//! `error <reason>` or a JSON line:
+//!
+//! ```text
+//! say <text>              say something for a few seconds
+//! sleep [secs]            fall asleep where it is (default 60 s)
+//! wake                    back to its usual self
+//! goto <x>                walk to screen x
+//! action <Action> [secs]  play any action, e.g. `action GivingFlowers`
+//! skin [pack]             change into an installed pack (none = built-in)
+//! step                    next case (test mode with --step)
+//! timer <time> [label]    announce label in e.g. 90s, 25m or 1h30m
+//! hello                   a second launch saying hi (single instance)
+//! state                   current state as JSON
+//! history                 the last state transitions as a JSON array
+//! subscribe               state updates as JSON lines until disconnected
+//! ping                    pong
+//! ```
+//!
+//! Unix only for now; elsewhere the pet simply runs without a socket.
+
+use std::path::PathBuf;
+
+use crate::paths;
+use crate::remote::RemoteCommand;
+use crate::{timers, Action};
+
+const SOCKET_FILE: &str = "ctl.sock";
+const SLEEP_SECS: f32 = 60.0;
+const HELLO: &str = "I'm already here!";
+
+pub fn socket_path() -> PathBuf {
+    paths::runtime_dir().join(SOCKET_FILE)
+}
+
+/// What a request line asks for.
+enum Request {
+    Command(RemoteCommand),
+    State,
+    History,
+    Subscribe,
+    Ping,
+}
+
+fn parse(line: &str) -> Result<Request, String> {
+    let line = line.trim();
+    let (verb, rest) = line.split_once(' ').unwrap_or((line, ""));
+    let rest = rest.trim();
+    let secs = |s: &str| -> Result<Option<f32>, String> {
+        if s.is_empty() {
+            return Ok(None);
+        }
+        s.parse()
+            .map(Some)
+            .map_err(|_| format!("not a number: {s:?}"))
+    };
+    let command = match verb {
+        "say" if !rest.is_empty() => RemoteCommand::Say {
+            text: rest.to_string(),
+            secs: None,
+        },
+        "sleep" => RemoteCommand::SetAction {
+            action: Action::Sleeping,
+            secs: secs(rest)?.or(Some(SLEEP_SECS)),
+        },
+        "wake" => RemoteCommand::SetAction {
+            action: Action::Idle,
+            secs: Some(0.0),
+        },
+        "goto" => RemoteCommand::Goto {
+            x: rest
+                .parse()
+                .map_err(|_| format!("not a screen x: {rest:?}"))?,
+        },
+        "action" => {
+            let (name, time) = rest.split_once(' ').unwrap_or((rest, ""));
+            RemoteCommand::SetAction {
+                action: Action::parse(name).ok_or_else(|| format!("unknown action {name:?}"))?,
+                secs: secs(time.trim())?,
+            }
+        }
+        "skin" => RemoteCommand::Skin {
+            pack: (!rest.is_empty()).then(|| rest.to_string()),
+        },
+        "step" => RemoteCommand::Step,
+        "timer" => {
+            let (time, label) = rest.split_once(' ').unwrap_or((rest, ""));
+            RemoteCommand::Timer {
+                secs: timers::parse_duration(time)
+                    .ok_or_else(|| format!("not a duration of up to a week: {time:?}"))?,
+                label: (!label.trim().is_empty()).then(|| label.trim().to_string()),
+            }
+        }
+        "hello" => RemoteCommand::Say {
+            text: HELLO.into(),
+            secs: None,
+        },
+        "state" => return Ok(Request::State),
+        "history" => return Ok(Request::History),
+        "subscribe" => return Ok(Request::Subscribe),
+        "ping" => return Ok(Request::Ping),
+        "" => return Err("empty request".into()),
+        _ => return Err(format!("unknown request {line:?}")),
+    };
+    Ok(Request::Command(command))
+}
+
+#[cfg(unix)]
+pub use unix::{forward, start, watch};
+
+#[cfg(unix)]
+mod unix {
+    use bevy::prelude::*;
+    use std::fs;
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::PathBuf;
+    use std::sync::mpsc::Sender;
+    use std::thread;
+    use std::time::Duration;
+
+    use super::{parse, socket_path, Request};
+    use crate::history;
+    use crate::remote::{subscribe, Listeners, Remote, RemoteCommand};
+
+    /// How long `state` waits for the pet to report.
+    const STATE_TIMEOUT: Duration = Duration::from_secs(2);
+
+    /// The socket we listen on; removed when the pet exits.
+    #[derive(Resource)]
+    pub struct Ipc {
+        path: PathBuf,
+    }
+
+    impl Drop for Ipc {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+
+    /// Listen on the control socket unless another pet already does.
+    pub fn start(remote: &Remote) -> Option<Ipc> {
+        let path = socket_path();
+        if UnixStream::connect(&path).is_ok() {
+            info!("Another pet owns {}; not listening", path.display());
+            return None;
+        }
+        // left behind by a pet that crashed
+        let _ = fs::remove_file(&path);
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        let listener = match UnixListener::bind(&path) {
+            Ok(listener) => listener,
+            Err(e) => {
+                warn!("Can't listen on {}: {e}", path.display());
+                return None;
+            }
+        };
+        let commands = remote.sender();
+        let listeners = remote.listeners();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let (commands, listeners) = (commands.clone(), listeners.clone());
+                thread::spawn(move || serve(stream, commands, listeners));
+            }
+        });
+        Some(Ipc { path })
+    }
+
+    fn serve(stream: UnixStream, commands: Sender<RemoteCommand>, listeners: Listeners) {
+        let Ok(mut out) = stream.try_clone() else {
+            return;
+        };
+        for line in BufReader::new(stream).lines() {
+            let Ok(line) = line else {
+                return;
+            };
+            let reply = match parse(&line) {
+                Ok(Request::Command(command)) => match commands.send(command) {
+                    Ok(()) => "ok".to_string(),
+                    Err(_) => "error shutting down".to_string(),
+                },
+                Ok(Request::Ping) => "pong".to_string(),
+                Ok(Request::State) => {
+                    // a new listener always gets the current state first
+                    let rx = subscribe(&listeners);
+                    std::iter::from_fn(|| rx.recv_timeout(STATE_TIMEOUT).ok())
+                        .find(|l| l.starts_with(r#"{"type":"state""#))
+                        .unwrap_or_else(|| "error no state".to_string())
+                }
+                Ok(Request::History) => history::json(),
+                Ok(Request::Subscribe) => {
+                    for update in subscribe(&listeners) {
+                        if writeln!(out, "{update}").is_err() {
+                            return;
+                        }
+                    }
+                    return;
+                }
+                Err(e) => format!("error {e}"),
+            };
+            if writeln!(out, "{reply}").is_err() {
+                return;
+            }
+        }
+    }
+
+    fn connect() -> Result<UnixStream, String> {
+        let path = socket_path();
+        UnixStream::connect(&path)
+            .map_err(|e| format!("no running pet at {} ({e})", path.display()))
+    }
+
+    /// Send one request line to the running pet and return its answer.
+    pub fn forward(line: &str) -> Result<String, String> {
+        let mut stream = connect()?;
+        writeln!(stream, "{line}").map_err(|e| e.to_string())?;
+        let mut reply = String::new();
+        BufReader::new(stream)
+            .read_line(&mut reply)
+            .map_err(|e| e.to_string())?;
+        match reply.trim_end().strip_prefix("error ") {
+            Some(e) => Err(e.to_string()),
+            None => Ok(reply.trim_end().to_string()),
+        }
+    }
+
+    /// Pass every state update to `on_update` until the pet exits.
+    pub fn watch(mut on_update: impl FnMut(&str)) -> Result<(), String> {
+        let mut stream = connect()?;
+        writeln!(stream, "subscribe").map_err(|e| e.to_string())?;
+        for line in BufReader::new(stream).lines() {
+            on_update(&line.map_err(|e| e.to_string())?);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(unix))]
+pub use fallback::{forward, start, watch};
+
+#[cfg(not(unix))]
+mod fallback {
+    use bevy::prelude::*;
+
+    use super::*;
+    use crate::remote::Remote;
+
+    #[derive(Resource)]
+    pub struct Ipc;
+
+    pub fn start(_remote: &Remote) -> Option<Ipc> {
+        warn!("The control socket is only available on Unix");
+        let _ = parse;
+        None
+    }
+
+    pub fn forward(_line: &str) -> Result<String, String> {
+        Err("the control socket is only available on Unix".into())
+    }
+
+    pub fn watch(_on_update: impl FnMut(&str)) -> Result<(), String> {
+        Err("the control socket is only available on Unix".into())
+    }
+}
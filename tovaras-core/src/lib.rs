@@ -0,0 +1,781 @@
+//! tovaras-core: the desktop pet itself (its state machine and physics, the
+//! behavior drivers, animation, asset loading and the windows it lives in)
+//! and the `tovaras` command line built on it. The `tovaras` binary only
+//! calls [`run`].
+//!
+//! Other Bevy apps can add the pet with [`TovarasPetPlugin`]:
+//!
+//! ```no_run
+//! use bevy::prelude::*;
+//! use tovaras_core::{BehaviorConfig, PetMode, TovarasPetPlugin};
+//!
+//! let mut behavior = BehaviorConfig::default();
+//! behavior.floor.jump = 40.0; // bouncier than usual
+//! App::new()
+//!     .add_plugins(DefaultPlugins)
+//!     .add_plugins(
+//!         TovarasPetPlugin::new()
+//!             .pack("cat")
+//!             .behavior(behavior)
+//!             .mode(PetMode::Random),
+//!     )
+//!     .run();
+//! ```
+
+use bevy::asset::AssetPlugin;
+use bevy::prelude::*;
+use bevy::window::ExitCondition;
+use bevy_egui::EguiPlugin;
+use serde::{Deserialize, Serialize};
+
+mod accessory;
+mod actions;
+mod animation;
+mod animdir;
+mod aseprite;
+mod atlas;
+mod bench;
+mod calendar;
+mod chroma;
+mod commands;
+mod config;
+mod crash;
+mod crop;
+mod ctl;
+mod debug;
+#[cfg(feature = "devtools")]
+mod devtools;
+mod drivers;
+mod dryrun;
+mod embed;
+mod export;
+mod extent;
+mod fallback;
+mod footprints;
+mod fuzz;
+mod gallery;
+#[cfg(feature = "http")]
+mod github;
+mod golden;
+mod headless;
+mod history;
+mod input;
+mod invariants;
+mod ipc;
+mod logging;
+#[cfg(feature = "mail")]
+mod mail;
+mod manifest;
+#[cfg(feature = "websocket")]
+mod mirror;
+mod monitors;
+mod nag;
+mod night;
+mod outline;
+mod overlay;
+mod pack_frames;
+mod packer;
+mod packs;
+mod particles;
+mod paths;
+mod pet_window;
+mod phrases;
+mod physics;
+#[cfg(feature = "wasm")]
+mod plugins;
+mod powersave;
+mod profile;
+mod recolor;
+mod remote;
+mod rng;
+mod scenario;
+#[cfg(feature = "scripting")]
+mod scripting;
+mod shapes;
+mod sharing;
+mod shimeji;
+mod skins;
+mod social;
+mod speech;
+mod stats;
+mod sysmon;
+mod timers;
+mod traits;
+#[cfg(feature = "twitch")]
+mod twitch;
+mod visit;
+#[cfg(feature = "http")]
+mod weather;
+#[cfg(feature = "http")]
+mod webhooks;
+#[cfg(feature = "websocket")]
+mod websocket;
+
+use animation::finalize_after_load;
+use chroma::Chroma;
+use config::Config;
+use crop::Crops;
+use fallback::SheetRetry;
+use footprints::FootprintTrail;
+use manifest::Manifest;
+use night::NightState;
+use overlay::Overlay;
+use packs::{ActivePack, SwitchPack};
+use particles::Particles;
+use pet_window::spawn_pet;
+use physics::apply_motion_and_orientation;
+use profile::Profile;
+use remote::Remote;
+
+pub use bench::CountingAlloc;
+pub use config::{BehaviorConfig, Weights};
+pub use embed::{PetMode, TovarasPetPlugin};
+pub use golden::check_goldens;
+pub use rng::{PetRng, Rng, Scripted, TinyRng};
+
+// ===== Scale (5x smaller window & sprite) =====
+const SCALE: f32 = 1.0 / 5.0;
+
+// Sprite sheet layout, clips and per-surface poses live in the animation
+// manifest (assets/pet.ron), see manifest.rs.
+
+// Gap kept between a freshly placed pet and the screen edges
+const START_MARGIN: i32 = 40;
+
+// Speeds and jump physics live in physics.rs, the test sequencer and random
+// behavior in drivers.rs.
+
+// ================================================
+
+/// How the pet is oriented on the segment it walks or climbs (see
+/// physics/segments.rs), and so the pose it takes there.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "devtools", derive(Reflect))]
+enum Surface {
+    Floor,
+    RightWall,
+    Ceiling,
+    LeftWall,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "devtools", derive(Reflect))]
+enum Action {
+    Idle,
+    Move,
+    Climb,
+    Jumping,
+    Landing,
+    Sleeping, // test mode only, never randomly scheduled
+    Hiding,
+    GivingFlowers, // floor-only in place
+    /// Registered by a pack (see actions.rs).
+    Custom(actions::CustomAction),
+}
+
+#[derive(Component)]
+struct Pet;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "devtools", derive(Reflect))]
+enum FlightKind {
+    None,
+    Parabola, // used for floor & wall jumps
+}
+
+#[derive(Component, Clone, Debug)]
+#[cfg_attr(feature = "devtools", derive(Reflect), reflect(Component))]
+struct PetState {
+    surface: Surface,
+    action: Action,
+    move_dir: f32,             // +1 or -1 for motion along the current surface
+    facing: f32,               // +1 or -1, same sense as move_dir; the way the sprite looks
+    window_pos: IVec2,         // top-left px
+    idle_clip: Option<String>, // manifest idle picked for the current idle stretch
+    restart_clip: bool,        // play the clip from its first frame (a one-shot action begins)
+
+    // Flight state
+    flight: FlightKind,
+    flight_from: Surface, // takeoff surface for visuals during flight
+    vx: f32,              // px/s
+    vy: f32,              // px/s (positive downward)
+    landing_left: f32,    // seconds to hold landing anim
+    landing_fix: f32,     // px still to slide during the landing hold to reach target_x
+    speed: f32,           // signed fraction of full surface speed (eased toward move_dir)
+    speed_v: f32,         // how fast `speed` changes on the floor, per second
+    stumble_left: f32,    // seconds of a stumble still to play before turning around
+    carry: f32,           // sub-pixel remainder of surface motion
+    surface_secs: f32,    // time on the current surface
+    corner_clear: bool,   // has been CORNER_MARGIN px away from the corners since arriving
+    flight_carry: Vec2,   // sub-pixel remainder of flight motion
+    squash: f32,          // >0 squash, <0 stretch (spring back to 0)
+    squash_v: f32,
+    perch: i32, // px above the floor while standing on another pet
+
+    // Targets
+    target_x: i32,                       // floor target X
+    wall_target: Option<(Surface, i32)>, // (Left/Right wall, target Y)
+}
+
+impl PetState {
+    /// Walking right on the floor, window top-left at `window_pos`.
+    fn new(window_pos: IVec2) -> Self {
+        Self {
+            surface: Surface::Floor,
+            action: Action::Move,
+            move_dir: 1.0,
+            facing: 1.0,
+            window_pos,
+            idle_clip: None,
+            restart_clip: false,
+            flight: FlightKind::None,
+            flight_from: Surface::Floor,
+            vx: 0.0,
+            vy: 0.0,
+            landing_left: 0.0,
+            landing_fix: 0.0,
+            speed: 0.0,
+            speed_v: 0.0,
+            stumble_left: 0.0,
+            carry: 0.0,
+            surface_secs: 0.0,
+            corner_clear: false,
+            flight_carry: Vec2::ZERO,
+            squash: 0.0,
+            squash_v: 0.0,
+            perch: 0,
+            target_x: 0,
+            wall_target: None,
+        }
+    }
+}
+
+/// The monitor the pet roams (px): its top-left on the virtual desktop and
+/// its size. The pet's positions are relative to `origin`. A 1920×1080
+/// monitor at the desktop's top-left until the pet's window reports its own
+/// (see monitors.rs); headless runs (see headless.rs) pick
+/// their own.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+struct ScreenGeometry {
+    origin: IVec2,
+    size: IVec2,
+}
+
+impl Default for ScreenGeometry {
+    fn default() -> Self {
+        Self::new(IVec2::new(1920, 1080))
+    }
+}
+
+impl ScreenGeometry {
+    /// A `size` monitor at the desktop's top-left.
+    fn new(size: IVec2) -> Self {
+        Self {
+            origin: IVec2::ZERO,
+            size,
+        }
+    }
+
+    /// Whether `point` (virtual desktop px) is on this monitor.
+    fn contains(&self, point: IVec2) -> bool {
+        let p = point - self.origin;
+        p.x >= 0 && p.y >= 0 && p.x < self.size.x && p.y < self.size.y
+    }
+
+    /// Where a window at `pos` on this monitor goes on the virtual desktop.
+    fn window_at(&self, pos: IVec2) -> WindowPosition {
+        WindowPosition::At(self.origin + pos)
+    }
+
+    /// The size of the rectangle the pet roams, for a `fw`×`fh` window.
+    fn desktop(&self, fw: i32, fh: i32) -> (i32, i32) {
+        (
+            self.size.x.max(fw + 2 * START_MARGIN),
+            self.size.y.max(fh + 2 * START_MARGIN),
+        )
+    }
+}
+
+/// Do what the command line asks: run a subcommand, or open the pet and run
+/// until it quits.
+pub fn run() {
+    // Mode selection
+    let args: Vec<String> = std::env::args().collect();
+    let headless = args.iter().any(|a| a == "--headless");
+    let bench = args.iter().any(|a| a == "--bench");
+    let fuzz = args.iter().any(|a| a == "--fuzz");
+    let dry_run = args.iter().any(|a| a == "--dry-run");
+    let debug = args.iter().any(|a| a == "--debug");
+    let assert = args.iter().any(|a| a == "--assert");
+
+    // Subcommands run and exit without opening a window
+    match args.get(1).map(String::as_str) {
+        Some("packs") => std::process::exit(packs::cli(&args[2..])),
+        Some("import-shimeji") => std::process::exit(shimeji::cli(&args[2..])),
+        Some("pack-frames") => std::process::exit(pack_frames::cli(&args[2..])),
+        Some("export-gif") => std::process::exit(export::cli(&args[2..])),
+        Some("ctl") => std::process::exit(ctl::cli(&args[2..])),
+        Some("export-profile") => std::process::exit(sharing::export_cli(&args[2..])),
+        Some("import-profile") => std::process::exit(sharing::import_cli(&args[2..])),
+        Some("golden") => std::process::exit(golden::cli(&args[2..])),
+        Some("stats") => std::process::exit(stats::cli(&args[2..])),
+        #[cfg(feature = "mail")]
+        Some("mail-password") => std::process::exit(mail::cli(&args[2..])),
+        #[cfg(not(feature = "mail"))]
+        Some("mail-password") => {
+            eprintln!("tovaras: built without the \"mail\" feature");
+            std::process::exit(2);
+        }
+        _ => {}
+    }
+
+    // --chroma <color>: opaque, capturable window for streaming
+    let chroma = match args.iter().position(|a| a == "--chroma") {
+        Some(i) => match args.get(i + 1).map(|c| Chroma::parse(c)) {
+            Some(Ok(chroma)) => Some(chroma),
+            Some(Err(e)) => {
+                eprintln!("tovaras: {e}");
+                std::process::exit(2);
+            }
+            None => {
+                eprintln!("tovaras: --chroma needs a color (green, blue, magenta or #rrggbb)");
+                std::process::exit(2);
+            }
+        },
+        None => None,
+    };
+
+    // --time-scale N: behavior and physics run N times as fast (virtual time)
+    let time_scale = match args.iter().position(|a| a == "--time-scale") {
+        Some(i) => match args.get(i + 1).and_then(|v| v.parse::<f32>().ok()) {
+            Some(scale) if scale > 0.0 && scale.is_finite() => scale,
+            _ => {
+                eprintln!("tovaras: --time-scale needs a positive number (e.g. 10 or 0.5)");
+                std::process::exit(2);
+            }
+        },
+        None => 1.0,
+    };
+
+    // --test [FILE]: cycle through a scenario file instead of the built-in one
+    let mut pet = TovarasPetPlugin::new().stepping(args.iter().any(|a| a == "--step"));
+    if let Some(i) = args.iter().position(|a| a == "--test") {
+        pet = pet.mode(PetMode::Test);
+        if let Some(file) = args.get(i + 1).filter(|a| !a.starts_with("--")) {
+            pet = pet
+                .scenario(std::path::Path::new(file))
+                .unwrap_or_else(|e| {
+                    eprintln!("tovaras: {e}");
+                    std::process::exit(2);
+                });
+        }
+    }
+
+    let config = Config::load();
+    // --mirror host:port: follow another machine's pet instead of running our own
+    let mirror_source = args
+        .iter()
+        .position(|a| a == "--mirror")
+        .and_then(|i| args.get(i + 1))
+        .or(config.mirror.source.as_ref())
+        .cloned();
+    // hand over to the pet that is already running
+    if config.single_instance
+        && !headless
+        && !bench
+        && !fuzz
+        && !dry_run
+        && ipc::forward("hello").is_ok()
+    {
+        println!("tovaras is already running");
+        return;
+    }
+    // An external sheet (--sheet wins over config.ron) replaces the embedded one
+    let sheet_arg = args
+        .iter()
+        .position(|a| a == "--sheet")
+        .and_then(|i| args.get(i + 1))
+        .map(|p| std::env::current_dir().unwrap_or_default().join(p));
+    let gallery = args.iter().any(|a| a == "--gallery");
+    if headless || bench || fuzz || dry_run || gallery {
+        let manifest = ActivePack::from_config(&config, sheet_arg).load_manifest();
+        if headless {
+            std::process::exit(headless::cli(&args, manifest, config));
+        }
+        if bench {
+            std::process::exit(bench::cli(&args, manifest, config));
+        }
+        if fuzz {
+            std::process::exit(fuzz::cli(&args, manifest, config));
+        }
+        if dry_run {
+            std::process::exit(dryrun::cli(&args, manifest, config));
+        }
+        gallery::run(manifest, config);
+        return;
+    }
+    if let Some(sheet) = sheet_arg {
+        pet = pet.sheet(sheet);
+    }
+    if let Some(source) = mirror_source {
+        #[cfg(feature = "websocket")]
+        {
+            pet = pet.mode(PetMode::Mirror(source));
+        }
+        #[cfg(not(feature = "websocket"))]
+        {
+            eprintln!("tovaras: can't mirror {source}: built without the \"websocket\" feature");
+            std::process::exit(2);
+        }
+    }
+
+    crash::install(&config);
+    // a mirror looks where the primary's pet looks
+    #[cfg(feature = "websocket")]
+    let face_cursor = input::face_cursor.run_if(not(resource_exists::<mirror::Mirror>));
+    #[cfg(not(feature = "websocket"))]
+    let face_cursor = input::face_cursor;
+    let mut app = App::new();
+    if let Some(file) = logging::LogFile::new(&config.log) {
+        app.insert_resource(file);
+    }
+    app.add_plugins(
+        DefaultPlugins
+            .set(logging::plugin(&args, &config.log))
+            .set(AssetPlugin {
+                file_path: ".".into(), // the pet sheet itself is embedded or read directly
+                ..default()
+            })
+            .set(WindowPlugin {
+                // every pet opens its own window (see pet_window.rs)
+                primary_window: None,
+                exit_condition: ExitCondition::DontExit,
+                ..default()
+            }),
+    )
+    .insert_resource(time_scaled(time_scale))
+    .insert_resource(ClearColor(
+        chroma.map_or(Color::srgba(0.0, 0.0, 0.0, 0.0), |c| c.0),
+    ))
+    .insert_resource(NightState::new(&config.night))
+    .insert_resource(Profile::load(&config.sync))
+    .insert_resource(stats::Stats::load())
+    .add_event::<input::Petted>()
+    .add_event::<particles::ParticleBurst>()
+    .add_event::<SwitchPack>()
+    .add_event::<speech::Say>()
+    .add_plugins(pet.config(config.clone()))
+    .init_resource::<input::Cursor>()
+    .add_systems(Startup, setup_particles.after(spawn_pet))
+    .add_systems(
+        Update,
+        (input::track_cursor, face_cursor)
+            .chain()
+            // a pet busy with another keeps facing it
+            .before(social::socialize)
+            .before(apply_motion_and_orientation),
+    )
+    .add_systems(
+        Update,
+        (
+            (skins::switch_skin_at_midnight, night::switch_night_clips)
+                .chain()
+                .before(finalize_after_load),
+            night::update_night_tint.after(apply_motion_and_orientation),
+        ),
+    )
+    .add_systems(
+        Update,
+        (
+            input::detect_petting,
+            particles::emit_moment_particles,
+            particles::spawn_particle_bursts,
+            particles::update_particles,
+        )
+            .chain()
+            .after(apply_motion_and_orientation),
+    )
+    .add_systems(
+        Update,
+        (
+            profile::update_needs,
+            profile::collect_items,
+            profile::sync_profile,
+        )
+            .chain()
+            .after(input::detect_petting),
+    )
+    .add_systems(
+        Update,
+        stats::track_stats
+            .after(input::detect_petting)
+            .after(apply_motion_and_orientation),
+    )
+    .add_systems(Last, (profile::save_on_exit, stats::save_on_exit))
+    .add_systems(
+        Update,
+        (speech::spawn_bubbles, speech::update_bubbles)
+            .chain()
+            .after(apply_motion_and_orientation),
+    );
+
+    let retry = SheetRetry::new(app.world().resource::<Manifest>());
+    app.insert_resource(retry).add_systems(
+        Update,
+        (
+            pet_window::exit_without_pets.after(pet_window::despawn_windowless_pets),
+            fallback::retry_failed_sheet.before(finalize_after_load),
+            fallback::show_sheet_error.after(apply_motion_and_orientation),
+            fallback::announce_sheet_error.before(speech::spawn_bubbles),
+            logging::log_transitions.after(apply_motion_and_orientation),
+            crash::record_state.after(apply_motion_and_orientation),
+        ),
+    );
+
+    if config.render.adaptive_tick_rate {
+        app.init_resource::<powersave::Pace>().add_systems(
+            Update,
+            powersave::govern_tick_rate.after(particles::update_particles),
+        );
+    }
+
+    if config.auto_crop {
+        app.insert_resource(Crops::default()).add_systems(
+            Update,
+            crop::fit_window_to_clip.after(apply_motion_and_orientation),
+        );
+    }
+
+    if !config.accessories.is_empty() {
+        app.add_systems(Startup, accessory::spawn_accessories.after(spawn_pet))
+            .add_systems(
+                Update,
+                accessory::follow_points.after(apply_motion_and_orientation),
+            );
+    }
+
+    if config.footprints.enabled {
+        app.add_systems(Startup, setup_footprints).add_systems(
+            Update,
+            (
+                footprints::spawn_footprints.after(apply_motion_and_orientation),
+                footprints::fade_footprints,
+            ),
+        );
+    }
+    app.init_resource::<phrases::Phrases>().add_systems(
+        Update,
+        (
+            (packs::cycle_pack_hotkey, packs::switch_pack)
+                .chain()
+                .before(finalize_after_load),
+            phrases::say_phrases
+                .after(packs::switch_pack)
+                .before(commands::arbitrate),
+        ),
+    );
+    if config.visit.enabled || !config.visit.friends.is_empty() {
+        app.insert_resource(visit::start(&config.visit))
+            .add_systems(
+                Update,
+                (
+                    visit::visit_hotkey,
+                    visit::travel.after(apply_motion_and_orientation),
+                    visit::welcome_guests,
+                    visit::move_guests,
+                ),
+            );
+    }
+    if !config.webhooks.is_empty() {
+        #[cfg(feature = "http")]
+        app.insert_resource(webhooks::Webhooks::new(&config.webhooks))
+            .add_event::<webhooks::Notable>()
+            .add_systems(
+                Update,
+                (
+                    webhooks::watch_level,
+                    webhooks::daily_summary,
+                    webhooks::send_webhooks,
+                )
+                    .chain()
+                    .after(profile::update_needs),
+            );
+        #[cfg(not(feature = "http"))]
+        warn!("Webhooks are configured, but tovaras was built without the \"http\" feature");
+    }
+    let remote = Remote::new();
+    if let Some(ipc) = ipc::start(&remote) {
+        app.insert_resource(ipc);
+    }
+    if config.websocket.enabled {
+        #[cfg(feature = "websocket")]
+        websocket::start(&config.websocket, &remote);
+        #[cfg(not(feature = "websocket"))]
+        warn!("The WebSocket API is on, but tovaras was built without the \"websocket\" feature");
+    }
+    if config.mirror.serve {
+        #[cfg(feature = "websocket")]
+        mirror::serve(&config.mirror, &remote);
+        #[cfg(not(feature = "websocket"))]
+        warn!("Serving mirrors is on, but tovaras was built without the \"websocket\" feature");
+    }
+    #[cfg(feature = "scripting")]
+    app.insert_resource(scripting::Scripts::new(remote.sender()))
+        .add_systems(
+            Update,
+            scripting::run_scripts.before(remote::apply_remote_commands),
+        );
+    #[cfg(not(feature = "scripting"))]
+    if paths::config_dir().join("scripts").is_dir() {
+        warn!("Found a scripts folder, but tovaras was built without the \"scripting\" feature");
+    }
+    #[cfg(feature = "wasm")]
+    app.insert_resource(plugins::Plugins::new(remote.sender()))
+        .add_systems(
+            Update,
+            plugins::run_plugins.before(remote::apply_remote_commands),
+        );
+    #[cfg(not(feature = "wasm"))]
+    if paths::config_dir().join("plugins").is_dir() {
+        warn!("Found a plugins folder, but tovaras was built without the \"wasm\" feature");
+    }
+    app.insert_resource(remote)
+        .init_resource::<timers::Timers>()
+        .add_systems(
+            Update,
+            (
+                remote::apply_remote_commands.before(commands::arbitrate),
+                remote::publish_state.after(apply_motion_and_orientation),
+                timers::ring_timers.after(input::detect_petting),
+            ),
+        );
+    if config
+        .calendar
+        .calendars
+        .iter()
+        .any(|calendar| calendar.enabled)
+    {
+        app.insert_resource(calendar::Calendar::start(&config.calendar))
+            .add_systems(
+                Update,
+                calendar::remind_of_events.before(commands::arbitrate),
+            );
+    }
+    if !config.github.token.trim().is_empty() {
+        #[cfg(feature = "http")]
+        app.insert_resource(github::Github::start(&config.github))
+            .add_systems(
+                Update,
+                (
+                    github::react_to_notifications.before(commands::arbitrate),
+                    (github::click_badge, github::show_badge)
+                        .chain()
+                        .after(apply_motion_and_orientation),
+                ),
+            );
+        #[cfg(not(feature = "http"))]
+        warn!("GitHub is configured, but tovaras was built without the \"http\" feature");
+    }
+    if !config.mail.host.trim().is_empty() {
+        #[cfg(feature = "mail")]
+        app.insert_resource(mail::connect(&config.mail))
+            .add_systems(Startup, mail::spawn_envelope.after(spawn_pet))
+            .add_systems(
+                Update,
+                (
+                    mail::react_to_mail.before(commands::arbitrate),
+                    mail::carry_envelope.after(apply_motion_and_orientation),
+                ),
+            );
+        #[cfg(not(feature = "mail"))]
+        warn!("Mail is configured, but tovaras was built without the \"mail\" feature");
+    }
+    #[cfg(feature = "http")]
+    if let Some(weather) = weather::Weather::start(&config.weather) {
+        app.insert_resource(weather)
+            .add_systems(Startup, weather::spawn_umbrella.after(spawn_pet))
+            .add_systems(
+                Update,
+                (
+                    weather::follow_weather
+                        .before(commands::arbitrate)
+                        .before(particles::spawn_particle_bursts),
+                    weather::hold_umbrella.after(apply_motion_and_orientation),
+                ),
+            );
+    }
+    #[cfg(not(feature = "http"))]
+    if config.weather.location.is_some() {
+        warn!("Weather is configured, but tovaras was built without the \"http\" feature");
+    }
+    if config.system_monitor.enabled {
+        app.insert_resource(sysmon::SystemMonitor::new(&config.system_monitor))
+            .add_systems(Startup, sysmon::spawn_disk_icon.after(spawn_pet))
+            .add_systems(
+                Update,
+                (
+                    sysmon::show_machine_mood.before(commands::arbitrate),
+                    sysmon::carry_disk_icon.after(apply_motion_and_orientation),
+                ),
+            );
+    }
+    if !config.nag.disks.is_empty() || !config.nag.backups.is_empty() {
+        app.insert_resource(nag::Nag::new(&config.nag))
+            .add_systems(Update, nag::nag_about_problems.before(commands::arbitrate));
+    }
+    if !config.twitch.channel.is_empty() {
+        #[cfg(feature = "twitch")]
+        app.insert_resource(twitch::connect(&config.twitch))
+            .add_systems(Update, twitch::react_to_chat.before(commands::arbitrate));
+        #[cfg(not(feature = "twitch"))]
+        warn!("Twitch chat is configured, but tovaras was built without the \"twitch\" feature");
+    }
+    if assert {
+        app.init_resource::<invariants::Violations>().add_systems(
+            PostUpdate,
+            invariants::assert_invariants.after(pet_window::place_pet_windows),
+        );
+    }
+    if debug {
+        app.add_plugins(EguiPlugin)
+            .init_gizmo_group::<debug::MarkerGizmos>()
+            .add_systems(
+                Startup,
+                (debug::spawn_hud.after(spawn_pet), debug::spawn_markers),
+            )
+            .add_systems(
+                Update,
+                (debug::show_hud, debug::draw_markers).after(apply_motion_and_orientation),
+            );
+        #[cfg(feature = "devtools")]
+        devtools::add(&mut app);
+    }
+    if let Some(chroma) = chroma {
+        app.insert_resource(chroma);
+    }
+    app.run();
+}
+
+/// The virtual clock the behavior runs on, `scale` times as fast as the wall
+/// clock; a slow frame may skip as much game time as usual, and a resting pet's
+/// longest wait (see powersave.rs) skips none.
+fn time_scaled(scale: f32) -> Time<Virtual> {
+    let mut time = Time::<Virtual>::default();
+    time.set_relative_speed(scale);
+    let max_delta = time.max_delta().max(powersave::REST_WAIT);
+    time.set_max_delta(max_delta.mul_f32(scale.max(1.0)));
+    time
+}
+
+fn setup_particles(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    commands.insert_resource(Particles::new(&mut images));
+}
+
+fn setup_footprints(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut overlay: ResMut<Overlay>,
+) {
+    overlay::spawn_overlay(&mut commands, &mut overlay);
+    commands.insert_resource(FootprintTrail::new(&mut images));
+}
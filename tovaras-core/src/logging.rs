@@ -0,0 +1,127 @@
+//! Logging: the level comes from `--log-level` or `log.level` in config.ron
+//! (`RUST_LOG`, when set, still wins), and `log: (file: true)` adds a copy in
+//! `<data_dir>/logs/tovaras.YYYY-MM-DD.log`, rotated daily. State transitions
+//! are logged with fields, so a glitch in a long session can be traced back:
+//!
+//! ```text
+//! INFO tovaras::logging: transition from_surface=Floor from_action=Walking from_flight=None to_surface=Floor to_action=Jumping to_flight=Parabola x=812 y=1004 dir=1.0 vx=310.5 vy=-420.0
+//! ```
+
+#[cfg(feature = "log_file")]
+use bevy::log::tracing_subscriber::fmt;
+use bevy::log::tracing_subscriber::Layer;
+use bevy::log::{BoxedLayer, Level, LogPlugin};
+use bevy::prelude::*;
+use std::path::PathBuf;
+use std::str::FromStr;
+#[cfg(feature = "log_file")]
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+
+use crate::config::LogConfig;
+use crate::{crash, history};
+use crate::{paths, Action, FlightKind, Pet, PetState, Surface};
+
+const LOGS_DIR: &str = "logs";
+
+/// Where the log file layer writes; read once while `LogPlugin` builds.
+#[derive(Resource)]
+pub struct LogFile {
+    dir: PathBuf,
+    keep: usize,
+}
+
+impl LogFile {
+    pub fn new(cfg: &LogConfig) -> Option<Self> {
+        cfg.file.then(|| Self {
+            dir: paths::data_dir().join(LOGS_DIR),
+            keep: cfg.keep_days.max(1),
+        })
+    }
+}
+
+/// `LogPlugin` at `--log-level` (or the configured level); exits with 2 on
+/// an unknown level.
+pub fn plugin(args: &[String], cfg: &LogConfig) -> LogPlugin {
+    let level = match args.iter().position(|a| a == "--log-level") {
+        Some(i) => args.get(i + 1).map(String::as_str).unwrap_or_default(),
+        None => cfg.level.as_str(),
+    };
+    let Ok(level) = Level::from_str(level) else {
+        eprintln!("tovaras: log level {level:?} is not one of error, warn, info, debug, trace");
+        std::process::exit(2);
+    };
+    LogPlugin {
+        level,
+        custom_layer: custom_layers,
+        ..default()
+    }
+}
+
+/// The recent lines for crash reports, and the log file if there is one.
+fn custom_layers(app: &mut App) -> Option<BoxedLayer> {
+    Some(crash::RecentLog.and_then(file_layer(app)).boxed())
+}
+
+#[cfg(feature = "log_file")]
+fn file_layer(app: &mut App) -> Option<BoxedLayer> {
+    let file = app.world_mut().remove_resource::<LogFile>()?;
+    let appender = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix("tovaras")
+        .filename_suffix("log")
+        .max_log_files(file.keep)
+        .build(&file.dir);
+    match appender {
+        Ok(appender) => Some(fmt::layer().with_ansi(false).with_writer(appender).boxed()),
+        Err(e) => {
+            // the logger isn't up yet
+            eprintln!("tovaras: no log file in {}: {e}", file.dir.display());
+            None
+        }
+    }
+}
+
+#[cfg(not(feature = "log_file"))]
+fn file_layer(app: &mut App) -> Option<BoxedLayer> {
+    let file = app.world_mut().remove_resource::<LogFile>()?;
+    eprintln!(
+        "tovaras: not keeping {} days of logs in {}: built without the \"log_file\" feature",
+        file.keep,
+        file.dir.display()
+    );
+    None
+}
+
+/// Log every change of surface, action or flight with where it happened, and
+/// keep it in the history.
+pub fn log_transitions(
+    mut last: Local<Option<(Surface, Action, FlightKind)>>,
+    pets: Query<&PetState, With<Pet>>,
+) {
+    let Ok(st) = pets.get_single() else {
+        return;
+    };
+    let now = (st.surface, st.action, st.flight);
+    let Some((surface, action, flight)) = last.replace(now) else {
+        info!(surface = ?st.surface, action = ?st.action, x = st.window_pos.x, y = st.window_pos.y, "start");
+        return;
+    };
+    if (surface, action, flight) == now {
+        return;
+    }
+    history::record(surface, action, st);
+    info!(
+        from_surface = ?surface,
+        from_action = ?action,
+        from_flight = ?flight,
+        to_surface = ?st.surface,
+        to_action = ?st.action,
+        to_flight = ?st.flight,
+        x = st.window_pos.x,
+        y = st.window_pos.y,
+        dir = st.move_dir,
+        vx = st.vx,
+        vy = st.vy,
+        "transition"
+    );
+}
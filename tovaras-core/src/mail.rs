@@ -0,0 +1,388 @@
+//! Unread mail (built with `--features mail`): with `mail` set in config.ron
+//! the pet stays logged in to an IMAP server over TLS, counts the unread
+//! messages in the mailbox and carries an envelope while there are any. When
+//! the count goes up it bounces.
+//!
+//! The server tells us of new mail as it arrives (IMAP IDLE, re-issued every
+//! `IDLE_RENEW`); servers without IDLE are asked every `POLL`. Any IMAP
+//! provider works. The password is read from the system keyring, under the
+//! `tovaras-mail` service and `user@host` account: `tovaras mail-password`
+//! puts it there.
+
+use bevy::prelude::*;
+use rustls::pki_types::ServerName;
+use rustls::{ClientConfig, ClientConnection, RootCertStore, StreamOwned};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::animation::{Anim, SheetInfo};
+use crate::commands::{Issued, PetCommand, Source};
+use crate::config::{Config, MailConfig};
+use crate::{shapes, Action, Pet};
+
+const KEYRING_SERVICE: &str = "tovaras-mail";
+/// Servers drop an IDLE after 30 minutes; it is started over before that.
+const IDLE_RENEW: Duration = Duration::from_secs(25 * 60);
+/// Between two counts when the server can't IDLE.
+const POLL: Duration = Duration::from_secs(2 * 60);
+const TIMEOUT: Duration = Duration::from_secs(30);
+const RETRY_MIN: Duration = Duration::from_secs(5);
+const RETRY_MAX: Duration = Duration::from_secs(300);
+/// Seconds the bounce holds off the driver.
+const BOUNCE_SECS: f32 = 2.0;
+const SAY_SECS: f32 = 6.0;
+/// Envelope width, as a part of the frame's.
+const ENVELOPE_WIDTH: f32 = 0.22;
+const ENVELOPE_COLOR: [u8; 3] = [246, 240, 222];
+
+#[derive(Resource)]
+pub struct Mail {
+    updates: Mutex<Receiver<usize>>,
+    /// Unread messages, once counted.
+    unread: Option<usize>,
+}
+
+/// Start watching the mailbox of `cfg` in the background.
+pub fn connect(cfg: &MailConfig) -> Mail {
+    let (tx, rx) = mpsc::channel();
+    let cfg = cfg.clone();
+    info!("Watching {} for unread mail", account(&cfg));
+    thread::spawn(move || {
+        let mut wait = RETRY_MIN;
+        loop {
+            match watch(&cfg, &tx) {
+                // the receiving side is gone: the app is shutting down
+                Ok(()) => return,
+                Err(e) => warn!("Mail {}: {e}; retrying in {wait:?}", account(&cfg)),
+            }
+            thread::sleep(wait);
+            wait = (wait * 2).min(RETRY_MAX);
+        }
+    });
+    Mail {
+        updates: Mutex::new(rx),
+        unread: None,
+    }
+}
+
+/// The keyring account the password is stored under.
+fn account(cfg: &MailConfig) -> String {
+    format!("{}@{}", cfg.user.trim(), cfg.host.trim())
+}
+
+fn password(cfg: &MailConfig) -> Result<String, String> {
+    keyring::Entry::new(KEYRING_SERVICE, &account(cfg))
+        .and_then(|entry| entry.get_password())
+        .map_err(|e| format!("no password in the keyring ({e}); run `tovaras mail-password`"))
+}
+
+/// Log in and send the unread count every time it may have changed.
+fn watch(cfg: &MailConfig, counts: &Sender<usize>) -> Result<(), String> {
+    let password = password(cfg)?;
+    let mut session = Session::open(cfg.host.trim(), cfg.port)?;
+    session.command(&format!(
+        "LOGIN {} {}",
+        quote(cfg.user.trim()),
+        quote(&password)
+    ))?;
+    let idle = session
+        .command("CAPABILITY")?
+        .iter()
+        .any(|line| line.split_whitespace().any(|cap| cap == "IDLE"));
+    session.command(&format!("SELECT {}", quote(&cfg.mailbox)))?;
+    loop {
+        let unread = session
+            .command("SEARCH UNSEEN")?
+            .iter()
+            .filter_map(|line| line.strip_prefix("* SEARCH"))
+            .map(|ids| ids.split_whitespace().count())
+            .sum();
+        if counts.send(unread).is_err() {
+            return Ok(());
+        }
+        if idle {
+            session.idle()?;
+        } else {
+            thread::sleep(POLL);
+        }
+    }
+}
+
+/// `text` as an IMAP quoted string.
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// A logged-in (or about to be) IMAP connection.
+struct Session {
+    stream: BufReader<StreamOwned<ClientConnection, TcpStream>>,
+    tag: u32,
+}
+
+impl Session {
+    fn open(host: &str, port: u16) -> Result<Self, String> {
+        let roots = RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.into(),
+        };
+        let tls =
+            ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+                .with_safe_default_protocol_versions()
+                .map_err(|e| e.to_string())?
+                .with_root_certificates(roots)
+                .with_no_client_auth();
+        let name = ServerName::try_from(host.to_string()).map_err(|e| e.to_string())?;
+        let conn = ClientConnection::new(Arc::new(tls), name).map_err(|e| e.to_string())?;
+        let tcp = TcpStream::connect((host, port)).map_err(|e| e.to_string())?;
+        let mut session = Self {
+            stream: BufReader::new(StreamOwned::new(conn, tcp)),
+            tag: 0,
+        };
+        session.read_timeout(TIMEOUT)?;
+        let greeting = session.line().map_err(|e| e.to_string())?;
+        if !greeting.starts_with("* OK") {
+            return Err(format!("unexpected greeting {greeting:?}"));
+        }
+        Ok(session)
+    }
+
+    fn send(&mut self, line: &str) -> Result<(), String> {
+        let stream = self.stream.get_mut();
+        stream
+            .write_all(format!("{line}\r\n").as_bytes())
+            .and_then(|()| stream.flush())
+            .map_err(|e| e.to_string())
+    }
+
+    /// One line from the server, without its line break.
+    fn line(&mut self) -> io::Result<String> {
+        let mut line = String::new();
+        if self.stream.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "connection closed",
+            ));
+        }
+        Ok(line.trim_end().to_string())
+    }
+
+    fn next_tag(&mut self) -> String {
+        self.tag += 1;
+        format!("a{}", self.tag)
+    }
+
+    /// Run `command`, returning the lines answered before it completed.
+    fn command(&mut self, command: &str) -> Result<Vec<String>, String> {
+        let tag = self.next_tag();
+        self.send(&format!("{tag} {command}"))?;
+        self.finish(&tag)
+    }
+
+    /// The lines up to the completion of `tag`, or what went wrong with it.
+    fn finish(&mut self, tag: &str) -> Result<Vec<String>, String> {
+        let mut lines = Vec::new();
+        loop {
+            let line = self.line().map_err(|e| e.to_string())?;
+            let Some(status) = line.strip_prefix(tag).and_then(|l| l.strip_prefix(' ')) else {
+                lines.push(line);
+                continue;
+            };
+            if status.starts_with("OK") {
+                return Ok(lines);
+            }
+            // the command itself is left out: it may hold the password
+            return Err(status.to_string());
+        }
+    }
+
+    /// Wait for the mailbox to change, or for `IDLE_RENEW` to pass.
+    fn idle(&mut self) -> Result<(), String> {
+        let tag = self.next_tag();
+        self.send(&format!("{tag} IDLE"))?;
+        let ready = self.line().map_err(|e| e.to_string())?;
+        if !ready.starts_with('+') {
+            return Err(format!("IDLE refused: {ready}"));
+        }
+        let until = Instant::now() + IDLE_RENEW;
+        while let Some(left) = until.checked_duration_since(Instant::now()) {
+            self.read_timeout(left.max(Duration::from_millis(1)))?;
+            match self.line() {
+                Ok(line) if changed(&line) => break,
+                Err(e) if !is_timeout(&e) => return Err(e.to_string()),
+                _ => {}
+            }
+        }
+        self.read_timeout(TIMEOUT)?;
+        self.send("DONE")?;
+        self.finish(&tag).map(drop)
+    }
+
+    fn read_timeout(&self, timeout: Duration) -> Result<(), String> {
+        self.stream
+            .get_ref()
+            .get_ref()
+            .set_read_timeout(Some(timeout))
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Whether an untagged line means the mailbox changed.
+fn changed(line: &str) -> bool {
+    let mut words = line.split_whitespace();
+    words.next() == Some("*")
+        && words
+            .nth(1)
+            .is_some_and(|kind| matches!(kind, "EXISTS" | "EXPUNGE" | "FETCH" | "RECENT"))
+}
+
+fn is_timeout(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+    )
+}
+
+/// Bounce when the unread count goes up.
+pub fn react_to_mail(mut mail: ResMut<Mail>, mut issue: EventWriter<Issued>) {
+    let Some(unread) = mail.updates.get_mut().unwrap().try_iter().last() else {
+        return;
+    };
+    if mail.unread.is_some_and(|before| unread > before) {
+        info!("{unread} unread messages");
+        issue.send(
+            PetCommand::SetAction {
+                action: Action::Jumping,
+                dir: None,
+                clip: None,
+                secs: Some(BOUNCE_SECS),
+            }
+            .by(Source::Ambient),
+        );
+        let text = match unread {
+            1 => "You've got mail!".to_string(),
+            n => format!("You've got mail! ({n} unread)"),
+        };
+        issue.send(
+            PetCommand::Say {
+                text,
+                secs: Some(SAY_SECS),
+            }
+            .by(Source::Ambient),
+        );
+    }
+    mail.unread = Some(unread);
+}
+
+#[derive(Component)]
+pub struct Envelope;
+
+pub fn spawn_envelope(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    pets: Query<Entity, With<Pet>>,
+) {
+    let Ok(pet) = pets.get_single() else {
+        return;
+    };
+    let child = commands
+        .spawn((
+            SpriteBundle {
+                texture: images.add(envelope_image()),
+                // just above the pet, below particles
+                transform: Transform::from_xyz(0.0, 0.0, 0.5),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            Envelope,
+        ))
+        .id();
+    commands.entity(pet).add_child(child);
+}
+
+/// A closed envelope, the flap's "V" cut out.
+fn envelope_image() -> Image {
+    shapes::mask_image(32, 22, ENVELOPE_COLOR, |x, y| {
+        // from the top corners down to just below the middle
+        let flap = -1.0 + 1.15 * (1.0 - x.abs());
+        if (y - flap).abs() < 0.14 {
+            0.0
+        } else {
+            1.0
+        }
+    })
+}
+
+/// Show the envelope in the pet's hand (or in front of it, for art without a
+/// "hand" point) while there is unread mail.
+pub fn carry_envelope(
+    mail: Res<Mail>,
+    sheet: Res<SheetInfo>,
+    pets: Query<(&Anim, &TextureAtlas), With<Pet>>,
+    mut envelopes: Query<(&mut Sprite, &mut Transform, &mut Visibility), With<Envelope>>,
+) {
+    let Ok((anim, atlas)) = pets.get_single() else {
+        return;
+    };
+    let Ok((mut sprite, mut tf, mut vis)) = envelopes.get_single_mut() else {
+        return;
+    };
+    let carried = sheet.ready && mail.unread.is_some_and(|unread| unread > 0);
+    let want = if carried {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    if *vis != want {
+        *vis = want;
+    }
+    if !carried {
+        return;
+    }
+    let frame = Vec2::new(sheet.frame_w, sheet.frame_h);
+    let size = Vec2::new(1.0, 22.0 / 32.0) * frame.x * ENVELOPE_WIDTH;
+    if sprite.custom_size != Some(size) {
+        sprite.custom_size = Some(size);
+    }
+    let at = anim
+        .point("hand", atlas.index, frame)
+        .unwrap_or(Vec2::new(frame.x * 0.2, -frame.y * 0.2));
+    if tf.translation.truncate() != at {
+        tf.translation = at.extend(tf.translation.z);
+    }
+}
+
+/// `tovaras mail-password`: store the mail password in the keyring.
+pub fn cli(args: &[String]) -> i32 {
+    if let Some(arg) = args.first() {
+        eprintln!("tovaras: unknown argument {arg:?}\nusage: tovaras mail-password");
+        return 1;
+    }
+    let cfg = Config::load().mail;
+    if cfg.host.trim().is_empty() || cfg.user.trim().is_empty() {
+        eprintln!("tovaras: set the mail host and user in config.ron first");
+        return 1;
+    }
+    let account = account(&cfg);
+    eprint!("Password for {account}: ");
+    let mut password = String::new();
+    if let Err(e) = io::stdin().read_line(&mut password) {
+        eprintln!("tovaras: {e}");
+        return 1;
+    }
+    let password = password.trim_end_matches(['\r', '\n']);
+    let stored = keyring::Entry::new(KEYRING_SERVICE, &account)
+        .and_then(|entry| entry.set_password(password));
+    match stored {
+        Ok(()) => {
+            println!("Stored the password for {account} in the keyring");
+            0
+        }
+        Err(e) => {
+            eprintln!("tovaras: {e}");
+            1
+        }
+    }
+}
@@ -0,0 +1,656 @@
+//! Animation manifest: describes the sprite sheet grid, the clips on it and
+//! which clip (and orientation) to show for every (surface, action) pair.
+//!
+//! The built-in manifest is `assets/pet.ron`; a custom one can be installed as
+//! `<data_dir>/pet.ron` (or come with a pet pack, see `packs.rs`), optionally pointing at its own `sheet` next to it, or
+//! at an Aseprite export (`aseprite: Some("pet.json")`) whose frame tags become
+//! the clips — then only the poses (or nothing at all) need to be written.
+//! Likewise `animations: Some("gifs")` names a directory of animated GIF/APNG
+//! files, one per clip, and clips with a `file` each come from their own
+//! horizontal strip image (`"walk": (file: Some("walk.png"), frames: 5, fps: 14.0)`).
+
+use bevy::math::URect;
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::actions::PackAction;
+use crate::recolor::Variant;
+use crate::traits::Traits;
+use crate::{animdir, aseprite, packer, paths, Action, Surface};
+
+const EMBEDDED: &str = include_str!("../assets/pet.ron");
+const MANIFEST_FILE: &str = "pet.ron";
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Deserialize)]
+#[cfg_attr(feature = "devtools", derive(Reflect))]
+pub enum LoopMode {
+    /// Start over after the last frame.
+    #[default]
+    Loop,
+    /// Play backwards, starting over from the last frame.
+    Reverse,
+    /// Forward then backward, and again.
+    PingPong,
+    /// Stop on the last frame.
+    Once,
+    /// Play backwards once and stop on the first frame (e.g. un-hiding).
+    OnceReverse,
+}
+
+impl LoopMode {
+    /// Frame a clip of `len` frames starts on.
+    pub fn first_frame(self, len: usize) -> usize {
+        match self {
+            LoopMode::Reverse | LoopMode::OnceReverse => len.saturating_sub(1),
+            _ => 0,
+        }
+    }
+
+    /// Frame a pass through a clip of `len` frames ends on.
+    pub fn last_frame(self, len: usize) -> usize {
+        match self {
+            LoopMode::Reverse | LoopMode::OnceReverse => 0,
+            _ => len.saturating_sub(1),
+        }
+    }
+
+    /// Frame shown after `frame`; `backwards` carries the ping-pong direction.
+    pub fn next_frame(self, frame: usize, len: usize, backwards: &mut bool) -> usize {
+        let last = len.saturating_sub(1);
+        match self {
+            LoopMode::Loop if frame >= last => 0,
+            LoopMode::Loop => frame + 1,
+            LoopMode::Reverse if frame == 0 => last,
+            LoopMode::Reverse => frame - 1,
+            LoopMode::Once => (frame + 1).min(last),
+            LoopMode::OnceReverse => frame.saturating_sub(1),
+            LoopMode::PingPong => {
+                if last == 0 {
+                    return 0;
+                }
+                if *backwards && frame == 0 {
+                    *backwards = false;
+                } else if !*backwards && frame >= last {
+                    *backwards = true;
+                }
+                if *backwards {
+                    frame - 1
+                } else {
+                    frame + 1
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Clip {
+    /// 0 = main sheet, 1.. = `extra_sheets` in order.
+    #[serde(default)]
+    pub sheet: usize,
+    /// Row within that sheet.
+    #[serde(default)]
+    pub row: usize,
+    /// Own horizontal strip image, relative to the manifest; replaces `sheet`/`row`.
+    #[serde(default)]
+    pub file: Option<String>,
+    pub frames: usize,
+    pub fps: f32,
+    #[serde(default)]
+    pub mode: LoopMode,
+    /// Seconds for individual frames (0-based), e.g. `{26: 2.0}` to hold the
+    /// last frame; the others keep `fps`.
+    #[serde(default)]
+    pub frame_durations: BTreeMap<usize, f32>,
+    /// Clip drawn facing the other way; played instead of mirroring this one
+    /// whenever a pose's `flip_x` applies.
+    #[serde(default)]
+    pub mirrored: Option<String>,
+    /// Clip played instead of this one at night (e.g. sleepy eyes); the
+    /// hours come from `night` in config.ron.
+    #[serde(default)]
+    pub night: Option<String>,
+    /// Sheet pixels (+x right, +y down) to shift the pet by while this clip
+    /// plays, for rows whose character isn't drawn centered in the frame.
+    #[serde(default)]
+    pub anchor: (f32, f32),
+    /// Width and height (sheet pixels) of the part of the frame this clip
+    /// needs, bottom-centered; the window takes this size while it plays.
+    #[serde(default)]
+    pub extent: Option<(f32, f32)>,
+    /// Named events fired when playback reaches the listed frames (0-based),
+    /// e.g. `{"footstep": [1, 3]}`.
+    #[serde(default)]
+    pub events: BTreeMap<String, Vec<usize>>,
+    /// Named attachment points ("head", "hand") in frame pixels (+y down),
+    /// one per frame or a single one for the whole clip. Accessories and
+    /// particles follow them.
+    #[serde(default)]
+    pub points: BTreeMap<String, Vec<(f32, f32)>>,
+    /// First atlas index when the layout isn't a grid (Aseprite); overrides `row`.
+    #[serde(skip)]
+    pub first: Option<usize>,
+    /// Per-frame seconds (Aseprite, `frame_durations`); overrides `fps` when present.
+    #[serde(skip)]
+    pub durations: Vec<f32>,
+}
+
+impl Clip {
+    /// Fold `frame_durations` into the per-frame `durations`.
+    fn resolve_durations(&mut self) {
+        if self.frame_durations.is_empty() {
+            return;
+        }
+        if self.durations.len() != self.frames {
+            self.durations = vec![1.0 / self.fps.max(0.001); self.frames];
+        }
+        for (&frame, &secs) in &self.frame_durations {
+            if let Some(d) = self.durations.get_mut(frame) {
+                *d = secs;
+            }
+        }
+    }
+
+    /// Seconds for one full pass through the clip.
+    pub fn duration(&self) -> f32 {
+        if self.durations.is_empty() {
+            self.frames as f32 / self.fps.max(1.0)
+        } else {
+            self.durations.iter().sum()
+        }
+    }
+}
+
+/// Additional sheet merged below the main one at load time. It must use the
+/// main sheet's grid (same columns and frame size).
+#[derive(Clone, Debug, Deserialize)]
+pub struct ExtraSheet {
+    pub file: String,
+    pub rows: usize,
+}
+
+/// When a mirror applies, relative to the movement sense `dir`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Deserialize)]
+pub enum Flip {
+    #[default]
+    Never,
+    Always,
+    IfForward,
+    IfBackward,
+}
+
+impl Flip {
+    pub fn applies(self, dir: f32) -> bool {
+        match self {
+            Flip::Never => false,
+            Flip::Always => true,
+            Flip::IfForward => dir > 0.0,
+            Flip::IfBackward => dir < 0.0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Pose {
+    pub surface: Surface,
+    pub action: Action,
+    pub clip: String,
+    /// Degrees, counter-clockwise.
+    #[serde(default)]
+    pub rotation: f32,
+    #[serde(default)]
+    pub flip_x: Flip,
+    #[serde(default)]
+    pub flip_y: Flip,
+}
+
+#[derive(Resource, Clone, Debug, Deserialize)]
+pub struct Manifest {
+    /// Optional sheet file, relative to the manifest (embedded sheet otherwise).
+    #[serde(default)]
+    pub sheet: Option<String>,
+    /// Optional Aseprite JSON export, relative to the manifest.
+    #[serde(default)]
+    pub aseprite: Option<String>,
+    /// Optional directory of GIF/APNG animations, relative to the manifest.
+    #[serde(default)]
+    pub animations: Option<String>,
+    /// More sheets extending the main one (e.g. holiday actions).
+    #[serde(default)]
+    pub extra_sheets: Vec<ExtraSheet>,
+    #[serde(default)]
+    pub columns: usize,
+    #[serde(default)]
+    pub rows: usize,
+    #[serde(default)]
+    pub clips: BTreeMap<String, Clip>,
+    #[serde(default = "default_poses")]
+    pub poses: Vec<Pose>,
+    #[serde(default = "default_fallback")]
+    pub fallback: String,
+    /// Clips to vary idle stretches on the floor with, and their weights,
+    /// e.g. `{"idle": 3.0, "idle2": 1.0}`; empty = the idle pose's clip.
+    #[serde(default)]
+    pub idles: BTreeMap<String, f32>,
+    /// Clip played while the pet stumbles to turn around on the floor; the
+    /// landing pose's when `None`.
+    #[serde(default)]
+    pub stumble: Option<String>,
+    /// Actions of its own, e.g. `{"dance": (clip: "dance", surfaces: {Floor: 8.0})}`
+    /// (see actions.rs).
+    #[serde(default)]
+    pub actions: BTreeMap<String, PackAction>,
+    /// Named color variants of the sheet (see `recolor.rs`).
+    #[serde(default)]
+    pub variants: BTreeMap<String, Variant>,
+    /// Variant picked in the settings, if the manifest has it.
+    #[serde(skip)]
+    pub variant: Option<String>,
+    /// Night clips replace their day clips while set (see `night.rs`).
+    #[serde(skip)]
+    pub is_night: bool,
+    #[serde(skip)]
+    pub dir: Option<PathBuf>,
+    /// Explicit atlas rects (Aseprite); empty means a `columns` x `rows` grid.
+    #[serde(skip)]
+    pub rects: Vec<URect>,
+    /// Sheet generated at load time (GIF/APNG directory); wins over `sheet`.
+    #[serde(skip)]
+    pub baked: Option<Image>,
+}
+
+fn default_poses() -> Vec<Pose> {
+    Manifest::embedded().poses
+}
+
+fn default_fallback() -> String {
+    "idle".into()
+}
+
+impl Manifest {
+    pub fn embedded() -> Self {
+        let mut manifest: Self = ron::from_str(EMBEDDED).expect("embedded pet.ron is malformed");
+        manifest.resolve_durations();
+        manifest.validate().expect("embedded pet.ron is invalid");
+        manifest
+    }
+
+    /// The manifest installed in `dir` if present and valid, else the built-in one.
+    pub fn load(dir: PathBuf) -> Self {
+        let path = dir.join(MANIFEST_FILE);
+        if !path.exists() {
+            return Self::embedded();
+        }
+        match Self::try_load(dir) {
+            Ok(manifest) => {
+                info!("Using animation manifest {}", path.display());
+                manifest
+            }
+            Err(e) => {
+                warn!("Ignoring {}: {e}", path.display());
+                Self::embedded()
+            }
+        }
+    }
+
+    /// The manifest installed in `dir`, or why it can't be used.
+    pub fn try_load(dir: PathBuf) -> Result<Self, String> {
+        let text = fs::read_to_string(dir.join(MANIFEST_FILE)).map_err(|e| e.to_string())?;
+        let mut m = ron::from_str::<Self>(&text).map_err(|e| e.to_string())?;
+        m.dir = Some(dir.clone());
+        m.import_aseprite()?;
+        m.import_animations()?;
+        m.resolve_durations();
+        m.import_strips()?;
+        if let Some(traits) = Traits::load(&dir) {
+            m.idles = traits.idles;
+        }
+        m.validate().map(|_| m)
+    }
+
+    /// Merge clips and frame rects from the referenced Aseprite export.
+    fn import_aseprite(&mut self) -> Result<(), String> {
+        let Some(file) = &self.aseprite else {
+            return Ok(());
+        };
+        let rel = PathBuf::from(file);
+        let base = self.dir.clone().unwrap_or_else(paths::data_dir);
+        let sheet = aseprite::load(&base.join(&rel)).map_err(|e| format!("{file}: {e}"))?;
+
+        self.rects = sheet.rects;
+        self.clips.extend(sheet.clips);
+        if self.sheet.is_none() {
+            // the PNG is named relative to the JSON file
+            let image = rel.parent().unwrap_or(&rel).join(&sheet.image);
+            self.sheet = Some(image.to_string_lossy().into_owned());
+        }
+        Ok(())
+    }
+
+    /// Decode the GIF/APNG directory into a generated grid sheet.
+    fn import_animations(&mut self) -> Result<(), String> {
+        let Some(dir) = &self.animations else {
+            return Ok(());
+        };
+        if self.aseprite.is_some() {
+            return Err("aseprite and animations can't be used together".into());
+        }
+        let base = self.dir.clone().unwrap_or_else(paths::data_dir);
+        let anims = animdir::load(&base.join(dir)).map_err(|e| format!("{dir}: {e}"))?;
+
+        self.columns = anims.columns;
+        self.rows = anims.rows;
+        for (name, mut clip) in anims.clips {
+            // a clip declared by hand only contributes its loop mode
+            if let Some(declared) = self.clips.get(&name) {
+                clip.mode = declared.mode;
+            }
+            self.clips.insert(name, clip);
+        }
+        self.baked = Some(anims.image);
+        Ok(())
+    }
+
+    fn resolve_durations(&mut self) {
+        self.clips.values_mut().for_each(Clip::resolve_durations);
+    }
+
+    /// Assemble clips declared with their own strip `file` into a generated sheet.
+    fn import_strips(&mut self) -> Result<(), String> {
+        let with_file = self.clips.values().filter(|c| c.file.is_some()).count();
+        if with_file == 0 {
+            return Ok(());
+        }
+        if with_file != self.clips.len() {
+            return Err("either every clip or no clip has a `file`".into());
+        }
+        if self.aseprite.is_some() || self.animations.is_some() || self.sheet.is_some() {
+            return Err("per-clip files can't be combined with sheet/aseprite/animations".into());
+        }
+
+        let base = self.dir.clone().unwrap_or_else(paths::data_dir);
+        let mut strips = Vec::new();
+        for (name, clip) in &self.clips {
+            let file = clip.file.as_deref().unwrap_or_default();
+            let img = image::open(base.join(file))
+                .map_err(|e| format!("clip {name:?}: {file}: {e}"))?
+                .into_rgba8();
+            if clip.frames == 0 || img.width() < clip.frames as u32 {
+                return Err(format!("clip {name:?}: bad frame count {}", clip.frames));
+            }
+            strips.push(packer::Strip {
+                name: name.clone(),
+                frames: packer::split_strip(&img, clip.frames),
+                durations: if clip.durations.is_empty() {
+                    vec![1.0 / clip.fps.max(0.001); clip.frames]
+                } else {
+                    clip.durations.clone()
+                },
+                mode: clip.mode,
+            });
+        }
+
+        let packed = packer::pack(strips);
+        self.columns = packed.columns;
+        self.rows = packed.rows;
+        self.clips = packed.clips;
+        self.baked = Some(packer::to_image(&packed.sheet));
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.rects.is_empty() && (self.columns == 0 || self.rows == 0) {
+            return Err("columns and rows must be positive".into());
+        }
+        if !self.rects.is_empty() && !self.extra_sheets.is_empty() {
+            return Err("extra_sheets can't be combined with an Aseprite export".into());
+        }
+        for (name, clip) in &self.clips {
+            if let Some((frame, secs)) = clip
+                .frame_durations
+                .iter()
+                .find(|(&f, &s)| f >= clip.frames || s <= 0.0)
+            {
+                return Err(format!(
+                    "clip {name:?}: bad duration {secs} for frame {frame}"
+                ));
+            }
+            if let Some((event, _)) = clip
+                .events
+                .iter()
+                .find(|(_, frames)| frames.iter().any(|&f| f >= clip.frames))
+            {
+                return Err(format!("clip {name:?}: event {event:?} on a missing frame"));
+            }
+            if clip.extent.is_some_and(|(w, h)| w <= 0.0 || h <= 0.0) {
+                return Err(format!("clip {name:?}: extent must be positive"));
+            }
+            if let Some((point, _)) = clip
+                .points
+                .iter()
+                .find(|(_, at)| at.len() != 1 && at.len() != clip.frames)
+            {
+                return Err(format!(
+                    "clip {name:?}: point {point:?} needs 1 or {} positions",
+                    clip.frames
+                ));
+            }
+            if let Some(first) = clip.first {
+                if first + clip.frames > self.rects.len() {
+                    return Err(format!("clip {name:?}: frames out of range"));
+                }
+                continue;
+            }
+            let Some(rows) = self.sheet_rows(clip.sheet) else {
+                return Err(format!("clip {name:?}: no sheet #{}", clip.sheet));
+            };
+            if clip.row >= rows {
+                return Err(format!("clip {name:?}: row {} out of range", clip.row));
+            }
+            if clip.frames == 0 || clip.frames > self.columns {
+                return Err(format!("clip {name:?}: bad frame count {}", clip.frames));
+            }
+        }
+        for (name, variant) in &self.variants {
+            variant
+                .validate()
+                .map_err(|e| format!("variant {name:?}: {e}"))?;
+        }
+        let known = |clip: &String| self.clips.contains_key(clip);
+        if !known(&self.fallback) {
+            return Err(format!("fallback clip {:?} not defined", self.fallback));
+        }
+        if let Some((name, clip)) = self
+            .clips
+            .iter()
+            .find(|(_, c)| c.mirrored.as_ref().is_some_and(|m| !known(m)))
+        {
+            return Err(format!(
+                "clip {name:?}: mirrored clip {:?} not defined",
+                clip.mirrored
+            ));
+        }
+        if let Some((name, clip)) = self
+            .clips
+            .iter()
+            .find(|(_, c)| c.night.as_ref().is_some_and(|n| !known(n)))
+        {
+            return Err(format!(
+                "clip {name:?}: night clip {:?} not defined",
+                clip.night
+            ));
+        }
+        if let Some((name, weight)) = self
+            .idles
+            .iter()
+            .find(|(name, &w)| !known(name) || w <= 0.0)
+        {
+            return Err(format!("idle {name:?}: unknown clip or weight {weight}"));
+        }
+        if let Some(clip) = self.stumble.as_ref().filter(|clip| !known(clip)) {
+            return Err(format!("stumble clip {clip:?} not defined"));
+        }
+        if let Some(pose) = self.poses.iter().find(|p| !known(&p.clip)) {
+            return Err(format!("pose uses undefined clip {:?}", pose.clip));
+        }
+        for (name, action) in &self.actions {
+            if !known(&action.clip) {
+                return Err(format!(
+                    "action {name:?}: clip {:?} not defined",
+                    action.clip
+                ));
+            }
+            if Action::parse(name).is_some_and(|a| !matches!(a, Action::Custom(_))) {
+                return Err(format!("action {name:?}: a built-in action has that name"));
+            }
+            action
+                .validate()
+                .map_err(|e| format!("action {name:?}: {e}"))?;
+        }
+        Ok(())
+    }
+
+    /// Select a color variant; unknown names keep the original colors.
+    pub fn select_variant(&mut self, name: Option<&str>) {
+        self.variant = match name {
+            Some(n) if self.variants.contains_key(n) => Some(n.to_string()),
+            Some(n) => {
+                warn!("Pet has no color variant {n:?}");
+                None
+            }
+            None => None,
+        };
+    }
+
+    pub fn active_variant(&self) -> Option<&Variant> {
+        self.variants.get(self.variant.as_ref()?)
+    }
+
+    /// Use `path` as the main sheet, e.g. one given on the command line.
+    pub fn override_sheet(&mut self, path: PathBuf) {
+        // an absolute path replaces the manifest directory when joined
+        self.sheet = Some(path.to_string_lossy().into_owned());
+        self.baked = None;
+    }
+
+    /// Path of the manifest's own sheet, if it declares one.
+    pub fn sheet_path(&self) -> Option<PathBuf> {
+        let sheet = self.sheet.as_ref()?;
+        Some(self.dir.clone().unwrap_or_else(paths::data_dir).join(sheet))
+    }
+
+    /// Paths of the extra sheets, in declaration order.
+    pub fn extra_sheet_paths(&self) -> Vec<PathBuf> {
+        let base = self.dir.clone().unwrap_or_else(paths::data_dir);
+        self.extra_sheets
+            .iter()
+            .map(|s| base.join(&s.file))
+            .collect()
+    }
+
+    fn sheet_rows(&self, sheet: usize) -> Option<usize> {
+        match sheet {
+            0 => Some(self.rows),
+            n => self.extra_sheets.get(n - 1).map(|s| s.rows),
+        }
+    }
+
+    /// Rows of the merged atlas (main sheet + extra sheets).
+    pub fn total_rows(&self) -> usize {
+        self.rows + self.extra_sheets.iter().map(|s| s.rows).sum::<usize>()
+    }
+
+    pub fn pose(&self, surface: Surface, action: Action) -> Option<&Pose> {
+        self.poses
+            .iter()
+            .find(|p| p.surface == surface && p.action == action)
+    }
+
+    pub fn clip(&self, name: &str) -> &Clip {
+        self.clips
+            .get(name)
+            .unwrap_or_else(|| &self.clips[&self.fallback])
+    }
+
+    /// Clip shown for a (surface, action) pair, its night version at night.
+    pub fn clip_for(&self, surface: Surface, action: Action) -> &Clip {
+        let name = self
+            .pose(surface, action)
+            .map_or(self.fallback.as_str(), |p| p.clip.as_str());
+        self.by_time(self.clip(name))
+    }
+
+    /// `clip`, or its night version at night.
+    pub fn by_time<'a>(&'a self, clip: &'a Clip) -> &'a Clip {
+        match clip.night.as_deref() {
+            Some(night) if self.is_night => self.clip(night),
+            _ => clip,
+        }
+    }
+
+    /// Name of the clip a stumble plays, if the manifest has one to play.
+    pub fn stumble_clip(&self) -> Option<&str> {
+        self.stumble.as_deref().or_else(|| {
+            self.pose(Surface::Floor, Action::Landing)
+                .map(|pose| pose.clip.as_str())
+        })
+    }
+
+    /// Weighted pick among `idles` for a uniform `roll` in 0..1.
+    pub fn pick_idle(&self, roll: f32) -> Option<&str> {
+        let total: f32 = self.idles.values().sum();
+        let mut left = roll * total;
+        for (name, &weight) in &self.idles {
+            if left < weight {
+                return Some(name);
+            }
+            left -= weight;
+        }
+        self.idles.keys().last().map(String::as_str)
+    }
+
+    /// First atlas index of a clip.
+    pub fn clip_start(&self, clip: &Clip) -> usize {
+        let sheet_offset: usize = (0..clip.sheet).filter_map(|i| self.sheet_rows(i)).sum();
+        clip.first
+            .unwrap_or((sheet_offset + clip.row) * self.columns)
+    }
+
+    /// Size of one frame (the window size before scaling) for a sheet image.
+    pub fn frame_size(&self, image: UVec2) -> Vec2 {
+        if self.rects.is_empty() {
+            Vec2::new(
+                image.x as f32 / self.columns as f32,
+                image.y as f32 / self.total_rows() as f32,
+            )
+        } else {
+            // largest frame, so no frame gets clipped by the window
+            self.rects
+                .iter()
+                .fold(Vec2::ZERO, |m, r| m.max(r.size().as_vec2()))
+        }
+    }
+
+    /// Atlas layout for a sheet image of the given size.
+    pub fn layout(&self, image: UVec2) -> TextureAtlasLayout {
+        if self.rects.is_empty() {
+            let frame = self.frame_size(image);
+            TextureAtlasLayout::from_grid(
+                UVec2::new(frame.x as u32, frame.y as u32),
+                self.columns as u32,
+                self.total_rows() as u32,
+                None,
+                None,
+            )
+        } else {
+            let mut layout = TextureAtlasLayout::new_empty(image);
+            for rect in &self.rects {
+                layout.add_texture(*rect);
+            }
+            layout
+        }
+    }
+}
@@ -0,0 +1,225 @@
+//! Mirroring: show the pet of another machine doing exactly what it does
+//! there (e.g. the office PC's pet on the home desktop).
+//!
+//! The primary opts in with `mirror: (serve: true)` and streams its state
+//! over a read-only WebSocket on `0.0.0.0:<mirror.port>`, using the same
+//! `{"type":"state",...}` messages as the WebSocket API; anything a mirror
+//! sends is ignored. An instance started with `--mirror host:port` (or
+//! `mirror: (source: "host:port")`) follows those snapshots instead of
+//! running its own behavior, and reconnects when the link drops. Positions
+//! are scaled from the primary's desktop to the mirror's, so the pet keeps
+//! to the floor and walls of a screen with another resolution.
+
+use bevy::prelude::*;
+use serde_json::Value;
+use std::io::ErrorKind;
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tungstenite::Message;
+
+use crate::config::MirrorConfig;
+use crate::packs::{ActivePack, SwitchPack};
+use crate::pet_window::PetWindow;
+use crate::remote::{subscribe, Listeners, Remote, Snapshot};
+use crate::{Action, FlightKind, Pet, PetState, ScreenGeometry};
+
+/// How often a mirror connection checks for state updates to send.
+const POLL: Duration = Duration::from_millis(50);
+const RETRY_MIN: Duration = Duration::from_secs(2);
+const RETRY_MAX: Duration = Duration::from_secs(60);
+
+/// Stream our pet's state to mirrors on a background thread.
+pub fn serve(cfg: &MirrorConfig, remote: &Remote) {
+    let listener = match TcpListener::bind(("0.0.0.0", cfg.port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            warn!("Can't serve mirrors on port {}: {e}", cfg.port);
+            return;
+        }
+    };
+    info!("Serving mirrors on port {}", cfg.port);
+    let listeners = remote.listeners();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let listeners = listeners.clone();
+            thread::spawn(move || {
+                if let Err(e) = stream_to(stream, listeners) {
+                    debug!("Mirror left: {e}");
+                }
+            });
+        }
+    });
+}
+
+fn stream_to(stream: TcpStream, listeners: Listeners) -> Result<(), String> {
+    let mut socket = tungstenite::accept(stream).map_err(|e| e.to_string())?;
+    socket
+        .get_ref()
+        .set_read_timeout(Some(POLL))
+        .map_err(|e| e.to_string())?;
+    let updates = subscribe(&listeners);
+    loop {
+        match socket.read() {
+            Ok(Message::Close(_)) => return Ok(()),
+            Ok(_) => {} // read-only
+            Err(tungstenite::Error::Io(e))
+                if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(e) => return Err(e.to_string()),
+        }
+        for line in updates.try_iter() {
+            socket
+                .send(Message::Text(line))
+                .map_err(|e| e.to_string())?;
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct Mirror {
+    snapshots: Mutex<Receiver<Snapshot>>,
+    /// Last snapshot followed and when it arrived (app seconds).
+    last: Option<(Snapshot, f32)>,
+}
+
+/// Follow the primary at `source` (`host:port`).
+pub fn connect(source: &str) -> Mirror {
+    let (tx, rx) = mpsc::channel();
+    let url = format!("ws://{source}");
+    info!("Mirroring the pet at {url}");
+    thread::spawn(move || {
+        let mut wait = RETRY_MIN;
+        loop {
+            match follow(&url, &tx) {
+                // the receiving side is gone: the app is shutting down
+                Ok(()) => return,
+                Err(e) => warn!("Mirror link to {url} lost: {e}; retrying in {wait:?}"),
+            }
+            thread::sleep(wait);
+            wait = (wait * 2).min(RETRY_MAX);
+        }
+    });
+    Mirror {
+        snapshots: Mutex::new(rx),
+        last: None,
+    }
+}
+
+fn follow(url: &str, snapshots: &Sender<Snapshot>) -> Result<(), String> {
+    let (mut socket, _) = tungstenite::connect(url).map_err(|e| e.to_string())?;
+    loop {
+        match socket.read().map_err(|e| e.to_string())? {
+            Message::Text(text) => {
+                let Ok(mut msg) = serde_json::from_str::<Value>(&text) else {
+                    continue;
+                };
+                if msg["type"] != "state" {
+                    continue;
+                }
+                // an action of a pack not loaded here (yet) shows as idling
+                let snapshot = serde_json::from_value(msg.clone()).or_else(|_| {
+                    msg["action"] = "Idle".into();
+                    serde_json::from_value(msg)
+                });
+                if let Ok(snapshot) = snapshot {
+                    if snapshots.send(snapshot).is_err() {
+                        return Ok(());
+                    }
+                }
+            }
+            Message::Close(_) => return Err("closed by the primary".into()),
+            _ => {}
+        }
+    }
+}
+
+/// `at` (0..=`from`) on the primary's desktop, scaled to 0..=`to` on ours.
+fn rescale(at: i32, from: i32, to: i32) -> i32 {
+    if from <= 0 {
+        return at.clamp(0, to.max(0));
+    }
+    (i64::from(at) * i64::from(to) / i64::from(from)) as i32
+}
+
+/// Put our pet where the primary's is, at the same place relative to the
+/// desktop; jumps fly on between snapshots.
+pub fn follow_primary(
+    time: Res<Time>,
+    mut mirror: ResMut<Mirror>,
+    active: Res<ActivePack>,
+    screen: Res<ScreenGeometry>,
+    windows: Query<&Window>,
+    mut pets: Query<(&mut PetState, &PetWindow), With<Pet>>,
+    mut switch: EventWriter<SwitchPack>,
+) {
+    let Some(mut now) = mirror.snapshots.get_mut().unwrap().try_iter().last() else {
+        return;
+    };
+    let Ok((mut st, pet_window)) = pets.get_single_mut() else {
+        return;
+    };
+    let Ok(win) = windows.get(pet_window.0) else {
+        return;
+    };
+    let (fw, fh) = (
+        win.resolution.physical_width() as i32,
+        win.resolution.physical_height() as i32,
+    );
+    let (screen_w, screen_h) = screen.desktop(fw, fh);
+    // older primaries don't say; take their desktop for the same as ours
+    if let Some((max_x, max_y)) = now.bounds {
+        now.x = rescale(now.x, max_x, screen_w - fw);
+        now.y = rescale(now.y, max_y, screen_h - fh);
+    }
+    let at = time.elapsed_seconds();
+    let last = mirror.last.take();
+    let pack_changed = last.as_ref().is_none_or(|(l, _)| l.pack != now.pack);
+    if pack_changed && now.pack != active.name {
+        switch.send(SwitchPack(now.pack.clone()));
+    }
+
+    if now.action == Action::Jumping {
+        if st.flight == FlightKind::None {
+            // taking off from where the primary is; the motion system takes it from here
+            st.flight = FlightKind::Parabola;
+            st.flight_from = now.surface;
+            st.wall_target = None;
+        }
+        // keep flying at the primary's pace until the next snapshot
+        if let Some((l, then)) = &last {
+            let dt = (at - then).max(0.001);
+            st.vx = (now.x - l.x) as f32 / dt;
+            st.vy = (now.y - l.y) as f32 / dt;
+        }
+        st.target_x = now.x;
+    } else {
+        st.flight = FlightKind::None;
+        if st.action != now.action {
+            st.idle_clip = None;
+        }
+    }
+    st.surface = now.surface;
+    st.action = now.action;
+    st.move_dir = now.dir;
+    st.facing = now.facing.unwrap_or(now.dir);
+    st.window_pos = IVec2::new(now.x, now.y);
+    mirror.last = Some((now, at));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positions_scale_to_the_local_desktop() {
+        // a 1920x1080 primary's floor and right wall on a 2560x1440 mirror
+        assert_eq!(rescale(980, 980, 1300), 1300);
+        assert_eq!(rescale(0, 1800, 2440), 0);
+        assert_eq!(rescale(900, 1800, 2440), 1220);
+        // no room on the primary
+        assert_eq!(rescale(0, 0, 1300), 0);
+        assert_eq!(rescale(5000, 0, 1300), 1300);
+    }
+}
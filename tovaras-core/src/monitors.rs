@@ -0,0 +1,135 @@
+//! The desktop's monitors, cached in [`Monitors`] so that nothing has to ask
+//! winit (main thread only) for them every frame.
+//!
+//! The list is read again when a window opens or changes scale factor, and
+//! every `CHECK_SECS` to notice monitors being plugged in, unplugged or
+//! switched to another resolution (Bevy has no event for those). The monitor
+//! the pet roams, [`ScreenGeometry`], follows the pet's window when the window
+//! manager moves it onto another one; the pet keeps its place on the desktop.
+
+use bevy::prelude::*;
+use bevy::time::common_conditions::on_real_timer;
+use bevy::window::{WindowCreated, WindowMoved, WindowScaleFactorChanged};
+use bevy::winit::WinitWindows;
+use std::time::Duration;
+
+use crate::animation::Anim;
+use crate::pet_window::{anchor_shift, PetWindow};
+use crate::physics::apply_motion_and_orientation;
+use crate::{Pet, PetState, ScreenGeometry};
+
+/// Seconds between looks at the monitor list.
+const CHECK_SECS: u64 = 2;
+
+/// Keeps [`Monitors`] and [`ScreenGeometry`] up to date. Needs the pet's window.
+pub struct MonitorsPlugin;
+
+impl Plugin for MonitorsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Monitors>().add_systems(
+            Update,
+            (
+                refresh_monitors.run_if(
+                    on_event::<WindowCreated>()
+                        .or_else(on_event::<WindowScaleFactorChanged>())
+                        .or_else(on_real_timer(Duration::from_secs(CHECK_SECS))),
+                ),
+                follow_pet_window,
+            )
+                .chain()
+                .before(apply_motion_and_orientation),
+        );
+    }
+}
+
+/// Every monitor of the desktop, in px on the virtual desktop.
+#[derive(Resource, Default, Debug)]
+pub struct Monitors {
+    pub all: Vec<ScreenGeometry>,
+}
+
+impl Monitors {
+    /// The monitor `point` (virtual desktop px) is on.
+    pub fn containing(&self, point: IVec2) -> Option<ScreenGeometry> {
+        self.all.iter().copied().find(|m| m.contains(point))
+    }
+}
+
+/// A monitor from winit's top-left position and size.
+fn geometry((x, y): (i32, i32), (w, h): (u32, u32)) -> ScreenGeometry {
+    ScreenGeometry {
+        origin: IVec2::new(x, y),
+        size: IVec2::new(w as i32, h as i32),
+    }
+}
+
+/// Read the monitor list again, and the monitor the pet's window is on.
+pub fn refresh_monitors(
+    mut monitors: ResMut<Monitors>,
+    mut screen: ResMut<ScreenGeometry>,
+    pets: Query<&PetWindow, With<Pet>>,
+    winit_windows: NonSend<WinitWindows>,
+) {
+    let Ok(pet_window) = pets.get_single() else {
+        return;
+    };
+    let Some(window) = winit_windows.get_window(pet_window.0) else {
+        return;
+    };
+    let all: Vec<_> = window
+        .available_monitors()
+        .map(|m| geometry(m.position().into(), m.size().into()))
+        .collect();
+    if monitors.all != all {
+        info!("{} monitor(s) on the desktop", all.len());
+        monitors.all = all;
+    }
+    if let Some(m) = window.current_monitor() {
+        roam(&mut screen, geometry(m.position().into(), m.size().into()));
+    }
+}
+
+/// Switch [`ScreenGeometry`] to the monitor the pet's window was moved onto,
+/// keeping the pet where the window went.
+pub fn follow_pet_window(
+    mut moves: EventReader<WindowMoved>,
+    monitors: Res<Monitors>,
+    mut screen: ResMut<ScreenGeometry>,
+    windows: Query<&Window>,
+    mut pets: Query<(&mut PetState, &Anim, &Transform, &PetWindow), With<Pet>>,
+) {
+    let Ok((mut st, anim, tf, pet_window)) = pets.get_single_mut() else {
+        moves.clear();
+        return;
+    };
+    let Some(moved) = moves.read().filter(|m| m.window == pet_window.0).last() else {
+        return;
+    };
+    let Ok(win) = windows.get(pet_window.0) else {
+        return;
+    };
+    let half = IVec2::new(
+        win.resolution.physical_width() as i32,
+        win.resolution.physical_height() as i32,
+    ) / 2;
+    let center = moved.position + half;
+    // every move of our own keeps the window on its monitor
+    if screen.contains(center) {
+        return;
+    }
+    let Some(to) = monitors.containing(center) else {
+        return;
+    };
+    st.window_pos = moved.position - to.origin - anchor_shift(anim, tf);
+    roam(&mut screen, to);
+}
+
+fn roam(screen: &mut ScreenGeometry, to: ScreenGeometry) {
+    if *screen != to {
+        info!(
+            "Roaming a {}x{} monitor at {}",
+            to.size.x, to.size.y, to.origin
+        );
+        *screen = to;
+    }
+}
@@ -0,0 +1,189 @@
+//! Nags about disk space and backups: with `nag` set in config.ron the pet
+//! checks every minute that the `disks` keep `min_free_gib` free and that the
+//! `backups` changed within `max_age_days`. While one of them doesn't, it
+//! nags, more insistently each time:
+//!
+//! 1. it says what is wrong;
+//! 2. it walks to the middle of the screen to say it;
+//! 3. from then on it hops as it says it.
+//!
+//! The first nags are `every_minutes` apart; the wait halves after each one,
+//! down to `MIN_WAIT_SECS`. A backup folder counts as changed when it or
+//! anything directly in it was.
+
+use bevy::prelude::*;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::commands::{Issued, PetCommand, Source};
+use crate::config::NagConfig;
+use crate::sysmon::disk_space;
+use crate::{paths, Action, ScreenGeometry};
+
+/// Seconds between two checks.
+const CHECK_SECS: f32 = 60.0;
+/// Shortest wait between two nags about the same thing.
+const MIN_WAIT_SECS: f32 = 5.0 * 60.0;
+/// Seconds a nag stays up, by insistence.
+const SAY_SECS: [f32; 3] = [8.0, 12.0, 20.0];
+const GIB: f64 = (1u64 << 30) as f64;
+const DAY_SECS: f32 = 24.0 * 3600.0;
+
+/// Something to nag about while it lasts.
+struct Problem {
+    text: String,
+    /// Times nagged so far.
+    nagged: usize,
+    /// Real time of the next nag, s.
+    due: f32,
+    /// Seconds from the next nag to the one after.
+    wait: f32,
+}
+
+#[derive(Resource)]
+pub struct Nag {
+    disks: Vec<PathBuf>,
+    min_free: u64,
+    backups: Vec<PathBuf>,
+    max_age: Duration,
+    every: f32,
+    check: Timer,
+    /// By the path they are about.
+    problems: BTreeMap<PathBuf, Problem>,
+}
+
+impl Nag {
+    pub fn new(cfg: &NagConfig) -> Self {
+        let mut check = Timer::from_seconds(CHECK_SECS, TimerMode::Repeating);
+        // check on the first update
+        check.set_elapsed(check.duration());
+        Self {
+            disks: cfg.disks.iter().map(|d| paths::expand_home(d)).collect(),
+            min_free: (f64::from(cfg.min_free_gib.max(0.0)) * GIB) as u64,
+            backups: cfg.backups.iter().map(|b| paths::expand_home(b)).collect(),
+            max_age: Duration::from_secs_f32(cfg.max_age_days.max(0.0) * DAY_SECS),
+            every: cfg.every_minutes.max(1.0) * 60.0,
+            check,
+            problems: BTreeMap::new(),
+        }
+    }
+
+    /// What is wrong right now, by path.
+    fn find_problems(&self) -> Vec<(PathBuf, String)> {
+        let mut found = Vec::new();
+        for disk in &self.disks {
+            match disk_space(disk) {
+                Ok((_, free)) if free < self.min_free => found.push((
+                    disk.clone(),
+                    format!(
+                        "Only {:.1} GiB left on {}!",
+                        free as f64 / GIB,
+                        disk.display()
+                    ),
+                )),
+                Ok(_) => {}
+                Err(e) => warn_once!("Can't read the space on {}: {e}", disk.display()),
+            }
+        }
+        let now = SystemTime::now();
+        for backup in &self.backups {
+            let text = match last_change(backup) {
+                None => format!("The backup at {} is missing!", backup.display()),
+                Some(at) => {
+                    let age = now.duration_since(at).unwrap_or_default();
+                    if age <= self.max_age {
+                        continue;
+                    }
+                    let days = (age.as_secs_f32() / DAY_SECS).floor();
+                    format!("No backup in {days} days ({})!", backup.display())
+                }
+            };
+            found.push((backup.clone(), text));
+        }
+        found
+    }
+}
+
+/// When `path`, or anything directly in it, last changed.
+fn last_change(path: &Path) -> Option<SystemTime> {
+    let own = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    let Ok(entries) = fs::read_dir(path) else {
+        return Some(own);
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| entry.metadata().and_then(|m| m.modified()).ok())
+        .chain([own])
+        .max()
+}
+
+/// Check the disks and backups now and then, and nag about what is wrong
+/// when it is due.
+pub fn nag_about_problems(
+    time: Res<Time<Real>>,
+    mut nag: ResMut<Nag>,
+    screen: Res<ScreenGeometry>,
+    mut issue: EventWriter<Issued>,
+) {
+    let now = time.elapsed_seconds();
+    let nag = &mut *nag;
+    if nag.check.tick(time.delta()).just_finished() {
+        let found = nag.find_problems();
+        nag.problems.retain(|path, _| {
+            let fixed = !found.iter().any(|(p, _)| p == path);
+            if fixed {
+                info!("No more nagging about {}", path.display());
+            }
+            !fixed
+        });
+        for (path, text) in found {
+            let every = nag.every;
+            let problem = nag.problems.entry(path).or_insert_with(|| {
+                info!("{text}");
+                Problem {
+                    text: String::new(),
+                    nagged: 0,
+                    due: now,
+                    wait: every,
+                }
+            });
+            problem.text = text;
+        }
+    }
+    let Some(problem) = nag
+        .problems
+        .values_mut()
+        .filter(|problem| problem.due <= now)
+        .min_by(|a, b| a.due.total_cmp(&b.due))
+    else {
+        return;
+    };
+    let level = problem.nagged.min(SAY_SECS.len() - 1);
+    problem.nagged += 1;
+    problem.due = now + problem.wait;
+    problem.wait = (problem.wait / 2.0).max(MIN_WAIT_SECS);
+    let insist = match level {
+        0 => None,
+        1 => Some(PetCommand::GoTo {
+            x: screen.size.x / 2,
+        }),
+        _ => Some(PetCommand::SetAction {
+            action: Action::Jumping,
+            dir: None,
+            clip: None,
+            secs: Some(SAY_SECS[level]),
+        }),
+    };
+    if let Some(command) = insist {
+        issue.send(command.by(Source::Ambient));
+    }
+    issue.send(
+        PetCommand::Say {
+            text: problem.text.clone(),
+            secs: Some(SAY_SECS[level]),
+        }
+        .by(Source::Ambient),
+    );
+}
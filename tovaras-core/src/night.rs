@@ -0,0 +1,141 @@
+//! Night dimming: tint the sprite warm/dim at night (or when the desktop is in
+//! dark mode / night light) so it isn't a bright beacon on a dark screen.
+//! The same schedule switches clips that declare a `night` alternative.
+//!
+//! The desktop's settings are asked on a background thread, since
+//! `gsettings` can take a while (or hang with a slow D-Bus).
+
+use bevy::prelude::*;
+use chrono::{Local, Timelike};
+use std::process::Command;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use crate::config::{Config, NightConfig};
+use crate::manifest::Manifest;
+use crate::Pet;
+
+// Clock/desktop re-check interval; spawning gsettings every frame would be silly.
+const POLL_SECS: f32 = 60.0;
+
+#[derive(Resource)]
+pub struct NightState {
+    is_night: bool,
+    /// 0.0 = day colors, 1.0 = full night tint.
+    blend: f32,
+    poll: Timer,
+    /// The desktop's answers, when following it.
+    updates: Option<Mutex<Receiver<bool>>>,
+    /// The desktop's last answer: dark mode or night light.
+    system_dark: bool,
+}
+
+impl NightState {
+    pub fn new(cfg: &NightConfig) -> Self {
+        let updates = (cfg.enabled && cfg.follow_system).then(|| {
+            let (tx, rx) = mpsc::channel();
+            thread::spawn(move || {
+                while tx.send(system_prefers_dark()).is_ok() {
+                    thread::sleep(Duration::from_secs_f32(POLL_SECS));
+                }
+            });
+            Mutex::new(rx)
+        });
+        let is_night = night_now(cfg, false);
+        Self {
+            is_night,
+            blend: if is_night { 1.0 } else { 0.0 },
+            poll: Timer::from_seconds(POLL_SECS, TimerMode::Repeating),
+            updates,
+            system_dark: false,
+        }
+    }
+}
+
+fn is_night_hour(hour: u32, start: u32, end: u32) -> bool {
+    if start <= end {
+        hour >= start && hour < end
+    } else {
+        // wraps over midnight (the usual case, e.g. 21..7)
+        hour >= start || hour < end
+    }
+}
+
+fn gsettings(schema: &str, key: &str) -> Option<String> {
+    let out = Command::new("gsettings")
+        .args(["get", schema, key])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+    Some(String::from_utf8_lossy(&out.stdout).trim().to_string())
+}
+
+/// GNOME-style dark mode or night light; other desktops simply report "no".
+fn system_prefers_dark() -> bool {
+    let dark = gsettings("org.gnome.desktop.interface", "color-scheme")
+        .is_some_and(|v| v.contains("dark"));
+    let night_light = gsettings(
+        "org.gnome.settings-daemon.plugins.color",
+        "night-light-enabled",
+    )
+    .is_some_and(|v| v == "true");
+    dark || night_light
+}
+
+fn night_now(cfg: &NightConfig, system_dark: bool) -> bool {
+    if !cfg.enabled {
+        return false;
+    }
+    is_night_hour(Local::now().hour(), cfg.start_hour, cfg.end_hour)
+        || (cfg.follow_system && system_dark)
+}
+
+/// Swap in the manifest's night clips (and back) when night starts or ends.
+pub fn switch_night_clips(night: Res<NightState>, mut manifest: ResMut<Manifest>) {
+    if manifest.is_night != night.is_night {
+        manifest.is_night = night.is_night;
+    }
+}
+
+/// Fade the pet's color modulation toward the configured night tint.
+pub fn update_night_tint(
+    time: Res<Time>,
+    cfg: Res<Config>,
+    mut night: ResMut<NightState>,
+    mut q: Query<&mut Sprite, With<Pet>>,
+) {
+    let night = &mut *night;
+    let answer = night
+        .updates
+        .as_mut()
+        .and_then(|updates| updates.get_mut().unwrap().try_iter().last());
+    if let Some(dark) = answer {
+        night.system_dark = dark;
+    }
+    if night.poll.tick(time.delta()).just_finished() || answer.is_some() {
+        night.is_night = night_now(&cfg.night, night.system_dark);
+    }
+
+    let target = if night.is_night { 1.0 } else { 0.0 };
+    let step = time.delta_seconds() / cfg.night.fade_secs.max(0.01);
+    night.blend = if night.blend < target {
+        (night.blend + step).min(target)
+    } else {
+        (night.blend - step).max(target)
+    };
+
+    let (r, g, b) = cfg.night.tint;
+    let k = night.blend;
+    let color = Color::srgb(
+        1.0 + (r - 1.0) * k,
+        1.0 + (g - 1.0) * k,
+        1.0 + (b - 1.0) * k,
+    );
+    for mut sprite in &mut q {
+        if sprite.color != color {
+            sprite.color = color;
+        }
+    }
+}
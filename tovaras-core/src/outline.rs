@@ -0,0 +1,107 @@
+//! Outline / soft glow around the pet, so it stays visible on wallpapers of
+//! the same colors. Baked into the sheet on load: every frame gets a distance
+//! field of its opaque pixels, and the halo is painted underneath the art.
+
+use bevy::prelude::*;
+
+use crate::config::{OutlineConfig, OutlineStyle};
+use crate::SCALE;
+
+/// Pixels at least this opaque cast the halo.
+const SOLID_ALPHA: u8 = 128;
+
+pub fn bake(img: &mut Image, frames: &[URect], cfg: &OutlineConfig) {
+    // thickness is given in screen pixels; the sheet is drawn scaled down
+    let reach = (cfg.thickness / SCALE).max(1.0);
+    let color = Srgba::from(Color::srgb(cfg.color.0, cfg.color.1, cfg.color.2)).to_u8_array();
+    for rect in frames {
+        bake_frame(img, *rect, reach, color, cfg);
+    }
+}
+
+fn bake_frame(img: &mut Image, rect: URect, reach: f32, color: [u8; 4], cfg: &OutlineConfig) {
+    let sheet_w = img.width() as usize;
+    let x0 = rect.min.x as usize;
+    let y0 = rect.min.y as usize;
+    let w = (rect.max.x.min(img.width()) as usize).saturating_sub(x0);
+    let h = (rect.max.y.min(img.height()) as usize).saturating_sub(y0);
+    if w == 0 || h == 0 {
+        return;
+    }
+    let at = |x: usize, y: usize| ((y0 + y) * sheet_w + x0 + x) * 4;
+
+    // chamfer distance to the nearest solid pixel, two passes
+    let mut dist = vec![f32::INFINITY; w * h];
+    for y in 0..h {
+        for x in 0..w {
+            if img.data[at(x, y) + 3] >= SOLID_ALPHA {
+                dist[y * w + x] = 0.0;
+            }
+        }
+    }
+    const D: f32 = std::f32::consts::SQRT_2;
+    for y in 0..h {
+        for x in 0..w {
+            let mut d = dist[y * w + x];
+            if x > 0 {
+                d = d.min(dist[y * w + x - 1] + 1.0);
+            }
+            if y > 0 {
+                d = d.min(dist[(y - 1) * w + x] + 1.0);
+                if x > 0 {
+                    d = d.min(dist[(y - 1) * w + x - 1] + D);
+                }
+                if x + 1 < w {
+                    d = d.min(dist[(y - 1) * w + x + 1] + D);
+                }
+            }
+            dist[y * w + x] = d;
+        }
+    }
+    for y in (0..h).rev() {
+        for x in (0..w).rev() {
+            let mut d = dist[y * w + x];
+            if x + 1 < w {
+                d = d.min(dist[y * w + x + 1] + 1.0);
+            }
+            if y + 1 < h {
+                d = d.min(dist[(y + 1) * w + x] + 1.0);
+                if x + 1 < w {
+                    d = d.min(dist[(y + 1) * w + x + 1] + D);
+                }
+                if x > 0 {
+                    d = d.min(dist[(y + 1) * w + x - 1] + D);
+                }
+            }
+            dist[y * w + x] = d;
+        }
+    }
+
+    for y in 0..h {
+        for x in 0..w {
+            let d = dist[y * w + x];
+            if d == 0.0 || d > reach {
+                continue;
+            }
+            let halo = match cfg.style {
+                OutlineStyle::Outline => 1.0,
+                OutlineStyle::Glow => (1.0 - d / reach).powi(2),
+            } * cfg.opacity.clamp(0.0, 1.0)
+                * (color[3] as f32 / 255.0);
+
+            // art over halo
+            let i = at(x, y);
+            let px = &mut img.data[i..i + 4];
+            let src_a = px[3] as f32 / 255.0;
+            let out_a = src_a + halo * (1.0 - src_a);
+            if out_a <= 0.0 {
+                continue;
+            }
+            for c in 0..3 {
+                let v = (px[c] as f32 * src_a + color[c] as f32 * halo * (1.0 - src_a)) / out_a;
+                px[c] = v.round() as u8;
+            }
+            px[3] = (out_a * 255.0).round() as u8;
+        }
+    }
+}
@@ -0,0 +1,96 @@
+//! Full-screen, transparent, click-through overlay window for effects that
+//! live outside the tiny pet window (footprints, ...).
+//!
+//! Overlay sprites must be put on [`OVERLAY_LAYER`] so only the overlay camera
+//! draws them; positions on the pet's monitor are converted with [`Overlay::to_world`].
+
+use bevy::prelude::*;
+use bevy::render::camera::RenderTarget;
+use bevy::render::view::RenderLayers;
+use bevy::window::{WindowLevel, WindowRef, WindowResolution};
+
+use crate::ScreenGeometry;
+
+pub const OVERLAY_LAYER: usize = 1;
+
+#[derive(Component)]
+pub struct OverlayWindow;
+
+#[derive(Resource, Default)]
+pub struct Overlay {
+    pub window: Option<Entity>,
+    /// Overlay size in physical pixels (== monitor pixels); it covers the pet's monitor.
+    pub size: Vec2,
+    /// The monitor it was last fitted to.
+    fitted: Option<ScreenGeometry>,
+}
+
+impl Overlay {
+    /// Pixel on the pet's monitor (top-left origin, y down) -> overlay world coordinates.
+    pub fn to_world(&self, screen: Vec2) -> Vec2 {
+        Vec2::new(screen.x - self.size.x / 2.0, self.size.y / 2.0 - screen.y)
+    }
+}
+
+/// Open the overlay window and its camera. Only called when some effect needs it.
+pub fn spawn_overlay(commands: &mut Commands, overlay: &mut Overlay) {
+    if overlay.window.is_some() {
+        return;
+    }
+    // Fallback size until the monitor is known
+    let size = Vec2::new(1920.0, 1080.0);
+    let mut resolution = WindowResolution::new(size.x, size.y);
+    resolution.set_scale_factor_override(Some(1.0));
+
+    let mut window = Window {
+        title: "tovaras-overlay".into(),
+        name: Some("tovaras-overlay".into()),
+        resolution,
+        resizable: false,
+        decorations: false,
+        transparent: true,
+        focused: false,
+        window_level: WindowLevel::AlwaysOnTop,
+        position: WindowPosition::At(IVec2::ZERO),
+        ..default()
+    };
+    // clicks go straight through to whatever is underneath
+    window.cursor.hit_test = false;
+
+    let entity = commands.spawn((window, OverlayWindow)).id();
+    commands.spawn((
+        Camera2dBundle {
+            camera: Camera {
+                target: RenderTarget::Window(WindowRef::Entity(entity)),
+                clear_color: ClearColorConfig::Custom(Color::NONE),
+                ..default()
+            },
+            ..default()
+        },
+        RenderLayers::layer(OVERLAY_LAYER),
+    ));
+
+    overlay.window = Some(entity);
+    overlay.size = size;
+}
+
+/// Cover the monitor the pet roams with the overlay, again when it changes.
+pub fn fit_overlay_to_monitor(
+    screen: Res<ScreenGeometry>,
+    mut overlay: ResMut<Overlay>,
+    mut windows: Query<&mut Window, With<OverlayWindow>>,
+) {
+    if overlay.fitted == Some(*screen) || overlay.window.is_none() {
+        return;
+    }
+    let Ok(mut win) = windows.get_single_mut() else {
+        return;
+    };
+
+    let size = screen.size.max(IVec2::ONE);
+    win.position = screen.window_at(IVec2::ZERO);
+    win.resolution
+        .set_physical_resolution(size.x as u32, size.y as u32);
+    overlay.size = size.as_vec2();
+    overlay.fitted = Some(*screen);
+}
@@ -0,0 +1,147 @@
+//! `tovaras pack-frames DIR`: build a sheet and manifest from loose frames.
+//!
+//! `DIR` holds one folder per clip, each with numbered PNGs:
+//!
+//! ```text
+//! mypet/idle/01.png 02.png ...
+//! mypet/walk/01.png 02.png ...
+//! ```
+//!
+//! The result is `DIR/sheet.png` and `DIR/pet.ron`, ready to be used as
+//! `<data_dir>/pet.ron` or zipped into a pack. Folders named like the
+//! built-in clips (idle, walk, climb, jump, land, hide, sleep, ...) get the
+//! built-in poses.
+
+use image::RgbaImage;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::manifest::{LoopMode, Manifest};
+use crate::packer::{self, Strip};
+
+const SHEET_FILE: &str = "sheet.png";
+const DEFAULT_FPS: f32 = 10.0;
+
+/// Leading number of a file name, so `2.png` sorts before `10.png`.
+fn frame_number(path: &Path) -> Option<u64> {
+    let stem = path.file_stem()?.to_str()?;
+    let digits: String = stem
+        .chars()
+        .rev()
+        .take_while(|c| c.is_ascii_digit())
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .collect();
+    digits.parse().ok()
+}
+
+fn read_clip(dir: &Path, fps: f32) -> Result<Option<Strip>, String> {
+    let Some(name) = dir.file_name().and_then(|n| n.to_str()) else {
+        return Ok(None);
+    };
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .map_err(|e| format!("{}: {e}", dir.display()))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|e| e.eq_ignore_ascii_case("png")))
+        .collect();
+    if files.is_empty() {
+        return Ok(None);
+    }
+    files.sort_by_key(|p| (frame_number(p), p.clone()));
+
+    let frames = files
+        .iter()
+        .map(|p| {
+            image::open(p)
+                .map(|img| img.into_rgba8())
+                .map_err(|e| format!("{}: {e}", p.display()))
+        })
+        .collect::<Result<Vec<RgbaImage>, _>>()?;
+    Ok(Some(Strip {
+        name: name.to_string(),
+        durations: vec![1.0 / fps; frames.len()],
+        frames,
+        mode: LoopMode::Loop,
+    }))
+}
+
+pub fn pack_dir(dir: &Path, fps: f32) -> Result<PathBuf, String> {
+    let mut subdirs: Vec<PathBuf> = fs::read_dir(dir)
+        .map_err(|e| format!("{}: {e}", dir.display()))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.is_dir())
+        .collect();
+    subdirs.sort();
+
+    let mut strips = Vec::new();
+    for sub in &subdirs {
+        strips.extend(read_clip(sub, fps)?);
+    }
+    if strips.is_empty() {
+        return Err("no clip folders with PNG frames found".into());
+    }
+    let fallback = strips
+        .iter()
+        .find(|s| s.name == "idle")
+        .unwrap_or(&strips[0])
+        .name
+        .clone();
+    let packed = packer::pack(strips);
+
+    // built-in poses for the clips that exist
+    let mut poses = String::new();
+    for pose in Manifest::embedded().poses {
+        if packed.clips.contains_key(&pose.clip) {
+            let _ = writeln!(
+                poses,
+                "        (surface: {:?}, action: {:?}, clip: {:?}, rotation: {:?}, flip_x: {:?}, flip_y: {:?}),",
+                pose.surface, pose.action, pose.clip, pose.rotation, pose.flip_x, pose.flip_y
+            );
+        }
+    }
+
+    let manifest = packed.manifest_ron(
+        "Generated by `tovaras pack-frames`",
+        SHEET_FILE,
+        &poses,
+        &fallback,
+    );
+    packed
+        .sheet
+        .save(dir.join(SHEET_FILE))
+        .map_err(|e| e.to_string())?;
+    let out = dir.join("pet.ron");
+    fs::write(&out, manifest).map_err(|e| e.to_string())?;
+    Ok(out)
+}
+
+/// `tovaras pack-frames DIR [--fps N]`; returns the exit code.
+pub fn cli(args: &[String]) -> i32 {
+    let fps = args
+        .iter()
+        .position(|a| a == "--fps")
+        .and_then(|i| args.get(i + 1))
+        .and_then(|v| v.parse::<f32>().ok())
+        .filter(|v| *v > 0.0)
+        .unwrap_or(DEFAULT_FPS);
+    let Some(dir) = args.first().filter(|a| !a.starts_with("--")) else {
+        eprintln!("usage: tovaras pack-frames DIR [--fps N]");
+        return 1;
+    };
+    match pack_dir(Path::new(dir), fps) {
+        Ok(out) => {
+            println!(
+                "Wrote {} and {}",
+                Path::new(dir).join(SHEET_FILE).display(),
+                out.display()
+            );
+            0
+        }
+        Err(e) => {
+            eprintln!("tovaras: {e}");
+            1
+        }
+    }
+}
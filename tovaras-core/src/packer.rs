@@ -0,0 +1,131 @@
+//! Packing loose animation frames into a grid sheet: one row per clip, the
+//! cell as large as the largest frame. Smaller frames are centered
+//! horizontally and rest on the bottom edge, so feet stay on the floor.
+
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::render::texture::ImageSampler;
+use image::{imageops, RgbaImage};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::manifest::{Clip, LoopMode};
+
+/// Frames of one clip, in playback order.
+pub struct Strip {
+    pub name: String,
+    pub frames: Vec<RgbaImage>,
+    /// Seconds per frame.
+    pub durations: Vec<f32>,
+    pub mode: LoopMode,
+}
+
+pub struct Packed {
+    pub sheet: RgbaImage,
+    pub columns: usize,
+    pub rows: usize,
+    pub clips: BTreeMap<String, Clip>,
+}
+
+/// Cut a horizontal strip into `frames` equally wide frames.
+pub fn split_strip(strip: &RgbaImage, frames: usize) -> Vec<RgbaImage> {
+    let w = strip.width() / frames.max(1) as u32;
+    (0..frames as u32)
+        .map(|i| imageops::crop_imm(strip, i * w, 0, w, strip.height()).to_image())
+        .collect()
+}
+
+pub fn pack(strips: Vec<Strip>) -> Packed {
+    let cell = strips
+        .iter()
+        .flat_map(|s| &s.frames)
+        .fold(UVec2::ONE, |m, f| m.max(UVec2::new(f.width(), f.height())));
+    let columns = strips.iter().map(|s| s.frames.len()).max().unwrap_or(1);
+    let rows = strips.len().max(1);
+
+    let mut sheet = RgbaImage::new(cell.x * columns as u32, cell.y * rows as u32);
+    let mut clips = BTreeMap::new();
+    for (row, strip) in strips.into_iter().enumerate() {
+        for (col, frame) in strip.frames.iter().enumerate() {
+            let x = col as u32 * cell.x + (cell.x - frame.width()) / 2;
+            let y = row as u32 * cell.y + cell.y - frame.height();
+            imageops::replace(&mut sheet, frame, x as i64, y as i64);
+        }
+        let total: f32 = strip.durations.iter().sum();
+        clips.insert(
+            strip.name,
+            Clip {
+                sheet: 0,
+                row,
+                file: None,
+                frames: strip.frames.len(),
+                fps: strip.frames.len() as f32 / total.max(f32::EPSILON),
+                mode: strip.mode,
+                frame_durations: BTreeMap::new(),
+                events: BTreeMap::new(),
+                points: BTreeMap::new(),
+                mirrored: None,
+                night: None,
+                anchor: (0.0, 0.0),
+                extent: None,
+                first: None,
+                durations: strip.durations,
+            },
+        );
+    }
+
+    Packed {
+        sheet,
+        columns,
+        rows,
+        clips,
+    }
+}
+
+impl Packed {
+    /// A complete `pet.ron` for this sheet saved as `sheet`; `poses` are
+    /// ready-made RON lines.
+    pub fn manifest_ron(&self, comment: &str, sheet: &str, poses: &str, fallback: &str) -> String {
+        format!(
+            "// {comment}\n(\n    sheet: Some({sheet:?}),\n    columns: {},\n    rows: {},\n    clips: {},\n    poses: [\n{poses}    ],\n    fallback: {fallback:?},\n)\n",
+            self.columns,
+            self.rows,
+            self.clips_ron(),
+        )
+    }
+
+    /// RON for the `clips` map of a manifest describing this sheet.
+    fn clips_ron(&self) -> String {
+        let mut out = String::from("{\n");
+        for (name, clip) in &self.clips {
+            let mode = match clip.mode {
+                LoopMode::Loop => String::new(),
+                other => format!(", mode: {other:?}"),
+            };
+            let _ = writeln!(
+                out,
+                "        {name:?}: (row: {}, frames: {}, fps: {:.3}{mode}),",
+                clip.row, clip.frames, clip.fps
+            );
+        }
+        out + "    }"
+    }
+}
+
+/// A packed sheet as a texture, sampled like the regular sheets.
+pub fn to_image(sheet: &RgbaImage) -> Image {
+    let mut image = Image::new(
+        Extent3d {
+            width: sheet.width(),
+            height: sheet.height(),
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        sheet.as_raw().clone(),
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD | RenderAssetUsages::MAIN_WORLD,
+    );
+    image.sampler = ImageSampler::nearest();
+    image
+}
@@ -0,0 +1,377 @@
+//! Pet packs: the distribution unit for community pets.
+//!
+//! A `.tovaras` file is a zip archive holding everything a pet needs:
+//!
+//! ```text
+//! pet.ron        animation manifest (required), paths are relative to the pack
+//! pack.ron       optional metadata: (name: "Cat", author: "...", version: "1.0", description: "...")
+//! *.png          sheets referenced by the manifest
+//! phrases.ron    optional lines the pet says now and then (see `phrases.rs`)
+//! ```
+//!
+//! Installed packs are unpacked to `<data_dir>/packs/<name>/`, where `name` is
+//! the archive's file stem (numbered when taken) or given with `--as`. A pack
+//! is only installed when its manifest loads and its sheets are there.
+//! `tovaras packs enable <name>` picks one (it sets `pack` in config.ron).
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::actions::Actions;
+use crate::animation::{Anim, SheetInfo};
+use crate::config::{self, Config};
+use crate::fallback::{placeholder_sheet, SheetRetry};
+use crate::manifest::Manifest;
+use crate::skins::SkinCatalog;
+use crate::traits;
+use crate::{ipc, paths, Pet};
+
+pub const EXTENSION: &str = "tovaras";
+const MANIFEST_FILE: &str = "pet.ron";
+pub const INFO_FILE: &str = "pack.ron";
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PackInfo {
+    pub name: String,
+    pub author: String,
+    pub version: String,
+    pub description: String,
+}
+
+pub fn packs_dir() -> PathBuf {
+    paths::data_dir().join("packs")
+}
+
+/// Directory of an installed pack (which may not exist).
+pub fn pack_dir(name: &str) -> PathBuf {
+    packs_dir().join(name)
+}
+
+/// Where to look for `pet.ron`: the selected pack if installed, else the data dir.
+pub fn manifest_dir(pack: Option<&str>) -> PathBuf {
+    match pack {
+        Some(name) if pack_dir(name).join(MANIFEST_FILE).is_file() => pack_dir(name),
+        Some(name) => {
+            warn!("Pack {name:?} is not installed, using the default pet");
+            paths::data_dir()
+        }
+        None => paths::data_dir(),
+    }
+}
+
+pub fn read_info(dir: &Path) -> PackInfo {
+    fs::read_to_string(dir.join(INFO_FILE))
+        .ok()
+        .and_then(|text| ron::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+/// Installed packs, sorted by directory name.
+pub fn list() -> Vec<(String, PackInfo)> {
+    let Ok(entries) = fs::read_dir(packs_dir()) else {
+        return Vec::new();
+    };
+    let mut packs: Vec<_> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().join(MANIFEST_FILE).is_file())
+        .filter_map(|e| {
+            let name = e.file_name().to_str()?.to_string();
+            Some((name, read_info(&e.path())))
+        })
+        .collect();
+    packs.sort_by(|a, b| a.0.cmp(&b.0));
+    packs
+}
+
+/// Whether `name` can be a directory under the packs directory.
+pub fn valid_name(name: &str) -> bool {
+    !name.is_empty() && !name.starts_with('.') && !name.contains(['/', '\\'])
+}
+
+/// `stem`, or `stem-2`, `stem-3`, ... when a pack of that name is installed.
+fn free_name(stem: &str) -> String {
+    (1..)
+        .map(|n| match n {
+            1 => stem.to_string(),
+            n => format!("{stem}-{n}"),
+        })
+        .find(|name| !pack_dir(name).exists())
+        .unwrap_or_default()
+}
+
+/// Unpack a `.tovaras` archive into the packs directory as `name` (default:
+/// a free name after the file stem); returns the pack name.
+pub fn install(file: &Path, name: Option<&str>) -> Result<String, String> {
+    let name = match name {
+        Some(name) if !valid_name(name) => {
+            return Err(format!("{name:?} is not a valid pack name"))
+        }
+        Some(name) if pack_dir(name).exists() => {
+            return Err(format!("pack {name:?} is already installed"))
+        }
+        Some(name) => name.to_string(),
+        None => file
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .filter(|s| valid_name(s))
+            .map(free_name)
+            .ok_or_else(|| format!("{}: bad pack file name", file.display()))?,
+    };
+    let dest = pack_dir(&name);
+
+    let archive = fs::File::open(file).map_err(|e| format!("{}: {e}", file.display()))?;
+    let mut zip = zip::ZipArchive::new(archive).map_err(|e| e.to_string())?;
+    if zip.index_for_name(MANIFEST_FILE).is_none() {
+        return Err(format!("no {MANIFEST_FILE} at the top of the archive"));
+    }
+
+    // unpack next to the final place, then move it in one step
+    let staging = packs_dir().join(format!(".{name}.partial"));
+    let _ = fs::remove_dir_all(&staging);
+    let result = extract(&mut zip, &staging)
+        .and_then(|_| verify(&staging))
+        .and_then(|_| fs::rename(&staging, &dest).map_err(|e| e.to_string()));
+    if result.is_err() {
+        let _ = fs::remove_dir_all(&staging);
+    }
+    result.map(|_| name)
+}
+
+fn extract(zip: &mut zip::ZipArchive<fs::File>, into: &Path) -> Result<(), String> {
+    for i in 0..zip.len() {
+        let mut entry = zip.by_index(i).map_err(|e| e.to_string())?;
+        // refuses absolute paths and `..`, so nothing lands outside the pack
+        let Some(rel) = entry.enclosed_name() else {
+            return Err(format!("unsafe path in archive: {}", entry.name()));
+        };
+        let out = into.join(rel);
+        if entry.is_dir() {
+            fs::create_dir_all(&out).map_err(|e| e.to_string())?;
+            continue;
+        }
+        if let Some(parent) = out.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let mut file = fs::File::create(&out).map_err(|e| e.to_string())?;
+        io::copy(&mut entry, &mut file).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Check that an unpacked pack's manifest loads and its sheets are there.
+fn verify(dir: &Path) -> Result<(), String> {
+    let manifest =
+        Manifest::try_load(dir.to_path_buf()).map_err(|e| format!("{MANIFEST_FILE}: {e}"))?;
+    let sheets = manifest.sheet_path().into_iter();
+    for sheet in sheets.chain(manifest.extra_sheet_paths()) {
+        if !sheet.is_file() {
+            let shown = sheet.strip_prefix(dir).unwrap_or(&sheet);
+            return Err(format!("missing sheet {}", shown.display()));
+        }
+    }
+    Ok(())
+}
+
+/// Make `name` (`None` = the built-in pet) the configured pet, and have the
+/// running pet change right away.
+pub fn enable(name: Option<&str>) -> Result<(), String> {
+    if let Some(name) = name {
+        if !valid_name(name) || !pack_dir(name).join(MANIFEST_FILE).is_file() {
+            return Err(format!("pack {name:?} is not installed"));
+        }
+    }
+    config::set_pack(name)?;
+    // not running is fine: the config is read at the next start
+    let _ = ipc::forward(&format!("skin {}", name.unwrap_or_default()));
+    Ok(())
+}
+
+pub fn remove(name: &str) -> Result<(), String> {
+    let dir = pack_dir(name);
+    if name.contains(['/', '\\']) || name.starts_with('.') || !dir.is_dir() {
+        return Err(format!("pack {name:?} is not installed"));
+    }
+    fs::remove_dir_all(&dir).map_err(|e| format!("{}: {e}", dir.display()))
+}
+
+/// The pack the running pet comes from (`None` = data dir / built-in pet).
+#[derive(Resource)]
+pub struct ActivePack {
+    pub name: Option<String>,
+    /// `--sheet` / `config.ron` sheet; dropped once another pack is picked.
+    pub sheet_override: Option<PathBuf>,
+    /// Color variant from `config.ron`, used by every pack that has it.
+    pub variant: Option<String>,
+}
+
+impl ActivePack {
+    /// The pet configured in `config.ron`; `sheet` (from `--sheet`) wins over its sheet.
+    pub fn from_config(config: &Config, sheet: Option<PathBuf>) -> Self {
+        Self {
+            name: config.pack.clone(),
+            sheet_override: sheet.or_else(|| config.sheet_path()),
+            variant: config.variant.clone(),
+        }
+    }
+
+    pub fn load_manifest(&self) -> Manifest {
+        let mut manifest = Manifest::load(manifest_dir(self.name.as_deref()));
+        if let Some(path) = &self.sheet_override {
+            manifest.override_sheet(path.clone());
+        }
+        manifest.select_variant(self.variant.as_deref());
+        manifest
+    }
+}
+
+/// Ask the running pet to change into another pack (`None` = default pet).
+#[derive(Event, Clone, Debug)]
+pub struct SwitchPack(pub Option<String>);
+
+/// `P` on the focused pet window cycles through the installed packs.
+pub fn cycle_pack_hotkey(
+    keys: Res<ButtonInput<KeyCode>>,
+    active: Res<ActivePack>,
+    mut switch: EventWriter<SwitchPack>,
+) {
+    if !keys.just_pressed(KeyCode::KeyP) {
+        return;
+    }
+    // default pet first, then the packs in list order, wrapping around
+    let mut choices = vec![None];
+    choices.extend(list().into_iter().map(|(name, _)| Some(name)));
+    let current = choices.iter().position(|c| *c == active.name).unwrap_or(0);
+    switch.send(SwitchPack(choices[(current + 1) % choices.len()].clone()));
+}
+
+/// Swap manifest and sheet in place; the window is resized by
+/// `finalize_after_load` while position and pet state carry over.
+#[allow(clippy::too_many_arguments)]
+pub fn switch_pack(
+    mut requests: EventReader<SwitchPack>,
+    mut active: ResMut<ActivePack>,
+    mut manifest: ResMut<Manifest>,
+    mut actions: ResMut<Actions>,
+    mut retry: ResMut<SheetRetry>,
+    mut sheet: ResMut<SheetInfo>,
+    mut images: ResMut<Assets<Image>>,
+    catalog: Res<SkinCatalog>,
+    mut pets: Query<(&mut Anim, &mut TextureAtlas), With<Pet>>,
+) {
+    let Some(SwitchPack(name)) = requests.read().last().cloned() else {
+        return;
+    };
+    if name == active.name {
+        return;
+    }
+    if name
+        .as_ref()
+        .is_some_and(|n| !pack_dir(n).join(MANIFEST_FILE).is_file())
+    {
+        warn!("Can't switch to pack {name:?}: not installed");
+        return;
+    }
+    info!(
+        "Switching to pack {}",
+        name.as_deref().unwrap_or("(default)")
+    );
+
+    // the startup sheet override would hide the new pack's own sheet
+    if name.is_some() {
+        active.sheet_override = None;
+    }
+    active.name = name;
+    *manifest = active.load_manifest();
+    actions.load_pack(&manifest);
+    *retry = SheetRetry::new(&manifest);
+
+    // the old sheet doesn't have the new pet's clips; its placeholder does
+    sheet.replace_texture(&mut images, placeholder_sheet(&manifest));
+    sheet.start_loading(&catalog, &manifest);
+
+    // restart on the new pet's idle clip; the next motion tick picks the pose
+    let idle = manifest.clip(&manifest.fallback);
+    let start = manifest.clip_start(idle);
+    for (mut anim, mut atlas) in &mut pets {
+        *anim = Anim::new(start, idle);
+        atlas.index = anim.first_index();
+    }
+}
+
+/// `tovaras packs <list | install FILE [--as NAME] | remove NAME | enable NAME |
+/// combine A B NAME>`; returns the exit code.
+pub fn cli(args: &[String]) -> i32 {
+    let arg = |i: usize| args.get(i).map(String::as_str);
+    let result = match (arg(0), arg(1)) {
+        (Some("list") | None, _) => {
+            let packs = list();
+            if packs.is_empty() {
+                println!("No packs installed in {}", packs_dir().display());
+            }
+            let enabled = Config::load().pack;
+            for (name, info) in packs {
+                let title = if info.name.is_empty() {
+                    &name
+                } else {
+                    &info.name
+                };
+                let mark = if enabled.as_deref() == Some(&name) {
+                    '*'
+                } else {
+                    ' '
+                };
+                let mut line = format!("{mark} {name:<20} {title}");
+                if !info.version.is_empty() {
+                    line += &format!(" {}", info.version);
+                }
+                if !info.author.is_empty() {
+                    line += &format!(" by {}", info.author);
+                }
+                println!("{line}");
+            }
+            Ok(())
+        }
+        (Some("install"), Some(file)) => {
+            let name = match (arg(2), arg(3)) {
+                (Some("--as"), Some(name)) => Some(name),
+                _ => None,
+            };
+            install(Path::new(file), name).map(|name| {
+                println!("Installed {name}; use it with `tovaras packs enable {name}`");
+            })
+        }
+        (Some("remove"), Some(name)) => remove(name).and_then(|_| {
+            println!("Removed {name}");
+            if Config::load().pack.as_deref() != Some(name) {
+                return Ok(());
+            }
+            println!("It was enabled; back to the default pet");
+            enable(None)
+        }),
+        (Some("enable"), Some(name)) => {
+            let pack = Some(name).filter(|n| *n != "default");
+            enable(pack).map(|_| println!("Enabled {name}"))
+        }
+        (Some("combine"), Some(a)) => match (arg(2), arg(3)) {
+            (Some(b), Some(name)) => traits::combine(a, b, name).map(|_| {
+                println!("Installed {name}; use it with `tovaras packs enable {name}`");
+            }),
+            _ => Err("usage: tovaras packs combine PACK PACK NAME".into()),
+        },
+        _ => Err(format!(
+            "usage: tovaras packs <list | install FILE.{EXTENSION} [--as NAME] | remove NAME | \
+             enable NAME|default | combine PACK PACK NAME>"
+        )),
+    };
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("tovaras: {e}");
+            1
+        }
+    }
+}
@@ -0,0 +1,300 @@
+//! Small particle effects for key moments: petals while giving flowers, a dust
+//! puff on landing, hearts when petted and floating "z"s while sleeping.
+//!
+//! Everything goes through [`ParticleBurst`] events, so any system (state
+//! transitions today, animation frame events later) can trigger effects.
+//! Particles live in the pet window's world, around the sprite at the origin;
+//! hearts and "z"s rise from the manifest's "head" point and petals fall from
+//! its "hand" point when the current clip declares them.
+
+use bevy::prelude::*;
+
+use crate::animation::{Anim, FrameEvent, SheetInfo};
+use crate::config::Config;
+use crate::input::Petted;
+use crate::pet_window::PetWindow;
+use crate::rng::{PetRng, TinyRng};
+use crate::{shapes, Action, Pet, PetState};
+
+// Seconds between petals while GivingFlowers plays
+const PETAL_INTERVAL: f32 = 0.35;
+// Seconds between "z"s while Sleeping
+const ZZZ_INTERVAL: f32 = 1.1;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ParticleKind {
+    Petal,
+    Dust,
+    Heart,
+    Zzz,
+    /// Weather around the pet (see weather.rs).
+    #[cfg_attr(not(feature = "http"), allow(dead_code))]
+    Rain,
+    #[cfg_attr(not(feature = "http"), allow(dead_code))]
+    Snow,
+}
+
+#[derive(Event)]
+pub struct ParticleBurst {
+    pub kind: ParticleKind,
+    pub count: usize,
+}
+
+#[derive(Component)]
+pub struct Particle {
+    vel: Vec2,
+    gravity: f32,
+    spin: f32,
+    age: f32,
+    life: f32,
+}
+
+#[derive(Resource)]
+pub struct Particles {
+    petal: Handle<Image>,
+    dust: Handle<Image>,
+    heart: Handle<Image>,
+    zzz: Handle<Image>,
+    rain: Handle<Image>,
+    snow: Handle<Image>,
+    rng: TinyRng,
+    prev_action: Option<Action>,
+    petal_left: f32,
+    zzz_left: f32,
+}
+
+impl Particles {
+    pub fn new(images: &mut Assets<Image>) -> Self {
+        Self {
+            petal: images.add(shapes::soft_oval(6, 4, [245, 140, 170])),
+            dust: images.add(shapes::soft_oval(8, 8, [190, 180, 165])),
+            heart: images.add(shapes::heart(9, 9, [235, 60, 90])),
+            zzz: images.add(shapes::letter_z(7, 7, [120, 150, 230])),
+            rain: images.add(shapes::soft_oval(2, 8, [150, 185, 235])),
+            snow: images.add(shapes::soft_oval(6, 6, [250, 252, 255])),
+            rng: TinyRng::seeded(),
+            prev_action: None,
+            petal_left: 0.0,
+            zzz_left: 0.0,
+        }
+    }
+}
+
+/// Turn pet state transitions and petting into particle bursts.
+pub fn emit_moment_particles(
+    time: Res<Time>,
+    cfg: Res<Config>,
+    mut parts: ResMut<Particles>,
+    mut petted: EventReader<Petted>,
+    mut frame_events: EventReader<FrameEvent>,
+    mut bursts: EventWriter<ParticleBurst>,
+    q: Query<&PetState>,
+) {
+    for _ in petted.read() {
+        bursts.send(ParticleBurst {
+            kind: ParticleKind::Heart,
+            count: 3,
+        });
+    }
+
+    for event in frame_events.read() {
+        match event.name.as_str() {
+            "flower_reveal" => {
+                bursts.send(ParticleBurst {
+                    kind: ParticleKind::Petal,
+                    count: 4,
+                });
+            }
+            "footstep" if !cfg.reduced_motion => {
+                bursts.send(ParticleBurst {
+                    kind: ParticleKind::Dust,
+                    count: 1,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    let Ok(st) = q.get_single() else {
+        return;
+    };
+    let entered = parts.prev_action != Some(st.action);
+    parts.prev_action = Some(st.action);
+
+    match st.action {
+        Action::Landing if entered => {
+            bursts.send(ParticleBurst {
+                kind: ParticleKind::Dust,
+                count: 6,
+            });
+        }
+        Action::GivingFlowers => {
+            parts.petal_left -= time.delta_seconds();
+            if entered || parts.petal_left <= 0.0 {
+                parts.petal_left = PETAL_INTERVAL;
+                bursts.send(ParticleBurst {
+                    kind: ParticleKind::Petal,
+                    count: 1,
+                });
+            }
+        }
+        // purely decorative, so reduced-motion mode skips it
+        Action::Sleeping if !cfg.reduced_motion => {
+            parts.zzz_left -= time.delta_seconds();
+            if parts.zzz_left <= 0.0 {
+                parts.zzz_left = ZZZ_INTERVAL;
+                bursts.send(ParticleBurst {
+                    kind: ParticleKind::Zzz,
+                    count: 1,
+                });
+            }
+        }
+        _ => {}
+    }
+}
+
+pub fn spawn_particle_bursts(
+    mut commands: Commands,
+    mut parts: ResMut<Particles>,
+    mut bursts: EventReader<ParticleBurst>,
+    windows: Query<&Window>,
+    sheet: Res<SheetInfo>,
+    pets: Query<(&Anim, &TextureAtlas, &Transform, &PetWindow), With<Pet>>,
+) {
+    let Some(win) = pets
+        .get_single()
+        .ok()
+        .and_then(|(.., w)| windows.get(w.0).ok())
+    else {
+        bursts.clear();
+        return;
+    };
+    let (half_w, half_h) = (win.width() / 2.0, win.height() / 2.0);
+    let parts = &mut *parts;
+    // manifest attachment point in window coordinates
+    let frame = Vec2::new(sheet.frame_w, sheet.frame_h);
+    let pet_point = |name: &str| {
+        let (anim, atlas, tf, _) = pets.get_single().ok()?;
+        let local = anim.point(name, atlas.index, frame)?;
+        Some(tf.transform_point(local.extend(0.0)).truncate())
+    };
+
+    for burst in bursts.read() {
+        for _ in 0..burst.count {
+            let rng = &mut parts.rng;
+            let (texture, size, mut pos, vel, gravity, life) = match burst.kind {
+                // drift down from the bouquet, slightly sideways
+                ParticleKind::Petal => (
+                    parts.petal.clone(),
+                    Vec2::new(3.0, 2.0),
+                    Vec2::new(rng.range_f32(0.0, half_w * 0.6), half_h * 0.1),
+                    Vec2::new(rng.range_f32(-4.0, 6.0), rng.range_f32(-2.0, 4.0)),
+                    -8.0,
+                    2.0,
+                ),
+                // puff sideways along the floor line
+                ParticleKind::Dust => (
+                    parts.dust.clone(),
+                    Vec2::splat(3.0),
+                    Vec2::new(rng.range_f32(-half_w * 0.4, half_w * 0.4), -half_h * 0.8),
+                    Vec2::new(rng.range_f32(-14.0, 14.0), rng.range_f32(1.0, 5.0)),
+                    -4.0,
+                    0.6,
+                ),
+                // float up from the head
+                ParticleKind::Heart => (
+                    parts.heart.clone(),
+                    Vec2::splat(4.0),
+                    Vec2::new(rng.range_f32(-half_w * 0.5, half_w * 0.5), half_h * 0.3),
+                    Vec2::new(rng.range_f32(-3.0, 3.0), rng.range_f32(6.0, 10.0)),
+                    0.0,
+                    1.2,
+                ),
+                // drift up and away from the sleeping head, swaying right
+                ParticleKind::Zzz => (
+                    parts.zzz.clone(),
+                    Vec2::splat(rng.range_f32(3.0, 5.0)),
+                    Vec2::new(half_w * 0.2, half_h * 0.2),
+                    Vec2::new(rng.range_f32(2.0, 5.0), rng.range_f32(4.0, 6.0)),
+                    0.0,
+                    2.5,
+                ),
+                // fall past the pet from the top of the window
+                ParticleKind::Rain => (
+                    parts.rain.clone(),
+                    Vec2::new(0.6, 3.0),
+                    Vec2::new(rng.range_f32(-half_w, half_w), half_h),
+                    Vec2::new(rng.range_f32(-3.0, -1.0), rng.range_f32(-48.0, -40.0)),
+                    -20.0,
+                    1.0,
+                ),
+                ParticleKind::Snow => (
+                    parts.snow.clone(),
+                    Vec2::splat(rng.range_f32(1.2, 2.0)),
+                    Vec2::new(rng.range_f32(-half_w, half_w), half_h),
+                    Vec2::new(rng.range_f32(-3.0, 3.0), rng.range_f32(-10.0, -7.0)),
+                    0.0,
+                    3.5,
+                ),
+            };
+            let point = match burst.kind {
+                ParticleKind::Heart | ParticleKind::Zzz => Some("head"),
+                ParticleKind::Petal => Some("hand"),
+                ParticleKind::Dust | ParticleKind::Rain | ParticleKind::Snow => None,
+            };
+            if let Some(at) = point.and_then(pet_point) {
+                pos = at + Vec2::new(parts.rng.range_f32(-1.5, 1.5), 0.0);
+            }
+            // hearts, letters and raindrops stay upright, everything else tumbles
+            let spin = if matches!(
+                burst.kind,
+                ParticleKind::Heart | ParticleKind::Zzz | ParticleKind::Rain
+            ) {
+                0.0
+            } else {
+                parts.rng.range_f32(-2.0, 2.0)
+            };
+
+            commands.spawn((
+                SpriteBundle {
+                    texture,
+                    sprite: Sprite {
+                        custom_size: Some(size),
+                        ..default()
+                    },
+                    // above the pet sprite
+                    transform: Transform::from_translation(pos.extend(1.0)),
+                    ..default()
+                },
+                Particle {
+                    vel,
+                    gravity,
+                    spin,
+                    age: 0.0,
+                    life,
+                },
+            ));
+        }
+    }
+}
+
+pub fn update_particles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut q: Query<(Entity, &mut Particle, &mut Transform, &mut Sprite)>,
+) {
+    let dt = time.delta_seconds();
+    for (e, mut p, mut tf, mut sprite) in &mut q {
+        p.age += dt;
+        if p.age >= p.life {
+            commands.entity(e).despawn();
+            continue;
+        }
+        p.vel.y += p.gravity * dt;
+        tf.translation += (p.vel * dt).extend(0.0);
+        tf.rotate_z(p.spin * dt);
+        // fade out over the last third of the lifetime
+        let fade = ((p.life - p.age) / (p.life / 3.0)).min(1.0);
+        sprite.color.set_alpha(fade);
+    }
+}
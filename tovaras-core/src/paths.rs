@@ -0,0 +1,54 @@
+//! Per-user directories (XDG layout, Linux-first).
+
+use std::env;
+use std::path::{Path, PathBuf};
+
+const APP_DIR: &str = "tovaras";
+
+pub fn home() -> PathBuf {
+    env::var_os("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// `path` with a leading `~` standing for the home directory.
+pub fn expand_home(path: &str) -> PathBuf {
+    let path = Path::new(path.trim());
+    match path.strip_prefix("~") {
+        Ok(rest) => home().join(rest),
+        Err(_) => path.to_path_buf(),
+    }
+}
+
+/// `$XDG_DATA_HOME/tovaras`, falling back to `~/.local/share/tovaras`.
+pub fn data_dir() -> PathBuf {
+    match env::var_os("XDG_DATA_HOME") {
+        Some(p) if !p.is_empty() => PathBuf::from(p).join(APP_DIR),
+        _ => home().join(".local/share").join(APP_DIR),
+    }
+}
+
+/// `$XDG_CONFIG_HOME/tovaras`, falling back to `~/.config/tovaras`.
+pub fn config_dir() -> PathBuf {
+    match env::var_os("XDG_CONFIG_HOME") {
+        Some(p) if !p.is_empty() => PathBuf::from(p).join(APP_DIR),
+        _ => home().join(".config").join(APP_DIR),
+    }
+}
+
+/// Where seasonal / alternative sprite sheets are installed.
+pub fn skins_dir() -> PathBuf {
+    data_dir().join("skins")
+}
+
+/// `$XDG_RUNTIME_DIR/tovaras` for per-session files (presence of running pets),
+/// falling back to the temp directory.
+pub fn runtime_dir() -> PathBuf {
+    match env::var_os("XDG_RUNTIME_DIR") {
+        Some(p) if !p.is_empty() => PathBuf::from(p).join(APP_DIR),
+        _ => env::temp_dir().join(format!(
+            "{APP_DIR}-{}",
+            env::var("USER").unwrap_or_default()
+        )),
+    }
+}
@@ -0,0 +1,268 @@
+//! Window ownership: every pet entity owns the OS window it lives in.
+//!
+//! The pet carries [`PetWindow`] pointing at its window; windows (and their
+//! cameras) opened for an entity carry [`OwnedBy`] pointing back. Windows are
+//! opened at runtime with [`spawn_owned_window`] and closed when their owner
+//! goes away, so auxiliary windows (toys, speech bubbles) only need an owner.
+//! A pet whose window was closed is despawned, and the app exits once no pet
+//! is left. [`WindowingPlugin`] spawns the pet in its window and keeps the
+//! windows (the pet's and the shared overlay) sized and tidied up, with
+//! [`MonitorsPlugin`] keeping track of the monitor it roams.
+
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy::render::camera::RenderTarget;
+use bevy::render::view::RenderLayers;
+use bevy::window::{WindowLevel, WindowMode, WindowPosition, WindowRef, WindowResolution};
+
+use crate::animation::{load_assets, Anim, SheetInfo};
+use crate::chroma::Chroma;
+use crate::config::Config;
+use crate::manifest::Manifest;
+use crate::monitors::MonitorsPlugin;
+use crate::overlay::{self, Overlay};
+use crate::physics::apply_motion_and_orientation;
+use crate::{extent, Pet, PetState, ScreenGeometry, SCALE};
+
+/// Opens the pet's window at startup and manages window lifetimes. Needs the
+/// sheet from the animation plugin, and [`Manifest`].
+pub struct WindowingPlugin;
+
+impl Plugin for WindowingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Overlay>()
+            .add_plugins(MonitorsPlugin)
+            .add_systems(Startup, spawn_pet.after(load_assets))
+            .add_systems(
+                Update,
+                (
+                    (despawn_windowless_pets, close_orphaned_windows).chain(),
+                    overlay::fit_overlay_to_monitor,
+                    extent::fit_window_to_extent.after(apply_motion_and_orientation),
+                ),
+            )
+            .add_systems(PostUpdate, place_pet_windows);
+    }
+}
+
+/// The window a pet is drawn in and moved with.
+#[derive(Component, Clone, Copy)]
+pub struct PetWindow(pub Entity);
+
+/// Entity a window or camera belongs to; despawned together with it.
+#[derive(Component, Clone, Copy)]
+pub struct OwnedBy(pub Entity);
+
+/// Open `window` for `owner`, with a camera drawing `layers` into it. Transparent
+/// windows are cleared to transparent whatever the app's clear color is.
+pub fn spawn_owned_window(
+    commands: &mut Commands,
+    owner: Entity,
+    window: Window,
+    layers: RenderLayers,
+) -> Entity {
+    let transparent = window.transparent;
+    let entity = commands.spawn((window, OwnedBy(owner))).id();
+    commands.spawn((
+        Camera2dBundle {
+            camera: Camera {
+                target: RenderTarget::Window(WindowRef::Entity(entity)),
+                clear_color: if transparent {
+                    ClearColorConfig::Custom(Color::NONE)
+                } else {
+                    ClearColorConfig::Default
+                },
+                ..default()
+            },
+            ..default()
+        },
+        layers,
+        OwnedBy(owner),
+    ));
+    entity
+}
+
+/// Close windows (and cameras) whose owner is gone.
+pub fn close_orphaned_windows(
+    mut commands: Commands,
+    owned: Query<(Entity, &OwnedBy)>,
+    owners: Query<()>,
+) {
+    for (entity, owner) in &owned {
+        if owners.get(owner.0).is_err() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+/// A pet whose window was closed (by the user or the window manager) leaves.
+pub fn despawn_windowless_pets(
+    mut commands: Commands,
+    pets: Query<(Entity, &PetWindow)>,
+    windows: Query<(), With<Window>>,
+) {
+    for (pet, window) in &pets {
+        if windows.get(window.0).is_err() {
+            commands.entity(pet).despawn_recursive();
+        }
+    }
+}
+
+/// Quit once every pet is gone.
+pub fn exit_without_pets(
+    pets: Query<(), With<PetWindow>>,
+    mut seen: Local<bool>,
+    mut exit: EventWriter<AppExit>,
+) {
+    if !pets.is_empty() {
+        *seen = true;
+    } else if *seen {
+        exit.send(AppExit::Success);
+    }
+}
+
+/// How fast a smoothed window catches up (rad/s): it covers most of the way
+/// in about `3 / SPRING_RATE` s.
+const SPRING_RATE: f32 = 30.0;
+/// A smoothed window further than this from its pet jumps there (teleports,
+/// another monitor).
+const SPRING_SNAP_PX: f32 = 200.0;
+
+/// Where a pet's window is on its way to the pet with
+/// `render: (smooth_motion: true)`: a critically damped spring, in desktop px.
+#[derive(Component, Default)]
+pub struct WindowSpring {
+    pos: Option<Vec2>,
+    vel: Vec2,
+}
+
+impl WindowSpring {
+    /// Whether the window has caught up with its pet.
+    pub fn settled(&self) -> bool {
+        self.vel == Vec2::ZERO
+    }
+
+    /// Advance `dt` s toward `target`; returns the window position.
+    fn follow(&mut self, target: IVec2, dt: f32) -> IVec2 {
+        let target = target.as_vec2();
+        let pos = self.pos.unwrap_or(target);
+        let off = pos - target;
+        if off.length() > SPRING_SNAP_PX || (off.length() < 0.5 && self.vel.length() < 1.0) {
+            self.pos = Some(target);
+            self.vel = Vec2::ZERO;
+            return target.as_ivec2();
+        }
+        // exact step of x'' = -w² x - 2w x' from (off, vel), stable at any dt
+        let w = SPRING_RATE;
+        let decay = (-w * dt).exp();
+        let c = self.vel + w * off;
+        self.pos = Some(target + (off + c * dt) * decay);
+        self.vel = (self.vel - c * w * dt) * decay;
+        self.pos.unwrap_or(target).round().as_ivec2()
+    }
+}
+
+type PlacedPet<'a> = (
+    &'a PetState,
+    &'a Anim,
+    &'a Transform,
+    &'a PetWindow,
+    Option<&'a mut WindowSpring>,
+);
+
+/// Move each pet's window to where its [`PetState`] says, once per frame after
+/// everything that moves pets has run. The window is only written when the
+/// position changed: every write is a request to the window manager, and an
+/// idle or sleeping pet shouldn't send one per frame. With smoothing on, the
+/// window glides there instead of stepping several px per frame, which some
+/// window managers show as stutter.
+pub fn place_pet_windows(
+    time: Res<Time>,
+    screen: Res<ScreenGeometry>,
+    cfg: Res<Config>,
+    mut pets: Query<PlacedPet, With<Pet>>,
+    mut windows: Query<&mut Window>,
+) {
+    let _span = info_span!("move_window").entered();
+    for (st, anim, tf, pet_window, spring) in &mut pets {
+        let Ok(mut win) = windows.get_mut(pet_window.0) else {
+            continue;
+        };
+        let mut at = screen.origin + st.window_pos + anchor_shift(anim, tf);
+        if let Some(mut spring) = spring.filter(|_| cfg.render.smooth_motion) {
+            at = spring.follow(at, time.delta_seconds());
+        }
+        let at = WindowPosition::At(at);
+        if win.position != at {
+            win.position = at;
+        }
+    }
+}
+
+/// Window offset (screen px) for the playing clip's anchor, turned and mirrored
+/// like the sprite so the shift follows the pose onto walls and the ceiling.
+pub fn anchor_shift(anim: &Anim, tf: &Transform) -> IVec2 {
+    if anim.anchor == Vec2::ZERO {
+        return IVec2::ZERO;
+    }
+    // sprite-local, y up, with the pose's flips
+    let local = Vec2::new(
+        anim.anchor.x * tf.scale.x.signum(),
+        -anim.anchor.y * tf.scale.y.signum(),
+    );
+    let world = (tf.rotation * local.extend(0.0)).truncate() * SCALE;
+    IVec2::new(world.x.round() as i32, -world.y.round() as i32)
+}
+
+/// The borderless, transparent, always-on-top window a pet lives in.
+fn pet_window() -> Window {
+    Window {
+        title: "tovaras".into(),
+        name: Some("tovaras".into()),
+        resolution: WindowResolution::new(64., 64.), // overwritten after image load
+        resizable: false,
+        decorations: false,
+        transparent: true,
+        window_level: WindowLevel::AlwaysOnTop,
+        position: WindowPosition::Centered(MonitorSelection::Primary),
+        mode: WindowMode::Windowed,
+        ..default()
+    }
+}
+
+pub fn spawn_pet(
+    mut commands: Commands,
+    sheet: Res<SheetInfo>,
+    manifest: Res<Manifest>,
+    chroma: Option<Res<Chroma>>,
+) {
+    let idle = manifest.clip(&manifest.fallback);
+    let anim = Anim::new(manifest.clip_start(idle), idle);
+    let pet = commands.spawn((
+        SpriteBundle {
+            texture: sheet.texture.clone(),
+            // Start scaled down so the sprite matches the smaller window
+            transform: Transform {
+                translation: Vec3::ZERO,
+                rotation: Quat::IDENTITY,
+                scale: Vec3::splat(SCALE),
+            },
+            ..default()
+        },
+        TextureAtlas {
+            layout: sheet.atlas_layout.clone(),
+            index: anim.first_index(),
+        },
+        Pet,
+        anim,
+        PetState::new(IVec2::new(20, 20)),
+        WindowSpring::default(),
+    ));
+    let pet = pet.id();
+    let mut window = pet_window();
+    if let Some(chroma) = chroma {
+        chroma.apply(&mut window);
+    }
+    let window = spawn_owned_window(&mut commands, pet, window, RenderLayers::default());
+    commands.entity(pet).insert(PetWindow(window));
+}
@@ -0,0 +1,83 @@
+//! Pack phrases: a pack's `phrases.ron`, a list of lines, gives the pet
+//! things to say on its own, one every `MIN_SECS` to `MAX_SECS`. The lines
+//! are read again when the pack changes; the default pet's come from
+//! `phrases.ron` in the data dir.
+
+use bevy::prelude::*;
+use std::fs;
+use std::path::Path;
+
+use crate::commands::{Issued, PetCommand, Source};
+use crate::packs::{manifest_dir, ActivePack};
+use crate::rng::Rng;
+use crate::{Action, Pet, PetState};
+
+pub const PHRASES_FILE: &str = "phrases.ron";
+/// Seconds between two lines, at least and at most.
+const MIN_SECS: f32 = 10.0 * 60.0;
+const MAX_SECS: f32 = 20.0 * 60.0;
+/// Seconds a line stays up.
+const SAY_SECS: f32 = 8.0;
+
+/// Lines of a pack's phrase pool; missing or malformed = none.
+pub fn read(dir: &Path) -> Vec<String> {
+    fs::read_to_string(dir.join(PHRASES_FILE))
+        .ok()
+        .and_then(|text| ron::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+#[derive(Resource, Default)]
+pub struct Phrases {
+    lines: Vec<String>,
+    /// The next line, when there is no [`Rng`] to draw one.
+    next: usize,
+    /// Seconds to the next line.
+    left: f32,
+}
+
+/// Say one of the pack's lines now and then; none while hiding.
+pub fn say_phrases(
+    time: Res<Time<Real>>,
+    active: Res<ActivePack>,
+    mut phrases: ResMut<Phrases>,
+    mut rng: Option<ResMut<Rng>>,
+    pets: Query<&PetState, With<Pet>>,
+    mut issue: EventWriter<Issued>,
+) {
+    let wait = |rng: &mut Option<ResMut<Rng>>| match rng {
+        Some(rng) => rng.range_f32(MIN_SECS, MAX_SECS),
+        None => (MIN_SECS + MAX_SECS) / 2.0,
+    };
+    if active.is_changed() {
+        phrases.lines = read(&manifest_dir(active.name.as_deref()));
+        phrases.next = 0;
+        phrases.left = wait(&mut rng);
+        if !phrases.lines.is_empty() {
+            info!("{} phrases to say", phrases.lines.len());
+        }
+    }
+    if phrases.lines.is_empty() {
+        return;
+    }
+    phrases.left -= time.delta_seconds();
+    if phrases.left > 0.0 {
+        return;
+    }
+    phrases.left = wait(&mut rng);
+    if pets.get_single().map_or(true, |st| st.action == Action::Hiding) {
+        return;
+    }
+    let i = match rng.as_mut() {
+        Some(rng) => rng.range_i32(0, phrases.lines.len() as i32 - 1) as usize,
+        None => phrases.next % phrases.lines.len(),
+    };
+    phrases.next = i + 1;
+    issue.send(
+        PetCommand::Say {
+            text: phrases.lines[i].clone(),
+            secs: Some(SAY_SECS),
+        }
+        .by(Source::Ambient),
+    );
+}
@@ -0,0 +1,502 @@
+//! The pet's motion: walking and climbing along its surfaces (the screen
+//! edges, see [`segments`]), jumps,
+//! landings and squash & stretch ([`PhysicsPlugin`]), with the math that
+//! doesn't need the ECS in submodules.
+
+pub mod ballistics;
+pub mod segments;
+
+use bevy::prelude::*;
+use std::f32::consts::PI;
+
+use crate::actions::{Actions, Motion};
+use crate::animation::{animate_sprite, set_visual_for, Anim};
+use crate::config::{Config, MotionConfig};
+use crate::manifest::Manifest;
+use crate::pet_window::PetWindow;
+use crate::{Action, FlightKind, PetState, ScreenGeometry, Surface};
+use segments::Segments;
+
+/// Moves every pet along its surface or flight path and keeps its visuals in
+/// step with the [`PetState`] the drivers set. Needs [`Manifest`] and [`Config`].
+pub struct PhysicsPlugin;
+
+impl Plugin for PhysicsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ScreenGeometry>()
+            .add_systems(Update, apply_motion_and_orientation.after(animate_sprite));
+    }
+}
+
+// ===== Speeds (slowed down for “lazy” vibe) =====
+pub const SPEED_FLOOR: f32 = 70.0;
+const SPEED_WALL: f32 = 55.0;
+const SPEED_CEIL: f32 = 70.0;
+
+// ===== Jump physics =====
+pub const GRAVITY: f32 = 1800.0; // px/s^2 downward (+)
+pub const FLOOR_JUMP_VY0: f32 = -900.0; // px/s (negative = up)
+pub const WALL_JUMP_VY0: f32 = -880.0; // px/s (initial up)
+const MAX_JUMP_VX: f32 = 1200.0; // px/s, fastest a jump crosses the screen
+
+// Corners: a pet that just turned one may only turn back after a while or
+// once it got a few px away, so a driver flipping its direction at the
+// corner can't make it bounce between the two surfaces
+const CORNER_DWELL_SECS: f32 = 0.6;
+const CORNER_MARGIN: i32 = 4;
+
+// Floor walking (see walk_floor): a turn-around faster than this (a fraction
+// of full speed) stumbles first; slower than this a stop is over
+const STUMBLE_SPEED: f32 = 0.3;
+const REST_SPEED: f32 = 0.005;
+const SPRING_STEP: f32 = 1.0 / 240.0;
+
+// Landing behavior
+const LANDING_HOLD: f32 = 0.5; // animation hold on floor
+const LANDING_DRIFT: f32 = 70.0; // px/s slide along floor during landing (reduced)
+
+// Squash & stretch (procedural scale on top of the sprite art)
+const SQUASH_LAND: f32 = 0.3; // kick on touchdown (wider + shorter)
+const STRETCH_TAKEOFF: f32 = -0.25; // kick on takeoff (narrower + taller)
+const STRETCH_FLIGHT: f32 = 0.12; // extra stretch at full vertical speed
+const SQUASH_STIFFNESS: f32 = 320.0;
+const SQUASH_DAMPING: f32 = 16.0;
+
+/// 0..1 -> 0..1 with zero slope at both ends (ease in/out).
+fn smoothstep(t: f32) -> f32 {
+    let t = t.clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Advance an integer pixel coordinate by `delta`, carrying the sub-pixel rest
+/// so slow (eased) or high-refresh-rate motion doesn't stall on truncation.
+fn advance_px(pos: i32, delta: f32, carry: &mut f32) -> i32 {
+    let exact = *carry + delta;
+    let whole = exact.trunc();
+    *carry = exact - whole;
+    pos + whole as i32
+}
+
+/// Walk on the floor with `st.speed` as a velocity (a fraction of
+/// `SPEED_FLOOR`) heading for `target`: it speeds up at a steady rate up to
+/// full speed and stops on an underdamped spring, rocking a little past the
+/// stop. Asked to turn around at speed, it first stumbles for `stumble_secs`
+/// while it brakes.
+fn walk_floor(st: &mut PetState, target: f32, dt: f32, motion: &MotionConfig, stumble_secs: f32) {
+    if target == 0.0 {
+        st.stumble_left = 0.0;
+    } else if target * st.speed < -STUMBLE_SPEED && st.stumble_left <= 0.0 {
+        st.stumble_left = stumble_secs;
+    } else {
+        st.stumble_left -= dt;
+    }
+    if target != 0.0 && st.stumble_left <= 0.0 {
+        let secs = if st.speed * target >= 0.0 {
+            motion.ease_in_secs
+        } else {
+            motion.ease_out_secs
+        };
+        let before = st.speed;
+        let step = dt / secs.max(0.001);
+        st.speed = if st.speed < target {
+            (st.speed + step).min(target)
+        } else {
+            (st.speed - step).max(target)
+        };
+        st.speed_v = if dt > 0.0 {
+            (st.speed - before) / dt
+        } else {
+            0.0
+        };
+        return;
+    }
+    // stopping: damping from the overshoot (its ratio to the speed it stopped
+    // from), stiffness so it settles in about `ease_out_secs`
+    let overshoot = motion.overshoot.clamp(0.0, 0.9);
+    let zeta = if overshoot > 0.0 {
+        let l = -overshoot.ln();
+        l / (PI * PI + l * l).sqrt()
+    } else {
+        1.0
+    };
+    let omega = 4.0 / (zeta * motion.ease_out_secs.max(0.001));
+    // small steps keep the spring stable at low update rates
+    let mut left = dt;
+    while left > 0.0 {
+        let h = left.min(SPRING_STEP);
+        st.speed_v += (-omega * omega * st.speed - 2.0 * zeta * omega * st.speed_v) * h;
+        st.speed += st.speed_v * h;
+        left -= h;
+    }
+    if st.speed.abs() < REST_SPEED && st.speed_v.abs() < REST_SPEED * omega {
+        st.speed = 0.0;
+        st.speed_v = 0.0;
+    }
+}
+
+/// Walking or climbing speed on `surface`, px/s.
+fn speed_on(surface: Surface) -> f32 {
+    match surface {
+        Surface::Floor => SPEED_FLOOR,
+        Surface::LeftWall | Surface::RightWall => SPEED_WALL,
+        Surface::Ceiling => SPEED_CEIL,
+    }
+}
+
+/// Physics + window motion + ensuring correct visuals.
+pub fn apply_motion_and_orientation(
+    time: Res<Time>,
+    windows: Query<&Window>,
+    mut q: Query<(
+        &mut TextureAtlas,
+        &mut Anim,
+        &mut Transform,
+        &mut PetState,
+        &PetWindow,
+    )>,
+    cfg: Res<Config>,
+    manifest: Res<Manifest>,
+    actions: Res<Actions>,
+    screen: Res<ScreenGeometry>,
+) {
+    let Ok((mut atlas, mut anim, mut tf, mut st, pet_window)) = q.get_single_mut() else {
+        return;
+    };
+    let Ok(win) = windows.get(pet_window.0) else {
+        return;
+    };
+
+    let fw: i32 = win.resolution.physical_width() as i32;
+    let fh: i32 = win.resolution.physical_height() as i32;
+    let dt = time.delta_seconds();
+
+    let (screen_w, screen_h) = screen.desktop(fw, fh);
+    let max_x = screen_w.saturating_sub(fw);
+    let max_y = screen_h.saturating_sub(fh); // "floor" y
+    let segments = Segments::desktop(max_x, max_y);
+    let mut pos = st.window_pos;
+
+    // ENTER FLIGHT on Jumping (ceiling jumps disabled)
+    if matches!(st.action, Action::Jumping) && st.flight == FlightKind::None {
+        if matches!(st.surface, Surface::Ceiling) {
+            // disabled by spec
+            set_visual_for(
+                &manifest, st.surface, st.action, st.facing, None, &mut anim, &mut atlas, &mut tf,
+            );
+        } else {
+            let _span = info_span!("takeoff", from = ?st.surface).entered();
+            st.flight_from = st.surface;
+            // off the floor it looks where it jumps; off a wall it keeps its
+            // pose on the wall until it lands (move_dir is the landing heading)
+            if st.surface == Surface::Floor {
+                st.facing = st.move_dir;
+            }
+            set_visual_for(
+                &manifest,
+                st.flight_from,
+                Action::Jumping,
+                st.facing,
+                None,
+                &mut anim,
+                &mut atlas,
+                &mut tf,
+            );
+
+            // where it stands now; landing on the floor, as high as the pet it stands on
+            let floor_y = segments
+                .floor_below(pos.x, pos.y)
+                .map_or(max_y, |floor| floor.line)
+                - st.perch;
+            let jump = match st.surface {
+                // Floor->wall, or floor->floor when the wall is out of reach
+                Surface::Floor => {
+                    let wall = st.wall_target.map(|(wall, ty)| {
+                        let wall_x = segments
+                            .wall(wall, ty)
+                            .map_or(if wall == Surface::LeftWall { 0 } else { max_x }, |seg| {
+                                seg.line
+                            });
+                        (
+                            wall_x,
+                            ballistics::plan_floor_to_wall(pos.x, floor_y, wall_x, ty),
+                        )
+                    });
+                    match wall {
+                        // kept for the capture check in flight
+                        Some((_, Some(jump))) => jump,
+                        Some((wall_x, None)) => {
+                            // out of reach: toward it, along the floor
+                            st.wall_target = None;
+                            ballistics::plan_floor_jump(pos.x, floor_y, wall_x)
+                        }
+                        None => ballistics::plan_floor_jump(pos.x, floor_y, st.target_x),
+                    }
+                }
+                Surface::RightWall | Surface::LeftWall => {
+                    ballistics::plan_wall_jump(pos.x, pos.y, st.target_x, floor_y)
+                }
+                // disabled above
+                Surface::Ceiling => ballistics::Jump {
+                    launch: ballistics::Launch {
+                        vx: st.vx,
+                        vy: st.vy,
+                    },
+                    land_x: pos.x,
+                },
+            };
+            // a target out of reach gets a shorter jump; land where it comes down
+            st.target_x = jump.land_x;
+            let launch = jump.launch;
+            st.vx = launch.vx;
+            st.vy = launch.vy;
+            st.flight = FlightKind::Parabola;
+            st.flight_carry = Vec2::ZERO;
+            st.landing_left = 0.0;
+            st.squash_v = 0.0;
+            st.squash = STRETCH_TAKEOFF;
+        }
+    }
+
+    // Flight step: keep Jump sprite until floor/wall touch
+    if st.flight != FlightKind::None {
+        let _span = info_span!("flight").entered();
+        // the mean of the old and new speed: exact under constant gravity, so
+        // the pet stays on the planned arc at any frame rate
+        let (y0, vy0) = (pos.y, st.vy);
+        st.vy += GRAVITY * dt; // gravity downward (+)
+        let (vx, vy) = (st.vx, 0.5 * (vy0 + st.vy));
+        pos.x = advance_px(pos.x, vx * dt, &mut st.flight_carry.x);
+        pos.y = advance_px(pos.y, vy * dt, &mut st.flight_carry.y);
+        // before the clamp: how far past the floor this step took it
+        let exact_y = pos.y as f32 + st.flight_carry.y;
+
+        // Bounds temp clamp
+        pos.x = pos.x.clamp(0, max_x);
+        pos.y = pos.y.clamp(0, max_y);
+
+        // Keep jump visuals from the takeoff surface
+        set_visual_for(
+            &manifest,
+            st.flight_from,
+            Action::Jumping,
+            st.facing,
+            None,
+            &mut anim,
+            &mut atlas,
+            &mut tf,
+        );
+
+        // Hit wall target?
+        if let Some((wall, ty)) = st.wall_target {
+            let hit = segments.wall(wall, ty).filter(|seg| match wall {
+                Surface::LeftWall => pos.x <= seg.line,
+                _ => pos.x >= seg.line,
+            });
+            if let Some(seg) = hit {
+                // stick to wall at target y (clamped), start climbing
+                pos.x = seg.line;
+                pos.y = ty.clamp(seg.from, seg.to);
+                st.flight = FlightKind::None;
+                st.surface = wall;
+                st.action = Action::Climb;
+                st.move_dir = if st.vy <= 0.0 { 1.0 } else { -1.0 };
+                st.wall_target = None;
+            }
+        }
+
+        // Land on floor (or on another pet) if we reached it and no wall capture happened
+        let ground = segments
+            .floor_below(pos.x, y0)
+            .map_or(max_y, |floor| floor.line)
+            - st.perch;
+        if st.flight != FlightKind::None && pos.y >= ground && st.vy >= 0.0 {
+            // back to the moment it came down through the floor, part way into the step
+            let below = exact_y - ground as f32;
+            let past = (below / st.vy.max(1.0)).clamp(0.0, dt);
+            pos.x = (pos.x - (st.vx * past).round() as i32).clamp(0, max_x);
+            st.flight = FlightKind::None;
+            pos.y = ground;
+            st.surface = Surface::Floor;
+            st.action = Action::Landing;
+
+            // Heading rules:
+            // - RightWall -> land heading LEFT
+            // - LeftWall  -> land heading RIGHT
+            // - Floor     -> face towards target (vx sign)
+            st.move_dir = match st.flight_from {
+                Surface::RightWall => -1.0,
+                Surface::LeftWall => 1.0,
+                _ => {
+                    if st.vx >= 0.0 {
+                        1.0
+                    } else {
+                        -1.0
+                    }
+                }
+            };
+
+            // the landing hold slides off what is left instead of snapping there
+            let miss = st.target_x.clamp(0, max_x) - pos.x;
+            debug!("Landed {miss:+} px from the target at x {}", st.target_x);
+            st.landing_fix = miss as f32;
+
+            st.facing = st.move_dir;
+
+            st.landing_left = LANDING_HOLD;
+            st.squash_v = 0.0;
+            st.squash = SQUASH_LAND;
+            set_visual_for(
+                &manifest,
+                Surface::Floor,
+                Action::Landing,
+                st.facing,
+                None,
+                &mut anim,
+                &mut atlas,
+                &mut tf,
+            );
+            st.wall_target = None;
+        }
+        if st.flight == FlightKind::None {
+            // touched down or caught a wall: a new surface
+            st.facing = st.move_dir;
+            st.surface_secs = 0.0;
+            st.corner_clear = false;
+        }
+    } else {
+        let _span = info_span!("surface_motion", surface = ?st.surface).entered();
+        // Not in flight: normal motions + visuals
+        let def = actions.get(st.action);
+        let moving = def.motion == Motion::Walk;
+        let target = if moving { st.move_dir } else { 0.0 };
+        let v = if st.surface == Surface::Floor {
+            let stumble = manifest.stumble_clip();
+            let stumble_secs = stumble.map_or(0.0, |name| manifest.clip(name).duration());
+            walk_floor(&mut st, target, dt, &cfg.motion, stumble_secs);
+            st.speed
+        } else {
+            // Ease the surface speed toward the wanted direction instead of snapping
+            st.stumble_left = 0.0;
+            let speeding_up = moving && st.speed * st.move_dir >= 0.0;
+            let ease_secs = if speeding_up {
+                cfg.motion.ease_in_secs
+            } else {
+                cfg.motion.ease_out_secs
+            };
+            let step = dt / ease_secs.max(0.001);
+            st.speed = if st.speed < target {
+                (st.speed + step).min(target)
+            } else {
+                (st.speed - step).max(target)
+            };
+            st.speed.signum() * smoothstep(st.speed.abs())
+        };
+        let stumbling = st.stumble_left > 0.0;
+
+        // a moving pet looks where it goes (once done stumbling); a still one
+        // where it last looked (or at the cursor, see input.rs)
+        if (moving || def.motion == Motion::Land) && !stumbling {
+            st.facing = st.move_dir;
+        }
+        let clip = if stumbling {
+            manifest.stumble_clip()
+        } else {
+            st.idle_clip
+                .as_deref()
+                .filter(|_| st.surface == Surface::Floor && st.action == Action::Idle)
+                .or(def.clip.as_deref())
+        };
+        set_visual_for(
+            &manifest, st.surface, def.pose, st.facing, clip, &mut anim, &mut atlas, &mut tf,
+        );
+        if std::mem::take(&mut st.restart_clip) {
+            anim.restart(&mut atlas);
+        }
+        let surface_before = st.surface;
+        let may_turn = st.corner_clear || st.surface_secs >= CORNER_DWELL_SECS;
+
+        // a pet somehow off every segment stays where it is
+        if let Some(seg) = segments.under(st.surface, pos) {
+            let mut along = seg.along(pos);
+            let on_floor = seg.surface == Surface::Floor;
+            if on_floor && def.motion == Motion::Land {
+                // Slide during landing, spreading the landing miss over the hold
+                let share = (dt / st.landing_left.max(dt)).min(1.0);
+                let fix = st.landing_fix * share;
+                st.landing_fix -= fix;
+                let slide = LANDING_DRIFT * st.move_dir * dt + fix;
+                along = advance_px(along, slide, &mut st.carry);
+            } else if on_floor || moving || st.speed != 0.0 {
+                // everything but walking only glides to a stop
+                let step = seg.sign() as f32 * speed_on(seg.surface) * v * dt;
+                along = advance_px(along, step, &mut st.carry);
+                // the screen way it heads, and whether it got to that end
+                let heading = seg.sign() * st.move_dir as i32;
+                let at_end = (along - seg.end(heading)) * heading >= 0;
+                // on walls and the ceiling a glide turns corners too
+                let turns = moving || !on_floor;
+                let next = (at_end && turns && may_turn)
+                    .then(|| segments.turn(&seg, heading))
+                    .flatten();
+                if let Some((next, dir)) = next {
+                    // around the corner: onto the floor walking, elsewhere climbing
+                    pos = seg.point(seg.end(heading), 0);
+                    st.surface = next.surface;
+                    st.action = if next.surface == Surface::Floor {
+                        Action::Move
+                    } else {
+                        Action::Climb
+                    };
+                    st.move_dir = dir;
+                } else if on_floor && !moving && (along <= seg.from || along >= seg.to) {
+                    st.speed = 0.0;
+                    st.speed_v = 0.0;
+                }
+            }
+            if st.surface == seg.surface {
+                pos = seg.point(along.clamp(seg.from, seg.to), st.perch);
+            }
+        }
+
+        // Keep momentum around corners: same magnitude, new surface's direction;
+        // the sub-pixel rest belonged to the other axis
+        if st.surface != surface_before {
+            st.speed = st.speed.abs() * st.move_dir;
+            st.speed_v = 0.0;
+            st.carry = 0.0;
+            st.surface_secs = 0.0;
+            st.corner_clear = false;
+        } else {
+            st.surface_secs += dt;
+            st.corner_clear |= segments
+                .under(st.surface, pos)
+                .is_some_and(|seg| seg.end_distance(pos) >= CORNER_MARGIN);
+        }
+    }
+
+    // Landing hold timer
+    if matches!(st.action, Action::Landing) {
+        st.landing_left -= dt;
+        if st.landing_left <= 0.0 {
+            st.action = Action::Move; // continue walking on floor
+            st.speed = st.move_dir * (LANDING_DRIFT / SPEED_FLOOR).min(1.0);
+            st.speed_v = 0.0;
+        }
+    }
+
+    // Squash & stretch: damped spring back to neutral, layered on the visual scale
+    let accel = -SQUASH_STIFFNESS * st.squash - SQUASH_DAMPING * st.squash_v;
+    st.squash_v += accel * dt;
+    st.squash += st.squash_v * dt;
+    let mut deform = st.squash;
+    if st.flight != FlightKind::None {
+        // stretched while moving fast, relaxed around the apex
+        deform -= STRETCH_FLIGHT * (st.vy.abs() / -FLOOR_JUMP_VY0).min(1.0);
+    }
+    if !cfg.render.integer_scale {
+        tf.scale.x *= 1.0 + deform;
+        tf.scale.y *= 1.0 - deform;
+    }
+
+    st.window_pos = IVec2::new(pos.x.clamp(0, max_x), pos.y.clamp(0, max_y));
+}
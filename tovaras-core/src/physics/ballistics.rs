@@ -0,0 +1,202 @@
+//! Jump planning: the launch velocity that takes the pet from where it stands
+//! to where it wants to go under `GRAVITY` (screen px, +y down).
+//!
+//! Jumps leave at their usual upward speed (`FLOOR_JUMP_VY0` from the floor,
+//! `WALL_JUMP_VY0` off a wall) when that works. A target above the usual apex
+//! gets a faster launch, one whose apex would leave the top of the desktop a
+//! slower one, and a target too far for `MAX_JUMP_VX` the longest flight that
+//! still stays on screen. What is still out of reach after that gets a shorter
+//! jump: [`Jump::land_x`] says where it really comes down.
+//!
+//! The flight step in physics.rs follows these arcs exactly (constant
+//! acceleration, no per-frame drift), so a pet lands within a pixel or two of
+//! `land_x`; the landing hold slides off the rest.
+
+use super::{FLOOR_JUMP_VY0, GRAVITY, MAX_JUMP_VX, WALL_JUMP_VY0};
+
+/// Take-off velocity, px/s (+y down).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Launch {
+    pub vx: f32,
+    pub vy: f32,
+}
+
+/// A planned jump.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Jump {
+    pub launch: Launch,
+    /// Where the arc comes down at the target height: the target's x, or
+    /// nearer when the target is out of reach.
+    pub land_x: i32,
+}
+
+/// Seconds until a body leaving `y0` at `vy0` comes down through `y`, or `None`
+/// when its arc peaks below `y` (a negative discriminant).
+pub fn time_to_fall_to(y0: f32, y: f32, vy0: f32) -> Option<f32> {
+    // y0 + vy0 t + g t² / 2 = y
+    let (a, b, c) = (0.5 * GRAVITY, vy0, y0 - y);
+    let disc = b * b - 4.0 * a * c;
+    // the later root: on the way down
+    (disc >= 0.0).then(|| (-b + disc.sqrt()) / (2.0 * a))
+}
+
+/// Lowest y an apex may reach: a pet at the very top while still rising would
+/// be flying into the edge of the desktop.
+const TOP_MARGIN: i32 = 1;
+
+/// Upward speed whose apex is `rise` px above the start.
+fn speed_to_rise(rise: f32) -> f32 {
+    (2.0 * GRAVITY * rise.max(0.0)).sqrt()
+}
+
+/// From `(x0, y0)` to `(x1, y1)`, coming down through `y1`; leaves at `vy0`
+/// unless the target or the limits need another launch.
+fn plan(x0: i32, y0: i32, x1: i32, y1: i32, vy0: f32) -> Jump {
+    let (y0, y1) = (y0 as f32, y1.max(TOP_MARGIN) as f32);
+    let dx = (x1 - x0) as f32;
+    // the apex stays below the top of the desktop (y 0) and at or above the target
+    let top = TOP_MARGIN as f32;
+    let (min_up, max_up) = (speed_to_rise(y0 - y1), speed_to_rise(y0 - top));
+    // at `min_up` the target is the apex, where the roots meet
+    let flight = |up: f32| time_to_fall_to(y0, y1, -up).unwrap_or(up / GRAVITY);
+    let mut up = (-vy0).clamp(min_up, max_up);
+    let mut t = flight(up);
+    if dx.abs() > MAX_JUMP_VX * t {
+        // a higher arc gives a far target more time
+        up = max_up;
+        t = flight(up);
+    }
+    let vx = if t > 0.0 { dx / t } else { 0.0 };
+    let reached = vx.abs() <= MAX_JUMP_VX;
+    let vx = vx.clamp(-MAX_JUMP_VX, MAX_JUMP_VX);
+    Jump {
+        launch: Launch { vx, vy: -up },
+        land_x: if reached {
+            x1
+        } else {
+            x0 + (vx * t).round() as i32
+        },
+    }
+}
+
+/// Floor to floor: from `from_x` to `target_x` on the floor at `floor_y`.
+pub fn plan_floor_jump(from_x: i32, floor_y: i32, target_x: i32) -> Jump {
+    plan(from_x, floor_y, target_x, floor_y, FLOOR_JUMP_VY0)
+}
+
+/// Floor to wall: from `from_x` on the floor at `floor_y` to height `target_y`
+/// on the wall at `wall_x`, or `None` when the wall is out of reach.
+pub fn plan_floor_to_wall(from_x: i32, floor_y: i32, wall_x: i32, target_y: i32) -> Option<Jump> {
+    let jump = plan(from_x, floor_y, wall_x, target_y, FLOOR_JUMP_VY0);
+    (jump.land_x == wall_x).then_some(jump)
+}
+
+/// Wall to floor: from `from` on a wall down to `target_x` on the floor at
+/// `floor_y`.
+pub fn plan_wall_jump(from_x: i32, from_y: i32, target_x: i32, floor_y: i32) -> Jump {
+    plan(from_x, from_y, target_x, floor_y, WALL_JUMP_VY0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// Highest point (smallest y) of an arc leaving `y0` with `launch`.
+    fn apex(y0: i32, launch: Launch) -> f32 {
+        y0 as f32 - launch.vy * launch.vy / (2.0 * GRAVITY)
+    }
+
+    /// Whether the highest arc that stays on screen has time enough to cover
+    /// the distance from `(x0, y0)` to `(x1, y1)`.
+    fn reachable(x0: i32, y0: i32, x1: i32, y1: i32) -> bool {
+        let up = speed_to_rise((y0 - TOP_MARGIN) as f32);
+        let t = time_to_fall_to(y0 as f32, y1.max(TOP_MARGIN) as f32, -up).unwrap_or(up / GRAVITY);
+        // a hair of slack for rounding at the very limit
+        ((x1 - x0) as f32).abs() < MAX_JUMP_VX * t * 0.999
+    }
+
+    /// The invariants every plan keeps.
+    fn check(jump: Jump, x0: i32, y0: i32, x1: i32, y1: i32) -> Result<(), TestCaseError> {
+        prop_assert!(jump.launch.vx.is_finite() && jump.launch.vy.is_finite());
+        prop_assert!(jump.launch.vx.abs() <= MAX_JUMP_VX, "vx {}", jump.launch.vx);
+        // (a pet already at the very top can only stay there)
+        let top = apex(y0, jump.launch);
+        prop_assert!(top >= TOP_MARGIN.min(y0) as f32 - 0.01, "apex at y {top}");
+        // never past the target, never behind the start
+        prop_assert!(
+            (x0.min(x1)..=x0.max(x1)).contains(&jump.land_x),
+            "lands at {} going from {x0} to {x1}",
+            jump.land_x
+        );
+        if reachable(x0, y0, x1, y1) {
+            prop_assert_eq!(jump.land_x, x1);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn time_to_fall_to_a_target_above_the_apex_is_none() {
+        // leaves y 500 at 600 px/s up: the apex is 100 px higher, at y 400
+        assert_eq!(time_to_fall_to(500.0, 399.0, -600.0), None);
+        assert!(time_to_fall_to(500.0, 401.0, -600.0).is_some());
+    }
+
+    proptest! {
+        #[test]
+        fn time_to_fall_to_is_none_exactly_above_the_apex(
+            y0 in 0.0f32..4000.0,
+            up in 0.0f32..2000.0,
+            dy in -1000.0f32..1000.0,
+        ) {
+            let apex = y0 - up * up / (2.0 * GRAVITY);
+            // away from the apex itself, where rounding decides
+            prop_assume!(dy.abs() > 0.5);
+            let t = time_to_fall_to(y0, apex + dy, -up);
+            if dy < 0.0 {
+                prop_assert_eq!(t, None);
+            } else {
+                let t = t.unwrap();
+                prop_assert!(t >= 0.0);
+                let y = y0 - up * t + 0.5 * GRAVITY * t * t;
+                prop_assert!((y - (apex + dy)).abs() < 0.5, "at y {y} after {t} s");
+            }
+        }
+
+        #[test]
+        fn floor_jumps_stay_on_screen_and_land_on_target(
+            from_x in 0i32..8000,
+            floor_y in 0i32..4000,
+            target_x in 0i32..8000,
+        ) {
+            let jump = plan_floor_jump(from_x, floor_y, target_x);
+            check(jump, from_x, floor_y, target_x, floor_y)?;
+        }
+
+        #[test]
+        fn floor_to_wall_jumps_stay_on_screen_and_land_on_target(
+            from_x in 0i32..8000,
+            floor_y in 0i32..4000,
+            wall_x in 0i32..8000,
+            target_y in -100i32..4000,
+        ) {
+            let reached = plan_floor_to_wall(from_x, floor_y, wall_x, target_y);
+            if let Some(jump) = reached {
+                check(jump, from_x, floor_y, wall_x, target_y)?;
+            } else {
+                prop_assert!(!reachable(from_x, floor_y, wall_x, target_y));
+            }
+        }
+
+        #[test]
+        fn wall_jumps_stay_on_screen_and_land_on_target(
+            from_x in 0i32..8000,
+            from_y in 0i32..4000,
+            target_x in 0i32..8000,
+            floor_y in 0i32..4000,
+        ) {
+            let jump = plan_wall_jump(from_x, from_y, target_x, floor_y);
+            check(jump, from_x, from_y, target_x, floor_y)?;
+        }
+    }
+}
@@ -0,0 +1,163 @@
+//! The surfaces the pet walks and climbs on, as straight segments with an
+//! orientation (the [`Surface`] it stands on them as), in window positions:
+//! the top-left the pet's window has while it is there.
+//!
+//! Today these are the four edges of the desktop ([`Segments::desktop`]); more
+//! (the top of a taskbar, a window's titlebar, the seam between two monitors)
+//! only need adding to the list. A pet walks along its segment and, at an end
+//! that meets another segment, turns onto it; at an end that meets none it
+//! stops there.
+
+use bevy::prelude::IVec2;
+
+use crate::Surface;
+
+/// A straight stretch of one orientation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Segment {
+    pub surface: Surface,
+    /// y of a floor or ceiling, x of a wall.
+    pub line: i32,
+    /// Where it starts and ends along it: x on floors and ceilings, y on
+    /// walls; `from <= to`.
+    pub from: i32,
+    pub to: i32,
+}
+
+impl Segment {
+    fn horizontal(&self) -> bool {
+        matches!(self.surface, Surface::Floor | Surface::Ceiling)
+    }
+
+    /// Screen px (+x right, +y down) along the segment for a step with
+    /// `move_dir` +1: right on floors and ceilings, up walls.
+    pub fn sign(&self) -> i32 {
+        if self.horizontal() {
+            1
+        } else {
+            -1
+        }
+    }
+
+    /// How far along it `pos` is.
+    pub fn along(&self, pos: IVec2) -> i32 {
+        if self.horizontal() {
+            pos.x
+        } else {
+            pos.y
+        }
+    }
+
+    /// The window position `along` it; on floors `perch` px higher (standing
+    /// on another pet).
+    pub fn point(&self, along: i32, perch: i32) -> IVec2 {
+        match self.surface {
+            Surface::Floor => IVec2::new(along, self.line - perch),
+            Surface::Ceiling => IVec2::new(along, self.line),
+            Surface::LeftWall | Surface::RightWall => IVec2::new(self.line, along),
+        }
+    }
+
+    /// Whether a pet at `pos` (`perch` px up) stands on it.
+    pub fn holds(&self, pos: IVec2, perch: i32) -> bool {
+        let along = self.along(pos);
+        (self.from..=self.to).contains(&along) && self.point(along, perch) == pos
+    }
+
+    /// How far `pos` is from the nearer end.
+    pub fn end_distance(&self, pos: IVec2) -> i32 {
+        let along = self.along(pos);
+        (along - self.from).min(self.to - along)
+    }
+
+    /// The end a step the screen way `heading` (±1) goes toward.
+    pub fn end(&self, heading: i32) -> i32 {
+        if heading < 0 {
+            self.from
+        } else {
+            self.to
+        }
+    }
+
+    /// How far `pos` is from it, across and past its ends.
+    fn distance(&self, pos: IVec2) -> i32 {
+        let along = self.along(pos);
+        let across = if self.horizontal() { pos.y } else { pos.x };
+        let past = (self.from - along).max(along - self.to).max(0);
+        (across - self.line).abs() + past
+    }
+}
+
+/// Every segment the pet can be on.
+#[derive(Clone, Debug, Default)]
+pub struct Segments(Vec<Segment>);
+
+impl Segments {
+    /// The edges of a desktop where the window's top-left ranges over
+    /// `0..=max_x` × `0..=max_y`.
+    pub fn desktop(max_x: i32, max_y: i32) -> Self {
+        let (max_x, max_y) = (max_x.max(0), max_y.max(0));
+        let edge = |surface, line, to| Segment {
+            surface,
+            line,
+            from: 0,
+            to,
+        };
+        Self(vec![
+            edge(Surface::Floor, max_y, max_x),
+            edge(Surface::RightWall, max_x, max_y),
+            edge(Surface::Ceiling, 0, max_x),
+            edge(Surface::LeftWall, 0, max_y),
+        ])
+    }
+
+    /// The segment a pet on `surface` at `pos` is on: the nearest one of that
+    /// orientation.
+    pub fn under(&self, surface: Surface, pos: IVec2) -> Option<Segment> {
+        self.0
+            .iter()
+            .filter(|seg| seg.surface == surface)
+            .min_by_key(|seg| seg.distance(pos))
+            .copied()
+    }
+
+    /// Where a pet leaving `seg` at its end toward `heading` (screen ±1) goes
+    /// on: the segment meeting it there, and the `move_dir` away from the
+    /// corner along it.
+    pub fn turn(&self, seg: &Segment, heading: i32) -> Option<(Segment, f32)> {
+        let corner = seg.point(seg.end(heading), 0);
+        self.0
+            .iter()
+            .filter(|next| next.surface != seg.surface)
+            .find_map(|next| {
+                let away = if next.point(next.from, 0) == corner {
+                    1
+                } else if next.point(next.to, 0) == corner {
+                    -1
+                } else {
+                    return None;
+                };
+                Some((*next, (away * next.sign()) as f32))
+            })
+    }
+
+    /// The floor a pet at x falls onto from height `y`: the nearest one at or
+    /// below it.
+    pub fn floor_below(&self, x: i32, y: i32) -> Option<Segment> {
+        self.0
+            .iter()
+            .filter(|seg| {
+                seg.surface == Surface::Floor && seg.line >= y && (seg.from..=seg.to).contains(&x)
+            })
+            .min_by_key(|seg| seg.line)
+            .copied()
+    }
+
+    /// The wall of orientation `wall` that reaches height `y`.
+    pub fn wall(&self, wall: Surface, y: i32) -> Option<Segment> {
+        self.0
+            .iter()
+            .find(|seg| seg.surface == wall && (seg.from..=seg.to).contains(&y))
+            .copied()
+    }
+}
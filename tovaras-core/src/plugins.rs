@@ -0,0 +1,288 @@
+//! WASM plugins (built with `--features wasm`).
+//!
+//! Every `*.wasm` module in `<config_dir>/plugins` is loaded at startup and
+//! reloaded when it changes. Plugins are sandboxed: they get no files,
+//! network or clock, only the two imports below, a capped memory and a fuel
+//! budget per call. The ABI is plain core WebAssembly passing JSON:
+//!
+//! ```text
+//! exports  memory
+//!          alloc(len: i32) -> i32         room for a message; the plugin owns it
+//!          on_start()                     optional
+//!          on_state(ptr: i32, len: i32)   a state snapshot, every frame
+//! imports  tovaras.command(ptr: i32, len: i32)   issue a command
+//!          tovaras.log(ptr: i32, len: i32)       write a line to the log
+//! ```
+//!
+//! Snapshots are JSON objects with `action`, `surface`, `x`, `y`, `dir`,
+//! `level`, `energy`, `affection`, `clicks` (since the last snapshot) and
+//! `dt`. Commands are the JSON the remote APIs take (see `remote.rs`), e.g.
+//! `{"cmd":"say","text":"hi"}`. A plugin that traps, runs out of fuel or
+//! sends a malformed command is paused until its file changes.
+
+use bevy::prelude::*;
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::mpsc::Sender;
+use std::time::SystemTime;
+use wasmtime::{Caller, Engine, Instance, Linker, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+use crate::input::Petted;
+use crate::profile::Profile;
+use crate::remote::RemoteCommand;
+use crate::{paths, Action, Pet, PetState, Surface};
+
+const PLUGINS_DIR: &str = "plugins";
+/// Seconds between checks for new or edited plugins.
+const RESCAN_SECS: f32 = 1.0;
+/// Fuel (roughly wasm instructions) a single call may burn.
+const FUEL_PER_CALL: u64 = 5_000_000;
+/// Linear memory a plugin may grow to, bytes.
+const MAX_MEMORY: usize = 16 << 20;
+
+/// Per-plugin state its imports can reach.
+struct Host {
+    /// Commands issued during the current call.
+    issued: Vec<RemoteCommand>,
+    limits: StoreLimits,
+}
+
+#[derive(Serialize)]
+struct Snapshot {
+    action: Action,
+    surface: Surface,
+    /// Window top-left, screen px.
+    x: i32,
+    y: i32,
+    dir: f32,
+    level: u32,
+    energy: f32,
+    affection: f32,
+    clicks: usize,
+    dt: f32,
+}
+
+struct Plugin {
+    path: PathBuf,
+    modified: SystemTime,
+    store: Store<Host>,
+    instance: Instance,
+    failed: bool,
+}
+
+impl Plugin {
+    fn load(
+        engine: &Engine,
+        linker: &Linker<Host>,
+        path: PathBuf,
+        modified: SystemTime,
+    ) -> wasmtime::Result<Self> {
+        let module = Module::from_file(engine, &path)?;
+        let host = Host {
+            issued: Vec::new(),
+            limits: StoreLimitsBuilder::new()
+                .memory_size(MAX_MEMORY)
+                .instances(1)
+                .build(),
+        };
+        let mut store = Store::new(engine, host);
+        store.limiter(|host| &mut host.limits);
+        // start functions run on this budget too
+        store.set_fuel(FUEL_PER_CALL)?;
+        let instance = linker.instantiate(&mut store, &module)?;
+        Ok(Self {
+            path,
+            modified,
+            store,
+            instance,
+            failed: false,
+        })
+    }
+
+    /// Call export `name` (with `message` copied into the plugin's memory) if
+    /// it exists, and return the commands it issued.
+    fn call(&mut self, name: &str, message: Option<&[u8]>) -> Vec<RemoteCommand> {
+        if self.failed {
+            return Vec::new();
+        }
+        if let Err(e) = self.try_call(name, message) {
+            warn!(
+                "{}: {name}: {e}; paused until replaced",
+                self.path.display()
+            );
+            self.failed = true;
+        }
+        std::mem::take(&mut self.store.data_mut().issued)
+    }
+
+    fn try_call(&mut self, name: &str, message: Option<&[u8]>) -> wasmtime::Result<()> {
+        let Some(func) = self.instance.get_func(&mut self.store, name) else {
+            return Ok(());
+        };
+        self.store.set_fuel(FUEL_PER_CALL)?;
+        let Some(bytes) = message else {
+            return func.typed::<(), ()>(&self.store)?.call(&mut self.store, ());
+        };
+        let func = func.typed::<(i32, i32), ()>(&self.store)?;
+        let alloc = self
+            .instance
+            .get_typed_func::<i32, i32>(&mut self.store, "alloc")?;
+        let memory = self
+            .instance
+            .get_memory(&mut self.store, "memory")
+            .ok_or_else(|| wasmtime::Error::msg("no exported memory"))?;
+        let len = i32::try_from(bytes.len())?;
+        let ptr = alloc.call(&mut self.store, len)?;
+        memory.write(&mut self.store, usize::try_from(ptr)?, bytes)?;
+        func.call(&mut self.store, (ptr, len))
+    }
+}
+
+/// `len` bytes at `ptr` in the calling plugin's memory.
+fn read_guest(caller: &mut Caller<'_, Host>, ptr: i32, len: i32) -> wasmtime::Result<Vec<u8>> {
+    let memory = caller
+        .get_export("memory")
+        .and_then(|e| e.into_memory())
+        .ok_or_else(|| wasmtime::Error::msg("no exported memory"))?;
+    let start = usize::try_from(ptr)?;
+    let end = start.saturating_add(usize::try_from(len)?);
+    memory
+        .data(&caller)
+        .get(start..end)
+        .map(<[u8]>::to_vec)
+        .ok_or_else(|| wasmtime::Error::msg("message out of bounds"))
+}
+
+fn plugin_linker(engine: &Engine) -> wasmtime::Result<Linker<Host>> {
+    let mut linker = Linker::new(engine);
+    linker.func_wrap(
+        "tovaras",
+        "command",
+        |mut caller: Caller<'_, Host>, ptr: i32, len: i32| {
+            let bytes = read_guest(&mut caller, ptr, len)?;
+            let command = serde_json::from_slice::<RemoteCommand>(&bytes)?;
+            caller.data_mut().issued.push(command);
+            Ok(())
+        },
+    )?;
+    linker.func_wrap(
+        "tovaras",
+        "log",
+        |mut caller: Caller<'_, Host>, ptr: i32, len: i32| {
+            let bytes = read_guest(&mut caller, ptr, len)?;
+            info!("plugin: {}", String::from_utf8_lossy(&bytes));
+            Ok(())
+        },
+    )?;
+    Ok(linker)
+}
+
+#[derive(Resource)]
+pub struct Plugins {
+    engine: Engine,
+    linker: Linker<Host>,
+    dir: PathBuf,
+    plugins: Vec<Plugin>,
+    commands: Sender<RemoteCommand>,
+    rescan: Timer,
+}
+
+impl Plugins {
+    pub fn new(commands: Sender<RemoteCommand>) -> Self {
+        let mut config = wasmtime::Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config).expect("wasm engine config");
+        let linker = plugin_linker(&engine).expect("wasm plugin imports");
+        let mut plugins = Self {
+            engine,
+            linker,
+            dir: paths::config_dir().join(PLUGINS_DIR),
+            plugins: Vec::new(),
+            commands,
+            rescan: Timer::from_seconds(RESCAN_SECS, TimerMode::Repeating),
+        };
+        plugins.reload();
+        plugins
+    }
+
+    /// Load new and replaced plugins, forget deleted ones.
+    fn reload(&mut self) {
+        let files: Vec<(PathBuf, SystemTime)> = fs::read_dir(&self.dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|e| e == "wasm"))
+            .filter_map(|p| {
+                let modified = fs::metadata(&p).and_then(|m| m.modified()).ok()?;
+                Some((p, modified))
+            })
+            .collect();
+        self.plugins
+            .retain(|p| files.iter().any(|(f, m)| *f == p.path && *m == p.modified));
+        for (path, modified) in files {
+            if self.plugins.iter().any(|p| p.path == path) {
+                continue;
+            }
+            match Plugin::load(&self.engine, &self.linker, path.clone(), modified) {
+                Ok(mut plugin) => {
+                    info!("Loaded plugin {}", path.display());
+                    let issued = plugin.call("on_start", None);
+                    self.send(issued);
+                    self.plugins.push(plugin);
+                }
+                Err(e) => warn!("{}: {e}", path.display()),
+            }
+        }
+    }
+
+    fn send(&self, issued: Vec<RemoteCommand>) {
+        for command in issued {
+            let _ = self.commands.send(command);
+        }
+    }
+}
+
+/// Hand every plugin a snapshot and pass on the commands they issue.
+pub fn run_plugins(
+    time: Res<Time>,
+    mut plugins: ResMut<Plugins>,
+    profile: Res<Profile>,
+    mut petted: EventReader<Petted>,
+    pets: Query<&PetState, With<Pet>>,
+) {
+    if plugins.rescan.tick(time.delta()).just_finished() {
+        plugins.reload();
+    }
+    let clicks = petted.read().count();
+    if plugins.plugins.is_empty() {
+        return;
+    }
+    let Ok(st) = pets.get_single() else {
+        return;
+    };
+    let needs = profile.needs();
+    let snapshot = Snapshot {
+        action: st.action,
+        surface: st.surface,
+        x: st.window_pos.x,
+        y: st.window_pos.y,
+        dir: st.move_dir,
+        level: profile.level(),
+        energy: needs.energy,
+        affection: needs.affection,
+        clicks,
+        dt: time.delta_seconds(),
+    };
+    let Ok(message) = serde_json::to_vec(&snapshot) else {
+        return;
+    };
+    let plugins = &mut *plugins;
+    for plugin in plugins.plugins.iter_mut() {
+        let issued = plugin.call("on_state", Some(&message));
+        for command in issued {
+            let _ = plugins.commands.send(command);
+        }
+    }
+}
@@ -0,0 +1,134 @@
+//! The update rate follows what the pet is doing, so a quiet pet costs next to
+//! nothing while a jumping one stays smooth ([`Pace`]):
+//!
+//! - Jumping, landing, walking or climbing (or still coming to a stop),
+//!   particles in the air, a smoothed window still gliding, or the mouse used
+//!   on a window in the last `INPUT_SECS`: an update on every display refresh.
+//! - Idle in place: an update when a clip is due to show its next frame or the
+//!   driver to pick its next action, and at least every `IDLE_WAIT`.
+//! - Sleeping or hiding: the same, at least every [`REST_WAIT`].
+//!
+//! Clicks and other window events wake the app at once; remote commands wait
+//! for the next wake-up. `render: (adaptive_tick_rate: false)` in config.ron
+//! keeps updating on every refresh.
+
+use bevy::input::mouse::MouseButtonInput;
+use bevy::prelude::*;
+use bevy::winit::{UpdateMode, WinitSettings};
+use std::time::Duration;
+
+use crate::actions::{Actions, Motion};
+use crate::animation::Anim;
+use crate::drivers::{RandomCtrl, TestSeq};
+use crate::particles::Particle;
+use crate::pet_window::WindowSpring;
+use crate::{FlightKind, Pet, PetState};
+
+/// Longest wait between two updates while resting (about 2 FPS).
+pub const REST_WAIT: Duration = Duration::from_millis(500);
+/// Longest wait between two updates while idle (about 10 FPS).
+const IDLE_WAIT: Duration = Duration::from_millis(100);
+/// Seconds at the full rate after the mouse was last used on a window.
+const INPUT_SECS: f32 = 2.0;
+
+/// How often the app updates.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Pace {
+    /// On every display refresh.
+    #[default]
+    Active,
+    /// When the next frame or decision is due, at least every `IDLE_WAIT`.
+    Idle,
+    /// When the next frame or decision is due, at least every [`REST_WAIT`].
+    Resting,
+}
+
+impl Pace {
+    /// The pace a pet in state `st` needs.
+    fn of(st: &PetState, actions: &Actions) -> Self {
+        if st.flight != FlightKind::None || st.speed != 0.0 {
+            return Self::Active;
+        }
+        match actions.get(st.action).motion {
+            Motion::Rest => Self::Resting,
+            Motion::Still => Self::Idle,
+            Motion::Walk | Motion::Jump | Motion::Land => Self::Active,
+        }
+    }
+
+    /// `None` for updates on every refresh.
+    fn longest_wait(self) -> Option<Duration> {
+        match self {
+            Self::Active => None,
+            Self::Idle => Some(IDLE_WAIT),
+            Self::Resting => Some(REST_WAIT),
+        }
+    }
+}
+
+/// Pick the [`Pace`] the busiest pet needs and run the event loop at it:
+/// continuous updates, or a wait for the next animation frame or driver
+/// decision.
+#[allow(clippy::too_many_arguments)]
+pub fn govern_tick_rate(
+    real: Res<Time<Real>>,
+    time: Res<Time<Virtual>>,
+    mut settings: ResMut<WinitSettings>,
+    mut pace: ResMut<Pace>,
+    mut cursor: EventReader<CursorMoved>,
+    mut buttons: EventReader<MouseButtonInput>,
+    ctrl: Option<Res<RandomCtrl>>,
+    seq: Option<Res<TestSeq>>,
+    actions: Res<Actions>,
+    pets: Query<(&PetState, &Anim), With<Pet>>,
+    particles: Query<(), With<Particle>>,
+    springs: Query<&WindowSpring>,
+    mut last_input: Local<Option<f32>>,
+) {
+    let now = real.elapsed_seconds();
+    if cursor.read().count() + buttons.read().count() > 0 {
+        *last_input = Some(now);
+    }
+    let busy = pets.is_empty()
+        || !particles.is_empty()
+        || !springs.iter().all(WindowSpring::settled)
+        || last_input.is_some_and(|at| now - at < INPUT_SECS);
+    let next = if busy {
+        Pace::Active
+    } else {
+        // `None` (every refresh) sorts first
+        pets.iter()
+            .map(|(st, _)| Pace::of(st, &actions))
+            .min_by_key(|pace| pace.longest_wait())
+            .unwrap_or_default()
+    };
+
+    let mode = match next.longest_wait() {
+        None => UpdateMode::Continuous,
+        Some(longest) => {
+            let decision = match (&ctrl, &seq) {
+                (_, Some(seq)) if !seq.stepping => Some(seq.left),
+                (Some(ctrl), None) => Some(ctrl.left),
+                _ => None,
+            };
+            // animations and drivers run on the virtual clock, the wait is wall time
+            let due = pets
+                .iter()
+                .filter(|(_, anim)| anim.len > 1)
+                .map(|(_, anim)| anim.timer.remaining())
+                .chain(decision.map(|left| Duration::from_secs_f32(left.max(0.0))))
+                .map(|d| d.div_f32(time.relative_speed()))
+                .min()
+                .unwrap_or(longest);
+            UpdateMode::reactive_low_power(due.min(longest))
+        }
+    };
+    if *pace != next {
+        debug!("Update pace: {next:?}");
+        *pace = next;
+    }
+    if settings.focused_mode != mode {
+        settings.focused_mode = mode;
+        settings.unfocused_mode = mode;
+    }
+}
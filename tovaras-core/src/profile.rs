@@ -0,0 +1,388 @@
+//! The pet's persistent profile: needs, experience level and inventory.
+//!
+//! The profile lives in `<data_dir>/profile`, or in `sync.folder` when the same
+//! pet should follow the user between machines through Syncthing, Dropbox or a
+//! similar synced folder. Every machine writes only its own `<machine>.ron`
+//! and merges everybody else's:
+//!
+//! - needs carry the time they last changed; the newest copy wins;
+//! - experience and inventory are per-machine tallies that only grow; merging
+//!   keeps the larger tally for each machine and the totals are their sums.
+//!
+//! Merging is order-independent and idempotent, so late, stale or duplicated
+//! files never lose or double anything. The "conflicted copy" files sync tools
+//! make when two machines race are merged like any other and then removed.
+
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::SyncConfig;
+use crate::input::Petted;
+use crate::{paths, Action, Pet, PetState, Surface};
+
+const PROFILE_DIR: &str = "profile";
+/// Seconds awake to go from rested to exhausted.
+const AWAKE_SECS: f32 = 4.0 * 3600.0;
+/// Seconds asleep (or away) to fully recover.
+const REST_SECS: f32 = 30.0 * 60.0;
+/// Seconds without attention for affection to drop from full to none.
+const LONELY_SECS: f32 = 8.0 * 3600.0;
+const PET_AFFECTION: f32 = 0.1;
+const PET_XP: u64 = 5;
+const FLOWERS_XP: u64 = 2;
+/// Floor walks per flower picked up.
+const WALKS_PER_FLOWER: u32 = 3;
+pub const FLOWER: &str = "flower";
+
+/// How the pet is doing right now.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Needs {
+    /// 0 exhausted .. 1 rested.
+    pub energy: f32,
+    /// 0 lonely .. 1 loved.
+    pub affection: f32,
+    /// Unix seconds of the last change.
+    pub updated: u64,
+}
+
+impl Default for Needs {
+    fn default() -> Self {
+        Self {
+            energy: 1.0,
+            affection: 0.5,
+            updated: 0,
+        }
+    }
+}
+
+/// Grow-only counts of one item kept by one machine.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Tally {
+    added: u64,
+    removed: u64,
+}
+
+/// One machine's `<machine>.ron`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct SaveFile {
+    machine: String,
+    needs: Needs,
+    /// Experience earned on each machine.
+    xp: BTreeMap<String, u64>,
+    /// Item -> machine -> tally.
+    inventory: BTreeMap<String, BTreeMap<String, Tally>>,
+}
+
+impl SaveFile {
+    fn merge(&mut self, other: &SaveFile) {
+        if other.needs.updated > self.needs.updated {
+            self.needs = other.needs;
+        }
+        for (machine, &xp) in &other.xp {
+            let ours = self.xp.entry(machine.clone()).or_default();
+            *ours = (*ours).max(xp);
+        }
+        for (item, machines) in &other.inventory {
+            let tallies = self.inventory.entry(item.clone()).or_default();
+            for (machine, tally) in machines {
+                let ours = tallies.entry(machine.clone()).or_default();
+                ours.added = ours.added.max(tally.added);
+                ours.removed = ours.removed.max(tally.removed);
+            }
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct Profile {
+    dir: PathBuf,
+    machine: String,
+    save: SaveFile,
+    sync: Timer,
+    last_error: Option<String>,
+}
+
+impl Profile {
+    /// Merge every saved copy and catch up on the time the pet was away.
+    pub fn load(cfg: &SyncConfig) -> Self {
+        let dir = cfg
+            .folder_path()
+            .unwrap_or_else(|| paths::data_dir().join(PROFILE_DIR));
+        let machine = cfg.machine.clone().unwrap_or_else(hostname);
+        let mut profile = Self {
+            dir,
+            save: SaveFile {
+                machine: machine.clone(),
+                ..default()
+            },
+            machine,
+            sync: Timer::from_seconds(cfg.interval_secs.max(1.0), TimerMode::Repeating),
+            last_error: None,
+        };
+        profile.pull();
+        let needs = &mut profile.save.needs;
+        if needs.updated > 0 {
+            let away = unix_now().saturating_sub(needs.updated) as f32;
+            needs.energy = (needs.energy + away / REST_SECS).min(1.0);
+            needs.affection = (needs.affection - away / LONELY_SECS).max(0.0);
+        }
+        needs.updated = unix_now();
+        info!(
+            "Profile from {}: level {}, {} flowers",
+            profile.dir.display(),
+            profile.level(),
+            profile.count(FLOWER)
+        );
+        profile
+    }
+
+    pub fn needs(&self) -> Needs {
+        self.save.needs
+    }
+
+    pub fn xp(&self) -> u64 {
+        self.save.xp.values().sum()
+    }
+
+    pub fn level(&self) -> u32 {
+        level_for(self.xp())
+    }
+
+    /// Items of `item` in the inventory, counted over all machines.
+    pub fn count(&self, item: &str) -> u64 {
+        let Some(tallies) = self.save.inventory.get(item) else {
+            return 0;
+        };
+        let added: u64 = tallies.values().map(|t| t.added).sum();
+        let removed: u64 = tallies.values().map(|t| t.removed).sum();
+        added.saturating_sub(removed)
+    }
+
+    /// Every item there is at least one of, counted over all machines.
+    pub fn inventory(&self) -> BTreeMap<String, u64> {
+        self.save
+            .inventory
+            .keys()
+            .map(|item| (item.clone(), self.count(item)))
+            .filter(|&(_, n)| n > 0)
+            .collect()
+    }
+
+    pub fn add_xp(&mut self, xp: u64) {
+        let level = self.level();
+        *self.save.xp.entry(self.machine.clone()).or_default() += xp;
+        if self.level() > level {
+            info!("Level up: {}", self.level());
+        }
+    }
+
+    pub fn add_item(&mut self, item: &str, n: u64) {
+        self.tally(item).added += n;
+    }
+
+    /// Use up one `item`; false when there is none.
+    pub fn take_item(&mut self, item: &str) -> bool {
+        if self.count(item) == 0 {
+            return false;
+        }
+        self.tally(item).removed += 1;
+        true
+    }
+
+    fn tally(&mut self, item: &str) -> &mut Tally {
+        (self.save.inventory.entry(item.to_string()).or_default())
+            .entry(self.machine.clone())
+            .or_default()
+    }
+
+    fn set_needs(&mut self, energy: f32, affection: f32) {
+        self.save.needs = Needs {
+            energy: energy.clamp(0.0, 1.0),
+            affection: affection.clamp(0.0, 1.0),
+            updated: unix_now(),
+        };
+    }
+
+    /// Start over from an exported profile: every saved copy (other machines'
+    /// too, or they would be merged back in) is replaced by our file.
+    pub fn restore(
+        &mut self,
+        xp: u64,
+        energy: f32,
+        affection: f32,
+        inventory: &BTreeMap<String, u64>,
+    ) -> io::Result<()> {
+        for entry in fs::read_dir(&self.dir).into_iter().flatten().flatten() {
+            let path = entry.path();
+            if path.extension().is_some_and(|e| e == "ron") {
+                fs::remove_file(&path)?;
+            }
+        }
+        self.save = SaveFile {
+            machine: self.machine.clone(),
+            ..default()
+        };
+        self.save.xp.insert(self.machine.clone(), xp);
+        for (item, &n) in inventory {
+            self.add_item(item, n);
+        }
+        self.set_needs(energy, affection);
+        self.push()
+    }
+
+    fn own_file(&self) -> PathBuf {
+        self.dir.join(format!("{}.ron", self.machine))
+    }
+
+    /// Merge every machine's file (and conflict copies, which are then removed).
+    fn pull(&mut self) {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.starts_with('.') || path.extension().is_none_or(|e| e != "ron") {
+                continue;
+            }
+            let Ok(text) = fs::read_to_string(&path) else {
+                continue;
+            };
+            match ron::from_str::<SaveFile>(&text) {
+                Ok(other) => self.save.merge(&other),
+                Err(e) => {
+                    warn!("Skipping unreadable profile {}: {e}", path.display());
+                    continue;
+                }
+            }
+            if is_conflict_copy(&name) {
+                let _ = fs::remove_file(&path);
+            }
+        }
+    }
+
+    /// Write our file; a temporary file and rename keep readers from seeing half of it.
+    fn push(&self) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let text =
+            ron::ser::to_string_pretty(&self.save, Default::default()).map_err(io::Error::other)?;
+        let tmp = self.dir.join(format!(".{}.ron.tmp", self.machine));
+        fs::write(&tmp, text)?;
+        fs::rename(&tmp, self.own_file())
+    }
+
+    fn sync_now(&mut self) {
+        self.pull();
+        let error = self.push().err().map(|e| e.to_string());
+        if let Some(e) = &error {
+            if self.last_error.as_ref() != Some(e) {
+                warn!("Could not save profile to {}: {e}", self.dir.display());
+            }
+        }
+        self.last_error = error;
+    }
+}
+
+/// 1, 2, 3, ... at 0, 50, 200, 450, ... experience.
+pub fn level_for(xp: u64) -> u32 {
+    ((xp as f64 / 50.0).sqrt() as u32) + 1
+}
+
+/// Syncthing's `x.sync-conflict-<date>-<id>.ron`, Dropbox's `x (host's conflicted copy <date>).ron`.
+fn is_conflict_copy(name: &str) -> bool {
+    name.contains(".sync-conflict-") || name.contains("conflicted copy")
+}
+
+fn hostname() -> String {
+    let name = fs::read_to_string("/etc/hostname")
+        .ok()
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .unwrap_or_default();
+    let name: String = (name.trim().chars())
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        .collect();
+    if name.is_empty() {
+        "this-machine".to_string()
+    } else {
+        name
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Tire while awake, rest while sleeping, miss the user, enjoy petting.
+/// Needs follow the wall clock, whatever `--time-scale` says.
+pub fn update_needs(
+    time: Res<Time<Real>>,
+    mut profile: ResMut<Profile>,
+    mut petted: EventReader<Petted>,
+    pets: Query<&PetState, With<Pet>>,
+) {
+    let dt = time.delta_seconds();
+    let needs = profile.needs();
+    let sleeping = pets.iter().any(|st| st.action == Action::Sleeping);
+    let mut energy = if sleeping {
+        needs.energy + dt / REST_SECS
+    } else {
+        needs.energy - dt / AWAKE_SECS
+    };
+    let mut affection = needs.affection - dt / LONELY_SECS;
+    for _ in petted.read() {
+        affection += PET_AFFECTION;
+        profile.add_xp(PET_XP);
+    }
+    energy = energy.clamp(0.0, 1.0);
+    profile.set_needs(energy, affection);
+}
+
+/// Pick up a flower every few floor walks; giving flowers uses one up.
+pub fn collect_items(
+    mut profile: ResMut<Profile>,
+    pets: Query<&PetState, With<Pet>>,
+    mut last: Local<Option<(Action, Surface)>>,
+    mut walks: Local<u32>,
+) {
+    let Ok(st) = pets.get_single() else {
+        return;
+    };
+    let now = (st.action, st.surface);
+    let before = last.replace(now);
+    if before == Some(now) {
+        return;
+    }
+    if before == Some((Action::Move, Surface::Floor)) {
+        *walks += 1;
+        if walks.is_multiple_of(WALKS_PER_FLOWER) {
+            profile.add_item(FLOWER, 1);
+        }
+    }
+    if st.action == Action::GivingFlowers {
+        profile.take_item(FLOWER);
+        profile.add_xp(FLOWERS_XP);
+    }
+}
+
+pub fn sync_profile(time: Res<Time<Real>>, mut profile: ResMut<Profile>) {
+    if profile.sync.tick(time.delta()).just_finished() {
+        profile.sync_now();
+    }
+}
+
+pub fn save_on_exit(mut exits: EventReader<AppExit>, mut profile: ResMut<Profile>) {
+    if exits.read().next().is_some() {
+        profile.sync_now();
+    }
+}
@@ -0,0 +1,96 @@
+//! Color variants of one sheet ("orange cat", "black cat"), declared by the
+//! manifest and baked into the texture at load:
+//!
+//! ```ron
+//! variants: {
+//!     "black": (palette: {"#f4a261": "#2b2b2b", "#e76f51": "#1a1a1a"}),
+//!     "blue": (hue_shift: 180.0, saturation: 0.8),
+//! },
+//! ```
+//!
+//! Palette entries swap exact colors; pixels not in the palette get the HSV
+//! adjustment (hue in degrees, saturation/value as multipliers).
+
+use bevy::color::ColorToPacked;
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Variant {
+    /// `"#rrggbb"` -> `"#rrggbb"`.
+    pub palette: BTreeMap<String, String>,
+    pub hue_shift: f32,
+    pub saturation: f32,
+    pub value: f32,
+}
+
+impl Default for Variant {
+    fn default() -> Self {
+        Self {
+            palette: BTreeMap::new(),
+            hue_shift: 0.0,
+            saturation: 1.0,
+            value: 1.0,
+        }
+    }
+}
+
+pub fn parse_hex(s: &str) -> Option<[u8; 3]> {
+    let hex = s.strip_prefix('#').unwrap_or(s);
+    if hex.len() != 6 {
+        return None;
+    }
+    let byte = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some([byte(0)?, byte(2)?, byte(4)?])
+}
+
+impl Variant {
+    pub fn validate(&self) -> Result<(), String> {
+        for (from, to) in &self.palette {
+            if parse_hex(from).is_none() || parse_hex(to).is_none() {
+                return Err(format!("bad palette entry {from:?} -> {to:?}"));
+            }
+        }
+        Ok(())
+    }
+
+    fn shifts_hsv(&self) -> bool {
+        self.hue_shift != 0.0 || self.saturation != 1.0 || self.value != 1.0
+    }
+}
+
+/// Recolor an RGBA8 sheet in place.
+pub fn apply(img: &mut Image, variant: &Variant) {
+    let palette: Vec<([u8; 3], [u8; 3])> = variant
+        .palette
+        .iter()
+        .filter_map(|(from, to)| Some((parse_hex(from)?, parse_hex(to)?)))
+        .collect();
+    let shift = variant.shifts_hsv();
+
+    for px in img.data.chunks_exact_mut(4) {
+        if px[3] == 0 {
+            continue;
+        }
+        let rgb = [px[0], px[1], px[2]];
+        if let Some((_, to)) = palette.iter().find(|(from, _)| *from == rgb) {
+            px[..3].copy_from_slice(to);
+        } else if shift {
+            px[..3].copy_from_slice(&shift_hsv(rgb, variant));
+        }
+    }
+}
+
+fn shift_hsv([r, g, b]: [u8; 3], v: &Variant) -> [u8; 3] {
+    let c = Color::srgb_u8(r, g, b);
+    let hsv = Hsva::from(c);
+    let shifted = Hsva::new(
+        (hsv.hue + v.hue_shift).rem_euclid(360.0),
+        (hsv.saturation * v.saturation).clamp(0.0, 1.0),
+        (hsv.value * v.value).clamp(0.0, 1.0),
+        hsv.alpha,
+    );
+    Srgba::from(shifted).to_u8_array_no_alpha()
+}
@@ -0,0 +1,261 @@
+//! Remote control of the running pet, shared by the local APIs.
+//!
+//! Servers (see `websocket.rs`) run on their own threads: they push parsed
+//! [`RemoteCommand`]s into the channel from [`Remote::sender`] and register a
+//! listener with [`Remote::listeners`] to receive state updates as JSON lines.
+//! Commands are JSON objects tagged by `cmd`:
+//!
+//! ```text
+//! {"cmd":"set_action","action":"Sleeping","secs":30}
+//! {"cmd":"goto","x":500}
+//! {"cmd":"say","text":"build done","secs":5}
+//! {"cmd":"skin","pack":"cat"}            (null = the built-in pet)
+//! {"cmd":"step"}                          (next case, test mode with --step)
+//! {"cmd":"timer","secs":1500,"label":"tea"}
+//! ```
+//!
+//! Updates are `{"type":"state",...}` snapshots whenever the pet moves or
+//! changes action, and `{"type":"transition","from":...,"to":...}` when its
+//! surface or action changes.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+use crate::commands::{Issued, PetCommand, Source};
+use crate::drivers::TestSeq;
+use crate::packs::{ActivePack, SwitchPack};
+use crate::pet_window::PetWindow;
+use crate::timers::Timers;
+use crate::{Action, Pet, PetState, ScreenGeometry, Surface};
+
+/// Seconds lower sources (the random driver) stay off after a remote action.
+const HOLD_SECS: f32 = 10.0;
+/// Seconds between state snapshots at most.
+const PUBLISH_SECS: f32 = 0.1;
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+pub enum RemoteCommand {
+    /// Play `action` where the pet is, for `secs` before the usual behavior resumes.
+    SetAction {
+        action: Action,
+        #[serde(default)]
+        secs: Option<f32>,
+    },
+    /// Walk (or jump down) to screen x, the pet's center.
+    Goto { x: i32 },
+    Say {
+        text: String,
+        #[serde(default)]
+        secs: Option<f32>,
+    },
+    /// Change into an installed pack.
+    Skin { pack: Option<String> },
+    /// Go on to the next case (test mode with `--step`).
+    Step,
+    /// Announce `label` in `secs` (see `timers.rs`).
+    Timer {
+        secs: f32,
+        #[serde(default)]
+        label: Option<String>,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize)]
+struct Pose {
+    surface: Surface,
+    action: Action,
+}
+
+/// The pet as streamed to clients (and followed by mirrors, see `mirror.rs`).
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub surface: Surface,
+    pub action: Action,
+    pub dir: f32,
+    /// Which way it looks; `dir` when missing (older primaries).
+    #[serde(default)]
+    pub facing: Option<f32>,
+    /// Window top-left, screen px.
+    pub x: i32,
+    pub y: i32,
+    /// The farthest `x` and `y` go (the window in the bottom-right corner),
+    /// so a mirror with another resolution can scale them; missing from
+    /// older primaries.
+    #[serde(default)]
+    pub bounds: Option<(i32, i32)>,
+    pub pack: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Outgoing<'a> {
+    State(&'a Snapshot),
+    Transition { from: Pose, to: Pose },
+}
+
+/// A connected client waiting for updates.
+pub struct Listener {
+    tx: Sender<String>,
+    /// Has had the current state since it joined.
+    greeted: bool,
+}
+
+/// Every connected client; closed ones are dropped on the next update.
+pub type Listeners = Arc<Mutex<Vec<Listener>>>;
+
+/// Join `listeners`; the current state arrives first, then every update.
+pub fn subscribe(listeners: &Listeners) -> Receiver<String> {
+    let (tx, rx) = mpsc::channel();
+    listeners
+        .lock()
+        .unwrap()
+        .push(Listener { tx, greeted: false });
+    rx
+}
+
+#[derive(Resource)]
+pub struct Remote {
+    commands: Mutex<Receiver<RemoteCommand>>,
+    sender: Sender<RemoteCommand>,
+    listeners: Listeners,
+    last: Option<Snapshot>,
+    publish: Timer,
+}
+
+impl Remote {
+    pub fn new() -> Self {
+        let (sender, rx) = mpsc::channel();
+        Self {
+            commands: Mutex::new(rx),
+            sender,
+            listeners: Listeners::default(),
+            last: None,
+            publish: Timer::from_seconds(PUBLISH_SECS, TimerMode::Repeating),
+        }
+    }
+
+    /// Where servers send the commands they receive.
+    pub fn sender(&self) -> Sender<RemoteCommand> {
+        self.sender.clone()
+    }
+
+    /// Shared list servers [`subscribe`] their clients to.
+    pub fn listeners(&self) -> Listeners {
+        self.listeners.clone()
+    }
+
+    /// Send `msg` to every client, or only to those that just joined.
+    fn broadcast(&self, msg: &Outgoing, newcomers_only: bool) {
+        let Ok(line) = serde_json::to_string(msg) else {
+            return;
+        };
+        self.listeners.lock().unwrap().retain_mut(|client| {
+            if newcomers_only && client.greeted {
+                return true;
+            }
+            client.greeted = true;
+            client.tx.send(line.clone()).is_ok()
+        });
+    }
+}
+
+/// Pass queued commands on: pet commands to the arbiter (see `commands.rs`),
+/// pack switches, steps and timers to their systems.
+pub fn apply_remote_commands(
+    mut remote: ResMut<Remote>,
+    mut seq: Option<ResMut<TestSeq>>,
+    mut timers: Option<ResMut<Timers>>,
+    mut switch: EventWriter<SwitchPack>,
+    mut issue: EventWriter<Issued>,
+) {
+    let commands: Vec<RemoteCommand> = remote.commands.get_mut().unwrap().try_iter().collect();
+    for command in commands {
+        let command = match command {
+            RemoteCommand::SetAction { action, secs } => PetCommand::SetAction {
+                action,
+                dir: None,
+                clip: None,
+                secs: Some(secs.unwrap_or(HOLD_SECS)),
+            },
+            RemoteCommand::Goto { x } => PetCommand::GoTo { x },
+            RemoteCommand::Say { text, secs } => PetCommand::Say { text, secs },
+            RemoteCommand::Skin { pack } => {
+                switch.send(SwitchPack(pack));
+                continue;
+            }
+            RemoteCommand::Step => {
+                if let Some(seq) = seq.as_mut() {
+                    seq.advance = true;
+                }
+                continue;
+            }
+            RemoteCommand::Timer { secs, label } => {
+                if let Some(timers) = timers.as_mut() {
+                    timers.add(secs, label);
+                }
+                continue;
+            }
+        };
+        issue.send(command.by(Source::Remote));
+    }
+}
+
+/// Stream the pet's state to connected clients.
+pub fn publish_state(
+    time: Res<Time>,
+    mut remote: ResMut<Remote>,
+    active: Res<ActivePack>,
+    screen: Res<ScreenGeometry>,
+    windows: Query<&Window>,
+    pets: Query<(&PetState, &PetWindow), With<Pet>>,
+) {
+    if !remote.publish.tick(time.delta()).just_finished() {
+        return;
+    }
+    let Ok((st, pet_window)) = pets.get_single() else {
+        return;
+    };
+    let bounds = windows.get(pet_window.0).ok().map(|win| {
+        let (fw, fh) = (
+            win.resolution.physical_width() as i32,
+            win.resolution.physical_height() as i32,
+        );
+        let (screen_w, screen_h) = screen.desktop(fw, fh);
+        (screen_w - fw, screen_h - fh)
+    });
+    let now = Snapshot {
+        surface: st.surface,
+        action: st.action,
+        dir: st.move_dir,
+        facing: Some(st.facing),
+        x: st.window_pos.x,
+        y: st.window_pos.y,
+        bounds,
+        pack: active.name.clone(),
+    };
+    if remote.last.as_ref() == Some(&now) {
+        // newcomers still get the current state
+        remote.broadcast(&Outgoing::State(&now), true);
+        return;
+    }
+    if let Some(last) = &remote.last {
+        if (last.surface, last.action) != (now.surface, now.action) {
+            let transition = Outgoing::Transition {
+                from: Pose {
+                    surface: last.surface,
+                    action: last.action,
+                },
+                to: Pose {
+                    surface: now.surface,
+                    action: now.action,
+                },
+            };
+            remote.broadcast(&transition, false);
+        }
+    }
+    remote.broadcast(&Outgoing::State(&now), false);
+    remote.last = Some(now);
+}
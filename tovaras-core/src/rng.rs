@@ -0,0 +1,188 @@
+//! Where the pet's randomness comes from. Behavior code draws through the
+//! [`PetRng`] trait, so the source can be swapped: [`TinyRng`] (xorshift, no
+//! external crates) by default, [`Scripted`] to replay a chosen sequence of
+//! rolls, or `rand`'s `StdRng` with the `rand` feature. The drivers get theirs
+//! as the [`Rng`] resource.
+
+use bevy::prelude::*;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A source of random numbers; everything but [`next_u32`](Self::next_u32)
+/// is built on it.
+pub trait PetRng: Send + Sync {
+    fn next_u32(&mut self) -> u32;
+
+    /// What it was seeded with, to replay it (`--headless --seed`).
+    fn seed(&self) -> Option<u32> {
+        None
+    }
+
+    /// Uniform in 0..1, from the top 24 bits (all an `f32` holds exactly).
+    fn f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1u32 << 24) as f32
+    }
+    fn range_f32(&mut self, a: f32, b: f32) -> f32 {
+        a + (b - a) * self.f32()
+    }
+    /// Uniform in `a..=b`; `a` when the range is empty.
+    fn range_i32(&mut self, a: i32, b: i32) -> i32 {
+        if b <= a {
+            a
+        } else {
+            a + (self.f32() * ((b - a + 1) as f32)).floor() as i32
+        }
+    }
+    fn chance(&mut self, p: f32) -> bool {
+        self.f32() < p
+    }
+    /// -1.0 or +1.0 with equal probability.
+    fn sign(&mut self) -> f32 {
+        if self.chance(0.5) {
+            -1.0
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Simple xorshift RNG.
+pub struct TinyRng {
+    state: u32,
+    pub seed: u32,
+}
+
+impl TinyRng {
+    pub fn seeded() -> Self {
+        Self::from_seed(clock_seed())
+    }
+    /// Same seed, same sequence (headless runs replay with `--seed`).
+    pub fn from_seed(seed: u32) -> Self {
+        Self {
+            state: seed ^ 0xA3C59AC3,
+            seed,
+        }
+    }
+}
+
+impl PetRng for TinyRng {
+    fn next_u32(&mut self) -> u32 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.state = x;
+        x
+    }
+
+    fn seed(&self) -> Option<u32> {
+        Some(self.seed)
+    }
+}
+
+/// A test double: the same draws over and over, in order.
+pub struct Scripted {
+    draws: Vec<u32>,
+    next: usize,
+}
+
+impl Scripted {
+    pub fn new(draws: Vec<u32>) -> Self {
+        Self { draws, next: 0 }
+    }
+
+    /// Draws that [`PetRng::f32`] turns into `rolls` (each in 0..=1; 1 is
+    /// the highest roll below it), e.g. `[0.9, 0.1]` for "pick the last
+    /// action, then go left".
+    pub fn rolls(rolls: &[f32]) -> Self {
+        const ONE: u32 = 1 << 24;
+        Self::new(
+            rolls
+                .iter()
+                .map(|roll| ((roll.clamp(0.0, 1.0) * ONE as f32) as u32).min(ONE - 1) << 8)
+                .collect(),
+        )
+    }
+}
+
+impl PetRng for Scripted {
+    fn next_u32(&mut self) -> u32 {
+        let Some(&draw) = self.draws.get(self.next % self.draws.len().max(1)) else {
+            return 0;
+        };
+        self.next += 1;
+        draw
+    }
+}
+
+/// `rand`'s standard generator, seeded like [`TinyRng`].
+#[cfg(feature = "rand")]
+pub struct StdBackend {
+    rng: rand::rngs::StdRng,
+    seed: u32,
+}
+
+#[cfg(feature = "rand")]
+impl StdBackend {
+    pub fn from_seed(seed: u32) -> Self {
+        use rand::SeedableRng;
+        Self {
+            rng: rand::rngs::StdRng::seed_from_u64(seed.into()),
+            seed,
+        }
+    }
+}
+
+#[cfg(feature = "rand")]
+impl PetRng for StdBackend {
+    fn next_u32(&mut self) -> u32 {
+        rand::RngCore::next_u32(&mut self.rng)
+    }
+
+    fn seed(&self) -> Option<u32> {
+        Some(self.seed)
+    }
+}
+
+/// The drivers' source of randomness.
+#[derive(Resource, Deref, DerefMut)]
+pub struct Rng(pub Box<dyn PetRng>);
+
+impl Rng {
+    /// The default source, seeded from the clock.
+    pub fn seeded() -> Self {
+        Self::from_seed(clock_seed())
+    }
+
+    /// The default source: [`TinyRng`], or `StdRng` with the `rand` feature.
+    /// Same seed, same sequence.
+    pub fn from_seed(seed: u32) -> Self {
+        #[cfg(feature = "rand")]
+        let rng = StdBackend::from_seed(seed);
+        #[cfg(not(feature = "rand"))]
+        let rng = TinyRng::from_seed(seed);
+        Self(Box::new(rng))
+    }
+}
+
+fn clock_seed() -> u32 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::from_secs(1))
+        .subsec_nanos()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rolls_stay_below_one() {
+        let mut rng = Scripted::new(vec![0, u32::MAX]);
+        assert_eq!(rng.f32(), 0.0);
+        assert!(rng.f32() < 1.0);
+        let mut rng = Scripted::rolls(&[0.25, 1.0]);
+        assert_eq!(rng.f32(), 0.25);
+        assert!(rng.f32() < 1.0);
+        assert_eq!(Scripted::rolls(&[1.0]).range_i32(0, 9), 9);
+    }
+}
@@ -0,0 +1,119 @@
+//! Test mode scenarios: the cases `tovaras --test` cycles through, from a RON
+//! file (`--test FILE`) or the built-in tour of every surface and action
+//! (`assets/scenario.ron`, which describes the format).
+
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+use crate::actions::{Actions, Span};
+use crate::drivers::{JumpPreset, TestCase, CASE_DUR};
+use crate::manifest::Manifest;
+use crate::{Action, Surface};
+
+const EMBEDDED: &str = include_str!("../assets/scenario.ron");
+
+/// One case as written in a scenario file.
+#[derive(Clone, Deserialize)]
+pub struct Case {
+    surface: Surface,
+    action: Action,
+    #[serde(default = "forward")]
+    dir: f32,
+    /// Seconds; `None` = one pass of the clip for actions that last that
+    /// long (GivingFlowers), or `CASE_DUR`.
+    #[serde(default)]
+    secs: Option<f32>,
+    #[serde(default)]
+    preset: JumpPreset,
+}
+
+fn forward() -> f32 {
+    1.0
+}
+
+/// Default start height of wall jumps (fraction of the wall, from the top).
+pub fn halfway() -> f32 {
+    0.5
+}
+
+impl Case {
+    fn validate(&self) -> Result<(), String> {
+        if self.dir.abs() != 1.0 {
+            return Err(format!("dir must be 1.0 or -1.0, not {}", self.dir));
+        }
+        if !self.secs.is_none_or(|s| s > 0.0) {
+            return Err("secs must be positive".into());
+        }
+        if !Actions::default().get(self.action).allowed_on(self.surface) {
+            return Err(format!("no {:?} on the {:?}", self.action, self.surface));
+        }
+        let fractions = match self.preset {
+            JumpPreset::FloorPct {
+                start_pct,
+                target_pct,
+            } => vec![start_pct, target_pct],
+            JumpPreset::FloorToWall {
+                start_pct,
+                target_y_pct,
+                ..
+            } => vec![start_pct, target_y_pct],
+            JumpPreset::WallToFloorPct {
+                target_pct,
+                start_y_pct,
+            } => vec![target_pct, start_y_pct],
+            JumpPreset::None => vec![],
+        };
+        if let Some(bad) = fractions.iter().find(|f| !(0.0..=1.0).contains(*f)) {
+            return Err(format!(
+                "{bad} is not a fraction of the desktop (0.0..=1.0)"
+            ));
+        }
+        if let JumpPreset::FloorToWall { wall, .. } = self.preset {
+            if !matches!(wall, Surface::LeftWall | Surface::RightWall) {
+                return Err(format!("FloorToWall needs a wall, not {wall:?}"));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Cases of the scenario `file`, or of the built-in one.
+pub fn load(file: Option<&Path>) -> Result<Vec<Case>, String> {
+    let (text, name) = match file {
+        Some(path) => (
+            fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?,
+            path.display().to_string(),
+        ),
+        None => (EMBEDDED.to_string(), "built-in scenario".into()),
+    };
+    let cases: Vec<Case> = ron::from_str(&text).map_err(|e| format!("{name}: {e}"))?;
+    if cases.is_empty() {
+        return Err(format!("{name}: no cases"));
+    }
+    for (i, case) in cases.iter().enumerate() {
+        case.validate()
+            .map_err(|e| format!("{name}: case {}: {e}", i + 1))?;
+    }
+    Ok(cases)
+}
+
+/// The cases with their durations worked out for `manifest`.
+pub fn test_cases(cases: &[Case], actions: &Actions, manifest: &Manifest) -> Vec<TestCase> {
+    cases
+        .iter()
+        .map(|case| {
+            let def = actions.get(case.action);
+            TestCase {
+                surface: case.surface,
+                action: case.action,
+                dir: case.dir,
+                dur: case.secs.unwrap_or(match def.span {
+                    Span::Clip => def.secs(manifest, case.surface, 0.0),
+                    _ => CASE_DUR,
+                }),
+                preset: case.preset,
+            }
+        })
+        .collect()
+}
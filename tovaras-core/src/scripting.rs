@@ -0,0 +1,253 @@
+//! User scripts (built with `--features scripting`).
+//!
+//! Every `*.rhai` file in `<config_dir>/scripts` is loaded at startup and
+//! reloaded when it changes. A script defines any of these handlers:
+//!
+//! ```rhai
+//! fn on_start() { this.walks = 0; }
+//! fn on_tick(dt) { }
+//! fn on_action_changed(from, to) { if to == "Move" { this.walks += 1; } }
+//! fn on_clicked() { say("Walked " + this.walks + " times today"); }
+//! ```
+//!
+//! `this` is a map private to the script that survives between calls (and is
+//! reset on reload). Handlers can read `pet()` (a map with `action`,
+//! `surface`, `x`, `y`, `dir`, `level`, `energy` and `affection`) and issue
+//! commands: `set_action(name)`, `set_action(name, secs)`, `move_to(x)`,
+//! `say(text)`, `say(text, secs)` and `skin(pack)` (`skin(())` = built-in).
+//! A script that fails is paused until its file changes.
+
+use bevy::prelude::*;
+use rhai::{CallFnOptions, Dynamic, Engine, EvalAltResult, ImmutableString, Map, Scope, AST};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use crate::input::Petted;
+use crate::profile::Profile;
+use crate::remote::RemoteCommand;
+use crate::{paths, Action, Pet, PetState};
+
+const SCRIPTS_DIR: &str = "scripts";
+/// Seconds between checks for new or edited scripts.
+const RESCAN_SECS: f32 = 1.0;
+/// Work a single handler call may do before it is stopped.
+const MAX_OPERATIONS: u64 = 200_000;
+
+struct Script {
+    path: PathBuf,
+    modified: SystemTime,
+    ast: AST,
+    this: Dynamic,
+    failed: bool,
+}
+
+impl Script {
+    fn has(&self, name: &str, params: usize) -> bool {
+        self.ast
+            .iter_functions()
+            .any(|f| f.name == name && f.params.len() == params)
+    }
+
+    /// Call handler `name` if the script defines it.
+    fn call(&mut self, engine: &Engine, name: &str, args: Vec<Dynamic>) {
+        if self.failed || !self.has(name, args.len()) {
+            return;
+        }
+        let options = CallFnOptions::new()
+            .eval_ast(false)
+            .bind_this_ptr(&mut self.this);
+        let result = engine.call_fn_with_options::<Dynamic>(
+            options,
+            &mut Scope::new(),
+            &self.ast,
+            name,
+            args,
+        );
+        if let Err(e) = result {
+            warn!("{}: {name}: {e}; paused until edited", self.path.display());
+            self.failed = true;
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct Scripts {
+    engine: Engine,
+    dir: PathBuf,
+    scripts: Vec<Script>,
+    /// Commands issued by handlers, forwarded after each round.
+    issued: Arc<Mutex<Vec<RemoteCommand>>>,
+    /// What `pet()` returns.
+    pet: Arc<Mutex<Map>>,
+    commands: Sender<RemoteCommand>,
+    rescan: Timer,
+}
+
+impl Scripts {
+    pub fn new(commands: Sender<RemoteCommand>) -> Self {
+        let issued: Arc<Mutex<Vec<RemoteCommand>>> = Arc::default();
+        let pet: Arc<Mutex<Map>> = Arc::default();
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine.on_print(|text| info!("script: {text}"));
+
+        let out = issued.clone();
+        let set_action = move |name: &str, secs: Option<f32>| {
+            let action = parse_action(name)?;
+            out.lock()
+                .unwrap()
+                .push(RemoteCommand::SetAction { action, secs });
+            Ok::<_, Box<EvalAltResult>>(())
+        };
+        let set_timed = set_action.clone();
+        let set_whole = set_action.clone();
+        engine.register_fn("set_action", move |name: &str| set_action(name, None));
+        engine.register_fn("set_action", move |name: &str, secs: f64| {
+            set_timed(name, Some(secs as f32))
+        });
+        engine.register_fn("set_action", move |name: &str, secs: i64| {
+            set_whole(name, Some(secs as f32))
+        });
+        let out = issued.clone();
+        engine.register_fn("move_to", move |x: i64| {
+            out.lock()
+                .unwrap()
+                .push(RemoteCommand::Goto { x: x as i32 });
+        });
+        let out = issued.clone();
+        let say = move |text: ImmutableString, secs: Option<f32>| {
+            out.lock().unwrap().push(RemoteCommand::Say {
+                text: text.to_string(),
+                secs,
+            });
+        };
+        let (say_timed, say_whole) = (say.clone(), say.clone());
+        engine.register_fn("say", move |text: ImmutableString| say(text, None));
+        engine.register_fn("say", move |text: ImmutableString, secs: f64| {
+            say_timed(text, Some(secs as f32))
+        });
+        engine.register_fn("say", move |text: ImmutableString, secs: i64| {
+            say_whole(text, Some(secs as f32))
+        });
+        let out = issued.clone();
+        engine.register_fn("skin", move |pack: ImmutableString| {
+            out.lock().unwrap().push(RemoteCommand::Skin {
+                pack: Some(pack.to_string()),
+            });
+        });
+        let out = issued.clone();
+        engine.register_fn("skin", move |_: ()| {
+            out.lock().unwrap().push(RemoteCommand::Skin { pack: None });
+        });
+        let state = pet.clone();
+        engine.register_fn("pet", move || state.lock().unwrap().clone());
+
+        let mut scripts = Self {
+            engine,
+            dir: paths::config_dir().join(SCRIPTS_DIR),
+            scripts: Vec::new(),
+            issued,
+            pet,
+            commands,
+            rescan: Timer::from_seconds(RESCAN_SECS, TimerMode::Repeating),
+        };
+        scripts.reload();
+        scripts
+    }
+
+    /// Load new and edited scripts, forget deleted ones.
+    fn reload(&mut self) {
+        let files: Vec<(PathBuf, SystemTime)> = fs::read_dir(&self.dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|e| e == "rhai"))
+            .filter_map(|p| {
+                let modified = fs::metadata(&p).and_then(|m| m.modified()).ok()?;
+                Some((p, modified))
+            })
+            .collect();
+        self.scripts
+            .retain(|s| files.iter().any(|(p, m)| *p == s.path && *m == s.modified));
+        for (path, modified) in files {
+            if self.scripts.iter().any(|s| s.path == path) {
+                continue;
+            }
+            match self.engine.compile_file(path.clone()) {
+                Ok(ast) => {
+                    info!("Loaded script {}", path.display());
+                    let mut script = Script {
+                        path,
+                        modified,
+                        ast,
+                        this: Map::new().into(),
+                        failed: false,
+                    };
+                    script.call(&self.engine, "on_start", vec![]);
+                    self.scripts.push(script);
+                }
+                Err(e) => warn!("{}: {e}", path.display()),
+            }
+        }
+    }
+}
+
+fn parse_action(name: &str) -> Result<Action, Box<EvalAltResult>> {
+    Action::parse(name).ok_or_else(|| format!("unknown action {name:?}").into())
+}
+
+/// Feed events to the scripts and pass on the commands they issue.
+pub fn run_scripts(
+    time: Res<Time>,
+    mut scripts: ResMut<Scripts>,
+    profile: Res<Profile>,
+    mut petted: EventReader<Petted>,
+    pets: Query<&PetState, With<Pet>>,
+    mut last_action: Local<Option<Action>>,
+) {
+    if scripts.rescan.tick(time.delta()).just_finished() {
+        scripts.reload();
+    }
+    let Ok(st) = pets.get_single() else {
+        return;
+    };
+    {
+        let needs = profile.needs();
+        let mut pet = scripts.pet.lock().unwrap();
+        pet.insert("action".into(), format!("{:?}", st.action).into());
+        pet.insert("surface".into(), format!("{:?}", st.surface).into());
+        pet.insert("x".into(), (st.window_pos.x as i64).into());
+        pet.insert("y".into(), (st.window_pos.y as i64).into());
+        pet.insert("dir".into(), (st.move_dir as f64).into());
+        pet.insert("level".into(), (profile.level() as i64).into());
+        pet.insert("energy".into(), (needs.energy as f64).into());
+        pet.insert("affection".into(), (needs.affection as f64).into());
+    }
+
+    let clicks = petted.read().count();
+    let changed = last_action
+        .replace(st.action)
+        .filter(|before| *before != st.action);
+    let dt = time.delta_seconds() as f64;
+    let scripts = &mut *scripts;
+    for script in scripts.scripts.iter_mut() {
+        for _ in 0..clicks {
+            script.call(&scripts.engine, "on_clicked", vec![]);
+        }
+        if let Some(before) = changed {
+            let names = vec![
+                format!("{before:?}").into(),
+                format!("{:?}", st.action).into(),
+            ];
+            script.call(&scripts.engine, "on_action_changed", names);
+        }
+        script.call(&scripts.engine, "on_tick", vec![dt.into()]);
+    }
+    for command in scripts.issued.lock().unwrap().drain(..) {
+        let _ = scripts.commands.send(command);
+    }
+}
@@ -0,0 +1,82 @@
+//! Tiny procedurally generated textures (footprints, particles, ...), so small
+//! effects don't need extra art files.
+
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+
+/// Build an RGBA image by evaluating `coverage(x, y)` in [-1, 1]² for every pixel;
+/// the result (0..1) becomes the alpha of `color`.
+pub fn mask_image(w: u32, h: u32, color: [u8; 3], coverage: impl Fn(f32, f32) -> f32) -> Image {
+    let mut data = Vec::with_capacity((w * h * 4) as usize);
+    for py in 0..h {
+        for px in 0..w {
+            let x = (px as f32 + 0.5) / w as f32 * 2.0 - 1.0;
+            let y = (py as f32 + 0.5) / h as f32 * 2.0 - 1.0;
+            let a = coverage(x, y).clamp(0.0, 1.0);
+            data.extend_from_slice(&[color[0], color[1], color[2], (a * 255.0) as u8]);
+        }
+    }
+    Image::new(
+        Extent3d {
+            width: w,
+            height: h,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD | RenderAssetUsages::MAIN_WORLD,
+    )
+}
+
+/// Soft-edged ellipse filling the whole image.
+pub fn soft_oval(w: u32, h: u32, color: [u8; 3]) -> Image {
+    mask_image(w, h, color, |x, y| {
+        let d = (x * x + y * y).sqrt();
+        (1.0 - d) * 4.0
+    })
+}
+
+/// Classic implicit heart curve, lobes at the top.
+pub fn heart(w: u32, h: u32, color: [u8; 3]) -> Image {
+    mask_image(w, h, color, |x, y| {
+        let (x, y) = (x * 1.25, -y * 1.25 + 0.2);
+        let f = (x * x + y * y - 1.0).powi(3) - x * x * y * y * y;
+        if f <= 0.0 {
+            1.0
+        } else {
+            0.0
+        }
+    })
+}
+
+/// A chunky letter "Z" (top bar, diagonal, bottom bar).
+pub fn letter_z(w: u32, h: u32, color: [u8; 3]) -> Image {
+    mask_image(w, h, color, |x, y| {
+        let bar = 0.3;
+        let top = y < -1.0 + bar;
+        let bottom = y > 1.0 - bar;
+        // diagonal from top-right to bottom-left
+        let diagonal = (x + y).abs() < bar;
+        if top || bottom || diagonal {
+            1.0
+        } else {
+            0.0
+        }
+    })
+}
+
+/// Faceless-ish blob with two eye holes, standing on the bottom edge; used
+/// as a stand-in pet when the real sheet can't be loaded.
+pub fn blob(w: u32, h: u32, color: [u8; 3]) -> Image {
+    mask_image(w, h, color, |x, y| {
+        let (bx, by) = (x / 0.8, (y - 0.3) / 0.7);
+        let body = (1.0 - (bx * bx + by * by).sqrt()) * 6.0;
+        let eye = |ex: f32| {
+            let d = ((x - ex).powi(2) + (y - 0.15).powi(2)).sqrt();
+            ((d - 0.1) * 12.0).clamp(0.0, 1.0)
+        };
+        body * eye(-0.28) * eye(0.28)
+    })
+}
@@ -0,0 +1,196 @@
+//! Sharing a pet: `tovaras export-profile [FILE]` bundles the profile and
+//! the look of the pet into one file, `tovaras import-profile FILE` adopts it
+//! on another machine or account:
+//!
+//! ```ron
+//! (
+//!     format: 1,
+//!     name: "Kit",
+//!     pack: Some("kit"),
+//!     variant: None,
+//!     traits: Some((parents: ("cat", "fox"), idles: {"idle": 0.75, "yawn": 0.25})),
+//!     level: 3,
+//!     xp: 230,
+//!     energy: 0.8,
+//!     affection: 0.6,
+//!     inventory: {"flower": 4},
+//! )
+//! ```
+//!
+//! Files are validated before anything is written, and files made by a newer
+//! tovaras (a higher `format`) are refused.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::config::Config;
+use crate::ipc;
+use crate::packs;
+use crate::profile::{level_for, Profile};
+use crate::traits::Traits;
+
+const FORMAT: u32 = 1;
+const DEFAULT_FILE: &str = "tovaras-profile.ron";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SharedPet {
+    /// Version of this layout.
+    format: u32,
+    name: String,
+    /// Installed pack the pet looks like (`None` = the built-in pet).
+    pack: Option<String>,
+    variant: Option<String>,
+    /// What a combined pet inherited (see `traits.rs`).
+    #[serde(default)]
+    traits: Option<Traits>,
+    level: u32,
+    xp: u64,
+    energy: f32,
+    affection: f32,
+    #[serde(default)]
+    inventory: BTreeMap<String, u64>,
+}
+
+impl SharedPet {
+    fn validate(&self) -> Result<(), String> {
+        if self.format == 0 || self.format > FORMAT {
+            return Err(format!(
+                "format {} is not supported (this tovaras reads up to {FORMAT}); update tovaras",
+                self.format
+            ));
+        }
+        if self.name.trim().is_empty() {
+            return Err("the pet has no name".into());
+        }
+        if self.level != level_for(self.xp) {
+            return Err(format!(
+                "level {} doesn't match {} experience",
+                self.level, self.xp
+            ));
+        }
+        for (need, value) in [("energy", self.energy), ("affection", self.affection)] {
+            if !(0.0..=1.0).contains(&value) {
+                return Err(format!("{need} {value} is outside 0..1"));
+            }
+        }
+        if self.inventory.keys().any(|item| item.trim().is_empty()) {
+            return Err("unnamed inventory item".into());
+        }
+        if let Some(traits) = &self.traits {
+            if let Some((clip, weight)) = traits
+                .idles
+                .iter()
+                .find(|(_, w)| !w.is_finite() || **w < 0.0)
+            {
+                return Err(format!("idle {clip:?}: bad weight {weight}"));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn export(file: &Path) -> Result<(), String> {
+    let config = Config::load();
+    let profile = Profile::load(&config.sync);
+    let pack = config.pack.clone();
+    let dir = pack.as_deref().map(packs::pack_dir);
+    let title = dir.as_deref().map(packs::read_info).map(|info| info.name);
+    let needs = profile.needs();
+    let pet = SharedPet {
+        format: FORMAT,
+        name: title
+            .filter(|t| !t.is_empty())
+            .or_else(|| pack.clone())
+            .unwrap_or_else(|| "tovaras".into()),
+        traits: dir.as_deref().and_then(Traits::load),
+        pack,
+        variant: config.variant.clone(),
+        level: profile.level(),
+        xp: profile.xp(),
+        energy: needs.energy,
+        affection: needs.affection,
+        inventory: profile.inventory(),
+    };
+    let text = ron::ser::to_string_pretty(&pet, Default::default()).map_err(|e| e.to_string())?;
+    fs::write(file, text).map_err(|e| format!("{}: {e}", file.display()))?;
+    println!(
+        "Exported {} (level {}) to {}",
+        pet.name,
+        pet.level,
+        file.display()
+    );
+    Ok(())
+}
+
+fn import(file: &Path, replace: bool) -> Result<(), String> {
+    let text = fs::read_to_string(file).map_err(|e| format!("{}: {e}", file.display()))?;
+    let pet: SharedPet = ron::from_str(&text)
+        .map_err(|e| format!("{} is not a tovaras profile: {e}", file.display()))?;
+    pet.validate()
+        .map_err(|e| format!("{}: {e}", file.display()))?;
+    if ipc::forward("ping").is_ok() {
+        return Err("quit tovaras before importing a profile".into());
+    }
+
+    let config = Config::load();
+    let mut profile = Profile::load(&config.sync);
+    if !replace && (profile.xp() > 0 || !profile.inventory().is_empty()) {
+        return Err(format!(
+            "there already is a level {} pet here; pass --replace to give it up",
+            profile.level()
+        ));
+    }
+    profile
+        .restore(pet.xp, pet.energy, pet.affection, &pet.inventory)
+        .map_err(|e| format!("can't save the profile: {e}"))?;
+    println!("Welcome home, {} (level {})!", pet.name, pet.level);
+
+    let Some(pack) = &pet.pack else {
+        return Ok(());
+    };
+    if packs::pack_dir(pack).is_dir() {
+        println!(
+            "{} wears the pack {pack:?}; enable it with `tovaras packs enable {pack}`",
+            pet.name
+        );
+    } else if let Some((a, b)) = pet.traits.as_ref().map(|t| &t.parents) {
+        println!(
+            "{} is a combined pet; recreate its look with `tovaras packs combine {a} {b} {pack}`",
+            pet.name
+        );
+    } else {
+        println!(
+            "{} wears the pack {pack:?}, which is not installed here",
+            pet.name
+        );
+    }
+    Ok(())
+}
+
+/// `tovaras export-profile [FILE]`; returns the exit code.
+pub fn export_cli(args: &[String]) -> i32 {
+    let file = args.first().map_or(DEFAULT_FILE, String::as_str);
+    exit_code(export(Path::new(file)))
+}
+
+/// `tovaras import-profile FILE [--replace]`; returns the exit code.
+pub fn import_cli(args: &[String]) -> i32 {
+    let replace = args.iter().any(|a| a == "--replace");
+    let Some(file) = args.iter().find(|a| !a.starts_with("--")) else {
+        eprintln!("usage: tovaras import-profile FILE [--replace]");
+        return 1;
+    };
+    exit_code(import(Path::new(file), replace))
+}
+
+fn exit_code(result: Result<(), String>) -> i32 {
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("tovaras: {e}");
+            1
+        }
+    }
+}
@@ -0,0 +1,175 @@
+//! Import of Shimeji-ee mascots as tovaras packs.
+//!
+//! A mascot is a folder of `shime*.png` frames plus `conf/actions.xml`
+//! (either inside the folder or in the Shimeji-ee root two levels up). Actions
+//! with their own animation are mapped onto tovaras clips — `Stand` → idle,
+//! `Walk` → walk, `ClimbWall` → climb, ... — and packed into one sheet. The
+//! result is installed like any other pack.
+//!
+//! Shimeji art faces left, so the generated poses mirror it when moving right.
+
+use image::RgbaImage;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::manifest::LoopMode;
+use crate::packer::{self, Strip};
+use crate::packs;
+
+/// Shimeji ticks are 40 ms.
+const TICK_SECS: f32 = 0.04;
+const SHEET_FILE: &str = "sheet.png";
+
+/// Shimeji action -> clip name and loop mode.
+const ACTION_MAP: &[(&str, &str, LoopMode)] = &[
+    ("Stand", "idle", LoopMode::Loop),
+    ("Walk", "walk", LoopMode::Loop),
+    ("Sit", "sit", LoopMode::Loop),
+    ("Sprawl", "sleep", LoopMode::Loop),
+    ("ClimbWall", "climb", LoopMode::Loop),
+    ("GrabWall", "grab_wall", LoopMode::Loop),
+    ("ClimbCeiling", "climb_ceiling", LoopMode::Loop),
+    ("GrabCeiling", "grab_ceiling", LoopMode::Loop),
+    ("Jumping", "jump", LoopMode::Once),
+    ("Bouncing", "land", LoopMode::Once),
+];
+
+/// (surface, action, clip, flip_x) for every pose a Shimeji mascot can fill.
+const POSES: &[(&str, &str, &str, &str)] = &[
+    ("Floor", "Move", "walk", "IfForward"),
+    ("Floor", "Idle", "idle", "Never"),
+    ("Floor", "Sleeping", "sleep", "Never"),
+    ("Floor", "GivingFlowers", "sit", "Never"),
+    ("Floor", "Hiding", "sleep", "Never"),
+    ("Floor", "Jumping", "jump", "IfForward"),
+    ("Floor", "Landing", "land", "IfForward"),
+    ("RightWall", "Climb", "climb", "Always"),
+    ("RightWall", "Hiding", "grab_wall", "Always"),
+    ("RightWall", "Jumping", "jump", "Never"),
+    ("Ceiling", "Climb", "climb_ceiling", "IfForward"),
+    ("Ceiling", "Hiding", "grab_ceiling", "Never"),
+    ("LeftWall", "Climb", "climb", "Never"),
+    ("LeftWall", "Hiding", "grab_wall", "Never"),
+    ("LeftWall", "Jumping", "jump", "Always"),
+];
+
+fn find_actions(mascot: &Path) -> Option<PathBuf> {
+    let local = mascot.join("conf/actions.xml");
+    let shared = mascot.parent()?.parent()?.join("conf/actions.xml");
+    [local, shared].into_iter().find(|p| p.is_file())
+}
+
+/// Frames of every mapped action that has its own animation.
+fn read_strips(mascot: &Path, xml: &str) -> Result<Vec<Strip>, String> {
+    let doc = roxmltree::Document::parse(xml).map_err(|e| e.to_string())?;
+    let mut strips = Vec::new();
+    for action in doc.descendants().filter(|n| n.has_tag_name("Action")) {
+        let Some(&(_, clip, mode)) = ACTION_MAP
+            .iter()
+            .find(|(name, ..)| action.attribute("Name") == Some(*name))
+        else {
+            continue;
+        };
+        // only the first animation; later ones are conditional variants
+        let Some(animation) = action.children().find(|n| n.has_tag_name("Animation")) else {
+            continue;
+        };
+        let mut frames = Vec::new();
+        let mut durations = Vec::new();
+        for pose in animation.children().filter(|n| n.has_tag_name("Pose")) {
+            let Some(file) = pose.attribute("Image") else {
+                continue;
+            };
+            let path = mascot.join(file.trim_start_matches('/'));
+            let img: RgbaImage = image::open(&path)
+                .map_err(|e| format!("{}: {e}", path.display()))?
+                .into_rgba8();
+            let ticks: f32 = pose
+                .attribute("Duration")
+                .and_then(|d| d.parse().ok())
+                .unwrap_or(1.0);
+            frames.push(img);
+            durations.push(ticks.max(1.0) * TICK_SECS);
+        }
+        if frames.is_empty() || strips.iter().any(|s: &Strip| s.name == clip) {
+            continue;
+        }
+        strips.push(Strip {
+            name: clip.into(),
+            frames,
+            durations,
+            mode,
+        });
+    }
+    Ok(strips)
+}
+
+/// Convert a mascot folder into an installed pack; returns the pack name.
+pub fn import(mascot: &Path, name: Option<&str>) -> Result<String, String> {
+    let name = match name {
+        Some(n) => n.to_string(),
+        None => mascot
+            .canonicalize()
+            .ok()
+            .and_then(|p| p.file_name()?.to_str().map(str::to_lowercase))
+            .ok_or("can't derive a pack name, pass one explicitly")?,
+    };
+    let dest = packs::pack_dir(&name);
+    if dest.exists() {
+        return Err(format!("pack {name:?} is already installed"));
+    }
+
+    let actions = find_actions(mascot).ok_or("conf/actions.xml not found")?;
+    let xml = fs::read_to_string(&actions).map_err(|e| format!("{}: {e}", actions.display()))?;
+    let strips = read_strips(mascot, &xml)?;
+    if !strips.iter().any(|s| s.name == "idle") {
+        return Err("mascot has no Stand animation".into());
+    }
+    let packed = packer::pack(strips);
+
+    let mut poses = String::new();
+    for (surface, action, clip, flip) in POSES {
+        if packed.clips.contains_key(*clip) {
+            let _ = writeln!(
+                poses,
+                "        (surface: {surface}, action: {action}, clip: {clip:?}, flip_x: {flip}),"
+            );
+        }
+    }
+    let comment = format!("Imported from the Shimeji-ee mascot {mascot:?}");
+    let manifest = packed.manifest_ron(&comment, SHEET_FILE, &poses, "idle");
+
+    fs::create_dir_all(&dest).map_err(|e| e.to_string())?;
+    let written = packed
+        .sheet
+        .save(dest.join(SHEET_FILE))
+        .map_err(|e| e.to_string())
+        .and_then(|_| fs::write(dest.join("pet.ron"), manifest).map_err(|e| e.to_string()))
+        .and_then(|_| {
+            let info = format!("(name: {name:?}, description: \"Imported from Shimeji-ee\")\n");
+            fs::write(dest.join("pack.ron"), info).map_err(|e| e.to_string())
+        });
+    if written.is_err() {
+        let _ = fs::remove_dir_all(&dest);
+    }
+    written.map(|_| name)
+}
+
+/// `tovaras import-shimeji DIR [NAME]`; returns the exit code.
+pub fn cli(args: &[String]) -> i32 {
+    let Some(dir) = args.first() else {
+        eprintln!("usage: tovaras import-shimeji MASCOT_DIR [NAME]");
+        return 1;
+    };
+    match import(Path::new(dir), args.get(1).map(String::as_str)) {
+        Ok(name) => {
+            println!("Imported {name}; use it with `tovaras packs enable {name}`");
+            0
+        }
+        Err(e) => {
+            eprintln!("tovaras: {e}");
+            1
+        }
+    }
+}
@@ -0,0 +1,168 @@
+//! Seasonal skins: alternative sprite sheets picked by calendar date.
+//!
+//! Sheets are installed in `<data_dir>/skins/` next to a `seasons.ron` index:
+//!
+//! ```ron
+//! [
+//!     (file: "winter.png", from: (12, 1), to: (2, 28)),
+//!     (file: "halloween.png", from: (10, 24), to: (10, 31)),
+//! ]
+//! ```
+//!
+//! Ranges are inclusive `(month, day)` pairs and may wrap over New Year.
+//! When several ranges match, the shortest one wins, so a one-week holiday
+//! sheet beats a whole-season one. No match means the embedded default sheet.
+
+use bevy::prelude::*;
+use chrono::{Datelike, Local, NaiveDate};
+use serde::Deserialize;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::animation::{decode_sheet, SheetInfo};
+use crate::manifest::Manifest;
+use crate::paths;
+
+const INDEX_FILE: &str = "seasons.ron";
+// How often we look at the wall clock for a date change.
+const DATE_CHECK_SECS: f32 = 30.0;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct SeasonalSkin {
+    pub file: String,
+    pub from: (u32, u32),
+    pub to: (u32, u32),
+}
+
+/// Month/day packed so that plain integer comparison follows the calendar.
+fn md_key((month, day): (u32, u32)) -> u32 {
+    month * 32 + day
+}
+
+impl SeasonalSkin {
+    fn contains(&self, month: u32, day: u32) -> bool {
+        let (from, to, today) = (md_key(self.from), md_key(self.to), md_key((month, day)));
+        if from <= to {
+            from <= today && today <= to
+        } else {
+            // wraps over New Year (e.g. Dec 1 .. Feb 28)
+            today >= from || today <= to
+        }
+    }
+
+    /// Rough length of the range, only used to rank overlapping ranges.
+    fn span(&self) -> u32 {
+        let (from, to) = (md_key(self.from), md_key(self.to));
+        if from <= to {
+            to - from
+        } else {
+            md_key((12, 31)) - from + to
+        }
+    }
+}
+
+#[derive(Resource, Clone)]
+pub struct SkinCatalog {
+    dir: PathBuf,
+    skins: Vec<SeasonalSkin>,
+    /// File name of the sheet currently shown (`None` = embedded default).
+    pub active: Option<String>,
+    day: NaiveDate,
+    check: Timer,
+}
+
+impl SkinCatalog {
+    /// Read `seasons.ron` from the skins directory; a missing index is not an error.
+    pub fn load() -> Self {
+        let dir = paths::skins_dir();
+        let index = dir.join(INDEX_FILE);
+        let skins = match fs::read_to_string(&index) {
+            Ok(text) => match ron::from_str::<Vec<SeasonalSkin>>(&text) {
+                Ok(skins) => skins,
+                Err(e) => {
+                    warn!("Ignoring malformed {}: {e}", index.display());
+                    Vec::new()
+                }
+            },
+            Err(_) => Vec::new(),
+        };
+
+        let today = Local::now().date_naive();
+        let mut catalog = Self {
+            dir,
+            skins,
+            active: None,
+            day: today,
+            check: Timer::from_seconds(DATE_CHECK_SECS, TimerMode::Repeating),
+        };
+        catalog.active = catalog.pick(today).map(|s| s.file.clone());
+        catalog
+    }
+
+    /// No seasonal skins, for pets that aren't this user's own (visiting guests).
+    pub fn empty() -> Self {
+        Self {
+            dir: PathBuf::new(),
+            skins: Vec::new(),
+            active: None,
+            day: Local::now().date_naive(),
+            check: Timer::from_seconds(DATE_CHECK_SECS, TimerMode::Repeating),
+        }
+    }
+
+    pub fn pick(&self, date: NaiveDate) -> Option<&SeasonalSkin> {
+        self.skins
+            .iter()
+            .filter(|s| s.contains(date.month(), date.day()))
+            .min_by_key(|s| s.span())
+    }
+
+    /// Decode the active seasonal sheet, or `None` to use the regular one.
+    pub fn load_active(&self) -> Option<Image> {
+        let file = self.active.as_ref()?;
+        let path = self.dir.join(file);
+        let bytes = match fs::read(&path) {
+            Ok(b) => b,
+            Err(e) => {
+                warn!("Seasonal skin {} unavailable: {e}", path.display());
+                return None;
+            }
+        };
+        match decode_sheet(&bytes) {
+            Ok(img) => {
+                info!("Using seasonal skin {}", path.display());
+                Some(img)
+            }
+            Err(e) => {
+                warn!("Seasonal skin {} failed to decode: {e}", path.display());
+                None
+            }
+        }
+    }
+}
+
+/// Re-evaluate the seasonal pick whenever the local date rolls over.
+pub fn switch_skin_at_midnight(
+    time: Res<Time>,
+    mut catalog: ResMut<SkinCatalog>,
+    mut sheet: ResMut<SheetInfo>,
+    manifest: Res<Manifest>,
+) {
+    catalog.check.tick(time.delta());
+    if !catalog.check.just_finished() {
+        return;
+    }
+    let today = Local::now().date_naive();
+    if today == catalog.day {
+        return;
+    }
+    catalog.day = today;
+
+    let next = catalog.pick(today).map(|s| s.file.clone());
+    if next == catalog.active {
+        return;
+    }
+    catalog.active = next;
+    // built in the background; the old skin stays up until it is done
+    sheet.start_loading(&catalog, &manifest);
+}
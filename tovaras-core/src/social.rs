@@ -0,0 +1,583 @@
+//! Pet-to-pet interactions between tovaras instances on the same desktop.
+//!
+//! Every running pet publishes where it is in a small presence file under the
+//! runtime directory and reads the others'. When two pets stand near each
+//! other on the floor, the one with the lower pid occasionally invites the
+//! other to an interaction by naming it in its own presence record; the other
+//! pet joins when it is free. Both then play their part for the same time:
+//!
+//! - flowers: the host gives flowers facing the guest, who waits facing back;
+//! - nap: both fall asleep where they are;
+//! - chase: the host runs toward the guest, who runs away.
+//!
+//! Between interactions pets on the floor don't walk through each other: one
+//! that runs into another bumps and turns back, hops over it, or now and then
+//! climbs on top for a moment (the one below keeps still meanwhile).
+//!
+//! With `flock` on and five or more pets around, the pets loosely follow the
+//! one with the lowest pid around the screen edge, each keeping its place in
+//! line; a click on any of them scatters the flock for a few seconds.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::actions::Actions;
+use crate::commands::{Issued, JumpTarget, PetCommand, Source};
+use crate::config::Config;
+use crate::drivers::RandomCtrl;
+use crate::input::Petted;
+use crate::manifest::Manifest;
+use crate::pet_window::PetWindow;
+use crate::physics::SPEED_FLOOR;
+use crate::rng::{PetRng, TinyRng};
+use crate::{paths, Action, FlightKind, PetState, ScreenGeometry, Surface};
+
+const PRESENCE_DIR: &str = "pets";
+/// Seconds between presence updates.
+const POLL_SECS: f32 = 0.5;
+/// Records older than this belong to pets that quit or crashed.
+const STALE_SECS: u64 = 5;
+/// Chance per poll to start something with a nearby pet.
+const INVITE_CHANCE: f32 = 0.04;
+/// Seconds a host waits for the guest to join.
+const JOIN_SECS: f32 = 2.0;
+/// Part of a window's width that is actually pet (the rest is padding).
+const BODY: f32 = 0.6;
+/// Height of a pet's window a pet standing on it is lifted by.
+const STACK_HEIGHT: f32 = 0.5;
+/// Seconds spent standing on another pet.
+const STACK_SECS: f32 = 3.0;
+/// Seconds after getting down before climbing onto a pet again.
+const STACK_COOLDOWN: f32 = 20.0;
+/// Chances to climb on / hop over a pet in the way (otherwise bump and turn).
+const STACK_CHANCE: f32 = 0.15;
+const HOP_CHANCE: f32 = 0.3;
+/// Squash kick of a bump.
+const BUMP_SQUASH: f32 = 0.15;
+/// Pets (ours included) it takes to form a flock.
+const FLOCK_MIN: usize = 5;
+/// Room between flock members along the screen edge, in window widths.
+const FLOCK_SPACING: f32 = 1.2;
+/// A follower this close to its place in line stops, px.
+const FLOCK_SLACK: i32 = 40;
+/// Seconds a flock stays scattered after a click among it.
+const SCATTER_SECS: u64 = 4;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Interaction {
+    Flowers,
+    Nap,
+    Chase,
+}
+
+/// Invitation: (guest pid, kind, seconds).
+type Invite = (u32, Interaction, f32);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Presence {
+    pid: u32,
+    /// Window top-left and size, screen px.
+    pos: (i32, i32),
+    size: (i32, i32),
+    surface: Surface,
+    /// Floor speed, px/s (+ right), to guess where the pet is between polls.
+    #[serde(default)]
+    vx: f32,
+    /// Px above the floor while standing on another pet.
+    #[serde(default)]
+    perch: i32,
+    /// Unix seconds of the last click on the pet (scatters the flock).
+    #[serde(default)]
+    clicked: u64,
+    busy: bool,
+    invite: Option<Invite>,
+    /// Unix seconds of the last update.
+    stamp: u64,
+}
+
+impl Presence {
+    fn center_x(&self) -> i32 {
+        self.pos.0 + self.size.0 / 2
+    }
+}
+
+struct Active {
+    partner: u32,
+    kind: Interaction,
+    host: bool,
+    left: f32,
+}
+
+#[derive(Resource)]
+pub struct Social {
+    dir: PathBuf,
+    pid: u32,
+    poll: Timer,
+    cooldown: f32,
+    invite: Option<Invite>,
+    active: Option<Active>,
+    rng: TinyRng,
+    /// Pets seen at the last poll, and when (app seconds).
+    peers: Vec<Presence>,
+    polled: f32,
+    /// Seconds left standing on a pet (negative: since getting down).
+    stack_left: f32,
+    /// Unix seconds of the last click on our pet.
+    clicked: u64,
+}
+
+impl Social {
+    pub fn new() -> Self {
+        Self {
+            dir: paths::runtime_dir().join(PRESENCE_DIR),
+            pid: std::process::id(),
+            poll: Timer::from_seconds(POLL_SECS, TimerMode::Repeating),
+            cooldown: 10.0,
+            invite: None,
+            active: None,
+            rng: TinyRng::seeded(),
+            peers: Vec::new(),
+            polled: 0.0,
+            stack_left: -STACK_COOLDOWN,
+            clicked: 0,
+        }
+    }
+
+    fn own_file(&self) -> PathBuf {
+        self.dir.join(format!("{}.ron", self.pid))
+    }
+
+    fn publish(&self, me: &Presence) {
+        let Ok(text) = ron::to_string(me) else {
+            return;
+        };
+        // write + rename so readers never see half a record
+        let tmp = self.dir.join(format!(".{}.tmp", self.pid));
+        let _ = fs::create_dir_all(&self.dir)
+            .and_then(|_| fs::write(&tmp, text))
+            .and_then(|_| fs::rename(&tmp, self.own_file()));
+    }
+
+    fn peers(&self, now: u64) -> Vec<Presence> {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        entries
+            .filter_map(|e| fs::read_to_string(e.ok()?.path()).ok())
+            .filter_map(|text| ron::from_str::<Presence>(&text).ok())
+            .filter(|p| p.pid != self.pid && now.saturating_sub(p.stamp) <= STALE_SECS)
+            .collect()
+    }
+}
+
+impl Drop for Social {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(self.own_file());
+    }
+}
+
+/// Where a window at `pos` is along the screen edge the pets walk around, px:
+/// floor left to right, right wall up, ceiling right to left, left wall down.
+fn track(surface: Surface, pos: (i32, i32), max_x: i32, max_y: i32) -> i32 {
+    match surface {
+        Surface::Floor => pos.0,
+        Surface::RightWall => max_x + (max_y - pos.1),
+        Surface::Ceiling => max_x + max_y + (max_x - pos.0),
+        Surface::LeftWall => 2 * max_x + max_y + pos.1,
+    }
+}
+
+/// `dir` that moves a pet on `surface` forward along the track.
+fn forward(surface: Surface) -> f32 {
+    match surface {
+        Surface::Floor | Surface::RightWall => 1.0,
+        Surface::Ceiling | Surface::LeftWall => -1.0,
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Publish our presence, start/join interactions and play our part in them.
+#[allow(clippy::too_many_arguments)]
+pub fn socialize(
+    time: Res<Time>,
+    cfg: Res<Config>,
+    manifest: Res<Manifest>,
+    actions: Res<Actions>,
+    mut social: ResMut<Social>,
+    mut ctrl: ResMut<RandomCtrl>,
+    windows: Query<&Window>,
+    pets: Query<(&PetState, &PetWindow)>,
+    mut issue: EventWriter<Issued>,
+) {
+    let Ok((st, pet_window)) = pets.get_single() else {
+        return;
+    };
+    let Ok(win) = windows.get(pet_window.0) else {
+        return;
+    };
+    let dt = time.delta_seconds();
+    social.cooldown -= dt;
+    if let Some(active) = social.active.as_mut() {
+        active.left -= dt;
+    }
+    if !social.poll.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let now = unix_now();
+    let peers = social.peers(now);
+    social.peers = peers.clone();
+    social.polled = time.elapsed_seconds();
+    let grounded = st.surface == Surface::Floor && st.flight == FlightKind::None;
+    let me = Presence {
+        pid: social.pid,
+        pos: (st.window_pos.x, st.window_pos.y),
+        size: (
+            win.resolution.physical_width() as i32,
+            win.resolution.physical_height() as i32,
+        ),
+        surface: st.surface,
+        vx: if grounded {
+            st.speed * SPEED_FLOOR
+        } else {
+            0.0
+        },
+        perch: st.perch,
+        clicked: social.clicked,
+        busy: social.active.is_some(),
+        invite: social.invite,
+        stamp: now,
+    };
+
+    // Finished, or the partner left / gave up
+    if let Some(active) = &social.active {
+        let partner = peers.iter().find(|p| p.pid == active.partner);
+        let partner_in = partner.is_some_and(|p| {
+            if active.host {
+                p.busy
+            } else {
+                p.invite.is_some_and(|(guest, ..)| guest == social.pid)
+            }
+        });
+        // the guest gets a moment to notice the invitation
+        let waiting = active.host
+            && !partner_in
+            && partner.is_some()
+            && social
+                .invite
+                .is_some_and(|(.., secs)| secs - active.left < JOIN_SECS);
+        if active.left <= 0.0 || (!partner_in && !waiting) || !grounded {
+            social.active = None;
+            social.invite = None;
+            social.cooldown = cfg.social.cooldown_secs;
+            ctrl.left = 0.0; // hand control back to the random driver
+            if matches!(st.action, Action::Sleeping | Action::GivingFlowers) {
+                issue.send(PetCommand::set_action(Action::Idle).by(Source::Social));
+            }
+        }
+    }
+
+    let near = |p: &Presence| {
+        p.surface == Surface::Floor
+            && (p.center_x() - me.center_x()).abs() as f32 <= cfg.social.distance
+    };
+    let free = grounded
+        && social.active.is_none()
+        && social.cooldown <= 0.0
+        && !matches!(
+            st.action,
+            Action::Jumping | Action::Landing | Action::Hiding
+        );
+
+    if free {
+        // Accept an invitation addressed to us
+        if let Some((host, (_, kind, secs))) = peers.iter().find_map(|p| {
+            p.invite
+                .filter(|(guest, ..)| *guest == social.pid)
+                .map(|i| (p, i))
+        }) {
+            social.active = Some(Active {
+                partner: host.pid,
+                kind,
+                host: false,
+                left: secs,
+            });
+        } else if let Some(guest) = peers
+            .iter()
+            .filter(|p| p.pid > social.pid && !p.busy && near(p))
+            .min_by_key(|p| (p.center_x() - me.center_x()).abs())
+        {
+            if social.rng.chance(INVITE_CHANCE) {
+                let kind = match social.rng.next_u32() % 3 {
+                    0 => Interaction::Flowers,
+                    1 => Interaction::Nap,
+                    _ => Interaction::Chase,
+                };
+                let secs = match kind {
+                    Interaction::Flowers => {
+                        actions.secs(Action::GivingFlowers, &manifest, Surface::Floor, 0.0)
+                    }
+                    Interaction::Nap => social.rng.range_f32(6.0, 10.0),
+                    Interaction::Chase => social.rng.range_f32(3.0, 5.0),
+                };
+                info!("Inviting pet {} to {kind:?}", guest.pid);
+                social.invite = Some((guest.pid, kind, secs));
+                social.active = Some(Active {
+                    partner: guest.pid,
+                    kind,
+                    host: true,
+                    left: secs,
+                });
+            }
+        }
+    }
+
+    let me = Presence {
+        busy: social.active.is_some(),
+        invite: social.invite,
+        ..me
+    };
+    social.publish(&me);
+
+    // Play our part
+    let Some(active) = &social.active else {
+        return;
+    };
+    let Some(partner) = peers
+        .iter()
+        .find(|p| p.pid == active.partner && (p.busy || !active.host))
+    else {
+        return; // the guest hasn't joined yet
+    };
+    let toward = if partner.center_x() >= me.center_x() {
+        1.0
+    } else {
+        -1.0
+    };
+    let (action, dir) = match (active.kind, active.host) {
+        (Interaction::Flowers, true) => (Action::GivingFlowers, toward),
+        (Interaction::Flowers, false) => (Action::Idle, toward),
+        (Interaction::Nap, _) => (Action::Sleeping, st.facing),
+        (Interaction::Chase, true) => (Action::Move, toward),
+        (Interaction::Chase, false) => (Action::Move, -toward),
+    };
+    issue.send(
+        PetCommand::SetAction {
+            action,
+            dir: Some(dir),
+            clip: None,
+            secs: None,
+        }
+        .by(Source::Social),
+    );
+    // keep the random driver from picking something else meanwhile
+    ctrl.left = ctrl.left.max(POLL_SECS * 2.0);
+}
+
+/// Keep pets on the floor from walking through each other. A bump turns the
+/// pet back on the spot; hops, climbs and holds go through the arbiter.
+#[allow(clippy::too_many_arguments)]
+pub fn avoid_collisions(
+    time: Res<Time>,
+    mut social: ResMut<Social>,
+    mut ctrl: ResMut<RandomCtrl>,
+    screen: Res<ScreenGeometry>,
+    windows: Query<&Window>,
+    mut pets: Query<(&mut PetState, &PetWindow)>,
+    mut issue: EventWriter<Issued>,
+) {
+    let Ok((mut st, pet_window)) = pets.get_single_mut() else {
+        return;
+    };
+    let Ok(win) = windows.get(pet_window.0) else {
+        return;
+    };
+    social.stack_left -= time.delta_seconds();
+    if st.surface != Surface::Floor
+        || st.flight != FlightKind::None
+        || st.action == Action::Landing
+        || social.active.is_some()
+    {
+        return;
+    }
+    let (w, h) = (
+        win.resolution.physical_width() as i32,
+        win.resolution.physical_height() as i32,
+    );
+    let max_x = screen.desktop(w, h).0 - w;
+    let half = (w as f32 * BODY / 2.0) as i32;
+    let me = st.window_pos.x + w / 2;
+    // floor pets where they should be by now
+    let since = time.elapsed_seconds() - social.polled;
+    let others: Vec<(&Presence, i32)> = social
+        .peers
+        .iter()
+        .filter(|p| p.surface == Surface::Floor)
+        .map(|p| (p, p.center_x() + (p.vx * since) as i32))
+        .collect();
+    let body = |p: &Presence| (p.size.0 as f32 * BODY / 2.0) as i32;
+    let mut hold = |st: &PetState, ctrl: &mut RandomCtrl| {
+        if st.action == Action::Move {
+            issue.send(PetCommand::set_action(Action::Idle).by(Source::Social));
+        }
+        ctrl.left = ctrl.left.max(POLL_SECS);
+    };
+
+    if st.perch > 0 {
+        let below = others
+            .iter()
+            .any(|&(p, x)| p.perch == 0 && (x - me).abs() < body(p));
+        if below && social.stack_left > 0.0 {
+            hold(&st, &mut ctrl);
+            return;
+        }
+        // time's up or the one below walked off: jump down beside it
+        let mut dir = if st.move_dir >= 0.0 { 1 } else { -1 };
+        if !(1..max_x).contains(&(st.window_pos.x + dir * w)) {
+            dir = -dir;
+        }
+        let x = (st.window_pos.x + dir * w).clamp(1, (max_x - 1).max(1));
+        issue.send(jump_to(JumpTarget::Floor(x)));
+        social.stack_left = 0.0;
+        return;
+    }
+    // someone is standing on us
+    if others
+        .iter()
+        .any(|&(p, x)| p.perch > 0 && (x - me).abs() < half)
+    {
+        hold(&st, &mut ctrl);
+        return;
+    }
+    if st.action != Action::Move {
+        return;
+    }
+    let Some(&(other, x)) = others
+        .iter()
+        .filter(|&&(p, x)| {
+            p.perch == 0 && (x - me) as f32 * st.move_dir > 0.0 && (x - me).abs() < body(p) + half
+        })
+        .min_by_key(|&&(_, x)| (x - me).abs())
+    else {
+        return;
+    };
+    let (reach, height) = (body(other), other.size.1);
+    let dir = st.move_dir as i32;
+    let beyond = x + dir * (reach + half) - w / 2;
+    let roll = social.rng.f32();
+    if roll < STACK_CHANCE && social.stack_left < -STACK_COOLDOWN {
+        issue.send(jump_to(JumpTarget::OnPet {
+            x: (x - w / 2).clamp(1, (max_x - 1).max(1)),
+            perch: (height as f32 * STACK_HEIGHT) as i32,
+        }));
+        social.stack_left = STACK_SECS;
+    } else if roll < STACK_CHANCE + HOP_CHANCE && (1..max_x).contains(&beyond) {
+        issue.send(jump_to(JumpTarget::Floor(beyond)));
+    } else {
+        // bump and turn back
+        st.move_dir = -st.move_dir;
+        st.speed = 0.0;
+        st.squash = BUMP_SQUASH;
+    }
+}
+
+/// Follow the flock's leader around the screen edge, or scatter after a click.
+pub fn flock(
+    mut social: ResMut<Social>,
+    mut ctrl: ResMut<RandomCtrl>,
+    mut petted: EventReader<Petted>,
+    screen: Res<ScreenGeometry>,
+    windows: Query<&Window>,
+    pets: Query<(&PetState, &PetWindow)>,
+    mut issue: EventWriter<Issued>,
+) {
+    let now = unix_now();
+    if petted.read().count() > 0 {
+        social.clicked = now;
+    }
+    let Ok((st, pet_window)) = pets.get_single() else {
+        return;
+    };
+    let Ok(win) = windows.get(pet_window.0) else {
+        return;
+    };
+    if social.peers.len() + 1 < FLOCK_MIN
+        || social.active.is_some()
+        || st.flight != FlightKind::None
+        || st.perch > 0
+        || matches!(st.action, Action::Jumping | Action::Landing)
+        || now.saturating_sub(social.clicked) <= SCATTER_SECS
+    {
+        return;
+    }
+    let (w, h) = (
+        win.resolution.physical_width() as i32,
+        win.resolution.physical_height() as i32,
+    );
+    let (screen_w, screen_h) = screen.desktop(w, h);
+    let (max_x, max_y) = (screen_w - w, screen_h - h);
+    let lap = 2 * (max_x + max_y);
+    let at = |p: &Presence| track(p.surface, p.pos, max_x, max_y);
+    let me = track(st.surface, (st.window_pos.x, st.window_pos.y), max_x, max_y);
+    // shortest signed way from `a` to `b` along the track
+    let way = |a: i32, b: i32| {
+        let d = (b - a).rem_euclid(lap.max(1));
+        if d > lap / 2 {
+            d - lap
+        } else {
+            d
+        }
+    };
+
+    let scatter = social
+        .peers
+        .iter()
+        .find(|p| now.saturating_sub(p.clicked) <= SCATTER_SECS);
+    let step = if let Some(clicked) = scatter {
+        // run away from the pet that was clicked
+        Some(if way(at(clicked), me) >= 0 { 1 } else { -1 })
+    } else {
+        let Some(leader) = social.peers.iter().min_by_key(|p| p.pid) else {
+            return;
+        };
+        if leader.pid > social.pid {
+            return; // we lead: the random driver decides
+        }
+        let rank = social.peers.iter().filter(|p| p.pid < social.pid).count() as i32;
+        let place = at(leader) - rank * (w as f32 * FLOCK_SPACING) as i32;
+        let d = way(me, place);
+        (d.abs() > FLOCK_SLACK).then_some(d.signum())
+    };
+
+    let (moving, resting) = match st.surface {
+        Surface::Floor => (Action::Move, Action::Idle),
+        _ => (Action::Climb, Action::Hiding),
+    };
+    let command = match step {
+        Some(step) => Some(PetCommand::SetAction {
+            action: moving,
+            dir: Some(forward(st.surface) * step as f32),
+            clip: None,
+            secs: None,
+        }),
+        None if st.action == moving => Some(PetCommand::set_action(resting)),
+        None => None,
+    };
+    if let Some(command) = command {
+        issue.send(command.by(Source::Social));
+    }
+    ctrl.left = ctrl.left.max(POLL_SECS);
+}
+
+/// A jump of ours, for the arbiter.
+fn jump_to(target: JumpTarget) -> Issued {
+    PetCommand::Jump { target }.by(Source::Social)
+}
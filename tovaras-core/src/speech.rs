@@ -0,0 +1,179 @@
+//! Speech bubbles: a short text shown above the pet for a few seconds.
+//!
+//! Any system can make the pet talk by sending a [`Say`] event. The bubble is
+//! an auxiliary click-through window owned by the bubble entity, so it closes
+//! on its own once the bubble is despawned. A new line replaces the old one.
+
+use bevy::prelude::*;
+use bevy::render::view::RenderLayers;
+use bevy::sprite::Anchor;
+use bevy::text::{Text2dBounds, TextLayoutInfo};
+use bevy::window::{WindowLevel, WindowResolution};
+
+use crate::pet_window::{spawn_owned_window, PetWindow};
+use crate::{Pet, PetState, ScreenGeometry};
+
+/// Bubbles are drawn only by their own window's camera.
+const SPEECH_LAYER: usize = 3;
+/// Widest line before the text wraps, px.
+const MAX_WIDTH: f32 = 240.0;
+const PAD: f32 = 8.0;
+/// Gap between the bubble and the top of the pet window, px.
+const GAP: i32 = 4;
+const FONT_SIZE: f32 = 14.0;
+const DEFAULT_SECS: f32 = 4.0;
+const TEXT_COLOR: Color = Color::srgb(0.12, 0.12, 0.14);
+const BUBBLE_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.92);
+
+/// Make the pet say `text` for `secs` (a default time when `None`).
+#[derive(Event, Clone, Debug)]
+pub struct Say {
+    pub text: String,
+    pub secs: Option<f32>,
+}
+
+#[derive(Component)]
+pub struct Bubble {
+    window: Entity,
+    left: f32,
+    /// Bubble size in px, once the text is laid out.
+    size: Option<Vec2>,
+}
+
+#[derive(Component)]
+pub struct BubbleBackground;
+
+/// Open a bubble window for the latest line, replacing the one on screen.
+pub fn spawn_bubbles(
+    mut commands: Commands,
+    mut says: EventReader<Say>,
+    old: Query<Entity, With<Bubble>>,
+) {
+    let Some(say) = says.read().last().cloned() else {
+        return;
+    };
+    for bubble in &old {
+        commands.entity(bubble).despawn_recursive();
+    }
+    let text = say.text.trim();
+    if text.is_empty() {
+        return;
+    }
+
+    let mut resolution = WindowResolution::new(MAX_WIDTH, FONT_SIZE + 2.0 * PAD);
+    resolution.set_scale_factor_override(Some(1.0));
+    let mut window = Window {
+        title: "tovaras-speech".into(),
+        name: Some("tovaras-speech".into()),
+        resolution,
+        resizable: false,
+        decorations: false,
+        transparent: true,
+        focused: false,
+        visible: false, // shown once it is sized and placed
+        window_level: WindowLevel::AlwaysOnTop,
+        ..default()
+    };
+    window.cursor.hit_test = false;
+
+    let layer = RenderLayers::layer(SPEECH_LAYER);
+    let bubble = commands.spawn_empty().id();
+    let window = spawn_owned_window(&mut commands, bubble, window, layer.clone());
+    commands
+        .entity(bubble)
+        .insert((
+            Text2dBundle {
+                text: Text::from_section(
+                    text,
+                    TextStyle {
+                        font_size: FONT_SIZE,
+                        color: TEXT_COLOR,
+                        ..default()
+                    },
+                )
+                .with_justify(JustifyText::Center),
+                text_anchor: Anchor::Center,
+                text_2d_bounds: Text2dBounds {
+                    size: Vec2::new(MAX_WIDTH - 2.0 * PAD, f32::INFINITY),
+                },
+                transform: Transform::from_xyz(0.0, 0.0, 1.0),
+                ..default()
+            },
+            layer.clone(),
+            Bubble {
+                window,
+                left: say.secs.unwrap_or(DEFAULT_SECS),
+                size: None,
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: BUBBLE_COLOR,
+                        custom_size: Some(Vec2::ONE),
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(0.0, 0.0, -1.0),
+                    ..default()
+                },
+                BubbleBackground,
+                layer,
+            ));
+        });
+}
+
+/// Size bubbles to their text, keep them above the pet and close them in time.
+pub fn update_bubbles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut bubbles: Query<(Entity, &mut Bubble, &TextLayoutInfo, &Children)>,
+    mut backgrounds: Query<&mut Sprite, With<BubbleBackground>>,
+    pets: Query<(&PetState, &PetWindow), With<Pet>>,
+    mut windows: Query<&mut Window>,
+    screen: Res<ScreenGeometry>,
+) {
+    let pet = pets.get_single().ok().and_then(|(st, pw)| {
+        let win = windows.get(pw.0).ok()?;
+        Some((st.window_pos, win.physical_size().as_ivec2()))
+    });
+    for (entity, mut bubble, layout, children) in &mut bubbles {
+        bubble.left -= time.delta_seconds();
+        let Some((pet_pos, pet_size)) = pet.filter(|_| bubble.left > 0.0) else {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        };
+        if layout.logical_size == Vec2::ZERO {
+            continue; // not laid out yet
+        }
+        let size = (layout.logical_size + Vec2::splat(2.0 * PAD)).ceil();
+        if bubble.size != Some(size) {
+            bubble.size = Some(size);
+            for &child in children {
+                if let Ok(mut sprite) = backgrounds.get_mut(child) {
+                    sprite.custom_size = Some(size);
+                }
+            }
+        }
+        let Ok(mut win) = windows.get_mut(bubble.window) else {
+            continue;
+        };
+        if win.resolution.size() != size {
+            win.resolution.set(size.x, size.y);
+        }
+        // above the pet, or below it when there is no room (on the ceiling)
+        let (w, h) = (size.x as i32, size.y as i32);
+        let x = (pet_pos.x + pet_size.x / 2 - w / 2).max(0);
+        let mut y = pet_pos.y - h - GAP;
+        if y < 0 {
+            y = pet_pos.y + pet_size.y + GAP;
+        }
+        let at = screen.window_at(IVec2::new(x, y));
+        if win.position != at {
+            win.position = at;
+        }
+        if !win.visible {
+            win.visible = true;
+        }
+    }
+}
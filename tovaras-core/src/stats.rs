@@ -0,0 +1,231 @@
+//! Behavior statistics: time spent in each action on each surface, jumps
+//! and petting, summed over every session in `<data_dir>/stats.ron`.
+//!
+//! The running pet saves its totals every minute and when it quits;
+//! `tovaras stats` prints them and `tovaras stats --csv` writes them as CSV
+//! for spreadsheets and dashboards:
+//!
+//! ```text
+//! kind,surface,action,seconds,count
+//! pose,Floor,Move,5123.4,830
+//! pose,LeftWall,Climb,402.9,61
+//! jump,Floor,,,212
+//! petted,,,,57
+//! ```
+
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::input::Petted;
+use crate::{paths, Action, FlightKind, Pet, PetState, Surface};
+
+const STATS_FILE: &str = "stats.ron";
+/// Seconds between saves while running.
+const SAVE_SECS: f32 = 60.0;
+
+/// Time in one action on one surface.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct PoseTotals {
+    secs: f64,
+    /// Times the pet went into it.
+    times: u64,
+}
+
+/// What `stats.ron` holds.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Totals {
+    /// Unix seconds the first session started.
+    since: u64,
+    /// Surface -> action -> totals.
+    poses: BTreeMap<String, BTreeMap<String, PoseTotals>>,
+    /// Jumps by the surface they started from.
+    jumps: BTreeMap<String, u64>,
+    petted: u64,
+}
+
+impl Totals {
+    fn load(path: &Path) -> Self {
+        let Ok(text) = fs::read_to_string(path) else {
+            return Self::default();
+        };
+        ron::from_str(&text).unwrap_or_else(|e| {
+            warn!(
+                "Starting new statistics, {} is unreadable: {e}",
+                path.display()
+            );
+            Self::default()
+        })
+    }
+
+    fn pose(&mut self, surface: Surface, action: Action) -> &mut PoseTotals {
+        let surface = self.poses.entry(format!("{surface:?}")).or_default();
+        surface.entry(format!("{action:?}")).or_default()
+    }
+
+    /// `kind,surface,action,seconds,count` rows.
+    fn csv(&self) -> String {
+        let mut out = String::from("kind,surface,action,seconds,count\n");
+        for (surface, actions) in &self.poses {
+            for (action, pose) in actions {
+                out += &format!("pose,{surface},{action},{:.1},{}\n", pose.secs, pose.times);
+            }
+        }
+        for (surface, count) in &self.jumps {
+            out += &format!("jump,{surface},,,{count}\n");
+        }
+        out += &format!("petted,,,,{}\n", self.petted);
+        out
+    }
+
+    fn summary(&self) -> String {
+        let total: f64 = self
+            .poses
+            .values()
+            .flat_map(|a| a.values())
+            .map(|p| p.secs)
+            .sum();
+        let mut out = format!(
+            "{:.1} hours with your pet since {}\n",
+            total / 3600.0,
+            chrono::DateTime::from_timestamp(self.since as i64, 0)
+                .map_or_else(|| "?".into(), |t| t.format("%Y-%m-%d").to_string())
+        );
+        let mut poses: Vec<(&String, &String, &PoseTotals)> = (self.poses.iter())
+            .flat_map(|(s, actions)| actions.iter().map(move |(a, p)| (s, a, p)))
+            .collect();
+        poses.sort_by(|a, b| b.2.secs.total_cmp(&a.2.secs));
+        for (surface, action, pose) in poses {
+            let share = if total > 0.0 { pose.secs / total } else { 0.0 };
+            out += &format!(
+                "  {:<10} {:<14} {:>5.1}%  {:>6} times\n",
+                surface,
+                action,
+                share * 100.0,
+                pose.times
+            );
+        }
+        let jumps: u64 = self.jumps.values().sum();
+        out += &format!("{jumps} jumps, petted {} times\n", self.petted);
+        out
+    }
+}
+
+/// Running totals, including earlier sessions.
+#[derive(Resource)]
+pub struct Stats {
+    path: PathBuf,
+    totals: Totals,
+    save: Timer,
+    /// Pose and flight of the previous frame.
+    last: Option<(Surface, Action, FlightKind)>,
+}
+
+impl Stats {
+    pub fn load() -> Self {
+        let path = stats_file();
+        let mut totals = Totals::load(&path);
+        if totals.since == 0 {
+            totals.since = unix_now();
+        }
+        Self {
+            path,
+            totals,
+            save: Timer::from_seconds(SAVE_SECS, TimerMode::Repeating),
+            last: None,
+        }
+    }
+
+    /// Write the totals; a temporary file and rename keep readers from seeing half of them.
+    fn save(&self) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let text = ron::ser::to_string_pretty(&self.totals, Default::default())
+            .map_err(io::Error::other)?;
+        let tmp = self.path.with_extension("ron.tmp");
+        fs::write(&tmp, text)?;
+        fs::rename(&tmp, &self.path)
+    }
+
+    fn save_or_warn(&self) {
+        if let Err(e) = self.save() {
+            warn!("Could not save statistics to {}: {e}", self.path.display());
+        }
+    }
+}
+
+fn stats_file() -> PathBuf {
+    paths::data_dir().join(STATS_FILE)
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Add this frame to the totals; pet time follows `--time-scale`, saving the wall clock.
+pub fn track_stats(
+    time: Res<Time>,
+    real: Res<Time<Real>>,
+    mut stats: ResMut<Stats>,
+    mut petted: EventReader<Petted>,
+    pets: Query<&PetState, With<Pet>>,
+) {
+    stats.totals.petted += petted.read().count() as u64;
+    if let Ok(st) = pets.get_single() {
+        let now = (st.surface, st.action, st.flight);
+        let last = stats.last.replace(now);
+        let totals = &mut stats.totals;
+        let pose = totals.pose(st.surface, st.action);
+        pose.secs += time.delta_seconds_f64();
+        if last.is_none_or(|(s, a, _)| (s, a) != (st.surface, st.action)) {
+            pose.times += 1;
+        }
+        let took_off =
+            st.flight != FlightKind::None && last.is_none_or(|(_, _, f)| f == FlightKind::None);
+        if took_off {
+            *totals
+                .jumps
+                .entry(format!("{:?}", st.flight_from))
+                .or_default() += 1;
+        }
+    }
+    if stats.save.tick(real.delta()).just_finished() {
+        stats.save_or_warn();
+    }
+}
+
+pub fn save_on_exit(mut exits: EventReader<AppExit>, stats: Res<Stats>) {
+    if exits.read().next().is_some() {
+        stats.save_or_warn();
+    }
+}
+
+/// `tovaras stats [--csv]`; returns the exit code.
+pub fn cli(args: &[String]) -> i32 {
+    if let Some(arg) = args.iter().find(|a| *a != "--csv") {
+        eprintln!("tovaras: unknown argument {arg:?}\nusage: tovaras stats [--csv]");
+        return 1;
+    }
+    let path = stats_file();
+    if !path.exists() {
+        eprintln!("tovaras: no statistics yet; they are saved every minute while the pet runs");
+        return 1;
+    }
+    let totals = Totals::load(&path);
+    if args.is_empty() {
+        print!("{}", totals.summary());
+    } else {
+        print!("{}", totals.csv());
+    }
+    0
+}
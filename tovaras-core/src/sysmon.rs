@@ -0,0 +1,291 @@
+//! System monitor mood: with `system_monitor: (enabled: true)` in config.ron
+//! the pet's posture shows how the machine is doing, at a glance:
+//!
+//! - all is well: it lounges in place (a pack's idle clip called "relax", if
+//!   there is one);
+//! - the CPU is pegged (`cpu_busy` or more for `PEGGED_SAMPLES` samples in a
+//!   row): it paces back and forth;
+//! - the `disk` is `disk_full` or fuller: it carries a floppy disk, whatever
+//!   else it does.
+//!
+//! CPU use comes from `/proc/stat` (Linux), disk space from `statvfs`.
+
+use bevy::prelude::*;
+use std::ffi::CString;
+use std::fs;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+
+use crate::animation::{Anim, SheetInfo};
+use crate::commands::{Issued, PetCommand, Source};
+use crate::config::SystemMonitorConfig;
+use crate::manifest::Manifest;
+use crate::pet_window::PetWindow;
+use crate::{paths, shapes, Action, Pet, PetState, ScreenGeometry};
+
+/// Seconds between two samples.
+const SAMPLE_SECS: f32 = 2.0;
+/// Samples in a row at `cpu_busy` or more before the CPU counts as pegged.
+const PEGGED_SAMPLES: u32 = 3;
+/// Seconds a lounge holds off the driver (it is renewed before it runs out).
+const RELAX_SECS: f32 = 10.0;
+/// Seconds between two turns while pacing.
+const PACE_SECS: f32 = 3.0;
+/// How far the pet paces to either side, px.
+const PACE_PX: i32 = 120;
+/// Disk icon width, as a part of the frame's.
+const DISK_WIDTH: f32 = 0.2;
+const DISK_COLOR: [u8; 3] = [70, 80, 100];
+
+/// How the machine is doing.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+enum Health {
+    #[default]
+    Fine,
+    CpuPegged,
+}
+
+#[derive(Resource)]
+pub struct SystemMonitor {
+    cpu_busy: f32,
+    disk: PathBuf,
+    disk_full: f32,
+    sample: Timer,
+    /// Busy and total CPU time at the last sample.
+    last_cpu: Option<(u64, u64)>,
+    busy_samples: u32,
+    health: Health,
+    disk_is_full: bool,
+    /// Seconds to the next lounge or turn.
+    act_left: f32,
+    /// Desktop x the pet paces around, and the side it heads for next.
+    pacing: Option<(i32, i32)>,
+}
+
+impl SystemMonitor {
+    pub fn new(cfg: &SystemMonitorConfig) -> Self {
+        Self {
+            cpu_busy: cfg.cpu_busy,
+            disk: paths::expand_home(&cfg.disk),
+            disk_full: cfg.disk_full,
+            sample: Timer::from_seconds(SAMPLE_SECS, TimerMode::Repeating),
+            last_cpu: None,
+            busy_samples: 0,
+            health: Health::Fine,
+            disk_is_full: false,
+            act_left: 0.0,
+            pacing: None,
+        }
+    }
+
+    /// Take a sample of the CPU and the disk.
+    fn sample(&mut self) {
+        match cpu_times() {
+            Ok(now) => {
+                if let Some((busy, total)) = self.last_cpu {
+                    let used = now.0.saturating_sub(busy) as f32
+                        / now.1.saturating_sub(total).max(1) as f32;
+                    if used >= self.cpu_busy {
+                        self.busy_samples += 1;
+                    } else {
+                        self.busy_samples = 0;
+                    }
+                }
+                self.last_cpu = Some(now);
+            }
+            Err(e) => warn_once!("Can't read the CPU use: {e}"),
+        }
+        let health = if self.busy_samples >= PEGGED_SAMPLES {
+            Health::CpuPegged
+        } else {
+            Health::Fine
+        };
+        if health != self.health {
+            info!("System monitor: {health:?}");
+            self.health = health;
+            self.act_left = 0.0;
+            self.pacing = None;
+        }
+        match disk_space(&self.disk) {
+            Ok((total, available)) => {
+                let full = total > 0 && 1.0 - available as f32 / total as f32 >= self.disk_full;
+                if full != self.disk_is_full {
+                    let now = if full {
+                        "nearly full"
+                    } else {
+                        "no longer full"
+                    };
+                    info!("{} is {now}", self.disk.display());
+                    self.disk_is_full = full;
+                }
+            }
+            Err(e) => warn_once!("Can't read the space on {}: {e}", self.disk.display()),
+        }
+    }
+}
+
+/// Busy and total CPU time so far, in ticks, from `/proc/stat`.
+fn cpu_times() -> Result<(u64, u64), String> {
+    let stat = fs::read_to_string("/proc/stat").map_err(|e| e.to_string())?;
+    let ticks: Vec<u64> = stat
+        .lines()
+        .next()
+        .and_then(|line| line.strip_prefix("cpu "))
+        .ok_or("no cpu line in /proc/stat")?
+        .split_whitespace()
+        .filter_map(|n| n.parse().ok())
+        .collect();
+    let total = ticks.iter().sum();
+    // idle and iowait
+    let idle: u64 = ticks.iter().skip(3).take(2).sum();
+    Ok((total - idle, total))
+}
+
+/// Total and available bytes of the filesystem holding `path`.
+pub fn disk_space(path: &Path) -> Result<(u64, u64), String> {
+    let c_path = CString::new(path.as_os_str().as_bytes()).map_err(|e| e.to_string())?;
+    // SAFETY: `statvfs` only writes into the zeroed struct we pass it.
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return Err(io::Error::last_os_error().to_string());
+    }
+    // the field types differ between platforms
+    #[allow(clippy::unnecessary_cast)]
+    let (block, blocks, available) = (
+        stat.f_frsize as u64,
+        stat.f_blocks as u64,
+        stat.f_bavail as u64,
+    );
+    Ok((blocks * block, available * block))
+}
+
+/// Sample the machine, and lounge or pace to show how it is doing.
+pub fn show_machine_mood(
+    time: Res<Time<Real>>,
+    manifest: Res<Manifest>,
+    mut monitor: ResMut<SystemMonitor>,
+    screen: Res<ScreenGeometry>,
+    windows: Query<&Window>,
+    pets: Query<(&PetState, &PetWindow), With<Pet>>,
+    mut issue: EventWriter<Issued>,
+) {
+    let monitor = &mut *monitor;
+    if monitor.sample.tick(time.delta()).just_finished() {
+        monitor.sample();
+    }
+    monitor.act_left -= time.delta_seconds();
+    if monitor.act_left > 0.0 {
+        return;
+    }
+    let Ok((st, pet_window)) = pets.get_single() else {
+        return;
+    };
+    let command = match monitor.health {
+        Health::Fine => {
+            monitor.act_left = RELAX_SECS - SAMPLE_SECS;
+            PetCommand::SetAction {
+                action: Action::Idle,
+                dir: None,
+                clip: manifest.clips.contains_key("relax").then(|| "relax".into()),
+                secs: Some(RELAX_SECS),
+            }
+        }
+        Health::CpuPegged => {
+            monitor.act_left = PACE_SECS;
+            let Ok(win) = windows.get(pet_window.0) else {
+                return;
+            };
+            let (around, side) = *monitor.pacing.get_or_insert_with(|| {
+                let center = st.window_pos.x + win.resolution.physical_width() as i32 / 2;
+                let room = PACE_PX.min(screen.size.x / 2);
+                (center.clamp(room, screen.size.x - room), 1)
+            });
+            monitor.pacing = Some((around, -side));
+            PetCommand::GoTo {
+                x: around + side * PACE_PX,
+            }
+        }
+    };
+    issue.send(command.by(Source::Ambient));
+}
+
+#[derive(Component)]
+pub struct DiskIcon;
+
+pub fn spawn_disk_icon(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    pets: Query<Entity, With<Pet>>,
+) {
+    let Ok(pet) = pets.get_single() else {
+        return;
+    };
+    let child = commands
+        .spawn((
+            SpriteBundle {
+                texture: images.add(floppy_image()),
+                // just above the pet, below particles
+                transform: Transform::from_xyz(0.0, 0.0, 0.5),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            DiskIcon,
+        ))
+        .id();
+    commands.entity(pet).add_child(child);
+}
+
+/// A floppy disk: the shutter's slot and the label cut out, one corner
+/// clipped.
+fn floppy_image() -> Image {
+    shapes::mask_image(24, 24, DISK_COLOR, |x, y| {
+        let corner = x - y > 1.6;
+        let slot = (0.05..0.3).contains(&x) && (-0.85..-0.4).contains(&y);
+        let label = x.abs() < 0.65 && (0.1..0.9).contains(&y);
+        if corner || slot || label {
+            0.0
+        } else {
+            1.0
+        }
+    })
+}
+
+/// Show the disk icon in the pet's hand (or in front of it) while the disk
+/// is full.
+pub fn carry_disk_icon(
+    monitor: Res<SystemMonitor>,
+    sheet: Res<SheetInfo>,
+    pets: Query<(&Anim, &TextureAtlas), With<Pet>>,
+    mut icons: Query<(&mut Sprite, &mut Transform, &mut Visibility), With<DiskIcon>>,
+) {
+    let Ok((anim, atlas)) = pets.get_single() else {
+        return;
+    };
+    let Ok((mut sprite, mut tf, mut vis)) = icons.get_single_mut() else {
+        return;
+    };
+    let carried = sheet.ready && monitor.disk_is_full;
+    let want = if carried {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    if *vis != want {
+        *vis = want;
+    }
+    if !carried {
+        return;
+    }
+    let frame = Vec2::new(sheet.frame_w, sheet.frame_h);
+    let size = Vec2::splat(frame.x * DISK_WIDTH);
+    if sprite.custom_size != Some(size) {
+        sprite.custom_size = Some(size);
+    }
+    let at = anim
+        .point("hand", atlas.index, frame)
+        .unwrap_or(Vec2::new(frame.x * 0.2, -frame.y * 0.2));
+    if tf.translation.truncate() != at {
+        tf.translation = at.extend(tf.translation.z);
+    }
+}
@@ -0,0 +1,197 @@
+//! Alarm timers the pet announces: `tovaras ctl timer 25m tea` (or
+//! `{"cmd":"timer","secs":1500,"label":"tea"}`) sets one. When it goes off
+//! the pet walks to the middle of the screen, hops for attention and shows
+//! the label until it is clicked; timers due meanwhile ring one after the
+//! other.
+//!
+//! Timers run on the wall clock (the time scale doesn't speed them up, and
+//! time asleep counts) and only last as long as the pet runs.
+
+use bevy::prelude::*;
+use std::time::{Duration, SystemTime};
+
+use crate::commands::{Issued, PetCommand, Source};
+use crate::input::Petted;
+use crate::pet_window::PetWindow;
+use crate::{Action, Pet, PetState, ScreenGeometry, Surface};
+
+/// Said when a timer has no label.
+const DEFAULT_LABEL: &str = "Time's up!";
+/// Seconds between two hops (or steps back toward the middle) while ringing.
+const HOP_SECS: f32 = 3.0;
+/// Close enough to the middle to hop there, px.
+const NEAR_PX: i32 = 24;
+/// The longest a timer can be set for: a week.
+pub const MAX_SECS: f32 = 7.0 * 24.0 * 3600.0;
+
+/// A timer still to go off.
+struct Alarm {
+    label: String,
+    due: SystemTime,
+}
+
+/// The timers set, and the one ringing.
+#[derive(Resource, Default)]
+pub struct Timers {
+    pending: Vec<Alarm>,
+    /// The ringing timer's label, and seconds to the next hop.
+    ringing: Option<(String, f32)>,
+}
+
+impl Timers {
+    /// Go off in `secs` (at most [`MAX_SECS`]), saying `label` (or
+    /// [`DEFAULT_LABEL`]).
+    pub fn add(&mut self, secs: f32, label: Option<String>) {
+        let label = label
+            .map(|label| label.trim().to_string())
+            .filter(|label| !label.is_empty())
+            .unwrap_or_else(|| DEFAULT_LABEL.into());
+        let due = secs
+            .is_finite()
+            .then(|| Duration::try_from_secs_f32(secs.clamp(0.0, MAX_SECS)).ok())
+            .flatten()
+            .and_then(|wait| SystemTime::now().checked_add(wait));
+        let Some(due) = due else {
+            warn!("Timer {label:?}: {secs} s is not a duration");
+            return;
+        };
+        info!("Timer {label:?} set for {secs} s");
+        self.pending.push(Alarm { label, due });
+    }
+
+    /// The earliest timer due by now, taken off the list.
+    fn take_due(&mut self) -> Option<Alarm> {
+        let now = SystemTime::now();
+        let (i, _) = self
+            .pending
+            .iter()
+            .enumerate()
+            .filter(|(_, alarm)| alarm.due <= now)
+            .min_by_key(|(_, alarm)| alarm.due)?;
+        Some(self.pending.swap_remove(i))
+    }
+}
+
+/// A duration like `90`, `90s`, `25m`, `1h` or `1h30m`, in seconds; `None`
+/// past [`MAX_SECS`].
+pub fn parse_duration(text: &str) -> Option<f32> {
+    let total = if let Ok(secs) = text.parse::<f32>() {
+        secs
+    } else {
+        sum_units(text)?
+    };
+    (0.0..=MAX_SECS).contains(&total).then_some(total)
+}
+
+/// The seconds in `1h30m` and the like.
+fn sum_units(text: &str) -> Option<f32> {
+    let mut total = 0.0;
+    let mut number = String::new();
+    for c in text.chars() {
+        let unit = match c {
+            'h' => 3600.0,
+            'm' => 60.0,
+            's' => 1.0,
+            _ => {
+                number.push(c);
+                continue;
+            }
+        };
+        let n: f32 = number
+            .parse()
+            .ok()
+            .filter(|n: &f32| n.is_finite() && *n >= 0.0)?;
+        total += n * unit;
+        number.clear();
+    }
+    number.is_empty().then_some(total)
+}
+
+/// Ring the next timer due: bring the pet to the middle of the screen, keep it
+/// hopping there with the label shown, and stop once the pet is clicked.
+pub fn ring_timers(
+    time: Res<Time<Real>>,
+    mut timers: ResMut<Timers>,
+    mut petted: EventReader<Petted>,
+    screen: Res<ScreenGeometry>,
+    windows: Query<&Window>,
+    pets: Query<(&PetState, &PetWindow), With<Pet>>,
+    mut issue: EventWriter<Issued>,
+) {
+    let clicked = petted.read().count() > 0;
+    if clicked && timers.ringing.take().is_some() {
+        // an empty line takes the bubble down
+        issue.send(
+            PetCommand::Say {
+                text: String::new(),
+                secs: None,
+            }
+            .by(Source::Remote),
+        );
+    }
+    if timers.ringing.is_none() {
+        let Some(alarm) = timers.take_due() else {
+            return;
+        };
+        info!("Timer {:?} went off", alarm.label);
+        issue.send(
+            PetCommand::Say {
+                text: alarm.label.clone(),
+                secs: Some(f32::INFINITY),
+            }
+            .by(Source::Remote),
+        );
+        timers.ringing = Some((alarm.label, 0.0));
+    }
+    let Some((_, wait)) = timers.ringing.as_mut() else {
+        return;
+    };
+    *wait -= time.delta_seconds();
+    if *wait > 0.0 {
+        return;
+    }
+    *wait = HOP_SECS;
+    let Ok((st, pet_window)) = pets.get_single() else {
+        return;
+    };
+    let Ok(win) = windows.get(pet_window.0) else {
+        return;
+    };
+    let middle = screen.size.x / 2;
+    let center = st.window_pos.x + win.resolution.physical_width() as i32 / 2;
+    let command = if st.surface == Surface::Floor && (middle - center).abs() <= NEAR_PX {
+        PetCommand::SetAction {
+            action: Action::Jumping,
+            dir: None,
+            clip: None,
+            secs: Some(HOP_SECS * 2.0),
+        }
+    } else {
+        PetCommand::GoTo { x: middle }
+    };
+    issue.send(command.by(Source::Remote));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations_parse_up_to_a_week() {
+        assert_eq!(parse_duration("90"), Some(90.0));
+        assert_eq!(parse_duration("1h30m"), Some(5400.0));
+        assert_eq!(parse_duration("168h"), Some(MAX_SECS));
+        for bad in ["inf", "NaN", "1e30", "1e30s", "inf m", "169h", "-5", "5x"] {
+            assert_eq!(parse_duration(bad), None, "{bad}");
+        }
+    }
+
+    #[test]
+    fn huge_and_odd_timers_do_not_panic() {
+        let mut timers = Timers::default();
+        for secs in [f32::INFINITY, f32::NAN, 1e30, f32::MAX, -1.0] {
+            timers.add(secs, None);
+        }
+        assert_eq!(timers.pending.len(), 3);
+    }
+}
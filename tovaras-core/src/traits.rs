@@ -0,0 +1,144 @@
+//! "Make a friend": a new pet derived from two installed packs.
+//!
+//! `tovaras packs combine A B NAME` installs the pack NAME, which looks like
+//! A (its manifest and sheets are copied) and takes after both parents:
+//!
+//! - idle weights are blended: each idle clip (of A, or of B when A has a
+//!   clip of that name) is weighted by the mean of its share in A and in B;
+//! - phrase pools (`phrases.ron`, a list of lines) are combined, without
+//!   duplicates;
+//! - `pack.ron` names both parents.
+//!
+//! The blended weights go to the new pack's `traits.ron`, which takes
+//! precedence over the `idles` of its manifest.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::manifest::Manifest;
+use crate::packs::{self, PackInfo, INFO_FILE};
+use crate::phrases::{self, PHRASES_FILE};
+
+const TRAITS_FILE: &str = "traits.ron";
+
+/// What a derived pet inherited.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Traits {
+    /// Packs it was combined from.
+    pub parents: (String, String),
+    /// Idle clip weights, replacing the manifest's.
+    pub idles: BTreeMap<String, f32>,
+}
+
+impl Traits {
+    /// `traits.ron` in a pack directory, if there is a readable one.
+    pub fn load(dir: &Path) -> Option<Self> {
+        let text = fs::read_to_string(dir.join(TRAITS_FILE)).ok()?;
+        ron::from_str(&text).ok()
+    }
+}
+
+/// Idle weights as shares of their total.
+fn shares(idles: &BTreeMap<String, f32>) -> BTreeMap<String, f32> {
+    let total: f32 = idles.values().sum();
+    idles
+        .iter()
+        .map(|(name, &w)| (name.clone(), if total > 0.0 { w / total } else { 0.0 }))
+        .collect()
+}
+
+/// Idles of the child, which has `a`'s art, weighted after both parents.
+fn blend_idles(a: &Manifest, b: &Manifest) -> BTreeMap<String, f32> {
+    let (mine, theirs) = (shares(&a.idles), shares(&b.idles));
+    // b's favorites count too when a has a clip of that name
+    let names = mine
+        .keys()
+        .chain(theirs.keys().filter(|name| a.clips.contains_key(*name)));
+    names
+        .map(|name| {
+            let share = |s: &BTreeMap<String, f32>| s.get(name).copied().unwrap_or(0.0);
+            (name.clone(), (share(&mine) + share(&theirs)) / 2.0)
+        })
+        .collect()
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<(), String> {
+    fs::create_dir_all(to).map_err(|e| format!("{}: {e}", to.display()))?;
+    let entries = fs::read_dir(from).map_err(|e| format!("{}: {e}", from.display()))?;
+    for entry in entries.flatten() {
+        let (src, dest) = (entry.path(), to.join(entry.file_name()));
+        if src.is_dir() {
+            copy_dir(&src, &dest)?;
+        } else {
+            fs::copy(&src, &dest).map_err(|e| format!("{}: {e}", src.display()))?;
+        }
+    }
+    Ok(())
+}
+
+fn write_ron(path: &Path, value: &impl Serialize) -> Result<(), String> {
+    let text = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+        .map_err(|e| e.to_string())?;
+    fs::write(path, text).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+/// Install pack `name` derived from the installed packs `a` and `b`.
+pub fn combine(a: &str, b: &str, name: &str) -> Result<(), String> {
+    let installed = packs::list();
+    for parent in [a, b] {
+        if !installed.iter().any(|(n, _)| n == parent) {
+            return Err(format!("pack {parent:?} is not installed"));
+        }
+    }
+    if !packs::valid_name(name) {
+        return Err(format!("{name:?} is not a valid pack name"));
+    }
+    let dest = packs::pack_dir(name);
+    if dest.exists() {
+        return Err(format!("pack {name:?} is already installed"));
+    }
+    let (dir_a, dir_b) = (packs::pack_dir(a), packs::pack_dir(b));
+    let traits = Traits {
+        parents: (a.to_string(), b.to_string()),
+        idles: blend_idles(
+            &Manifest::load(dir_a.clone()),
+            &Manifest::load(dir_b.clone()),
+        ),
+    };
+    let mut phrases = phrases::read(&dir_a);
+    for line in phrases::read(&dir_b) {
+        if !phrases.contains(&line) {
+            phrases.push(line);
+        }
+    }
+    let title = |dir: &Path, pack: &str| {
+        let info = packs::read_info(dir);
+        if info.name.is_empty() {
+            pack.to_string()
+        } else {
+            info.name
+        }
+    };
+    let info = PackInfo {
+        name: name.to_string(),
+        author: String::new(),
+        version: "1.0".into(),
+        description: format!("Friend of {} and {}", title(&dir_a, a), title(&dir_b, b)),
+    };
+
+    // build next to the final place, then move it in one step
+    let staging = packs::packs_dir().join(format!(".{name}.partial"));
+    let _ = fs::remove_dir_all(&staging);
+    let result = copy_dir(&dir_a, &staging)
+        .and_then(|_| write_ron(&staging.join(TRAITS_FILE), &traits))
+        .and_then(|_| write_ron(&staging.join(PHRASES_FILE), &phrases))
+        .and_then(|_| write_ron(&staging.join(INFO_FILE), &info))
+        .and_then(|_| fs::rename(&staging, &dest).map_err(|e| e.to_string()));
+    if result.is_err() {
+        let _ = fs::remove_dir_all(&staging);
+    }
+    result
+}
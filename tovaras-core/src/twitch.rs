@@ -0,0 +1,361 @@
+//! Twitch chat reactions (built with `--features twitch`).
+//!
+//! The pet joins the configured channel's chat over Twitch's IRC WebSocket:
+//!
+//! - `!pet` pets it (hearts, affection);
+//! - `!flowers` has it give flowers when it is standing on the floor;
+//! - subs, resubs, gifted subs and raids make it hop and celebrate.
+//!
+//! Follows are not announced in chat; with a `client_id` they come from an
+//! EventSub WebSocket session instead (the token needs the
+//! `moderator:read:followers` scope and must belong to the broadcaster).
+//! Both connections run on background threads and reconnect on their own.
+
+use bevy::prelude::*;
+use serde_json::{json, Value};
+use std::net::TcpStream;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{Message, WebSocket};
+
+use crate::actions::Actions;
+use crate::commands::{Issued, PetCommand, Source};
+use crate::config::{Config, TwitchConfig};
+use crate::input::Petted;
+use crate::manifest::Manifest;
+use crate::particles::{ParticleBurst, ParticleKind};
+use crate::{Action, FlightKind, Pet, PetState, Surface};
+
+const IRC_URL: &str = "wss://irc-ws.chat.twitch.tv:443";
+const EVENTSUB_URL: &str = "wss://eventsub.wss.twitch.tv/ws";
+const HELIX: &str = "https://api.twitch.tv/helix";
+/// Twitch pings chat every ~5 minutes; silence longer than this is a dead link.
+const IRC_TIMEOUT: Duration = Duration::from_secs(6 * 60);
+/// EventSub sends keepalives every 10 s.
+const EVENTSUB_TIMEOUT: Duration = Duration::from_secs(30);
+const RETRY_MIN: Duration = Duration::from_secs(5);
+const RETRY_MAX: Duration = Duration::from_secs(120);
+const CELEBRATE_HEARTS: usize = 12;
+const CELEBRATE_PETALS: usize = 10;
+
+type Socket = WebSocket<MaybeTlsStream<TcpStream>>;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum ChatCommand {
+    Pet,
+    Flowers,
+}
+
+#[derive(Debug)]
+enum ChatEvent {
+    Command {
+        user: String,
+        command: ChatCommand,
+    },
+    /// A follow, sub or raid, described for the log.
+    Celebrate(String),
+}
+
+#[derive(Resource)]
+pub struct Twitch {
+    events: Mutex<Receiver<ChatEvent>>,
+    /// Seconds until chat commands are obeyed again.
+    cooldown: f32,
+}
+
+/// Start the chat (and, with a client id, follower) connections.
+pub fn connect(cfg: &TwitchConfig) -> Twitch {
+    let (tx, rx) = mpsc::channel();
+    let irc = cfg.clone();
+    let chat = tx.clone();
+    thread::spawn(move || keep_running("chat", || run_chat(&irc, &chat)));
+    if !cfg.client_id.is_empty() {
+        let eventsub = cfg.clone();
+        thread::spawn(move || keep_running("follows", || run_follows(&eventsub, &tx)));
+    }
+    info!("Joining Twitch chat #{}", cfg.channel.to_lowercase());
+    Twitch {
+        events: Mutex::new(rx),
+        cooldown: 0.0,
+    }
+}
+
+/// Run `session` forever, backing off between failed attempts.
+fn keep_running(what: &str, mut session: impl FnMut() -> Result<(), String>) {
+    let mut wait = RETRY_MIN;
+    loop {
+        match session() {
+            // the receiving side is gone: the app is shutting down
+            Ok(()) => return,
+            Err(e) => warn!("Twitch {what} disconnected: {e}; retrying in {wait:?}"),
+        }
+        thread::sleep(wait);
+        wait = (wait * 2).min(RETRY_MAX);
+    }
+}
+
+fn open(url: &str, timeout: Duration) -> Result<Socket, String> {
+    let (socket, _) = tungstenite::connect(url).map_err(|e| e.to_string())?;
+    let stream = match socket.get_ref() {
+        MaybeTlsStream::Plain(s) => s,
+        MaybeTlsStream::Rustls(s) => s.get_ref(),
+        _ => return Ok(socket),
+    };
+    stream
+        .set_read_timeout(Some(timeout))
+        .map_err(|e| e.to_string())?;
+    Ok(socket)
+}
+
+fn bare_token(token: &str) -> &str {
+    token.strip_prefix("oauth:").unwrap_or(token)
+}
+
+fn run_chat(cfg: &TwitchConfig, events: &Sender<ChatEvent>) -> Result<(), String> {
+    let channel = cfg.channel.to_lowercase();
+    let nick = cfg.nick.clone().unwrap_or_else(|| channel.clone());
+    let mut socket = open(IRC_URL, IRC_TIMEOUT)?;
+    for line in [
+        "CAP REQ :twitch.tv/tags twitch.tv/commands".to_string(),
+        format!("PASS oauth:{}", bare_token(&cfg.token)),
+        format!("NICK {}", nick.to_lowercase()),
+        format!("JOIN #{channel}"),
+    ] {
+        socket
+            .send(Message::Text(line))
+            .map_err(|e| e.to_string())?;
+    }
+    loop {
+        let text = match socket.read().map_err(|e| e.to_string())? {
+            Message::Text(text) => text,
+            Message::Close(_) => return Err("closed by Twitch".into()),
+            _ => continue,
+        };
+        for line in text.lines() {
+            let Some(msg) = IrcLine::parse(line) else {
+                continue;
+            };
+            let event = match msg.command {
+                "PING" => {
+                    let pong = format!("PONG :{}", msg.trailing);
+                    socket
+                        .send(Message::Text(pong))
+                        .map_err(|e| e.to_string())?;
+                    continue;
+                }
+                "NOTICE" if msg.trailing.contains("authentication failed") => {
+                    return Err(format!("login failed: {}", msg.trailing));
+                }
+                "PRIVMSG" => chat_command(msg.trailing).map(|command| ChatEvent::Command {
+                    user: msg.display_name(),
+                    command,
+                }),
+                "USERNOTICE" => celebration(&msg),
+                _ => None,
+            };
+            if let Some(event) = event {
+                if events.send(event).is_err() {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+fn chat_command(text: &str) -> Option<ChatCommand> {
+    match text.split_whitespace().next()?.to_lowercase().as_str() {
+        "!pet" => Some(ChatCommand::Pet),
+        "!flowers" => Some(ChatCommand::Flowers),
+        _ => None,
+    }
+}
+
+fn celebration(msg: &IrcLine) -> Option<ChatEvent> {
+    let what = match msg.tag("msg-id")? {
+        "sub" | "resub" => "subscribed",
+        "subgift" | "submysterygift" => "gifted subs",
+        "raid" => "raided",
+        _ => return None,
+    };
+    Some(ChatEvent::Celebrate(format!(
+        "{} {what}",
+        msg.display_name()
+    )))
+}
+
+/// `@tags :nick!user@host COMMAND #channel :trailing`
+struct IrcLine<'a> {
+    tags: &'a str,
+    nick: &'a str,
+    command: &'a str,
+    trailing: &'a str,
+}
+
+impl<'a> IrcLine<'a> {
+    fn parse(line: &'a str) -> Option<Self> {
+        let mut rest = line.trim_end();
+        let mut tags = "";
+        if let Some(tagged) = rest.strip_prefix('@') {
+            (tags, rest) = tagged.split_once(' ')?;
+        }
+        let mut nick = "";
+        if let Some(prefixed) = rest.strip_prefix(':') {
+            let (prefix, after) = prefixed.split_once(' ')?;
+            nick = prefix.split('!').next().unwrap_or_default();
+            rest = after;
+        }
+        let (head, trailing) = rest.split_once(" :").unwrap_or((rest, ""));
+        let command = head.split(' ').next()?;
+        Some(Self {
+            tags,
+            nick,
+            command,
+            trailing,
+        })
+    }
+
+    fn tag(&self, key: &str) -> Option<&'a str> {
+        self.tags
+            .split(';')
+            .find_map(|kv| kv.strip_prefix(key)?.strip_prefix('='))
+    }
+
+    fn display_name(&self) -> String {
+        self.tag("display-name")
+            .filter(|n| !n.is_empty())
+            .unwrap_or(self.nick)
+            .to_string()
+    }
+}
+
+fn helix(cfg: &TwitchConfig, req: ureq::Request) -> ureq::Request {
+    req.set("Client-Id", &cfg.client_id).set(
+        "Authorization",
+        &format!("Bearer {}", bare_token(&cfg.token)),
+    )
+}
+
+fn run_follows(cfg: &TwitchConfig, events: &Sender<ChatEvent>) -> Result<(), String> {
+    let url = format!("{HELIX}/users?login={}", cfg.channel.to_lowercase());
+    let users: Value = helix(cfg, ureq::get(&url))
+        .call()
+        .map_err(|e| e.to_string())?
+        .into_json()
+        .map_err(|e| e.to_string())?;
+    let Some(id) = users["data"][0]["id"].as_str() else {
+        return Err(format!("no Twitch user {:?}", cfg.channel));
+    };
+
+    let mut socket = open(EVENTSUB_URL, EVENTSUB_TIMEOUT)?;
+    loop {
+        let text = match socket.read().map_err(|e| e.to_string())? {
+            Message::Text(text) => text,
+            Message::Close(_) => return Err("closed by Twitch".into()),
+            _ => continue,
+        };
+        let Ok(msg) = serde_json::from_str::<Value>(&text) else {
+            continue;
+        };
+        let payload = &msg["payload"];
+        match msg["metadata"]["message_type"].as_str() {
+            Some("session_welcome") => {
+                let session = payload["session"]["id"].as_str().unwrap_or_default();
+                helix(cfg, ureq::post(&format!("{HELIX}/eventsub/subscriptions")))
+                    .send_json(json!({
+                        "type": "channel.follow",
+                        "version": "2",
+                        "condition": {
+                            "broadcaster_user_id": id,
+                            "moderator_user_id": id,
+                        },
+                        "transport": { "method": "websocket", "session_id": session },
+                    }))
+                    .map_err(|e| format!("can't subscribe to follows: {e}"))?;
+            }
+            Some("notification") => {
+                let name = payload["event"]["user_name"].as_str().unwrap_or("someone");
+                let event = ChatEvent::Celebrate(format!("{name} followed"));
+                if events.send(event).is_err() {
+                    return Ok(());
+                }
+            }
+            Some("session_reconnect") => return Err("asked to reconnect".into()),
+            _ => {}
+        }
+    }
+}
+
+/// Play what chat asked for.
+#[allow(clippy::too_many_arguments)]
+pub fn react_to_chat(
+    time: Res<Time>,
+    cfg: Res<Config>,
+    manifest: Res<Manifest>,
+    actions: Res<Actions>,
+    mut twitch: ResMut<Twitch>,
+    pets: Query<&PetState, With<Pet>>,
+    mut petted: EventWriter<Petted>,
+    mut bursts: EventWriter<ParticleBurst>,
+    mut issue: EventWriter<Issued>,
+) {
+    twitch.cooldown -= time.delta_seconds();
+    let events: Vec<ChatEvent> = twitch.events.get_mut().unwrap().try_iter().collect();
+    let Ok(st) = pets.get_single() else {
+        return;
+    };
+    for event in events {
+        let grounded = st.surface == Surface::Floor
+            && st.flight == FlightKind::None
+            && !matches!(
+                st.action,
+                Action::Jumping | Action::Landing | Action::Hiding
+            );
+        match event {
+            ChatEvent::Command { user, command } => {
+                if twitch.cooldown > 0.0 {
+                    continue;
+                }
+                twitch.cooldown = cfg.twitch.cooldown_secs;
+                info!("{user} in chat: {command:?}");
+                match command {
+                    ChatCommand::Pet => {
+                        petted.send(Petted);
+                    }
+                    ChatCommand::Flowers if grounded => {
+                        // the whole bouquet before the driver picks again
+                        let secs =
+                            actions.secs(Action::GivingFlowers, &manifest, Surface::Floor, 0.0);
+                        issue.send(
+                            PetCommand::SetAction {
+                                action: Action::GivingFlowers,
+                                dir: None,
+                                clip: None,
+                                secs: Some(secs),
+                            }
+                            .by(Source::Chat),
+                        );
+                    }
+                    ChatCommand::Flowers => {}
+                }
+            }
+            ChatEvent::Celebrate(what) => {
+                info!("Celebrating: {what}");
+                bursts.send(ParticleBurst {
+                    kind: ParticleKind::Heart,
+                    count: CELEBRATE_HEARTS,
+                });
+                bursts.send(ParticleBurst {
+                    kind: ParticleKind::Petal,
+                    count: CELEBRATE_PETALS,
+                });
+                if grounded {
+                    // hop in place
+                    issue.send(PetCommand::set_action(Action::Jumping).by(Source::Chat));
+                }
+            }
+        }
+    }
+}
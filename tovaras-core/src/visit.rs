@@ -0,0 +1,423 @@
+//! Visiting friends on the LAN: press **V** and the pet walks over to a
+//! friend's machine for a while, then comes back home.
+//!
+//! Both sides opt in with `visit: (enabled: true, code: "...")` in config.ron
+//! and must use the same pairing code. The protocol is one JSON line each way
+//! over TCP:
+//!
+//! ```text
+//! -> {"code":"1234","pack":"cat","variant":null,"stay_secs":60.0}
+//! <- {"ok":true}                      or {"ok":false,"reason":"busy"}
+//! ```
+//!
+//! The host shows one guest at a time in its own window, using the named pack
+//! if it is installed there and the built-in pet otherwise, and answers
+//! "busy" while it already has one. The visitor hides its own pet until the
+//! stay is over.
+
+use bevy::prelude::*;
+use bevy::render::view::RenderLayers;
+use bevy::window::{WindowLevel, WindowResolution};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::animation::{build_sheet, play, set_anim_if_changed, Anim};
+use crate::config::{Config, VisitConfig};
+use crate::manifest::{Clip, Flip, Manifest};
+use crate::packs::{self, ActivePack};
+use crate::pet_window::{spawn_owned_window, PetWindow};
+use crate::physics::SPEED_FLOOR;
+use crate::rng::{PetRng, TinyRng};
+use crate::skins::SkinCatalog;
+use crate::{Action, Pet, ScreenGeometry, Surface, SCALE};
+
+/// Guests are drawn only by their own window's camera.
+const GUEST_LAYER: usize = 2;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+/// Average seconds a guest stands around before strolling somewhere else.
+const WANDER_SECS: f32 = 8.0;
+/// Longest request line we read, bytes.
+const MAX_REQUEST: u64 = 1024;
+
+#[derive(Serialize, Deserialize)]
+struct Request {
+    code: String,
+    pack: Option<String>,
+    variant: Option<String>,
+    stay_secs: f32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Reply {
+    ok: bool,
+    #[serde(default)]
+    reason: String,
+}
+
+/// A visit accepted by the listener, waiting to be shown.
+struct Arrival {
+    pack: Option<String>,
+    variant: Option<String>,
+    stay_secs: f32,
+}
+
+#[derive(Resource)]
+pub struct Visits {
+    arrivals: Mutex<Receiver<Arrival>>,
+    /// Answer to our own outgoing visit: the stay in seconds, or why not.
+    outcome: Mutex<Option<Receiver<Result<f32, String>>>>,
+    /// Seconds until our pet is back home.
+    away_left: Option<f32>,
+    /// Set by the listener when it accepts a guest, cleared when the guest
+    /// leaves.
+    hosting: Arc<AtomicBool>,
+}
+
+#[derive(Component)]
+pub struct Guest {
+    left: f32,
+    x: f32,
+    target_x: f32,
+    dir: f32,
+    walk: (usize, Clip),
+    idle: (usize, Clip),
+    /// Mirroring of the walk pose.
+    flip: Flip,
+    rng: TinyRng,
+}
+
+/// Open the listener (when enabled) and return the resource.
+pub fn start(cfg: &VisitConfig) -> Visits {
+    let (tx, rx) = mpsc::channel();
+    let hosting = Arc::new(AtomicBool::new(false));
+    if cfg.enabled && !cfg.code.is_empty() {
+        match TcpListener::bind(("0.0.0.0", cfg.port)) {
+            Ok(listener) => {
+                info!("Accepting visits on port {}", cfg.port);
+                let code = cfg.code.clone();
+                let hosting = hosting.clone();
+                thread::spawn(move || listen(listener, code, tx, hosting));
+            }
+            Err(e) => warn!("Can't accept visits on port {}: {e}", cfg.port),
+        }
+    }
+    Visits {
+        arrivals: Mutex::new(rx),
+        outcome: Mutex::new(None),
+        away_left: None,
+        hosting,
+    }
+}
+
+/// Answer each peer on its own thread, so a slow one holds up no other.
+fn listen(
+    listener: TcpListener,
+    code: String,
+    arrivals: Sender<Arrival>,
+    hosting: Arc<AtomicBool>,
+) {
+    for stream in listener.incoming().flatten() {
+        let (code, arrivals, hosting) = (code.clone(), arrivals.clone(), hosting.clone());
+        thread::spawn(move || {
+            let _ = stream.set_read_timeout(Some(CONNECT_TIMEOUT));
+            let _ = stream.set_write_timeout(Some(CONNECT_TIMEOUT));
+            let reply = answer(read_request(&stream), &code, &arrivals, &hosting);
+            if let Ok(line) = serde_json::to_string(&reply) {
+                let _ = writeln!(&stream, "{line}");
+            }
+        });
+    }
+}
+
+fn answer(
+    req: Result<Request, String>,
+    code: &str,
+    arrivals: &Sender<Arrival>,
+    hosting: &AtomicBool,
+) -> Reply {
+    let declined = |reason: &str| Reply {
+        ok: false,
+        reason: reason.into(),
+    };
+    let req = match req {
+        Ok(req) if req.code != code => return declined("wrong pairing code"),
+        Ok(req) => req,
+        Err(e) => return declined(&e),
+    };
+    if hosting.swap(true, Ordering::SeqCst) {
+        return declined("busy");
+    }
+    let arrival = Arrival {
+        pack: req.pack,
+        variant: req.variant,
+        stay_secs: req.stay_secs.clamp(5.0, 600.0),
+    };
+    if arrivals.send(arrival).is_err() {
+        hosting.store(false, Ordering::SeqCst);
+        return declined("shutting down");
+    }
+    Reply {
+        ok: true,
+        reason: String::new(),
+    }
+}
+
+fn read_request(stream: &TcpStream) -> Result<Request, String> {
+    let mut line = String::new();
+    BufReader::new(stream.take(MAX_REQUEST))
+        .read_line(&mut line)
+        .map_err(|e| e.to_string())?;
+    if !line.ends_with('\n') {
+        return Err("request too long or cut off".into());
+    }
+    serde_json::from_str(&line).map_err(|e| e.to_string())
+}
+
+/// Ask `friend` to host our pet; blocking, run off the main thread.
+fn send_visit(friend: &str, req: &Request) -> Result<f32, String> {
+    let addr = friend
+        .to_socket_addrs()
+        .map_err(|e| format!("{friend}: {e}"))?
+        .next()
+        .ok_or(format!("{friend}: no address"))?;
+    let stream =
+        TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT).map_err(|e| format!("{friend}: {e}"))?;
+    let _ = stream.set_read_timeout(Some(CONNECT_TIMEOUT));
+    let line = serde_json::to_string(req).map_err(|e| e.to_string())?;
+    writeln!(&stream, "{line}").map_err(|e| e.to_string())?;
+    let mut answer = String::new();
+    BufReader::new(&stream)
+        .read_line(&mut answer)
+        .map_err(|e| e.to_string())?;
+    let reply: Reply = serde_json::from_str(&answer).map_err(|e| e.to_string())?;
+    if reply.ok {
+        Ok(req.stay_secs)
+    } else {
+        Err(format!("{friend} declined: {}", reply.reason))
+    }
+}
+
+/// `V` sends the pet to the first friend in the config.
+pub fn visit_hotkey(
+    keys: Res<ButtonInput<KeyCode>>,
+    cfg: Res<Config>,
+    active: Res<ActivePack>,
+    visits: Res<Visits>,
+) {
+    if !keys.just_pressed(KeyCode::KeyV) || visits.away_left.is_some() {
+        return;
+    }
+    let Some(friend) = cfg.visit.friends.first().cloned() else {
+        info!("No friends configured to visit (visit.friends in config.ron)");
+        return;
+    };
+    let req = Request {
+        code: cfg.visit.code.clone(),
+        pack: active.name.clone(),
+        variant: active.variant.clone(),
+        stay_secs: cfg.visit.stay_secs,
+    };
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = tx.send(send_visit(&friend, &req));
+    });
+    *visits.outcome.lock().unwrap() = Some(rx);
+}
+
+/// Hide our pet while it is away and bring it back when the stay is over.
+pub fn travel(
+    time: Res<Time>,
+    mut visits: ResMut<Visits>,
+    pets: Query<&PetWindow, With<Pet>>,
+    mut windows: Query<&mut Window>,
+) {
+    let answer = visits
+        .outcome
+        .get_mut()
+        .unwrap()
+        .as_ref()
+        .and_then(|rx| rx.try_recv().ok());
+    match answer {
+        Some(Ok(secs)) => {
+            info!("Visiting a friend for {secs:.0}s");
+            visits.away_left = Some(secs);
+            *visits.outcome.get_mut().unwrap() = None;
+        }
+        Some(Err(e)) => {
+            warn!("Visit failed: {e}");
+            *visits.outcome.get_mut().unwrap() = None;
+        }
+        None => {}
+    }
+
+    let away = match visits.away_left.as_mut() {
+        Some(left) => {
+            *left -= time.delta_seconds();
+            *left > 0.0
+        }
+        None => false,
+    };
+    if !away && visits.away_left.is_some() {
+        info!("Back home");
+        visits.away_left = None;
+    }
+    for pet_window in &pets {
+        if let Ok(mut win) = windows.get_mut(pet_window.0) {
+            if win.visible == away {
+                win.visible = !away;
+            }
+        }
+    }
+}
+
+/// Spawn a guest window for an accepted visit.
+pub fn welcome_guests(
+    mut commands: Commands,
+    mut visits: ResMut<Visits>,
+    mut images: ResMut<Assets<Image>>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+) {
+    let Ok(arrival) = visits.arrivals.get_mut().unwrap().try_recv() else {
+        return;
+    };
+
+    // the name comes from the network: only ever a directory under packs/
+    let mut manifest = match arrival.pack.as_deref() {
+        Some(name) if !packs::valid_name(name) => {
+            warn!("A visitor asked for pack {name:?}; showing the built-in pet");
+            Manifest::embedded()
+        }
+        Some(name) if packs::pack_dir(name).join("pet.ron").is_file() => {
+            Manifest::load(packs::pack_dir(name))
+        }
+        _ => Manifest::embedded(),
+    };
+    manifest.select_variant(arrival.variant.as_deref());
+    let (image, _) = build_sheet(&SkinCatalog::empty(), &manifest);
+    let frame = manifest.frame_size(image.size());
+    let layout = layouts.add(manifest.layout(image.size()));
+
+    let walk = manifest.clip_for(Surface::Floor, Action::Move);
+    let idle = manifest.clip_for(Surface::Floor, Action::Idle);
+    let anim = Anim::new(manifest.clip_start(walk), walk);
+    let guest = commands
+        .spawn((
+            SpriteBundle {
+                texture: images.add(image),
+                transform: Transform::from_scale(Vec3::splat(SCALE)),
+                ..default()
+            },
+            TextureAtlas {
+                layout,
+                index: anim.first_index(),
+            },
+            RenderLayers::layer(GUEST_LAYER),
+            Guest {
+                left: arrival.stay_secs,
+                x: 0.0,
+                target_x: 0.0,
+                dir: 1.0,
+                walk: (manifest.clip_start(walk), walk.clone()),
+                idle: (manifest.clip_start(idle), idle.clone()),
+                flip: manifest
+                    .pose(Surface::Floor, Action::Move)
+                    .map_or(Flip::Never, |p| p.flip_x),
+                rng: TinyRng::seeded(),
+            },
+            anim,
+        ))
+        .id();
+
+    let size = frame * SCALE;
+    let window = Window {
+        title: "tovaras guest".into(),
+        resolution: WindowResolution::new(size.x, size.y),
+        resizable: false,
+        decorations: false,
+        transparent: true,
+        window_level: WindowLevel::AlwaysOnTop,
+        visible: false, // shown once placed on the floor
+        ..default()
+    };
+    let window = spawn_owned_window(
+        &mut commands,
+        guest,
+        window,
+        RenderLayers::layer(GUEST_LAYER),
+    );
+    commands.entity(guest).insert(PetWindow(window));
+    info!("A friend's pet came to visit");
+}
+
+/// Walk in from the left, wander a little, then walk back out and leave.
+pub fn move_guests(
+    mut commands: Commands,
+    time: Res<Time>,
+    visits: Res<Visits>,
+    screen: Res<ScreenGeometry>,
+    mut windows: Query<&mut Window>,
+    mut guests: Query<(
+        Entity,
+        &mut Guest,
+        &mut Anim,
+        &mut TextureAtlas,
+        &mut Transform,
+        &PetWindow,
+    )>,
+) {
+    let dt = time.delta_seconds();
+    for (entity, mut guest, mut anim, mut atlas, mut tf, pet_window) in &mut guests {
+        let Ok(mut win) = windows.get_mut(pet_window.0) else {
+            continue;
+        };
+        let (w, h) = (
+            win.resolution.physical_width() as f32,
+            win.resolution.physical_height() as f32,
+        );
+        if !win.visible {
+            // arrive from just off the left edge
+            guest.x = -w;
+            guest.target_x = screen.size.x as f32 * 0.3;
+            win.visible = true;
+        }
+
+        guest.left -= dt;
+        if guest.left <= 0.0 {
+            guest.target_x = -w * 2.0; // going home
+        }
+        let dx = guest.target_x - guest.x;
+        let walking = dx.abs() > 2.0;
+        if walking {
+            guest.dir = dx.signum();
+            guest.x += guest.dir * SPEED_FLOOR * dt;
+        } else if guest.left > 0.0 && guest.rng.chance(dt / WANDER_SECS) {
+            let max_x = (screen.size.x as f32 - w).max(0.0);
+            guest.target_x = guest.rng.range_f32(0.0, max_x);
+        }
+        if guest.left <= 0.0 && guest.x <= -w * 1.5 {
+            commands.entity(entity).despawn_recursive();
+            visits.hosting.store(false, Ordering::SeqCst);
+            info!("The visiting pet went home");
+            continue;
+        }
+
+        let (start, clip) = if walking { &guest.walk } else { &guest.idle };
+        set_anim_if_changed(&mut anim, &mut atlas, *start, clip);
+        play(&mut anim, &mut atlas, time.delta(), |_, _| {});
+        tf.scale.x = if guest.flip.applies(guest.dir) {
+            -SCALE
+        } else {
+            SCALE
+        };
+        let at = screen.window_at(IVec2::new(guest.x as i32, screen.size.y - h as i32));
+        if win.position != at {
+            win.position = at;
+        }
+    }
+}
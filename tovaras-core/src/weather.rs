@@ -0,0 +1,285 @@
+//! Weather ambience: with a `weather` location in config.ron the pet looks up
+//! the current weather there (Open-Meteo, no account needed) every
+//! `refresh_minutes`.
+//!
+//! - Rain or snow falls around the pet, which holds up an umbrella.
+//! - In a heat wave (from `heat_celsius` on) it keeps to the shade at the
+//!   screen edges and pants there: a pack's `pant` action, or its idle clip
+//!   called "pant".
+//!
+//! Falling rain and snow keep the app updating at the full rate;
+//! `reduced_motion` leaves them out.
+
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use serde_json::Value;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use crate::animation::{Anim, SheetInfo};
+use crate::commands::{Issued, PetCommand, Source};
+use crate::config::{Config, WeatherConfig};
+use crate::manifest::Manifest;
+use crate::particles::{ParticleBurst, ParticleKind};
+use crate::pet_window::PetWindow;
+use crate::{shapes, Action, Pet, PetState, ScreenGeometry, Surface};
+
+const API_URL: &str = "https://api.open-meteo.com/v1/forecast";
+const TIMEOUT: Duration = Duration::from_secs(20);
+/// Seconds between two raindrops (or snowflakes).
+const RAIN_SECS: f32 = 0.08;
+const SNOW_SECS: f32 = 0.3;
+/// Seconds between two trips to the shade (or pants there) in a heat wave.
+const SHADE_SECS: f32 = 60.0;
+/// Seconds a pant holds off the driver.
+const PANT_SECS: f32 = 8.0;
+/// In the shade with the window this close to a screen edge, px.
+const SHADE_PX: i32 = 80;
+const UMBRELLA_COLOR: [u8; 3] = [90, 120, 205];
+
+/// What the weather is like, as far as the pet is concerned.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Sky {
+    #[default]
+    Fair,
+    Rain,
+    Snow,
+    Heat,
+}
+
+impl Sky {
+    /// From a WMO weather code and the temperature (°C).
+    fn of(code: u64, celsius: f32, heat_celsius: f32) -> Self {
+        match code {
+            51..=67 | 80..=82 | 95..=99 => Self::Rain,
+            71..=77 | 85..=86 => Self::Snow,
+            _ if celsius >= heat_celsius => Self::Heat,
+            _ => Self::Fair,
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct Weather {
+    updates: Mutex<Receiver<Sky>>,
+    sky: Sky,
+    /// Seconds to the next raindrop or snowflake.
+    fall_left: f32,
+    /// Seconds to the next trip to the shade.
+    shade_left: f32,
+}
+
+impl Weather {
+    /// Start looking up the weather at the location of `cfg` in the
+    /// background; `None` without a location.
+    pub fn start(cfg: &WeatherConfig) -> Option<Self> {
+        let (lat, lon) = cfg.location?;
+        let url =
+            format!("{API_URL}?latitude={lat}&longitude={lon}&current=temperature_2m,weather_code");
+        let refresh = Duration::from_secs_f32(cfg.refresh_minutes.max(1.0) * 60.0);
+        let heat = cfg.heat_celsius;
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let agent = ureq::AgentBuilder::new().timeout(TIMEOUT).build();
+            loop {
+                match fetch(&agent, &url, heat) {
+                    Ok(sky) => {
+                        if tx.send(sky).is_err() {
+                            return;
+                        }
+                    }
+                    Err(e) => warn!("Weather: {e}"),
+                }
+                thread::sleep(refresh);
+            }
+        });
+        Some(Self {
+            updates: Mutex::new(rx),
+            sky: Sky::Fair,
+            fall_left: 0.0,
+            shade_left: 0.0,
+        })
+    }
+}
+
+fn fetch(agent: &ureq::Agent, url: &str, heat_celsius: f32) -> Result<Sky, String> {
+    let body: Value = agent
+        .get(url)
+        .call()
+        .map_err(|e| e.to_string())?
+        .into_json()
+        .map_err(|e| e.to_string())?;
+    let current = &body["current"];
+    let (Some(code), Some(celsius)) = (
+        current["weather_code"].as_u64(),
+        current["temperature_2m"].as_f64(),
+    ) else {
+        return Err(format!("no current weather in {body}"));
+    };
+    Ok(Sky::of(code, celsius as f32, heat_celsius))
+}
+
+/// Let rain or snow fall around the pet, and in a heat wave take it to the
+/// shade and have it pant there.
+#[allow(clippy::too_many_arguments)]
+pub fn follow_weather(
+    time: Res<Time>,
+    cfg: Res<Config>,
+    manifest: Res<Manifest>,
+    mut weather: ResMut<Weather>,
+    screen: Res<ScreenGeometry>,
+    windows: Query<&Window>,
+    pets: Query<(&PetState, &PetWindow), With<Pet>>,
+    mut bursts: EventWriter<ParticleBurst>,
+    mut issue: EventWriter<Issued>,
+) {
+    let weather = &mut *weather;
+    if let Some(sky) = weather.updates.get_mut().unwrap().try_iter().last() {
+        if sky != weather.sky {
+            info!("Weather: {sky:?}");
+            weather.sky = sky;
+            weather.shade_left = 0.0;
+        }
+    }
+    let Ok((st, pet_window)) = pets.get_single() else {
+        return;
+    };
+    let dt = time.delta_seconds();
+    let (kind, every) = match weather.sky {
+        Sky::Rain => (ParticleKind::Rain, RAIN_SECS),
+        Sky::Snow => (ParticleKind::Snow, SNOW_SECS),
+        Sky::Heat => {
+            weather.shade_left -= dt;
+            if weather.shade_left > 0.0 {
+                return;
+            }
+            weather.shade_left = SHADE_SECS;
+            let Ok(win) = windows.get(pet_window.0) else {
+                return;
+            };
+            let width = win.resolution.physical_width() as i32;
+            // the nearer edge, and how far the window is from it
+            let (edge, off) = if st.window_pos.x + width / 2 < screen.size.x / 2 {
+                (0, st.window_pos.x)
+            } else {
+                (screen.size.x, screen.size.x - st.window_pos.x - width)
+            };
+            let command = if st.surface == Surface::Floor && off <= SHADE_PX {
+                let clip = manifest
+                    .clips
+                    .contains_key("pant")
+                    .then(|| "pant".to_string());
+                PetCommand::SetAction {
+                    action: Action::parse("pant").unwrap_or(Action::Idle),
+                    dir: None,
+                    clip,
+                    secs: Some(PANT_SECS),
+                }
+            } else {
+                PetCommand::GoTo { x: edge }
+            };
+            issue.send(command.by(Source::Ambient));
+            return;
+        }
+        Sky::Fair => return,
+    };
+    if cfg.reduced_motion || st.action == Action::Hiding {
+        return;
+    }
+    weather.fall_left -= dt;
+    if weather.fall_left <= 0.0 {
+        weather.fall_left = every;
+        bursts.send(ParticleBurst { kind, count: 1 });
+    }
+}
+
+#[derive(Component)]
+pub struct Umbrella;
+
+pub fn spawn_umbrella(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    pets: Query<Entity, With<Pet>>,
+) {
+    let Ok(pet) = pets.get_single() else {
+        return;
+    };
+    let child = commands
+        .spawn((
+            SpriteBundle {
+                texture: images.add(umbrella_image()),
+                sprite: Sprite {
+                    anchor: Anchor::BottomCenter,
+                    ..default()
+                },
+                // just above the pet, below particles
+                transform: Transform::from_xyz(0.0, 0.0, 0.5),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            Umbrella,
+        ))
+        .id();
+    commands.entity(pet).add_child(child);
+}
+
+/// An open umbrella, its handle's hook at the bottom.
+fn umbrella_image() -> Image {
+    shapes::mask_image(32, 40, UMBRELLA_COLOR, |x, y| {
+        // dome over the top half
+        let canopy = y < -0.1 && x * x + ((y + 0.1) / 0.9).powi(2) < 1.0;
+        let shaft = x.abs() < 0.07 && (-0.1..0.85).contains(&y);
+        // hook curling left at the bottom
+        let hook =
+            y >= 0.85 && (((x + 0.15).powi(2) + (y - 0.85).powi(2)).sqrt() - 0.15).abs() < 0.07;
+        if canopy || shaft || hook {
+            1.0
+        } else {
+            0.0
+        }
+    })
+}
+
+/// Hold the umbrella up from the pet's hand (or in front of it, for art
+/// without a "hand" point) while it rains or snows.
+pub fn hold_umbrella(
+    weather: Res<Weather>,
+    sheet: Res<SheetInfo>,
+    pets: Query<(&PetState, &Anim, &TextureAtlas), With<Pet>>,
+    mut umbrellas: Query<(&mut Sprite, &mut Transform, &mut Visibility), With<Umbrella>>,
+) {
+    let Ok((st, anim, atlas)) = pets.get_single() else {
+        return;
+    };
+    let Ok((mut sprite, mut tf, mut vis)) = umbrellas.get_single_mut() else {
+        return;
+    };
+    let held =
+        sheet.ready && matches!(weather.sky, Sky::Rain | Sky::Snow) && st.action != Action::Hiding;
+    let want = if held {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    if *vis != want {
+        *vis = want;
+    }
+    if !held {
+        return;
+    }
+    let frame = Vec2::new(sheet.frame_w, sheet.frame_h);
+    let at = anim
+        .point("hand", atlas.index, frame)
+        .unwrap_or(Vec2::new(frame.x * 0.2, -frame.y * 0.2));
+    // from the hand up to the top of the frame, so it isn't cut off
+    let height = (frame.y / 2.0 - at.y).max(1.0);
+    let size = Vec2::new((height * 0.8).min(frame.x), height);
+    if sprite.custom_size != Some(size) {
+        sprite.custom_size = Some(size);
+    }
+    if tf.translation.truncate() != at {
+        tf.translation = at.extend(tf.translation.z);
+    }
+}
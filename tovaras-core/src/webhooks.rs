@@ -0,0 +1,169 @@
+//! Outbound webhooks: notable moments are POSTed as JSON to the URLs listed
+//! under `webhooks` in config.ron, e.g. to a chat bot or home automation.
+//!
+//! ```json
+//! {"event":"level_up","level":3,"xp":210,"at":"2026-10-16T09:30:00+02:00"}
+//! {"event":"daily_summary","date":"2026-10-15","pettings":12,"flowers_given":3,"xp_gained":66,"level":3,"at":"..."}
+//! ```
+//!
+//! Requests go out from a background thread so a slow endpoint never stalls
+//! the pet; a failed delivery is retried once.
+
+use bevy::prelude::*;
+use chrono::NaiveDate;
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::mpsc::{self, Sender};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use crate::config::WebhookConfig;
+use crate::input::Petted;
+use crate::profile::Profile;
+use crate::{Action, Pet, PetState};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+const RETRY_AFTER: Duration = Duration::from_secs(5);
+
+/// Something worth telling the outside world about.
+#[derive(Event, Clone, Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Notable {
+    LevelUp {
+        level: u32,
+        xp: u64,
+    },
+    /// Yesterday in numbers, sent when the day rolls over.
+    DailySummary {
+        /// YYYY-MM-DD
+        date: String,
+        pettings: u32,
+        flowers_given: u32,
+        xp_gained: u64,
+        level: u32,
+    },
+}
+
+impl Notable {
+    fn name(&self) -> &'static str {
+        match self {
+            Notable::LevelUp { .. } => "level_up",
+            Notable::DailySummary { .. } => "daily_summary",
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct Webhooks {
+    hooks: Vec<WebhookConfig>,
+    /// (url, payload) for the delivery thread.
+    queue: Mutex<Sender<(String, Value)>>,
+}
+
+impl Webhooks {
+    pub fn new(hooks: &[WebhookConfig]) -> Self {
+        let (tx, rx) = mpsc::channel::<(String, Value)>();
+        thread::spawn(move || {
+            let agent = ureq::AgentBuilder::new().timeout(TIMEOUT).build();
+            for (url, payload) in rx {
+                let post = || {
+                    agent
+                        .post(&url)
+                        .send_json(&payload)
+                        .map_err(|e| e.to_string())
+                };
+                if let Err(e) = post() {
+                    thread::sleep(RETRY_AFTER);
+                    if let Err(again) = post() {
+                        warn!("Webhook {url} failed: {e}; retry: {again}");
+                    }
+                }
+            }
+        });
+        Self {
+            hooks: hooks.to_vec(),
+            queue: Mutex::new(tx),
+        }
+    }
+}
+
+/// Send every notable moment to the hooks that want it.
+pub fn send_webhooks(webhooks: Res<Webhooks>, mut events: EventReader<Notable>) {
+    for event in events.read() {
+        let Ok(mut payload) = serde_json::to_value(event) else {
+            continue;
+        };
+        payload["at"] = chrono::Local::now().to_rfc3339().into();
+        let queue = webhooks.queue.lock().unwrap();
+        for hook in &webhooks.hooks {
+            if hook.events.is_empty() || hook.events.iter().any(|e| e == event.name()) {
+                let _ = queue.send((hook.url.clone(), payload.clone()));
+            }
+        }
+    }
+}
+
+pub fn watch_level(
+    profile: Res<Profile>,
+    mut last: Local<Option<u32>>,
+    mut notable: EventWriter<Notable>,
+) {
+    let level = profile.level();
+    if last.is_some_and(|l| level > l) {
+        notable.send(Notable::LevelUp {
+            level,
+            xp: profile.xp(),
+        });
+    }
+    *last = Some(level);
+}
+
+/// Today's tally so far.
+#[derive(Default)]
+pub struct Day {
+    date: Option<NaiveDate>,
+    start_xp: u64,
+    pettings: u32,
+    flowers_given: u32,
+    last_action: Option<Action>,
+}
+
+/// Count the day's pettings and flowers; report them once the date changes.
+pub fn daily_summary(
+    profile: Res<Profile>,
+    mut petted: EventReader<Petted>,
+    pets: Query<&PetState, With<Pet>>,
+    mut day: Local<Day>,
+    mut notable: EventWriter<Notable>,
+) {
+    let today = chrono::Local::now().date_naive();
+    match day.date {
+        None => {
+            day.date = Some(today);
+            day.start_xp = profile.xp();
+        }
+        Some(date) if date != today => {
+            notable.send(Notable::DailySummary {
+                date: date.to_string(),
+                pettings: day.pettings,
+                flowers_given: day.flowers_given,
+                xp_gained: profile.xp().saturating_sub(day.start_xp),
+                level: profile.level(),
+            });
+            *day = Day {
+                date: Some(today),
+                start_xp: profile.xp(),
+                ..default()
+            };
+        }
+        Some(_) => {}
+    }
+    day.pettings += petted.read().count() as u32;
+    if let Ok(st) = pets.get_single() {
+        if st.action == Action::GivingFlowers && day.last_action != Some(st.action) {
+            day.flowers_given += 1;
+        }
+        day.last_action = Some(st.action);
+    }
+}
@@ -0,0 +1,86 @@
+//! Local WebSocket API for stream overlays, Stream Deck buttons and dashboards.
+//!
+//! Listens on `127.0.0.1:<websocket.port>` when enabled. Every text message
+//! is one JSON command (see `remote.rs`); the pet's state streams back as
+//! JSON text messages, and malformed commands are answered with
+//! `{"type":"error","message":"..."}`.
+
+use bevy::prelude::*;
+use serde_json::json;
+use std::io::ErrorKind;
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::time::Duration;
+use tungstenite::Message;
+
+use crate::config::WebSocketConfig;
+use crate::remote::{subscribe, Listeners, Remote, RemoteCommand};
+
+/// How often a connection checks for state updates to send.
+const POLL: Duration = Duration::from_millis(50);
+
+/// Start accepting clients on a background thread.
+pub fn start(cfg: &WebSocketConfig, remote: &Remote) {
+    let listener = match TcpListener::bind(("127.0.0.1", cfg.port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            warn!("Can't open the WebSocket API on port {}: {e}", cfg.port);
+            return;
+        }
+    };
+    info!("WebSocket API on ws://127.0.0.1:{}", cfg.port);
+    let commands = remote.sender();
+    let listeners = remote.listeners();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let (commands, listeners) = (commands.clone(), listeners.clone());
+            thread::spawn(move || {
+                if let Err(e) = serve(stream, commands, listeners) {
+                    debug!("WebSocket client left: {e}");
+                }
+            });
+        }
+    });
+}
+
+fn serve(
+    stream: TcpStream,
+    commands: Sender<RemoteCommand>,
+    listeners: Listeners,
+) -> Result<(), String> {
+    let mut socket = tungstenite::accept(stream).map_err(|e| e.to_string())?;
+    // short reads let us interleave incoming commands with outgoing updates
+    socket
+        .get_ref()
+        .set_read_timeout(Some(POLL))
+        .map_err(|e| e.to_string())?;
+    let updates = subscribe(&listeners);
+    loop {
+        match socket.read() {
+            Ok(Message::Text(text)) => match serde_json::from_str::<RemoteCommand>(&text) {
+                Ok(command) => {
+                    if commands.send(command).is_err() {
+                        return Ok(()); // shutting down
+                    }
+                }
+                Err(e) => {
+                    let reply = json!({ "type": "error", "message": e.to_string() });
+                    socket
+                        .send(Message::Text(reply.to_string()))
+                        .map_err(|e| e.to_string())?;
+                }
+            },
+            Ok(Message::Close(_)) => return Ok(()),
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(e) => return Err(e.to_string()),
+        }
+        for line in updates.try_iter() {
+            socket
+                .send(Message::Text(line))
+                .map_err(|e| e.to_string())?;
+        }
+    }
+}